@@ -0,0 +1,896 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::big::Big;
+use super::bls381::utils::{
+    deserialize_g1, deserialize_g2, hash_to_curve_g1, serialize_g1, serialize_g2,
+    subgroup_check_g1, subgroup_check_g2,
+};
+use super::ecp::ECP;
+use super::ecp2::ECP2;
+use super::hash_to_curve::expand_message_xmd;
+use super::kzg::{
+    g1_linear_combination, pairing_product_is_unity, scalar_add, scalar_from_bytes, scalar_inverse,
+    scalar_mul, scalar_sub, scalar_to_bytes,
+};
+use super::pair;
+use super::rom::{CURVE_ORDER, MODBYTES};
+use crate::errors::AmclError;
+use crate::rand::RAND;
+use std::vec::Vec;
+
+/// Ciphersuite ID of BLS12-381-SHA-256
+pub const CIPHERSUITE_ID: &[u8] = b"BBS_BLS12381G1_XMD:SHA-256_SSWU_RO_";
+/// API ID of the interface using hash to scalar for messages
+pub const API_ID: &[u8] = b"BBS_BLS12381G1_XMD:SHA-256_SSWU_RO_H2G_HM2S_";
+
+/// The number of bytes of a serialized scalar
+pub const OCTET_SCALAR_LENGTH: usize = 32;
+/// The number of bytes of a compressed G1 point
+pub const OCTET_POINT_LENGTH: usize = MODBYTES;
+/// The number of bytes of a compressed G2 point
+pub const PUBLIC_KEY_LENGTH: usize = MODBYTES * 2;
+/// The number of bytes of a signature, A || e
+pub const SIGNATURE_LENGTH: usize = OCTET_POINT_LENGTH + OCTET_SCALAR_LENGTH;
+
+// expand_len = ceil((ceil(log2(r)) + k) / 8)
+const EXPAND_LEN: usize = 48;
+
+// Fixed point P1 of the ciphersuite, compressed.
+const P1: [u8; OCTET_POINT_LENGTH] = [
+    0xa8, 0xce, 0x25, 0x61, 0x02, 0x84, 0x08, 0x21, 0xa3, 0xe9, 0x4e, 0xa9, 0x02, 0x5e, 0x46, 0x62,
+    0xb2, 0x05, 0x76, 0x2f, 0x97, 0x76, 0xb3, 0xa7, 0x66, 0xc8, 0x72, 0xb9, 0x48, 0xf1, 0xfd, 0x22,
+    0x5e, 0x7c, 0x59, 0x69, 0x85, 0x88, 0xe7, 0x0d, 0x11, 0x40, 0x6d, 0x16, 0x1b, 0x4e, 0x28, 0xc9,
+];
+
+// Concatenate domain separation tags.
+fn dst(prefix: &[u8], suffix: &[u8]) -> Vec<u8> {
+    let mut dst = prefix.to_vec();
+    dst.extend_from_slice(suffix);
+    dst
+}
+
+/*************************************************************************************************
+* Utility operations
+*
+* https://datatracker.ietf.org/doc/draft-irtf-cfrg-bbs-signatures/ section 4
+*************************************************************************************************/
+
+/// Hash To Scalar
+///
+/// OS2IP(expand_message_xmd(msg, dst, 48)) mod r
+pub fn hash_to_scalar(msg: &[u8], dst: &[u8]) -> Result<Big, AmclError> {
+    let uniform_bytes = expand_message_xmd(msg, EXPAND_LEN, dst)?;
+    let mut scalar = Big::from_bytes(&uniform_bytes);
+    scalar.rmod(&Big::new_ints(&CURVE_ORDER));
+    Ok(scalar)
+}
+
+/// Create Generators
+///
+/// Returns `count` generators of G1 derived from the API ID.
+pub fn create_generators(count: usize, api_id: &[u8]) -> Result<Vec<ECP>, AmclError> {
+    let seed_dst = dst(api_id, b"SIG_GENERATOR_SEED_");
+    let generator_dst = dst(api_id, b"SIG_GENERATOR_DST_");
+    let generator_seed = dst(api_id, b"MESSAGE_GENERATOR_SEED");
+
+    let mut v = expand_message_xmd(&generator_seed, EXPAND_LEN, &seed_dst)?;
+    let mut generators = Vec::with_capacity(count);
+    for i in 1..=count {
+        v.extend_from_slice(&(i as u64).to_be_bytes());
+        v = expand_message_xmd(&v, EXPAND_LEN, &seed_dst)?;
+        generators.push(hash_to_curve_g1(&v, &generator_dst));
+    }
+    Ok(generators)
+}
+
+/// Messages To Scalars
+pub fn messages_to_scalars(messages: &[&[u8]], api_id: &[u8]) -> Result<Vec<Big>, AmclError> {
+    let map_dst = dst(api_id, b"MAP_MSG_TO_SCALAR_AS_HASH_");
+    messages
+        .iter()
+        .map(|message| hash_to_scalar(message, &map_dst))
+        .collect()
+}
+
+// P1 as a point.
+fn p1() -> ECP {
+    deserialize_g1(&P1).expect("P1 is a valid point")
+}
+
+// Calculate Domain
+//
+// Binds the public key, generators, header and API ID.
+fn calculate_domain(
+    public_key: &[u8],
+    q_1: &ECP,
+    h_points: &[ECP],
+    header: &[u8],
+    api_id: &[u8],
+) -> Result<Big, AmclError> {
+    let domain_dst = dst(api_id, b"H2S_");
+
+    let mut dom_input = public_key.to_vec();
+    dom_input.extend_from_slice(&(h_points.len() as u64).to_be_bytes());
+    dom_input.extend_from_slice(&serialize_g1(q_1));
+    for h in h_points.iter() {
+        dom_input.extend_from_slice(&serialize_g1(h));
+    }
+    dom_input.extend_from_slice(api_id);
+    dom_input.extend_from_slice(&(header.len() as u64).to_be_bytes());
+    dom_input.extend_from_slice(header);
+
+    hash_to_scalar(&dom_input, &domain_dst)
+}
+
+// B = P1 + Q_1 * domain + sum(H_i * msg_i)
+fn compute_b(q_1: &ECP, h_points: &[ECP], scalars: &[Big], domain: &Big) -> ECP {
+    let mut b = g1_linear_combination(h_points, scalars);
+    b.add(&pair::g1mul(q_1, domain));
+    b.add(&p1());
+    b
+}
+
+// Octets To Public Key
+fn octets_to_public_key(public_key: &[u8]) -> Result<ECP2, AmclError> {
+    if public_key.len() != PUBLIC_KEY_LENGTH {
+        return Err(AmclError::InvalidG2Size);
+    }
+    let w = deserialize_g2(public_key)?;
+    if w.is_infinity() || !subgroup_check_g2(&w) {
+        return Err(AmclError::InvalidPoint);
+    }
+    Ok(w)
+}
+
+// Octets To Point, the identity is rejected.
+fn octets_to_point_g1(bytes: &[u8]) -> Result<ECP, AmclError> {
+    let point = deserialize_g1(bytes)?;
+    if point.is_infinity() || !subgroup_check_g1(&point) {
+        return Err(AmclError::InvalidPoint);
+    }
+    Ok(point)
+}
+
+// Octets To Signature
+fn octets_to_signature(signature: &[u8]) -> Result<(ECP, Big), AmclError> {
+    if signature.len() != SIGNATURE_LENGTH {
+        return Err(AmclError::InvalidSignature);
+    }
+    let a = octets_to_point_g1(&signature[..OCTET_POINT_LENGTH])?;
+    let e = scalar_from_bytes(&signature[OCTET_POINT_LENGTH..])?;
+    if e.is_zilch() {
+        return Err(AmclError::InvalidScalarRange);
+    }
+    Ok((a, e))
+}
+
+// Calculate Random Scalars
+fn calculate_random_scalars(count: usize, rng: &mut RAND) -> Vec<Big> {
+    let r = Big::new_ints(&CURVE_ORDER);
+    (0..count)
+        .map(|_| {
+            let mut bytes = [0u8; EXPAND_LEN];
+            for byte in bytes.iter_mut() {
+                *byte = rng.getbyte();
+            }
+            let mut scalar = Big::from_bytes(&bytes);
+            scalar.rmod(&r);
+            scalar
+        })
+        .collect()
+}
+
+/*************************************************************************************************
+* Key generation
+*
+* https://datatracker.ietf.org/doc/draft-irtf-cfrg-bbs-signatures/ section 3.4
+*************************************************************************************************/
+
+/// KeyGen
+///
+/// Derive a secret key from at least 32 bytes of key material.
+/// `key_dst` defaults to CIPHERSUITE_ID || "KEYGEN_DST_" when `None`.
+pub fn key_gen(
+    key_material: &[u8],
+    key_info: &[u8],
+    key_dst: Option<&[u8]>,
+) -> Result<[u8; OCTET_SCALAR_LENGTH], AmclError> {
+    if key_material.len() < 32 || key_info.len() > 65535 {
+        return Err(AmclError::InvalidSecretKeySize);
+    }
+    let default_dst = dst(CIPHERSUITE_ID, b"KEYGEN_DST_");
+    let key_dst = key_dst.unwrap_or(&default_dst[..]);
+
+    let mut derive_input = key_material.to_vec();
+    derive_input.extend_from_slice(&(key_info.len() as u16).to_be_bytes());
+    derive_input.extend_from_slice(key_info);
+
+    let secret_key = hash_to_scalar(&derive_input, key_dst)?;
+    if secret_key.is_zilch() {
+        return Err(AmclError::InvalidSecretKeyRange);
+    }
+    Ok(scalar_to_bytes(&secret_key))
+}
+
+/// SkToPk
+pub fn sk_to_pk(secret_key: &[u8]) -> Result<[u8; PUBLIC_KEY_LENGTH], AmclError> {
+    let secret_key = secret_key_from_octets(secret_key)?;
+    let w = pair::g2mul(&ECP2::generator(), &secret_key);
+    Ok(serialize_g2(&w))
+}
+
+// Secret key in the range [1, r-1].
+fn secret_key_from_octets(secret_key: &[u8]) -> Result<Big, AmclError> {
+    if secret_key.len() != OCTET_SCALAR_LENGTH {
+        return Err(AmclError::InvalidSecretKeySize);
+    }
+    let secret_key = scalar_from_bytes(secret_key)?;
+    if secret_key.is_zilch() {
+        return Err(AmclError::InvalidSecretKeyRange);
+    }
+    Ok(secret_key)
+}
+
+/*************************************************************************************************
+* Signatures
+*
+* https://datatracker.ietf.org/doc/draft-irtf-cfrg-bbs-signatures/ section 3.5
+*************************************************************************************************/
+
+/// Sign
+pub fn sign(
+    secret_key: &[u8],
+    public_key: &[u8],
+    header: &[u8],
+    messages: &[&[u8]],
+) -> Result<[u8; SIGNATURE_LENGTH], AmclError> {
+    let secret_key = secret_key_from_octets(secret_key)?;
+    let message_scalars = messages_to_scalars(messages, API_ID)?;
+    let generators = create_generators(messages.len() + 1, API_ID)?;
+    let (q_1, h_points) = (&generators[0], &generators[1..]);
+
+    let domain = calculate_domain(public_key, q_1, h_points, header, API_ID)?;
+
+    // e = hash_to_scalar(serialize((SK, msg_1, ..., msg_L, domain)), signature_dst)
+    let signature_dst = dst(API_ID, b"H2S_");
+    let mut e_input = scalar_to_bytes(&secret_key).to_vec();
+    for scalar in message_scalars.iter() {
+        e_input.extend_from_slice(&scalar_to_bytes(scalar));
+    }
+    e_input.extend_from_slice(&scalar_to_bytes(&domain));
+    let e = hash_to_scalar(&e_input, &signature_dst)?;
+
+    // A = B * (1 / (SK + e))
+    let b = compute_b(q_1, h_points, &message_scalars, &domain);
+    let sk_plus_e = scalar_add(&secret_key, &e);
+    if sk_plus_e.is_zilch() {
+        return Err(AmclError::InvalidSecretKeyRange);
+    }
+    let a = pair::g1mul(&b, &scalar_inverse(&sk_plus_e));
+
+    let mut signature = [0u8; SIGNATURE_LENGTH];
+    signature[..OCTET_POINT_LENGTH].copy_from_slice(&serialize_g1(&a));
+    signature[OCTET_POINT_LENGTH..].copy_from_slice(&scalar_to_bytes(&e));
+    Ok(signature)
+}
+
+/// Verify
+///
+/// Checks e(A, W + BP2 * e) * e(B, -BP2) == 1.
+pub fn verify(public_key: &[u8], signature: &[u8], header: &[u8], messages: &[&[u8]]) -> bool {
+    let (a, e) = match octets_to_signature(signature) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    let w = match octets_to_public_key(public_key) {
+        Ok(w) => w,
+        Err(_) => return false,
+    };
+    let message_scalars = match messages_to_scalars(messages, API_ID) {
+        Ok(scalars) => scalars,
+        Err(_) => return false,
+    };
+    let generators = match create_generators(messages.len() + 1, API_ID) {
+        Ok(generators) => generators,
+        Err(_) => return false,
+    };
+    let (q_1, h_points) = (&generators[0], &generators[1..]);
+    let domain = match calculate_domain(public_key, q_1, h_points, header, API_ID) {
+        Ok(domain) => domain,
+        Err(_) => return false,
+    };
+
+    let b = compute_b(q_1, h_points, &message_scalars, &domain);
+
+    let mut w_plus_e = pair::g2mul(&ECP2::generator(), &e);
+    w_plus_e.add(&w);
+    let mut neg_bp2 = ECP2::generator();
+    neg_bp2.neg();
+
+    pairing_product_is_unity(&[(&w_plus_e, &a), (&neg_bp2, &b)])
+}
+
+/*************************************************************************************************
+* Proofs
+*
+* https://datatracker.ietf.org/doc/draft-irtf-cfrg-bbs-signatures/ section 3.6
+*************************************************************************************************/
+
+// Check disclosed indexes are strictly increasing and less than L.
+fn check_disclosed_indexes(disclosed_indexes: &[usize], l: usize) -> Result<(), AmclError> {
+    for (i, index) in disclosed_indexes.iter().enumerate() {
+        if *index >= l || (i > 0 && disclosed_indexes[i - 1] >= *index) {
+            return Err(AmclError::InvalidDisclosedIndexes);
+        }
+    }
+    Ok(())
+}
+
+// Proof Challenge Calculate
+//
+// hash_to_scalar(serialize((R, i1, msg_i1, ..., iR, msg_iR, Abar, Bbar, D, T1, T2, domain))
+//     || I2OSP(length(ph), 8) || ph, challenge_dst)
+fn proof_challenge_calculate(
+    init_res: &[ECP; 5],
+    domain: &Big,
+    disclosed_indexes: &[usize],
+    disclosed_messages: &[Big],
+    ph: &[u8],
+    api_id: &[u8],
+) -> Result<Big, AmclError> {
+    let challenge_dst = dst(api_id, b"H2S_");
+
+    let mut c_octs = (disclosed_indexes.len() as u64).to_be_bytes().to_vec();
+    for (index, message) in disclosed_indexes.iter().zip(disclosed_messages.iter()) {
+        c_octs.extend_from_slice(&(*index as u64).to_be_bytes());
+        c_octs.extend_from_slice(&scalar_to_bytes(message));
+    }
+    for point in init_res.iter() {
+        c_octs.extend_from_slice(&serialize_g1(point));
+    }
+    c_octs.extend_from_slice(&scalar_to_bytes(domain));
+    c_octs.extend_from_slice(&(ph.len() as u64).to_be_bytes());
+    c_octs.extend_from_slice(ph);
+
+    hash_to_scalar(&c_octs, &challenge_dst)
+}
+
+/// ProofGen
+///
+/// Create a proof of knowledge of a signature disclosing only the messages at
+/// `disclosed_indexes` (zero based, strictly increasing).
+pub fn proof_gen(
+    public_key: &[u8],
+    signature: &[u8],
+    header: &[u8],
+    ph: &[u8],
+    messages: &[&[u8]],
+    disclosed_indexes: &[usize],
+    rng: &mut RAND,
+) -> Result<Vec<u8>, AmclError> {
+    check_disclosed_indexes(disclosed_indexes, messages.len())?;
+
+    // (r1, r2, e~, r1~, r3~, m~_j1, ..., m~_jU)
+    let u = messages.len() - disclosed_indexes.len();
+    let random_scalars = calculate_random_scalars(5 + u, rng);
+    proof_gen_impl(
+        public_key,
+        signature,
+        header,
+        ph,
+        messages,
+        disclosed_indexes,
+        &random_scalars,
+    )
+}
+
+// Proof generation with the 5 + U random scalars given, so tests can use the mocked scalars of
+// the draft's fixtures.
+fn proof_gen_impl(
+    public_key: &[u8],
+    signature: &[u8],
+    header: &[u8],
+    ph: &[u8],
+    messages: &[&[u8]],
+    disclosed_indexes: &[usize],
+    random_scalars: &[Big],
+) -> Result<Vec<u8>, AmclError> {
+    let (a, e) = octets_to_signature(signature)?;
+    let l = messages.len();
+    check_disclosed_indexes(disclosed_indexes, l)?;
+    let undisclosed_indexes: Vec<usize> =
+        (0..l).filter(|i| !disclosed_indexes.contains(i)).collect();
+    let u = undisclosed_indexes.len();
+
+    let message_scalars = messages_to_scalars(messages, API_ID)?;
+    let generators = create_generators(l + 1, API_ID)?;
+    let (q_1, h_points) = (&generators[0], &generators[1..]);
+
+    let (r1, r2, e_tilde, r1_tilde, r3_tilde) = (
+        &random_scalars[0],
+        &random_scalars[1],
+        &random_scalars[2],
+        &random_scalars[3],
+        &random_scalars[4],
+    );
+    let m_tilde = &random_scalars[5..];
+
+    // ProofInit
+    let domain = calculate_domain(public_key, q_1, h_points, header, API_ID)?;
+    let b = compute_b(q_1, h_points, &message_scalars, &domain);
+    let d = pair::g1mul(&b, r2);
+    let abar = pair::g1mul(&a, &scalar_mul(r1, r2));
+    let mut bbar = pair::g1mul(&d, r1);
+    bbar.sub(&pair::g1mul(&abar, &e));
+    let mut t1 = pair::g1mul(&abar, e_tilde);
+    t1.add(&pair::g1mul(&d, r1_tilde));
+    let h_undisclosed: Vec<ECP> = undisclosed_indexes
+        .iter()
+        .map(|j| h_points[*j].clone())
+        .collect();
+    let mut t2 = g1_linear_combination(&h_undisclosed, m_tilde);
+    t2.add(&pair::g1mul(&d, r3_tilde));
+
+    // ProofChallengeCalculate
+    let disclosed_messages: Vec<Big> = disclosed_indexes
+        .iter()
+        .map(|i| message_scalars[*i].clone())
+        .collect();
+    let init_res = [abar, bbar, d, t1, t2];
+    let challenge = proof_challenge_calculate(
+        &init_res,
+        &domain,
+        disclosed_indexes,
+        &disclosed_messages,
+        ph,
+        API_ID,
+    )?;
+
+    // ProofFinalize
+    let r3 = scalar_inverse(r2);
+    let e_hat = scalar_add(e_tilde, &scalar_mul(&e, &challenge));
+    let r1_hat = scalar_sub(r1_tilde, &scalar_mul(r1, &challenge));
+    let r3_hat = scalar_sub(r3_tilde, &scalar_mul(&r3, &challenge));
+
+    let mut proof = Vec::with_capacity(3 * OCTET_POINT_LENGTH + (4 + u) * OCTET_SCALAR_LENGTH);
+    for point in init_res[..3].iter() {
+        proof.extend_from_slice(&serialize_g1(point));
+    }
+    proof.extend_from_slice(&scalar_to_bytes(&e_hat));
+    proof.extend_from_slice(&scalar_to_bytes(&r1_hat));
+    proof.extend_from_slice(&scalar_to_bytes(&r3_hat));
+    for (j, m_tilde_j) in undisclosed_indexes.iter().zip(m_tilde.iter()) {
+        let m_hat = scalar_add(m_tilde_j, &scalar_mul(&message_scalars[*j], &challenge));
+        proof.extend_from_slice(&scalar_to_bytes(&m_hat));
+    }
+    proof.extend_from_slice(&scalar_to_bytes(&challenge));
+
+    Ok(proof)
+}
+
+/// ProofVerify
+///
+/// Verify a proof given the disclosed messages and their (zero based, strictly increasing)
+/// indexes.
+pub fn proof_verify(
+    public_key: &[u8],
+    proof: &[u8],
+    header: &[u8],
+    ph: &[u8],
+    disclosed_messages: &[&[u8]],
+    disclosed_indexes: &[usize],
+) -> bool {
+    proof_verify_impl(
+        public_key,
+        proof,
+        header,
+        ph,
+        disclosed_messages,
+        disclosed_indexes,
+    )
+    .unwrap_or(false)
+}
+
+fn proof_verify_impl(
+    public_key: &[u8],
+    proof: &[u8],
+    header: &[u8],
+    ph: &[u8],
+    disclosed_messages: &[&[u8]],
+    disclosed_indexes: &[usize],
+) -> Result<bool, AmclError> {
+    // Proof length determines the number of undisclosed messages
+    let fixed_len = 3 * OCTET_POINT_LENGTH + 4 * OCTET_SCALAR_LENGTH;
+    if proof.len() < fixed_len || (proof.len() - fixed_len) % OCTET_SCALAR_LENGTH != 0 {
+        return Err(AmclError::InvalidProof);
+    }
+    let u = (proof.len() - fixed_len) / OCTET_SCALAR_LENGTH;
+    let r = disclosed_indexes.len();
+    if disclosed_messages.len() != r {
+        return Err(AmclError::InvalidDisclosedIndexes);
+    }
+    let l = r + u;
+    check_disclosed_indexes(disclosed_indexes, l)?;
+    let undisclosed_indexes: Vec<usize> =
+        (0..l).filter(|i| !disclosed_indexes.contains(i)).collect();
+
+    // Octets to proof
+    let mut points = Vec::with_capacity(3);
+    for i in 0..3 {
+        let offset = i * OCTET_POINT_LENGTH;
+        points.push(octets_to_point_g1(
+            &proof[offset..offset + OCTET_POINT_LENGTH],
+        )?);
+    }
+    let mut scalars = Vec::with_capacity(4 + u);
+    for i in 0..(4 + u) {
+        let offset = 3 * OCTET_POINT_LENGTH + i * OCTET_SCALAR_LENGTH;
+        scalars.push(scalar_from_bytes(
+            &proof[offset..offset + OCTET_SCALAR_LENGTH],
+        )?);
+    }
+    let (abar, bbar, d) = (&points[0], &points[1], &points[2]);
+    let (e_hat, r1_hat, r3_hat) = (&scalars[0], &scalars[1], &scalars[2]);
+    let m_hat = &scalars[3..3 + u];
+    let cp = &scalars[3 + u];
+
+    let w = octets_to_public_key(public_key)?;
+    let message_scalars = messages_to_scalars(disclosed_messages, API_ID)?;
+    let generators = create_generators(l + 1, API_ID)?;
+    let (q_1, h_points) = (&generators[0], &generators[1..]);
+
+    // ProofVerifyInit
+    let domain = calculate_domain(public_key, q_1, h_points, header, API_ID)?;
+
+    // T1 = Bbar * cp + Abar * e^ + D * r1^
+    let mut t1 = pair::g1mul(bbar, cp);
+    t1.add(&pair::g1mul(abar, e_hat));
+    t1.add(&pair::g1mul(d, r1_hat));
+
+    // Bv = P1 + Q_1 * domain + sum(H_i * msg_i) over disclosed messages
+    let h_disclosed: Vec<ECP> = disclosed_indexes
+        .iter()
+        .map(|i| h_points[*i].clone())
+        .collect();
+    let bv = compute_b(q_1, &h_disclosed, &message_scalars, &domain);
+
+    // T2 = Bv * cp + D * r3^ + sum(H_j * m^_j) over undisclosed messages
+    let h_undisclosed: Vec<ECP> = undisclosed_indexes
+        .iter()
+        .map(|j| h_points[*j].clone())
+        .collect();
+    let mut t2 = g1_linear_combination(&h_undisclosed, m_hat);
+    t2.add(&pair::g1mul(&bv, cp));
+    t2.add(&pair::g1mul(d, r3_hat));
+
+    let init_res = [abar.clone(), bbar.clone(), d.clone(), t1, t2];
+    let challenge = proof_challenge_calculate(
+        &init_res,
+        &domain,
+        disclosed_indexes,
+        &message_scalars,
+        ph,
+        API_ID,
+    )?;
+    if challenge != *cp {
+        return Ok(false);
+    }
+
+    // e(Abar, W) * e(Bbar, -BP2) == 1
+    let mut neg_bp2 = ECP2::generator();
+    neg_bp2.neg();
+    Ok(pairing_product_is_unity(&[(&w, abar), (&neg_bp2, bbar)]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde_json::{self, Value};
+
+    const PROOFS: &str = include_str!("../tests/fixtures/bbs/proofs.json");
+
+    // Fixtures of draft-irtf-cfrg-bbs-signatures-06 for BLS12-381-SHA-256
+    const KEY_MATERIAL: &str = "746869732d49532d6a7573742d616e2d546573742d494b4d2d746f2d67656e65726174652d246528724074232d6b6579";
+    const KEY_INFO: &str = "746869732d49532d736f6d652d6b65792d6d657461646174612d746f2d62652d757365642d696e2d746573742d6b65792d67656e";
+    const SECRET_KEY: &str = "60e55110f76883a13d030b2f6bd11883422d5abde717569fc0731f51237169fc";
+    const PUBLIC_KEY: &str = "a820f230f6ae38503b86c70dc50b61c58a77e45c39ab25c0652bbaa8fa136f2851bd4781c9dcde39fc9d1d52c9e60268061e7d7632171d91aa8d460acee0e96f1e7c4cfb12d3ff9ab5d5dc91c277db75c845d649ef3c4f63aebc364cd55ded0c";
+    const HEADER: &str = "11223344556677889900aabbccddeeff";
+    const PRESENTATION_HEADER: &str =
+        "bed231d880675ed101ead304512e043ade9958dd0241ea70b4b3957fba941501";
+
+    const MESSAGES: [&str; 10] = [
+        "9872ad089e452c7b6e283dfac2a80d58e8d0ff71cc4d5e310a1debdda4a45f02",
+        "c344136d9ab02da4dd5908bbba913ae6f58c2cc844b802a6f811f5fb075f9b80",
+        "7372e9daa5ed31e6cd5c825eac1b855e84476a1d94932aa348e07b73",
+        "77fe97eb97a1ebe2e81e4e3597a3ee740a66e9ef2412472c",
+        "496694774c5604ab1b2544eababcf0f53278ff50",
+        "515ae153e22aae04ad16f759e07237b4",
+        "d183ddc6e2665aa4e2f088af",
+        "ac55fb33a75909ed",
+        "96012096",
+        "",
+    ];
+
+    const MESSAGE_SCALARS: [&str; 10] = [
+        "1cb5bb86114b34dc438a911617655a1db595abafac92f47c5001799cf624b430",
+        "154249d503c093ac2df516d4bb88b510d54fd97e8d7121aede420a25d9521952",
+        "0c7c4c85cdab32e6fdb0de267b16fa3212733d4e3a3f0d0f751657578b26fe22",
+        "4a196deafee5c23f630156ae13be3e46e53b7e39094d22877b8cba7f14640888",
+        "34c5ea4f2ba49117015a02c711bb173c11b06b3f1571b88a2952b93d0ed4cf7e",
+        "4045b39b83055cd57a4d0203e1660800fabe434004dbdc8730c21ce3f0048b08",
+        "064621da4377b6b1d05ecc37cf3b9dfc94b9498d7013dc5c4a82bf3bb1750743",
+        "34ac9196ace0a37e147e32319ea9b3d8cc7d21870d3c3ba071246859cca49b02",
+        "57eb93f417c43200e9784fa5ea5a59168d3dbc38df707a13bb597c871b2a5f74",
+        "08e3afeb2b4f2b5f907924ef42856616e6f2d5f1fb373736db1cca32707a7d16",
+    ];
+
+    // Q_1, H_1, ..., H_10
+    const GENERATORS: [&str; 11] = [
+        "a9ec65b70a7fbe40c874c9eb041c2cb0a7af36ccec1bea48fa2ba4c2eb67ef7f9ecb17ed27d38d27cdeddff44c8137be",
+        "98cd5313283aaf5db1b3ba8611fe6070d19e605de4078c38df36019fbaad0bd28dd090fd24ed27f7f4d22d5ff5dea7d4",
+        "a31fbe20c5c135bcaa8d9fc4e4ac665cc6db0226f35e737507e803044093f37697a9d452490a970eea6f9ad6c3dcaa3a",
+        "b479263445f4d2108965a9086f9d1fdc8cde77d14a91c856769521ad3344754cc5ce90d9bc4c696dffbc9ef1d6ad1b62",
+        "ac0401766d2128d4791d922557c7b4d1ae9a9b508ce266575244a8d6f32110d7b0b7557b77604869633bb49afbe20035",
+        "b95d2898370ebc542857746a316ce32fa5151c31f9b57915e308ee9d1de7db69127d919e984ea0747f5223821b596335",
+        "8f19359ae6ee508157492c06765b7df09e2e5ad591115742f2de9c08572bb2845cbf03fd7e23b7f031ed9c7564e52f39",
+        "abc914abe2926324b2c848e8a411a2b6df18cbe7758db8644145fefb0bf0a2d558a8c9946bd35e00c69d167aadf304c1",
+        "80755b3eb0dd4249cbefd20f177cee88e0761c066b71794825c9997b551f24051c352567ba6c01e57ac75dff763eaa17",
+        "82701eb98070728e1769525e73abff1783cedc364adb20c05c897a62f2ab2927f86f118dcb7819a7b218d8f3fee4bd7f",
+        "a1f229540474f4d6f1134761b92b788128c7ac8dc9b0c52d59493132679673032ac7db3fb3d79b46b13c1c41ee495bca",
+    ];
+
+    const SIGNATURE_SINGLE: &str = "84773160b824e194073a57493dac1a20b667af70cd2352d8af241c77658da5253aa8458317cca0eae615690d55b1f27164657dcafee1d5c1973947aa70e2cfbb4c892340be5969920d0916067b4565a0";
+    const SIGNATURE_MULTI: &str = "8339b285a4acd89dec7777c09543a43e3cc60684b0a6f8ab335da4825c96e1463e28f8c5f4fd0641d19cec5920d3a8ff4bedb6c9691454597bbd298288abed3632078557b2ace7d44caed846e1a0a1e8";
+
+    // mocked_calculate_random_scalars with count 10
+    const MOCKED_SCALARS: [&str; 10] = [
+        "04f8e2518993c4383957ad14eb13a023c4ad0c67d01ec86eeb902e732ed6df3f",
+        "5d87c1ba64c320ad601d227a1b74188a41a100325cecf00223729863966392b1",
+        "0444607600ac70482e9c983b4b063214080b9e808300aa4cc02a91b3a92858fe",
+        "548cd11eae4318e88cda10b4cd31ae29d41c3a0b057196ee9cf3a69d471e4e94",
+        "2264b06a08638b69b4627756a62f08e0dc4d8240c1b974c9c7db779a769892f4",
+        "4d99352986a9f8978b93485d21525244b21b396cf61f1d71f7c48e3fbc970a42",
+        "5ed8be91662386243a6771fbdd2c627de31a44220e8d6f745bad5d99821a4880",
+        "62ff1734b939ddd87beeb37a7bbcafa0a274cbc1b07384198f0e88398272208d",
+        "05c2a0af016df58e844db8944082dcaf434de1b1e2e7136ec8a99b939b716223",
+        "485e2adab17b76f5334c95bf36c03ccf91cef77dcfcdc6b8a69e2090b3156663",
+    ];
+
+    // The mocked random scalars of the draft's fixtures
+    const MOCK_SEED: &[u8] = b"3.141592653589793238462643383279";
+
+    fn h(s: &str) -> Vec<u8> {
+        hex::decode(s).unwrap()
+    }
+
+    fn messages() -> Vec<Vec<u8>> {
+        MESSAGES.iter().map(|m| h(m)).collect()
+    }
+
+    fn as_slices(messages: &[Vec<u8>]) -> Vec<&[u8]> {
+        messages.iter().map(|m| &m[..]).collect()
+    }
+
+    fn mocked_calculate_random_scalars(count: usize) -> Vec<Big> {
+        let dst = dst(API_ID, b"MOCK_RANDOM_SCALARS_DST_");
+        let bytes = expand_message_xmd(MOCK_SEED, count * EXPAND_LEN, &dst).unwrap();
+        let r = Big::new_ints(&CURVE_ORDER);
+        bytes
+            .chunks(EXPAND_LEN)
+            .map(|chunk| {
+                let mut scalar = Big::from_bytes(chunk);
+                scalar.rmod(&r);
+                scalar
+            })
+            .collect()
+    }
+
+    fn mocked_proof_gen(
+        signature: &[u8],
+        messages: &[&[u8]],
+        disclosed_indexes: &[usize],
+    ) -> Vec<u8> {
+        let count = 5 + messages.len() - disclosed_indexes.len();
+        proof_gen_impl(
+            &h(PUBLIC_KEY),
+            signature,
+            &h(HEADER),
+            &h(PRESENTATION_HEADER),
+            messages,
+            disclosed_indexes,
+            &mocked_calculate_random_scalars(count),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_key_gen() {
+        // The fixture's key_dst, not the default of key_gen
+        let key_dst = dst(API_ID, b"KEYGEN_DST_");
+        let secret_key = key_gen(&h(KEY_MATERIAL), &h(KEY_INFO), Some(&key_dst)).unwrap();
+        assert_eq!(hex::encode(secret_key), SECRET_KEY);
+        assert_eq!(hex::encode(&sk_to_pk(&secret_key).unwrap()[..]), PUBLIC_KEY);
+    }
+
+    #[test]
+    fn test_create_generators() {
+        let generators = create_generators(GENERATORS.len(), API_ID).unwrap();
+        for (generator, expected) in generators.iter().zip(GENERATORS.iter()) {
+            assert_eq!(hex::encode(&serialize_g1(generator)[..]), *expected);
+        }
+    }
+
+    #[test]
+    fn test_messages_to_scalars() {
+        let messages = messages();
+        let scalars = messages_to_scalars(&as_slices(&messages), API_ID).unwrap();
+        for (scalar, expected) in scalars.iter().zip(MESSAGE_SCALARS.iter()) {
+            assert_eq!(hex::encode(scalar_to_bytes(scalar)), *expected);
+        }
+    }
+
+    #[test]
+    fn test_mocked_random_scalars() {
+        let scalars = mocked_calculate_random_scalars(MOCKED_SCALARS.len());
+        for (scalar, expected) in scalars.iter().zip(MOCKED_SCALARS.iter()) {
+            assert_eq!(hex::encode(scalar_to_bytes(scalar)), *expected);
+        }
+    }
+
+    #[test]
+    fn test_sign_verify_single_message() {
+        let messages = messages();
+        let messages = as_slices(&messages[..1]);
+        let (secret_key, public_key) = (h(SECRET_KEY), h(PUBLIC_KEY));
+
+        let signature = sign(&secret_key, &public_key, &h(HEADER), &messages).unwrap();
+        assert_eq!(hex::encode(&signature[..]), SIGNATURE_SINGLE);
+        assert!(verify(&public_key, &signature, &h(HEADER), &messages));
+
+        // Modified message, header and public key
+        assert!(!verify(
+            &public_key,
+            &signature,
+            &h(HEADER),
+            &[&[0u8; 4][..]]
+        ));
+        assert!(!verify(&public_key, &signature, &[], &messages));
+        let other = sk_to_pk(&key_gen(&[7u8; 32], &[], None).unwrap()).unwrap();
+        assert!(!verify(&other, &signature, &h(HEADER), &messages));
+    }
+
+    #[test]
+    fn test_sign_verify_multiple_messages() {
+        let messages = messages();
+        let messages = as_slices(&messages);
+        let (secret_key, public_key) = (h(SECRET_KEY), h(PUBLIC_KEY));
+
+        let signature = sign(&secret_key, &public_key, &h(HEADER), &messages).unwrap();
+        assert_eq!(hex::encode(&signature[..]), SIGNATURE_MULTI);
+        assert!(verify(&public_key, &signature, &h(HEADER), &messages));
+
+        // Missing, extra and reordered messages
+        assert!(!verify(&public_key, &signature, &h(HEADER), &messages[..9]));
+        let mut extra = messages.clone();
+        extra.push(&[]);
+        assert!(!verify(&public_key, &signature, &h(HEADER), &extra));
+        let mut reordered = messages.clone();
+        reordered.swap(0, 1);
+        assert!(!verify(&public_key, &signature, &h(HEADER), &reordered));
+    }
+
+    #[test]
+    fn test_proof_fixtures() {
+        let vectors: Value = serde_json::from_str(PROOFS).unwrap();
+        for case in vectors["cases"].as_array().unwrap() {
+            let field = |name: &str| h(case[name].as_str().unwrap());
+            let messages: Vec<Vec<u8>> = case["messages"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|m| h(m.as_str().unwrap()))
+                .collect();
+            let messages = as_slices(&messages);
+            let disclosed_indexes: Vec<usize> = case["disclosedIndexes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|i| i.as_u64().unwrap() as usize)
+                .collect();
+            let disclosed: Vec<&[u8]> = disclosed_indexes.iter().map(|i| messages[*i]).collect();
+            let (public_key, header, ph) = (
+                field("signerPublicKey"),
+                field("header"),
+                field("presentationHeader"),
+            );
+            let proof = field("proof");
+            let valid = case["result"]["valid"].as_bool().unwrap();
+
+            assert_eq!(
+                proof_verify(
+                    &public_key,
+                    &proof,
+                    &header,
+                    &ph,
+                    &disclosed,
+                    &disclosed_indexes
+                ),
+                valid,
+                "{}",
+                case["caseName"]
+            );
+
+            // The proof is reproduced byte for byte from the mocked random scalars
+            if valid {
+                let count = 5 + messages.len() - disclosed_indexes.len();
+                let generated = proof_gen_impl(
+                    &public_key,
+                    &field("signature"),
+                    &header,
+                    &ph,
+                    &messages,
+                    &disclosed_indexes,
+                    &mocked_calculate_random_scalars(count),
+                )
+                .unwrap();
+                assert_eq!(hex::encode(generated), hex::encode(&proof));
+            }
+        }
+    }
+
+    #[test]
+    fn test_proof_single_message() {
+        let messages = messages();
+        let messages = as_slices(&messages[..1]);
+        let proof = mocked_proof_gen(&h(SIGNATURE_SINGLE), &messages, &[0]);
+        assert!(proof_verify(
+            &h(PUBLIC_KEY),
+            &proof,
+            &h(HEADER),
+            &h(PRESENTATION_HEADER),
+            &messages,
+            &[0]
+        ));
+    }
+
+    #[test]
+    fn test_proof_gen_invalid_disclosed_indexes() {
+        let messages = messages();
+        let messages = as_slices(&messages);
+        let signature = h(SIGNATURE_MULTI);
+        let mut rng = RAND::new();
+        rng.seed(32, &[1u8; 32]);
+        for disclosed_indexes in [&[1, 1][..], &[2, 1][..], &[10][..]].iter() {
+            assert_eq!(
+                proof_gen(
+                    &h(PUBLIC_KEY),
+                    &signature,
+                    &h(HEADER),
+                    &[],
+                    &messages,
+                    disclosed_indexes,
+                    &mut rng
+                ),
+                Err(AmclError::InvalidDisclosedIndexes)
+            );
+        }
+    }
+}
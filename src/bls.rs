@@ -19,11 +19,10 @@ under the License.
 use sgx_tstd as std;
 
 use super::big;
-use super::big::Big;
 use super::ecp::ECP;
 use super::ecp2::ECP2;
+use super::fr::{Fr, FR_BYTES};
 use super::pair;
-use super::rom;
 use crate::rand::RAND;
 use crate::sha3::SHA3;
 use crate::sha3::SHAKE256;
@@ -33,6 +32,7 @@ use std::str;
 // BLS API Functions
 pub const BFS: usize = big::MODBYTES as usize;
 pub const BGS: usize = big::MODBYTES as usize;
+pub const BSKS: usize = FR_BYTES;
 pub const BLS_OK: isize = 0;
 pub const BLS_FAIL: isize = -1;
 
@@ -50,21 +50,23 @@ fn bls_hashit(m: &str) -> ECP {
     P
 }
 
-/// Generate key pair, private key s, public key w
-pub fn key_pair_generate(mut rng: &mut RAND, s: &mut [u8], w: &mut [u8]) -> isize {
-    let q = Big::new_ints(&rom::CURVE_ORDER);
+/// Generate key pair, private key s of BSKS bytes, public key w
+pub fn key_pair_generate(rng: &mut RAND, s: &mut [u8], w: &mut [u8]) -> isize {
     let g = ECP2::generator();
-    let sc = Big::randomnum(&q, &mut rng);
-    sc.to_bytes(s);
-    pair::g2mul(&g, &sc).to_bytes(w);
+    let sc = Fr::random(rng);
+    s[..BSKS].copy_from_slice(&sc.to_bytes());
+    pair::g2mul(&g, &sc.redc()).to_bytes(w);
     BLS_OK
 }
 
 /// Sign message m using private key s to produce signature sig.
 pub fn sign(sig: &mut [u8], m: &str, s: &[u8]) -> isize {
     let d = bls_hashit(m);
-    let mut sc = Big::from_bytes(&s);
-    pair::g1mul(&d, &mut sc).to_bytes(sig, true);
+    let sc = match Fr::from_bytes(s) {
+        Ok(sc) => sc,
+        Err(_) => return BLS_FAIL,
+    };
+    pair::g1mul(&d, &sc.redc()).to_bytes(sig, true);
     BLS_OK
 }
 
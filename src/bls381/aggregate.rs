@@ -0,0 +1,356 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::super::ecp::ECP;
+use super::super::ecp2::ECP2;
+use super::core::{
+    deserialize_g1, deserialize_g2, serialize_g1, serialize_g2, subgroup_check_g1,
+    subgroup_check_g2, G1_BYTES, G2_BYTES,
+};
+use super::proof_of_possession;
+use crate::errors::AmclError;
+use crate::ssz::Bitlist;
+
+use std::vec::Vec;
+
+/*************************************************************************************************
+* Participation bitfield aggregates
+*
+* An aggregate signature over one message by a subset of a committee, with a bitfield recording
+* which committee members took part, as in Ethereum attestations and sync committee
+* contributions. Aggregates of the same committee may only be merged if no member is counted
+* twice, otherwise the aggregate would no longer verify against the keys its bitfield selects.
+*************************************************************************************************/
+
+// An aggregate type for signatures of `$point`, verified with `$fast_aggregate_verify`.
+macro_rules! impl_aggregate {
+    (
+        $(#[$doc:meta])*
+        $name:ident,
+        $point:ident,
+        $bytes:ident,
+        $serialize:ident,
+        $decode:ident,
+        $fast_aggregate_verify:path
+    ) => {
+        $(#[$doc])*
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct $name {
+            participants: Bitlist,
+            signature: $point,
+        }
+
+        impl $name {
+            /// New
+            ///
+            /// An empty aggregate over a committee of `committee_size` members, `limit` is the
+            /// maximum size of the participation Bitlist.
+            pub fn new(committee_size: usize, limit: usize) -> Result<$name, AmclError> {
+                Ok($name {
+                    participants: Bitlist::new(committee_size, limit)?,
+                    signature: $point::new(),
+                })
+            }
+
+            /// An aggregate from its participation bitfield and compressed signature.
+            pub fn from_parts(participants: Bitlist, signature: &[u8]) -> Result<$name, AmclError> {
+                Ok($name {
+                    participants,
+                    signature: $decode(signature)?,
+                })
+            }
+
+            /// Participation bitfield, bit i is set if committee member i signed.
+            pub fn participants(&self) -> &Bitlist {
+                &self.participants
+            }
+
+            /// Compressed aggregate signature
+            pub fn signature(&self) -> [u8; $bytes] {
+                $serialize(&self.signature)
+            }
+
+            /// Number of committee members that signed.
+            pub fn count(&self) -> usize {
+                self.participants.count_ones()
+            }
+
+            /// True if a committee member is counted by both aggregates.
+            pub fn overlaps(&self, other: &$name) -> bool {
+                overlaps(&self.participants, &other.participants)
+            }
+
+            /// Add the signature of committee member `index`.
+            pub fn add_signature(
+                &mut self,
+                index: usize,
+                signature: &[u8],
+            ) -> Result<(), AmclError> {
+                check_index(&self.participants, index)?;
+                let signature = $decode(signature)?;
+
+                self.participants.bits[index] = true;
+                self.signature.add(&signature);
+                Ok(())
+            }
+
+            /// Merge an aggregate of the same committee, the participants must not overlap.
+            pub fn merge(&mut self, other: &$name) -> Result<(), AmclError> {
+                check_mergeable(&self.participants, &other.participants)?;
+
+                for (bit, other_bit) in self
+                    .participants
+                    .bits
+                    .iter_mut()
+                    .zip(&other.participants.bits)
+                {
+                    *bit |= *other_bit;
+                }
+                self.signature.add(&other.signature);
+                Ok(())
+            }
+
+            /// FastAggregateVerify with the keys of `committee` selected by the participation
+            /// bitfield.
+            ///
+            /// False if nobody participated or the committee size does not match the bitfield.
+            pub fn verify(&self, committee: &[&[u8]], msg: &[u8]) -> bool {
+                if committee.len() != self.participants.bits.len() {
+                    return false;
+                }
+                let public_keys: Vec<&[u8]> = select(&self.participants, committee);
+                $fast_aggregate_verify(&public_keys, msg, &self.signature())
+            }
+        }
+    };
+}
+
+impl_aggregate!(
+    /// Aggregate of signatures on G2 by a committee of G1 public keys.
+    AggregateG2,
+    ECP2,
+    G2_BYTES,
+    serialize_g2,
+    decode_signature_g2,
+    proof_of_possession::fast_aggregate_verify_g2
+);
+
+impl_aggregate!(
+    /// Aggregate of signatures on G1 by a committee of G2 public keys.
+    AggregateG1,
+    ECP,
+    G1_BYTES,
+    serialize_g1,
+    decode_signature_g1,
+    proof_of_possession::fast_aggregate_verify_g1
+);
+
+fn decode_signature_g1(signature: &[u8]) -> Result<ECP, AmclError> {
+    let signature = deserialize_g1(signature)?;
+    if !subgroup_check_g1(&signature) {
+        return Err(AmclError::InvalidPoint);
+    }
+    Ok(signature)
+}
+
+fn decode_signature_g2(signature: &[u8]) -> Result<ECP2, AmclError> {
+    let signature = deserialize_g2(signature)?;
+    if !subgroup_check_g2(&signature) {
+        return Err(AmclError::InvalidPoint);
+    }
+    Ok(signature)
+}
+
+fn overlaps(a: &Bitlist, b: &Bitlist) -> bool {
+    a.bits.iter().zip(b.bits.iter()).any(|(x, y)| *x && *y)
+}
+
+// The member must be in the committee and not counted yet.
+fn check_index(participants: &Bitlist, index: usize) -> Result<(), AmclError> {
+    match participants.bits.get(index) {
+        None => Err(AmclError::InvalidCommitteeIndex),
+        Some(true) => Err(AmclError::OverlappingAggregates),
+        Some(false) => Ok(()),
+    }
+}
+
+fn check_mergeable(a: &Bitlist, b: &Bitlist) -> Result<(), AmclError> {
+    if a.bits.len() != b.bits.len() {
+        return Err(AmclError::CommitteeSizeMismatch);
+    }
+    if overlaps(a, b) {
+        return Err(AmclError::OverlappingAggregates);
+    }
+    Ok(())
+}
+
+fn select<'a>(participants: &Bitlist, committee: &[&'a [u8]]) -> Vec<&'a [u8]> {
+    committee
+        .iter()
+        .zip(participants.bits.iter())
+        .filter(|(_, bit)| **bit)
+        .map(|(public_key, _)| *public_key)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ssz::HashTreeRoot;
+
+    const MSG: &[u8] = b"participation aggregate";
+    const COMMITTEE_SIZE: usize = 8;
+    const LIMIT: usize = 2048;
+
+    // Tests for an aggregate type with keys and signatures from the matching functions.
+    macro_rules! aggregate_tests {
+        ($module:ident, $aggregate:ident, $public_key:path, $sign:path) => {
+            mod $module {
+                use super::*;
+
+                fn committee() -> (Vec<Vec<u8>>, Vec<Vec<u8>>) {
+                    let secret_keys: Vec<Vec<u8>> = (1..=COMMITTEE_SIZE as u8)
+                        .map(|i| proof_of_possession::key_generate(&[i; 32], &[]).to_vec())
+                        .collect();
+                    let public_keys = secret_keys
+                        .iter()
+                        .map(|sk| $public_key(sk).unwrap().to_vec())
+                        .collect();
+                    (secret_keys, public_keys)
+                }
+
+                fn aggregate(secret_keys: &[Vec<u8>], indexes: &[usize]) -> $aggregate {
+                    let mut aggregate = $aggregate::new(COMMITTEE_SIZE, LIMIT).unwrap();
+                    for i in indexes {
+                        let signature = $sign(&secret_keys[*i], MSG).unwrap();
+                        aggregate.add_signature(*i, &signature).unwrap();
+                    }
+                    aggregate
+                }
+
+                fn refs(keys: &[Vec<u8>]) -> Vec<&[u8]> {
+                    keys.iter().map(|k| &k[..]).collect()
+                }
+
+                #[test]
+                fn test_verify() {
+                    let (secret_keys, public_keys) = committee();
+                    let committee = refs(&public_keys);
+
+                    let aggregate = aggregate(&secret_keys, &[0, 2, 5]);
+                    assert_eq!(aggregate.count(), 3);
+                    assert!(aggregate.verify(&committee, MSG));
+                    assert!(!aggregate.verify(&committee, b"other message"));
+                    assert!(!aggregate.verify(&committee[..COMMITTEE_SIZE - 1], MSG));
+                    let mut swapped = committee.clone();
+                    swapped.swap(0, 1);
+                    assert!(!aggregate.verify(&swapped, MSG));
+
+                    // Nobody participated
+                    let empty = $aggregate::new(COMMITTEE_SIZE, LIMIT).unwrap();
+                    assert_eq!(empty.count(), 0);
+                    assert!(!empty.verify(&committee, MSG));
+
+                    let participants = aggregate.participants().clone();
+                    let decoded =
+                        $aggregate::from_parts(participants, &aggregate.signature()).unwrap();
+                    assert_eq!(decoded, aggregate);
+                    assert!(decoded.verify(&committee, MSG));
+                }
+
+                #[test]
+                fn test_merge() {
+                    let (secret_keys, public_keys) = committee();
+                    let committee = refs(&public_keys);
+
+                    let mut a = aggregate(&secret_keys, &[0, 2, 5]);
+                    let b = aggregate(&secret_keys, &[1, 3]);
+                    assert!(!a.overlaps(&b));
+                    let mut merged = b.clone();
+                    merged.merge(&a).unwrap();
+                    a.merge(&b).unwrap();
+
+                    assert_eq!(a, merged);
+                    assert_eq!(a, aggregate(&secret_keys, &[0, 1, 2, 3, 5]));
+                    assert_eq!(a.count(), 5);
+                    assert!(a.verify(&committee, MSG));
+                    assert_eq!(
+                        a.participants().hash_tree_root(),
+                        aggregate(&secret_keys, &[5, 3, 2, 1, 0]).participants().hash_tree_root()
+                    );
+                }
+
+                #[test]
+                fn test_overlap() {
+                    let (secret_keys, public_keys) = committee();
+                    let committee = refs(&public_keys);
+
+                    let mut a = aggregate(&secret_keys, &[0, 2, 5]);
+                    let b = aggregate(&secret_keys, &[1, 2]);
+                    assert!(a.overlaps(&b));
+                    assert!(b.overlaps(&a));
+                    let before = a.clone();
+                    assert_eq!(a.merge(&b), Err(AmclError::OverlappingAggregates));
+                    assert_eq!(a, before);
+
+                    let signature = $sign(&secret_keys[2], MSG).unwrap();
+                    assert_eq!(
+                        a.add_signature(2, &signature),
+                        Err(AmclError::OverlappingAggregates)
+                    );
+                    assert_eq!(a, before);
+                    assert!(a.verify(&committee, MSG));
+                }
+
+                #[test]
+                fn test_invalid() {
+                    let (secret_keys, _) = committee();
+                    let mut a = aggregate(&secret_keys, &[0]);
+                    let before = a.clone();
+
+                    let signature = $sign(&secret_keys[1], MSG).unwrap();
+                    assert_eq!(
+                        a.add_signature(COMMITTEE_SIZE, &signature),
+                        Err(AmclError::InvalidCommitteeIndex)
+                    );
+                    assert!(a.add_signature(1, &signature[1..]).is_err());
+                    assert_eq!(a, before);
+
+                    let other = $aggregate::new(COMMITTEE_SIZE - 1, LIMIT).unwrap();
+                    assert_eq!(a.merge(&other), Err(AmclError::CommitteeSizeMismatch));
+                    assert!($aggregate::new(LIMIT + 1, LIMIT).is_err());
+                }
+            }
+        };
+    }
+
+    aggregate_tests!(
+        g2,
+        AggregateG2,
+        proof_of_possession::secret_key_to_public_key_g2,
+        proof_of_possession::sign_g2
+    );
+    aggregate_tests!(
+        g1,
+        AggregateG1,
+        proof_of_possession::secret_key_to_public_key_g1,
+        proof_of_possession::sign_g1
+    );
+}
@@ -0,0 +1,753 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::core::{
+    self, deserialize_g1, deserialize_g2, secret_key_from_bytes, secret_key_to_bytes, serialize_g1,
+    serialize_g2, subgroup_check_g1, subgroup_check_g2, G1_BYTES, G2_BYTES, SECRET_KEY_BYTES,
+};
+use crate::errors::AmclError;
+
+use std::string::String;
+use std::vec::Vec;
+
+/*************************************************************************************************
+* ASN.1 DER, PEM and PKCS#8 key encodings
+*
+* Public keys are wrapped in a SubjectPublicKeyInfo (RFC 5280) and secret keys in a PKCS#8
+* PrivateKeyInfo (RFC 5208, RFC 5958), following the layout RFC 8410 uses for Ed25519: the
+* AlgorithmIdentifier holds only the OID of the key variant, the public key is the compressed
+* point in a BIT STRING and the secret key the 32 big-endian bytes in a nested OCTET STRING.
+*
+* The parser accepts DER only: definite, minimally encoded lengths and no trailing data.
+*
+* The OIDs of the key variants are not assigned by an IETF document, other implementations may
+* use different OIDs for the same keys.
+*************************************************************************************************/
+
+/// OID 1.3.6.1.4.1.44668.5.3.1.1.1, BLS12-381 public key on G1 (minimal-pubkey-size).
+pub const OID_BLS12_381_G1: [u8; 13] = [
+    0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0xdc, 0x7c, 0x05, 0x03, 0x01, 0x01, 0x01,
+];
+/// OID 1.3.6.1.4.1.44668.5.3.1.2.1, BLS12-381 public key on G2 (minimal-signature-size).
+pub const OID_BLS12_381_G2: [u8; 13] = [
+    0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0xdc, 0x7c, 0x05, 0x03, 0x01, 0x02, 0x01,
+];
+
+/// PEM label of a SubjectPublicKeyInfo
+pub const PEM_PUBLIC_KEY: &str = "PUBLIC KEY";
+/// PEM label of a PKCS#8 PrivateKeyInfo
+pub const PEM_PRIVATE_KEY: &str = "PRIVATE KEY";
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_ATTRIBUTES: u8 = 0xa0;
+const TAG_PUBLIC_KEY: u8 = 0x81;
+
+// Characters per line of a PEM body
+const PEM_LINE_LEN: usize = 64;
+
+/// Which group the public keys are on, the signatures are on the other group.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyVariant {
+    /// Public keys on G1 and signatures on G2, as in Ethereum.
+    MinimalPublicKeySize,
+    /// Public keys on G2 and signatures on G1.
+    MinimalSignatureSize,
+}
+
+impl KeyVariant {
+    /// Content bytes of the variant's OID
+    pub fn oid(&self) -> &'static [u8] {
+        match self {
+            KeyVariant::MinimalPublicKeySize => &OID_BLS12_381_G1,
+            KeyVariant::MinimalSignatureSize => &OID_BLS12_381_G2,
+        }
+    }
+
+    fn from_oid(oid: &[u8]) -> Result<KeyVariant, AmclError> {
+        if oid == OID_BLS12_381_G1 {
+            Ok(KeyVariant::MinimalPublicKeySize)
+        } else if oid == OID_BLS12_381_G2 {
+            Ok(KeyVariant::MinimalSignatureSize)
+        } else {
+            Err(AmclError::UnsupportedKeyAlgorithm)
+        }
+    }
+
+    // KeyValidate and compress a public key of this variant.
+    fn validate_public_key(&self, public_key: &[u8]) -> Result<Vec<u8>, AmclError> {
+        match self {
+            KeyVariant::MinimalPublicKeySize => {
+                let point = deserialize_g1(public_key)?;
+                if point.is_infinity() || !subgroup_check_g1(&point) {
+                    return Err(AmclError::InvalidPoint);
+                }
+                Ok(serialize_g1(&point).to_vec())
+            }
+            KeyVariant::MinimalSignatureSize => {
+                let point = deserialize_g2(public_key)?;
+                if point.is_infinity() || !subgroup_check_g2(&point) {
+                    return Err(AmclError::InvalidPoint);
+                }
+                Ok(serialize_g2(&point).to_vec())
+            }
+        }
+    }
+
+    fn public_key_len(&self) -> usize {
+        match self {
+            KeyVariant::MinimalPublicKeySize => G1_BYTES,
+            KeyVariant::MinimalSignatureSize => G2_BYTES,
+        }
+    }
+
+    fn secret_key_to_public_key(&self, secret_key: &[u8]) -> Result<Vec<u8>, AmclError> {
+        match self {
+            KeyVariant::MinimalPublicKeySize => {
+                Ok(core::secret_key_to_public_key_g2(secret_key)?.to_vec())
+            }
+            KeyVariant::MinimalSignatureSize => {
+                Ok(core::secret_key_to_public_key_g1(secret_key)?.to_vec())
+            }
+        }
+    }
+}
+
+/*************************************************************************************************
+* SubjectPublicKeyInfo and PrivateKeyInfo
+*************************************************************************************************/
+
+/// Public Key to DER
+///
+/// SubjectPublicKeyInfo of a compressed or uncompressed public key, which must pass KeyValidate.
+pub fn public_key_to_der(variant: KeyVariant, public_key: &[u8]) -> Result<Vec<u8>, AmclError> {
+    let public_key = variant.validate_public_key(public_key)?;
+
+    let mut bit_string = vec![0u8]; // no unused bits
+    bit_string.extend_from_slice(&public_key);
+
+    let mut spki = algorithm_identifier(variant);
+    write_tlv(TAG_BIT_STRING, &bit_string, &mut spki);
+    Ok(tlv(TAG_SEQUENCE, &spki))
+}
+
+/// Public Key from DER
+///
+/// Returns the variant and compressed public key of a SubjectPublicKeyInfo, the key must pass
+/// KeyValidate.
+pub fn public_key_from_der(der: &[u8]) -> Result<(KeyVariant, Vec<u8>), AmclError> {
+    let mut outer = Reader::new(der);
+    let mut spki = Reader::new(outer.read(TAG_SEQUENCE)?);
+    outer.finish()?;
+
+    let variant = read_algorithm_identifier(&mut spki)?;
+    let public_key = read_bit_string(&mut spki)?;
+    spki.finish()?;
+
+    if public_key.len() != variant.public_key_len() {
+        return Err(AmclError::InvalidDer);
+    }
+    Ok((variant, variant.validate_public_key(public_key)?))
+}
+
+/// Secret Key to DER
+///
+/// PKCS#8 PrivateKeyInfo (version 0) of a 32 byte secret key in the range [1, r).
+pub fn secret_key_to_der(variant: KeyVariant, secret_key: &[u8]) -> Result<Vec<u8>, AmclError> {
+    let secret_key = secret_key_to_bytes(&secret_key_from_bytes(secret_key)?);
+
+    let mut pki = tlv(TAG_INTEGER, &[0]);
+    pki.extend_from_slice(&algorithm_identifier(variant));
+    let private_key = tlv(TAG_OCTET_STRING, &secret_key);
+    write_tlv(TAG_OCTET_STRING, &private_key, &mut pki);
+    Ok(tlv(TAG_SEQUENCE, &pki))
+}
+
+/// Secret Key from DER
+///
+/// Returns the variant and secret key of a PKCS#8 PrivateKeyInfo or OneAsymmetricKey.
+/// Attributes are ignored, an embedded public key must match the secret key.
+pub fn secret_key_from_der(der: &[u8]) -> Result<(KeyVariant, [u8; SECRET_KEY_BYTES]), AmclError> {
+    let mut outer = Reader::new(der);
+    let mut pki = Reader::new(outer.read(TAG_SEQUENCE)?);
+    outer.finish()?;
+
+    let version = pki.read(TAG_INTEGER)?;
+    if version != [0] && version != [1] {
+        return Err(AmclError::InvalidDer);
+    }
+    let variant = read_algorithm_identifier(&mut pki)?;
+
+    let mut private_key = Reader::new(pki.read(TAG_OCTET_STRING)?);
+    let secret_key = private_key.read(TAG_OCTET_STRING)?;
+    private_key.finish()?;
+    secret_key_from_bytes(secret_key)?;
+
+    if pki.peek() == Some(TAG_ATTRIBUTES) {
+        pki.read(TAG_ATTRIBUTES)?;
+    }
+    // The public key only exists in version 1 (RFC 5958)
+    if version == [1] && pki.peek() == Some(TAG_PUBLIC_KEY) {
+        let public_key = read_bit_string_contents(pki.read(TAG_PUBLIC_KEY)?)?;
+        let public_key = variant.validate_public_key(public_key)?;
+        if public_key != variant.secret_key_to_public_key(secret_key)? {
+            return Err(AmclError::InvalidDer);
+        }
+    }
+    pki.finish()?;
+
+    let mut key = [0u8; SECRET_KEY_BYTES];
+    key.copy_from_slice(secret_key);
+    Ok((variant, key))
+}
+
+/// Public Key to PEM
+pub fn public_key_to_pem(variant: KeyVariant, public_key: &[u8]) -> Result<String, AmclError> {
+    Ok(der_to_pem(
+        &public_key_to_der(variant, public_key)?,
+        PEM_PUBLIC_KEY,
+    ))
+}
+
+/// Public Key from PEM
+pub fn public_key_from_pem(pem: &str) -> Result<(KeyVariant, Vec<u8>), AmclError> {
+    public_key_from_der(&pem_to_der(pem, PEM_PUBLIC_KEY)?)
+}
+
+/// Secret Key to PEM
+pub fn secret_key_to_pem(variant: KeyVariant, secret_key: &[u8]) -> Result<String, AmclError> {
+    Ok(der_to_pem(
+        &secret_key_to_der(variant, secret_key)?,
+        PEM_PRIVATE_KEY,
+    ))
+}
+
+/// Secret Key from PEM
+pub fn secret_key_from_pem(pem: &str) -> Result<(KeyVariant, [u8; SECRET_KEY_BYTES]), AmclError> {
+    secret_key_from_der(&pem_to_der(pem, PEM_PRIVATE_KEY)?)
+}
+
+fn algorithm_identifier(variant: KeyVariant) -> Vec<u8> {
+    tlv(TAG_SEQUENCE, &tlv(TAG_OID, variant.oid()))
+}
+
+// AlgorithmIdentifier with the OID of a key variant and no parameters.
+fn read_algorithm_identifier(reader: &mut Reader) -> Result<KeyVariant, AmclError> {
+    let mut algorithm = Reader::new(reader.read(TAG_SEQUENCE)?);
+    let oid = algorithm.read(TAG_OID)?;
+    algorithm.finish()?;
+    KeyVariant::from_oid(oid)
+}
+
+fn read_bit_string<'a>(reader: &mut Reader<'a>) -> Result<&'a [u8], AmclError> {
+    read_bit_string_contents(reader.read(TAG_BIT_STRING)?)
+}
+
+// Keys are whole bytes so there must be no unused bits.
+fn read_bit_string_contents(contents: &[u8]) -> Result<&[u8], AmclError> {
+    match contents.split_first() {
+        Some((0, bits)) => Ok(bits),
+        _ => Err(AmclError::InvalidDer),
+    }
+}
+
+/*************************************************************************************************
+* DER
+*************************************************************************************************/
+
+fn tlv(tag: u8, contents: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(contents.len() + 6);
+    write_tlv(tag, contents, &mut out);
+    out
+}
+
+fn write_tlv(tag: u8, contents: &[u8], out: &mut Vec<u8>) {
+    out.push(tag);
+    let len = contents.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let len_bytes = len.to_be_bytes();
+        let skip = len_bytes.iter().take_while(|b| **b == 0).count();
+        out.push(0x80 | (len_bytes.len() - skip) as u8);
+        out.extend_from_slice(&len_bytes[skip..]);
+    }
+    out.extend_from_slice(contents);
+}
+
+// Reads DER elements from a byte stream, only single byte tags are supported.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader { bytes, offset: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], AmclError> {
+        if len > self.bytes.len() - self.offset {
+            return Err(AmclError::InvalidDer);
+        }
+        let bytes = &self.bytes[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.offset).copied()
+    }
+
+    // Length octets, which must be definite and minimal.
+    fn read_len(&mut self) -> Result<usize, AmclError> {
+        let first = self.take(1)?[0];
+        if first < 0x80 {
+            return Ok(first as usize);
+        }
+
+        let num_bytes = (first & 0x7f) as usize;
+        if num_bytes == 0 || num_bytes > 4 {
+            return Err(AmclError::InvalidDer);
+        }
+        let len_bytes = self.take(num_bytes)?;
+        if len_bytes[0] == 0 {
+            return Err(AmclError::InvalidDer);
+        }
+        let len = len_bytes
+            .iter()
+            .fold(0usize, |len, byte| (len << 8) | *byte as usize);
+        if len < 0x80 {
+            return Err(AmclError::InvalidDer);
+        }
+        Ok(len)
+    }
+
+    // Contents of the next element, which must have the tag `tag`.
+    fn read(&mut self, tag: u8) -> Result<&'a [u8], AmclError> {
+        if self.take(1)?[0] != tag {
+            return Err(AmclError::InvalidDer);
+        }
+        let len = self.read_len()?;
+        self.take(len)
+    }
+
+    fn finish(&self) -> Result<(), AmclError> {
+        if self.offset != self.bytes.len() {
+            return Err(AmclError::InvalidDer);
+        }
+        Ok(())
+    }
+}
+
+/*************************************************************************************************
+* PEM
+*************************************************************************************************/
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// DER to PEM
+///
+/// Base64 of the DER between BEGIN and END lines with `label`, wrapped at 64 characters.
+pub fn der_to_pem(der: &[u8], label: &str) -> String {
+    let encoded = base64_encode(der);
+    let mut pem = String::new();
+    pem.push_str(&format!("-----BEGIN {}-----\n", label));
+    for line in encoded.as_bytes().chunks(PEM_LINE_LEN) {
+        // Base64 output is ASCII
+        pem.push_str(std::str::from_utf8(line).unwrap());
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+/// PEM to DER
+///
+/// Decodes the single PEM block labelled `label`, whitespace around and inside the base64 is
+/// ignored.
+pub fn pem_to_der(pem: &str, label: &str) -> Result<Vec<u8>, AmclError> {
+    let begin = format!("-----BEGIN {}-----", label);
+    let end = format!("-----END {}-----", label);
+
+    let body = pem
+        .trim()
+        .strip_prefix(begin.as_str())
+        .and_then(|rest| rest.strip_suffix(end.as_str()))
+        .ok_or(AmclError::InvalidPem)?;
+    if body.contains('-') {
+        return Err(AmclError::InvalidPem);
+    }
+
+    let encoded: Vec<u8> = body.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    base64_decode(&encoded)
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as usize) << 16 | (b[1] as usize) << 8 | b[2] as usize;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i)) & 0x3f] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// Standard base64 with padding, unused bits must be zero so every input has one encoding.
+fn base64_decode(encoded: &[u8]) -> Result<Vec<u8>, AmclError> {
+    if encoded.len() % 4 != 0 {
+        return Err(AmclError::InvalidPem);
+    }
+
+    let mut out = Vec::with_capacity(encoded.len() / 4 * 3);
+    let num_chunks = encoded.len() / 4;
+    for (i, chunk) in encoded.chunks(4).enumerate() {
+        let padding = chunk.iter().rev().take_while(|c| **c == b'=').count();
+        if padding > 2 || (padding > 0 && i + 1 != num_chunks) {
+            return Err(AmclError::InvalidPem);
+        }
+
+        let mut n = 0usize;
+        for c in &chunk[..4 - padding] {
+            let value = BASE64_ALPHABET
+                .iter()
+                .position(|a| a == c)
+                .ok_or(AmclError::InvalidPem)?;
+            n = n << 6 | value;
+        }
+        n <<= 6 * padding;
+
+        let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        if bytes[3 - padding..].iter().any(|b| *b != 0) {
+            return Err(AmclError::InvalidPem);
+        }
+        out.extend_from_slice(&bytes[..3 - padding]);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::ecp::ECP;
+    use super::super::super::ecp2::ECP2;
+    use super::super::core::{serialize_uncompressed_g1, serialize_uncompressed_g2};
+    use super::super::proof_of_possession::key_generate;
+    use super::*;
+    use crate::serde_json::{self, Value};
+
+    const KEYS: &str = include_str!("../../tests/fixtures/asn1/keys.json");
+
+    fn bytes(value: &Value) -> Vec<u8> {
+        hex::decode(value.as_str().unwrap()).unwrap()
+    }
+
+    fn cases() -> Vec<(KeyVariant, Value)> {
+        let keys: Value = serde_json::from_str(KEYS).unwrap();
+        keys["cases"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|case| {
+                let variant = match case["variant"].as_str().unwrap() {
+                    "minimalPublicKeySize" => KeyVariant::MinimalPublicKeySize,
+                    _ => KeyVariant::MinimalSignatureSize,
+                };
+                (variant, case.clone())
+            })
+            .collect()
+    }
+
+    // Replace the first occurrence of `from` in `der`.
+    fn replace(der: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+        let hex = hex::encode(der).replacen(&hex::encode(from), &hex::encode(to), 1);
+        assert_ne!(hex, hex::encode(der));
+        hex::decode(hex).unwrap()
+    }
+
+    #[test]
+    fn test_known_answers() {
+        for (variant, case) in cases() {
+            let secret_key = bytes(&case["secretKey"]);
+            let public_key = bytes(&case["publicKey"]);
+            let public_key_der = bytes(&case["publicKeyDer"]);
+            let secret_key_der = bytes(&case["secretKeyDer"]);
+            let public_key_pem = case["publicKeyPem"].as_str().unwrap();
+            let secret_key_pem = case["secretKeyPem"].as_str().unwrap();
+
+            assert_eq!(
+                variant.secret_key_to_public_key(&secret_key),
+                Ok(public_key.clone())
+            );
+            assert_eq!(
+                public_key_to_der(variant, &public_key),
+                Ok(public_key_der.clone())
+            );
+            assert_eq!(
+                public_key_from_der(&public_key_der),
+                Ok((variant, public_key.clone()))
+            );
+            assert_eq!(
+                public_key_to_pem(variant, &public_key).unwrap(),
+                public_key_pem
+            );
+            assert_eq!(
+                public_key_from_pem(public_key_pem),
+                Ok((variant, public_key.clone()))
+            );
+
+            assert_eq!(secret_key_to_der(variant, &secret_key), Ok(secret_key_der));
+            assert_eq!(
+                secret_key_to_pem(variant, &secret_key).unwrap(),
+                secret_key_pem
+            );
+            let mut key = [0u8; SECRET_KEY_BYTES];
+            key.copy_from_slice(&secret_key);
+            let expected = Ok((variant, key));
+            assert_eq!(secret_key_from_pem(secret_key_pem), expected);
+            assert_eq!(
+                secret_key_from_der(&bytes(&case["secretKeyV1Der"])),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_trips() {
+        for variant in [
+            KeyVariant::MinimalPublicKeySize,
+            KeyVariant::MinimalSignatureSize,
+        ] {
+            for ikm in [[50u8; 32], [51u8; 32]] {
+                let secret_key = key_generate(&ikm, &[]);
+                let public_key = variant.secret_key_to_public_key(&secret_key).unwrap();
+
+                let der = public_key_to_der(variant, &public_key).unwrap();
+                assert_eq!(public_key_from_der(&der), Ok((variant, public_key.clone())));
+                let pem = public_key_to_pem(variant, &public_key).unwrap();
+                assert_eq!(public_key_from_pem(&pem), Ok((variant, public_key.clone())));
+                let crlf = pem.replace('\n', "\r\n");
+                assert_eq!(public_key_from_pem(&crlf), Ok((variant, public_key)));
+
+                let der = secret_key_to_der(variant, &secret_key).unwrap();
+                assert_eq!(secret_key_from_der(&der), Ok((variant, secret_key)));
+                let pem = secret_key_to_pem(variant, &secret_key).unwrap();
+                assert_eq!(secret_key_from_pem(&pem), Ok((variant, secret_key)));
+
+                // The labels are not interchangeable
+                assert_eq!(public_key_from_pem(&pem), Err(AmclError::InvalidPem));
+            }
+        }
+
+        // Uncompressed public keys are encoded compressed
+        let (g1, g2) = (ECP::generator(), ECP2::generator());
+        let cases = cases();
+        assert_eq!(
+            public_key_to_der(cases[0].0, &serialize_uncompressed_g1(&g1)),
+            Ok(bytes(&cases[0].1["publicKeyDer"]))
+        );
+        assert_eq!(
+            public_key_to_der(cases[1].0, &serialize_uncompressed_g2(&g2)),
+            Ok(bytes(&cases[1].1["publicKeyDer"]))
+        );
+    }
+
+    #[test]
+    fn test_truncated() {
+        for (_, case) in cases() {
+            for der in ["publicKeyDer", "secretKeyDer", "secretKeyV1Der"] {
+                let der = bytes(&case[der]);
+                for len in 0..der.len() {
+                    assert!(secret_key_from_der(&der[..len]).is_err());
+                    assert!(public_key_from_der(&der[..len]).is_err());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_lengths_and_trailing_data() {
+        let cases = cases();
+        let der = bytes(&cases[0].1["publicKeyDer"]);
+        assert_eq!(der[..2], [0x30, 0x44]);
+
+        // Non-minimal lengths
+        let long_form = [&[0x30, 0x81, 0x44][..], &der[2..]].concat();
+        assert_eq!(public_key_from_der(&long_form), Err(AmclError::InvalidDer));
+        let leading_zero = [&[0x30, 0x82, 0x00, 0x44][..], &der[2..]].concat();
+        assert_eq!(
+            public_key_from_der(&leading_zero),
+            Err(AmclError::InvalidDer)
+        );
+        let der_v1 = bytes(&cases[1].1["secretKeyV1Der"]);
+        assert_eq!(der_v1[..3], [0x30, 0x81, 0x9b]);
+        let leading_zero = [&[0x30, 0x82, 0x00, 0x9b][..], &der_v1[3..]].concat();
+        assert_eq!(
+            secret_key_from_der(&leading_zero),
+            Err(AmclError::InvalidDer)
+        );
+
+        // Indefinite and oversized lengths
+        let indefinite = [&[0x30, 0x80][..], &der[2..], &[0x00, 0x00][..]].concat();
+        assert_eq!(public_key_from_der(&indefinite), Err(AmclError::InvalidDer));
+        let oversized = [&[0x30, 0x85, 0x00, 0x00, 0x00, 0x00, 0x44][..], &der[2..]].concat();
+        assert_eq!(public_key_from_der(&oversized), Err(AmclError::InvalidDer));
+
+        // Trailing data after the outer and an inner element
+        let trailing = [&der[..], &[0x00][..]].concat();
+        assert_eq!(public_key_from_der(&trailing), Err(AmclError::InvalidDer));
+        let oid = [&[TAG_OID, 13][..], &OID_BLS12_381_G1[..]].concat();
+        let with_null = [&oid[..], &[0x05, 0x00][..]].concat();
+        let mut parameters = replace(&der, &oid, &with_null);
+        parameters[1] += 2;
+        parameters[3] += 2;
+        assert_eq!(public_key_from_der(&parameters), Err(AmclError::InvalidDer));
+    }
+
+    #[test]
+    fn test_wrong_contents() {
+        let cases = cases();
+        let (variant, case) = &cases[0];
+        let der = bytes(&case["publicKeyDer"]);
+        let secret_key_der = bytes(&case["secretKeyDer"]);
+        let public_key = bytes(&case["publicKey"]);
+
+        // Wrong tags
+        let set = [&[0x31][..], &der[1..]].concat();
+        assert_eq!(public_key_from_der(&set), Err(AmclError::InvalidDer));
+        let octet_string = replace(&der, &[TAG_BIT_STRING, 0x31], &[TAG_OCTET_STRING, 0x31]);
+        assert_eq!(
+            public_key_from_der(&octet_string),
+            Err(AmclError::InvalidDer)
+        );
+        assert_eq!(
+            public_key_from_der(&secret_key_der),
+            Err(AmclError::InvalidDer)
+        );
+        assert_eq!(secret_key_from_der(&der), Err(AmclError::InvalidDer));
+
+        // Unknown OID and unsupported version
+        let mut unknown = OID_BLS12_381_G1;
+        unknown[12] = 0x03;
+        let unknown = replace(&der, &OID_BLS12_381_G1, &unknown);
+        assert_eq!(
+            public_key_from_der(&unknown),
+            Err(AmclError::UnsupportedKeyAlgorithm)
+        );
+        let version = replace(&secret_key_der, &[TAG_INTEGER, 1, 0], &[TAG_INTEGER, 1, 2]);
+        assert_eq!(secret_key_from_der(&version), Err(AmclError::InvalidDer));
+
+        // Unused bits in the BIT STRING
+        let unused = replace(
+            &der,
+            &[TAG_BIT_STRING, 0x31, 0x00],
+            &[TAG_BIT_STRING, 0x31, 0x01],
+        );
+        assert_eq!(public_key_from_der(&unused), Err(AmclError::InvalidDer));
+        let der_v1 = bytes(&case["secretKeyV1Der"]);
+        let unused = replace(
+            &der_v1,
+            &[TAG_PUBLIC_KEY, 0x31, 0x00],
+            &[TAG_PUBLIC_KEY, 0x31, 0x01],
+        );
+        assert_eq!(secret_key_from_der(&unused), Err(AmclError::InvalidDer));
+
+        // A G2 public key under the G1 OID, and the identity
+        let g2_key = bytes(&cases[1].1["publicKeyDer"]);
+        let g2_under_g1 = replace(&g2_key, &OID_BLS12_381_G2, &OID_BLS12_381_G1);
+        assert_eq!(
+            public_key_from_der(&g2_under_g1),
+            Err(AmclError::InvalidDer)
+        );
+        let identity = replace(&der, &public_key, &serialize_g1(&ECP::new()));
+        assert_eq!(public_key_from_der(&identity), Err(AmclError::InvalidPoint));
+
+        // A zero secret key
+        let mut secret_key = [0u8; SECRET_KEY_BYTES];
+        secret_key[31] = 1;
+        let zero = replace(&secret_key_der, &secret_key, &[0u8; SECRET_KEY_BYTES]);
+        assert!(secret_key_from_der(&zero).is_err());
+        assert!(secret_key_to_der(*variant, &[0u8; SECRET_KEY_BYTES]).is_err());
+    }
+
+    #[test]
+    fn test_embedded_public_key() {
+        for (variant, case) in cases() {
+            let der_v1 = bytes(&case["secretKeyV1Der"]);
+            let public_key = bytes(&case["publicKey"]);
+
+            // The public key of another secret key
+            let other = variant
+                .secret_key_to_public_key(&key_generate(&[50; 32], &[]))
+                .unwrap();
+            let mismatched = replace(&der_v1, &public_key, &other);
+            assert_eq!(secret_key_from_der(&mismatched), Err(AmclError::InvalidDer));
+
+            // A public key is only allowed in version 1
+            let v0 = replace(&der_v1, &[TAG_INTEGER, 1, 1], &[TAG_INTEGER, 1, 0]);
+            assert_eq!(secret_key_from_der(&v0), Err(AmclError::InvalidDer));
+        }
+    }
+
+    #[test]
+    fn test_pem() {
+        let cases = cases();
+        let pem = cases[1].1["publicKeyPem"].as_str().unwrap();
+        assert!(pem.ends_with("uA==\n-----END PUBLIC KEY-----\n"));
+
+        // Non-zero unused bits before the padding
+        let bits = pem.replace("uA==", "uB==");
+        assert_eq!(public_key_from_pem(&bits), Err(AmclError::InvalidPem));
+        assert_eq!(base64_decode(b"AAE="), Ok(vec![0, 1]));
+        assert_eq!(base64_decode(b"AAF="), Err(AmclError::InvalidPem));
+
+        // Missing, excess and misplaced padding
+        for padding in ["uA=", "uA", "uA===", "u===", "uA=A"] {
+            let invalid = pem.replace("uA==", padding);
+            assert_eq!(public_key_from_pem(&invalid), Err(AmclError::InvalidPem));
+        }
+        assert_eq!(base64_decode(b"AA==AAAA"), Err(AmclError::InvalidPem));
+
+        // Characters outside the alphabet and wrong armor
+        let invalid = pem.replacen('M', "-", 1);
+        assert_eq!(public_key_from_pem(&invalid), Err(AmclError::InvalidPem));
+        let invalid = pem.replacen('M', "_", 1);
+        assert_eq!(public_key_from_pem(&invalid), Err(AmclError::InvalidPem));
+        let invalid = pem.replace("-----END PUBLIC KEY-----\n", "");
+        assert_eq!(public_key_from_pem(&invalid), Err(AmclError::InvalidPem));
+        let invalid = pem.replace("PUBLIC KEY", "CERTIFICATE");
+        assert_eq!(public_key_from_pem(&invalid), Err(AmclError::InvalidPem));
+        let twice = [pem, pem].concat();
+        assert_eq!(public_key_from_pem(&twice), Err(AmclError::InvalidPem));
+    }
+}
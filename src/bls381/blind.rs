@@ -0,0 +1,302 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+
+use super::super::big::Big;
+use super::super::pair;
+use super::super::rom::CURVE_ORDER;
+use super::basic::{DST_G1, DST_G2};
+use super::core::{
+    deserialize_g1, deserialize_g2, hash_to_curve_g1, hash_to_curve_g2, secret_key_from_bytes,
+    secret_key_to_bytes, serialize_g1, serialize_g2, subgroup_check_g1, subgroup_check_g2,
+};
+use crate::errors::AmclError;
+use crate::rand::RAND;
+
+// Re-export constants from core.
+pub use super::core::{G1_BYTES, G2_BYTES, SECRET_KEY_BYTES};
+
+/// The required number of bytes for a blinding factor
+pub const BLINDING_FACTOR_BYTES: usize = SECRET_KEY_BYTES;
+
+// Generate a random scalar in the range [1, r-1].
+fn random_blinding_factor(rng: &mut RAND) -> Big {
+    let r = Big::new_ints(&CURVE_ORDER);
+    let mut factor = Big::randomnum(&r, rng);
+    while factor.is_zilch() {
+        factor = Big::randomnum(&r, rng);
+    }
+    factor
+}
+
+// Parse a blinding factor and return its inverse modulo r.
+fn inverse_blinding_factor(blinding_factor: &[u8]) -> Result<Big, AmclError> {
+    // A blinding factor has the same range as a secret key, [1, r-1].
+    let mut inverse = secret_key_from_bytes(blinding_factor)?;
+    inverse.invmodp(&Big::new_ints(&CURVE_ORDER));
+    Ok(inverse)
+}
+
+/*************************************************************************************************
+* Blind signatures on G1 (public keys on G2)
+*
+* Unblinded signatures are Basic Scheme signatures and verify with `basic::verify_g1`.
+*************************************************************************************************/
+
+/// Blind
+///
+/// Client side: hash `msg` to G1 and multiply by a random blinding factor.
+/// Returns (blinded message, blinding factor).
+pub fn blind_g1(msg: &[u8], rng: &mut RAND) -> ([u8; G1_BYTES], [u8; BLINDING_FACTOR_BYTES]) {
+    let factor = random_blinding_factor(rng);
+    let hash = hash_to_curve_g1(msg, DST_G1);
+    let blinded = pair::g1mul(&hash, &factor);
+
+    (serialize_g1(&blinded), secret_key_to_bytes(&factor))
+}
+
+/// Blind Sign
+///
+/// Signer side: multiply the blinded message by the secret key.
+pub fn blind_sign_g1(secret_key: &[u8], blinded_msg: &[u8]) -> Result<[u8; G1_BYTES], AmclError> {
+    let secret_key = secret_key_from_bytes(secret_key)?;
+    let blinded_msg = deserialize_g1(blinded_msg)?;
+    if blinded_msg.is_infinity() || !subgroup_check_g1(&blinded_msg) {
+        return Err(AmclError::InvalidPoint);
+    }
+
+    let blinded_signature = pair::g1mul(&blinded_msg, &secret_key);
+    Ok(serialize_g1(&blinded_signature))
+}
+
+/// Unblind
+///
+/// Client side: multiply the blinded signature by the inverse of the blinding factor.
+pub fn unblind_g1(
+    blinded_signature: &[u8],
+    blinding_factor: &[u8],
+) -> Result<[u8; G1_BYTES], AmclError> {
+    let inverse = inverse_blinding_factor(blinding_factor)?;
+    let blinded_signature = deserialize_g1(blinded_signature)?;
+    if !subgroup_check_g1(&blinded_signature) {
+        return Err(AmclError::InvalidPoint);
+    }
+
+    let signature = pair::g1mul(&blinded_signature, &inverse);
+    Ok(serialize_g1(&signature))
+}
+
+/*************************************************************************************************
+* Blind signatures on G2 (public keys on G1)
+*
+* Unblinded signatures are Basic Scheme signatures and verify with `basic::verify_g2`.
+*************************************************************************************************/
+
+/// Blind
+///
+/// Client side: hash `msg` to G2 and multiply by a random blinding factor.
+/// Returns (blinded message, blinding factor).
+pub fn blind_g2(msg: &[u8], rng: &mut RAND) -> ([u8; G2_BYTES], [u8; BLINDING_FACTOR_BYTES]) {
+    let factor = random_blinding_factor(rng);
+    let hash = hash_to_curve_g2(msg, DST_G2);
+    let blinded = pair::g2mul(&hash, &factor);
+
+    (serialize_g2(&blinded), secret_key_to_bytes(&factor))
+}
+
+/// Blind Sign
+///
+/// Signer side: multiply the blinded message by the secret key.
+pub fn blind_sign_g2(secret_key: &[u8], blinded_msg: &[u8]) -> Result<[u8; G2_BYTES], AmclError> {
+    let secret_key = secret_key_from_bytes(secret_key)?;
+    let blinded_msg = deserialize_g2(blinded_msg)?;
+    if blinded_msg.is_infinity() || !subgroup_check_g2(&blinded_msg) {
+        return Err(AmclError::InvalidPoint);
+    }
+
+    let blinded_signature = pair::g2mul(&blinded_msg, &secret_key);
+    Ok(serialize_g2(&blinded_signature))
+}
+
+/// Unblind
+///
+/// Client side: multiply the blinded signature by the inverse of the blinding factor.
+pub fn unblind_g2(
+    blinded_signature: &[u8],
+    blinding_factor: &[u8],
+) -> Result<[u8; G2_BYTES], AmclError> {
+    let inverse = inverse_blinding_factor(blinding_factor)?;
+    let blinded_signature = deserialize_g2(blinded_signature)?;
+    if !subgroup_check_g2(&blinded_signature) {
+        return Err(AmclError::InvalidPoint);
+    }
+
+    let signature = pair::g2mul(&blinded_signature, &inverse);
+    Ok(serialize_g2(&signature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::fp::FP;
+    use super::super::super::fp2::FP2;
+    use super::super::basic;
+    use super::super::core::{map_to_curve_g1, map_to_curve_g2};
+    use super::*;
+    use std::vec::Vec;
+
+    const MSG: &[u8] = b"blind message";
+
+    fn setup() -> ([u8; SECRET_KEY_BYTES], RAND) {
+        let mut rng = RAND::new();
+        rng.seed(32, &[17u8; 32]);
+        (basic::key_generate(&[3u8; 32], &[]), rng)
+    }
+
+    // Compressed infinity, a point outside the subgroup and an x with no point on the curve
+    fn invalid_g1() -> Vec<Vec<u8>> {
+        let mut infinity = [0u8; G1_BYTES];
+        infinity[0] = 0xc0;
+        let mut not_on_curve = serialize_g1(&hash_to_curve_g1(MSG, DST_G1));
+        not_on_curve[G1_BYTES - 1] ^= 1;
+        while deserialize_g1(&not_on_curve).is_ok() {
+            not_on_curve[G1_BYTES - 1] = not_on_curve[G1_BYTES - 1].wrapping_add(1);
+        }
+        vec![
+            infinity.to_vec(),
+            serialize_g1(&map_to_curve_g1(FP::new_int(1))).to_vec(),
+            not_on_curve.to_vec(),
+            not_on_curve[1..].to_vec(),
+        ]
+    }
+
+    fn invalid_g2() -> Vec<Vec<u8>> {
+        let mut infinity = [0u8; G2_BYTES];
+        infinity[0] = 0xc0;
+        let mut not_on_curve = serialize_g2(&hash_to_curve_g2(MSG, DST_G2));
+        not_on_curve[G2_BYTES - 1] ^= 1;
+        while deserialize_g2(&not_on_curve).is_ok() {
+            not_on_curve[G2_BYTES - 1] = not_on_curve[G2_BYTES - 1].wrapping_add(1);
+        }
+        vec![
+            infinity.to_vec(),
+            serialize_g2(&map_to_curve_g2(FP2::new_ints(1, 2))).to_vec(),
+            not_on_curve.to_vec(),
+            not_on_curve[1..].to_vec(),
+        ]
+    }
+
+    #[test]
+    fn test_blind_sign_g1() {
+        let (secret_key, mut rng) = setup();
+        let public_key = basic::secret_key_to_public_key_g1(&secret_key).unwrap();
+
+        let (blinded_msg, blinding_factor) = blind_g1(MSG, &mut rng);
+        let blinded_signature = blind_sign_g1(&secret_key, &blinded_msg).unwrap();
+        let signature = unblind_g1(&blinded_signature, &blinding_factor).unwrap();
+
+        assert!(basic::verify_g1(&public_key, MSG, &signature));
+        assert_eq!(
+            signature.to_vec(),
+            basic::sign_g1(&secret_key, MSG).unwrap().to_vec()
+        );
+
+        // A fresh blinding factor hides the message
+        let (other_blinded_msg, _) = blind_g1(MSG, &mut rng);
+        assert_ne!(other_blinded_msg.to_vec(), blinded_msg.to_vec());
+
+        // The blinded signature is not a signature on the message
+        assert!(!basic::verify_g1(&public_key, MSG, &blinded_signature));
+    }
+
+    #[test]
+    fn test_blind_sign_g2() {
+        let (secret_key, mut rng) = setup();
+        let public_key = basic::secret_key_to_public_key_g2(&secret_key).unwrap();
+
+        let (blinded_msg, blinding_factor) = blind_g2(MSG, &mut rng);
+        let blinded_signature = blind_sign_g2(&secret_key, &blinded_msg).unwrap();
+        let signature = unblind_g2(&blinded_signature, &blinding_factor).unwrap();
+
+        assert!(basic::verify_g2(&public_key, MSG, &signature));
+        assert_eq!(
+            signature.to_vec(),
+            basic::sign_g2(&secret_key, MSG).unwrap().to_vec()
+        );
+
+        let (other_blinded_msg, _) = blind_g2(MSG, &mut rng);
+        assert_ne!(other_blinded_msg.to_vec(), blinded_msg.to_vec());
+        assert!(!basic::verify_g2(&public_key, MSG, &blinded_signature));
+    }
+
+    #[test]
+    fn test_invalid_blinding_factor() {
+        let (secret_key, mut rng) = setup();
+        let (blinded_msg, _) = blind_g1(MSG, &mut rng);
+        let blinded_signature = blind_sign_g1(&secret_key, &blinded_msg).unwrap();
+        let (blinded_msg, _) = blind_g2(MSG, &mut rng);
+        let blinded_signature_g2 = blind_sign_g2(&secret_key, &blinded_msg).unwrap();
+
+        let zero = [0u8; BLINDING_FACTOR_BYTES];
+        let mut r = [0u8; BLINDING_FACTOR_BYTES];
+        r.copy_from_slice(&secret_key_to_bytes(&Big::new_ints(&CURVE_ORDER)));
+        for factor in [zero, r].iter() {
+            assert_eq!(
+                unblind_g1(&blinded_signature, factor),
+                Err(AmclError::InvalidSecretKeyRange)
+            );
+            assert_eq!(
+                unblind_g2(&blinded_signature_g2, factor),
+                Err(AmclError::InvalidSecretKeyRange)
+            );
+        }
+        assert_eq!(
+            unblind_g1(&blinded_signature, &zero[1..]),
+            Err(AmclError::InvalidSecretKeySize)
+        );
+    }
+
+    #[test]
+    fn test_invalid_points() {
+        let (secret_key, mut rng) = setup();
+        let (_, blinding_factor) = blind_g1(MSG, &mut rng);
+
+        for point in invalid_g1().iter() {
+            assert!(blind_sign_g1(&secret_key, point).is_err());
+        }
+        // Unblinding infinity gives infinity, the other points are rejected
+        for point in invalid_g1()[1..].iter() {
+            assert!(unblind_g1(point, &blinding_factor).is_err());
+        }
+        for point in invalid_g2().iter() {
+            assert!(blind_sign_g2(&secret_key, point).is_err());
+        }
+        for point in invalid_g2()[1..].iter() {
+            assert!(unblind_g2(point, &blinding_factor).is_err());
+        }
+
+        let non_subgroup = serialize_g1(&map_to_curve_g1(FP::new_int(1)));
+        assert_eq!(
+            blind_sign_g1(&secret_key, &non_subgroup),
+            Err(AmclError::InvalidPoint)
+        );
+        assert_eq!(
+            unblind_g1(&non_subgroup, &blinding_factor),
+            Err(AmclError::InvalidPoint)
+        );
+    }
+}
@@ -22,7 +22,10 @@ use super::super::big::Big;
 use super::super::ecp::ECP;
 use super::super::ecp2::ECP2;
 use super::super::fp::FP;
+use super::super::fp12::FP12;
 use super::super::fp2::FP2;
+use super::super::fp4::FP4;
+use super::super::fr::Fr;
 use super::super::hash_to_curve::*;
 use super::super::pair;
 use super::super::rom::*;
@@ -57,7 +60,7 @@ const Y_FLAG: u8 = 0b_0010_0000;
 /// Generate a new Secret Key based off Initial Keying Material (IKM) and Key Info (salt).
 /// https://tools.ietf.org/html/draft-irtf-cfrg-bls-signature-04#section-2.3
 pub(crate) fn key_generate(ikm: &[u8], key_info: &[u8]) -> [u8; SECRET_KEY_BYTES] {
-    let mut secret_key = Big::new();
+    let mut secret_key = Fr::new();
     let mut salt = KEY_SALT.to_vec();
 
     while secret_key.is_zilch() {
@@ -79,12 +82,10 @@ pub(crate) fn key_generate(ikm: &[u8], key_info: &[u8]) -> [u8; SECRET_KEY_BYTES
         let okm = HASH256::hkdf_extend(&prk, &info, KEY_GENERATION_L);
 
         // SK = OS2IP(OKM) mod r
-        let r = Big::new_ints(&CURVE_ORDER);
-        secret_key = Big::from_bytes(&okm);
-        secret_key.rmod(&r);
+        secret_key = Fr::new_big(&Big::from_bytes(&okm));
     }
 
-    secret_key_to_bytes(&secret_key)
+    secret_key.to_bytes()
 }
 
 // Converts secret key bytes to a Big
@@ -93,17 +94,13 @@ pub fn secret_key_from_bytes(secret_key: &[u8]) -> Result<Big, AmclError> {
         return Err(AmclError::InvalidSecretKeySize);
     }
 
-    // Prepend to MODBYTES in length
-    let mut secret_key_bytes = [0u8; MODBYTES];
-    secret_key_bytes[MODBYTES - SECRET_KEY_BYTES..].copy_from_slice(secret_key);
-
     // Ensure secret key is in the range [1, r-1].
-    let secret_key = Big::from_bytes(&secret_key_bytes);
-    if secret_key.is_zilch() || secret_key >= Big::new_ints(&CURVE_ORDER) {
+    let secret_key = Fr::from_bytes(secret_key).map_err(|_| AmclError::InvalidSecretKeyRange)?;
+    if secret_key.is_zilch() {
         return Err(AmclError::InvalidSecretKeyRange);
     }
 
-    Ok(secret_key)
+    Ok(secret_key.redc())
 }
 
 // Converts secret key Big to bytes
@@ -488,6 +485,298 @@ fn deserialize_uncompressed_g2(g2_bytes: &[u8]) -> Result<ECP2, AmclError> {
     Ok(point)
 }
 
+/*************************************************************************************************
+* Arkworks serialization
+*
+* The CanonicalSerialize encoding of short Weierstrass points in arkworks, as used for
+* BLS12-381 up to ark-bls12-381 0.3. Field elements are little-endian and Fp2 elements c0 || c1.
+* The flags are the two most significant bits of the last byte: 0x40 for infinity and 0x80 if
+* y > -y, which is only set in the compressed form. Scalars are 32 little-endian bytes and Gt
+* elements the 12 Fp coefficients of the Fp12 = Fp6[w], Fp6 = Fp2[v] tower in order.
+*************************************************************************************************/
+
+/// The number of bytes of an arkworks serialized scalar
+pub const SCALAR_BYTES_ARKWORKS: usize = 32;
+/// The number of bytes of an arkworks serialized Gt element
+pub const GT_BYTES_ARKWORKS: usize = MODBYTES * 12;
+
+// Flags in the last byte of arkworks serialized points
+const ARKWORKS_INFINITY_FLAG: u8 = 0b_0100_0000;
+const ARKWORKS_Y_FLAG: u8 = 0b_1000_0000;
+const ARKWORKS_FLAGS: u8 = ARKWORKS_INFINITY_FLAG | ARKWORKS_Y_FLAG;
+
+// Little-endian serialization of a reduced field element.
+fn fp_to_bytes_le(x: &Big, bytes: &mut [u8]) {
+    x.to_bytes(&mut bytes[..MODBYTES]);
+    bytes[..MODBYTES].reverse();
+}
+
+// Little-endian deserialization of a field element less than the modulus.
+fn fp_from_bytes_le(bytes: &[u8]) -> Result<Big, AmclError> {
+    let mut be = [0u8; MODBYTES];
+    be.copy_from_slice(&bytes[..MODBYTES]);
+    be.reverse();
+    let x = Big::from_bytes(&be);
+    if x >= Big::new_ints(&MODULUS) {
+        return Err(AmclError::InvalidPoint);
+    }
+    Ok(x)
+}
+
+fn fp2_to_bytes_le(x: &FP2, bytes: &mut [u8]) {
+    fp_to_bytes_le(&x.geta(), &mut bytes[..MODBYTES]);
+    fp_to_bytes_le(&x.getb(), &mut bytes[MODBYTES..]);
+}
+
+fn fp2_from_bytes_le(bytes: &[u8]) -> Result<FP2, AmclError> {
+    let c0 = fp_from_bytes_le(&bytes[..MODBYTES])?;
+    let c1 = fp_from_bytes_le(&bytes[MODBYTES..])?;
+    Ok(FP2::new_bigs(c0, c1))
+}
+
+// Remove the flags from the last byte.
+fn take_arkworks_flags(bytes: &[u8]) -> (Vec<u8>, bool, bool) {
+    let mut bytes = bytes.to_owned();
+    let last = bytes.len() - 1;
+    let flags = bytes[last] & ARKWORKS_FLAGS;
+    bytes[last] &= !ARKWORKS_FLAGS;
+    (
+        bytes,
+        flags & ARKWORKS_INFINITY_FLAG != 0,
+        flags & ARKWORKS_Y_FLAG != 0,
+    )
+}
+
+/// Take a G1 point and compress it to a 48 byte arkworks array.
+pub fn serialize_g1_arkworks(g1: &ECP) -> [u8; G1_BYTES] {
+    let mut result = [0u8; G1_BYTES];
+    if g1.is_infinity() {
+        result[G1_BYTES - 1] = ARKWORKS_INFINITY_FLAG;
+        return result;
+    }
+
+    fp_to_bytes_le(&g1.getx(), &mut result);
+
+    // Evaluate if y > -y
+    let mut tmp = g1.clone();
+    tmp.affine();
+    let y = tmp.gety();
+    tmp.neg();
+    if y > tmp.gety() {
+        result[G1_BYTES - 1] |= ARKWORKS_Y_FLAG;
+    }
+
+    result
+}
+
+/// Take a G1 point and convert it to a 96 byte arkworks array.
+pub fn serialize_uncompressed_g1_arkworks(g1: &ECP) -> [u8; G1_BYTES * 2] {
+    let mut result = [0u8; G1_BYTES * 2];
+    if g1.is_infinity() {
+        // arkworks represents infinity as (0, 1)
+        result[G1_BYTES] = 1;
+        result[G1_BYTES * 2 - 1] = ARKWORKS_INFINITY_FLAG;
+        return result;
+    }
+
+    fp_to_bytes_le(&g1.getx(), &mut result[..G1_BYTES]);
+    fp_to_bytes_le(&g1.gety(), &mut result[G1_BYTES..]);
+    result
+}
+
+/// Take a 48 or 96 byte arkworks array and convert to a G1 point.
+///
+/// The point is checked to be on the curve but not to be in the subgroup.
+pub fn deserialize_g1_arkworks(g1_bytes: &[u8]) -> Result<ECP, AmclError> {
+    if g1_bytes.len() != G1_BYTES && g1_bytes.len() != G1_BYTES * 2 {
+        return Err(AmclError::InvalidG1Size);
+    }
+    let compressed = g1_bytes.len() == G1_BYTES;
+    let (bytes, infinity, y_flag) = take_arkworks_flags(g1_bytes);
+
+    if infinity {
+        // Only the canonical encodings of infinity are accepted
+        let x = fp_from_bytes_le(&bytes[..G1_BYTES])?;
+        let y_is_canonical = compressed || {
+            let y = fp_from_bytes_le(&bytes[G1_BYTES..])?;
+            y.is_zilch() || y.is_unity()
+        };
+        if y_flag || !x.is_zilch() || !y_is_canonical {
+            return Err(AmclError::InvalidPoint);
+        }
+        return Ok(ECP::new());
+    }
+
+    let x = fp_from_bytes_le(&bytes[..G1_BYTES])?;
+    if !compressed {
+        if y_flag {
+            return Err(AmclError::InvalidYFlag);
+        }
+        let y = fp_from_bytes_le(&bytes[G1_BYTES..])?;
+        let point = ECP::new_bigs(&x, &y);
+        if point.is_infinity() {
+            return Err(AmclError::InvalidPoint);
+        }
+        return Ok(point);
+    }
+
+    let point = ECP::new_big(&x);
+    if point.is_infinity() {
+        return Err(AmclError::InvalidPoint);
+    }
+
+    // Confirm y value
+    let mut point_neg = point.clone();
+    point_neg.neg();
+    if (point.gety() > point_neg.gety()) != y_flag {
+        Ok(point_neg)
+    } else {
+        Ok(point)
+    }
+}
+
+/// Take a G2 point and compress it to a 96 byte arkworks array.
+pub fn serialize_g2_arkworks(g2: &ECP2) -> [u8; G2_BYTES] {
+    let mut result = [0u8; G2_BYTES];
+    if g2.is_infinity() {
+        result[G2_BYTES - 1] = ARKWORKS_INFINITY_FLAG;
+        return result;
+    }
+
+    fp2_to_bytes_le(&g2.getx(), &mut result);
+
+    // Evaluate if y > -y
+    let mut tmp = g2.clone();
+    tmp.affine();
+    let mut y = tmp.gety();
+    tmp.neg();
+    let mut y_neg = tmp.gety();
+    if zcash_cmp_fp2(&mut y, &mut y_neg) > 0 {
+        result[G2_BYTES - 1] |= ARKWORKS_Y_FLAG;
+    }
+
+    result
+}
+
+/// Take a G2 point and convert it to a 192 byte arkworks array.
+pub fn serialize_uncompressed_g2_arkworks(g2: &ECP2) -> [u8; G2_BYTES * 2] {
+    let mut result = [0u8; G2_BYTES * 2];
+    if g2.is_infinity() {
+        // arkworks represents infinity as (0, 1)
+        result[G2_BYTES] = 1;
+        result[G2_BYTES * 2 - 1] = ARKWORKS_INFINITY_FLAG;
+        return result;
+    }
+
+    fp2_to_bytes_le(&g2.getx(), &mut result[..G2_BYTES]);
+    fp2_to_bytes_le(&g2.gety(), &mut result[G2_BYTES..]);
+    result
+}
+
+/// Take a 96 or 192 byte arkworks array and convert to a G2 point.
+///
+/// The point is checked to be on the curve but not to be in the subgroup.
+pub fn deserialize_g2_arkworks(g2_bytes: &[u8]) -> Result<ECP2, AmclError> {
+    if g2_bytes.len() != G2_BYTES && g2_bytes.len() != G2_BYTES * 2 {
+        return Err(AmclError::InvalidG2Size);
+    }
+    let compressed = g2_bytes.len() == G2_BYTES;
+    let (bytes, infinity, y_flag) = take_arkworks_flags(g2_bytes);
+
+    if infinity {
+        // Only the canonical encodings of infinity are accepted
+        let x = fp2_from_bytes_le(&bytes[..G2_BYTES])?;
+        let y_is_canonical = compressed || {
+            let y = fp2_from_bytes_le(&bytes[G2_BYTES..])?;
+            y.is_zilch() || y.is_unity()
+        };
+        if y_flag || !x.is_zilch() || !y_is_canonical {
+            return Err(AmclError::InvalidPoint);
+        }
+        return Ok(ECP2::new());
+    }
+
+    let x = fp2_from_bytes_le(&bytes[..G2_BYTES])?;
+    if !compressed {
+        if y_flag {
+            return Err(AmclError::InvalidYFlag);
+        }
+        let y = fp2_from_bytes_le(&bytes[G2_BYTES..])?;
+        let point = ECP2::new_fp2s(x, y);
+        if point.is_infinity() {
+            return Err(AmclError::InvalidPoint);
+        }
+        return Ok(point);
+    }
+
+    let point = ECP2::new_fp2(&x);
+    if point.is_infinity() {
+        return Err(AmclError::InvalidPoint);
+    }
+
+    // Confirm y value
+    let mut point_neg = point.clone();
+    point_neg.neg();
+    if (zcash_cmp_fp2(&mut point.gety(), &mut point_neg.gety()) > 0) != y_flag {
+        Ok(point_neg)
+    } else {
+        Ok(point)
+    }
+}
+
+/// Convert a scalar to 32 little-endian bytes.
+pub fn serialize_scalar_arkworks(scalar: &Fr) -> [u8; SCALAR_BYTES_ARKWORKS] {
+    scalar.to_bytes_le()
+}
+
+/// Convert 32 little-endian bytes to a scalar, which must be less than the curve order.
+pub fn deserialize_scalar_arkworks(bytes: &[u8]) -> Result<Fr, AmclError> {
+    Fr::from_bytes_le(bytes)
+}
+
+// The coefficients of an Fp12 element in the arkworks order.
+//
+// The AMCL tower is a + b W + c W^2 over Fp4 = Fp2[j] with W^3 = j, the arkworks tower
+// (c00 + c01 v + c02 v^2) + (c10 + c11 v + c12 v^2) w with w^2 = v. Both have w^6 = 1 + u, so
+// (c00, c01, c02, c10, c11, c12) = (a.a, c.a, b.b, b.a, a.b, c.b).
+fn gt_coefficients(gt: &FP12) -> [FP2; 6] {
+    let mut gt = gt.clone();
+    let (a, b, c) = (gt.geta(), gt.getb(), gt.getc());
+    [a.geta(), c.geta(), b.getb(), b.geta(), a.getb(), c.getb()]
+}
+
+/// Take a Gt element and convert it to a 576 byte arkworks array.
+pub fn serialize_gt_arkworks(gt: &FP12) -> [u8; GT_BYTES_ARKWORKS] {
+    let mut result = [0u8; GT_BYTES_ARKWORKS];
+    for (i, coefficient) in gt_coefficients(gt).iter().enumerate() {
+        fp2_to_bytes_le(coefficient, &mut result[i * G2_BYTES..(i + 1) * G2_BYTES]);
+    }
+    result
+}
+
+/// Take a 576 byte arkworks array and convert to a Gt element.
+///
+/// The element must be in the order r subgroup of Fp12.
+pub fn deserialize_gt_arkworks(gt_bytes: &[u8]) -> Result<FP12, AmclError> {
+    if gt_bytes.len() != GT_BYTES_ARKWORKS {
+        return Err(AmclError::InvalidEncoding);
+    }
+    let mut c = Vec::with_capacity(6);
+    for chunk in gt_bytes.chunks(G2_BYTES) {
+        c.push(fp2_from_bytes_le(chunk)?);
+    }
+
+    let a = FP4::new_fp2s(c[0].clone(), c[4].clone());
+    let b = FP4::new_fp2s(c[3].clone(), c[2].clone());
+    let c = FP4::new_fp2s(c[1].clone(), c[5].clone());
+    let gt = FP12::new_fp4s(a, b, c);
+
+    if gt.is_zilch() || !gt.pow(&Big::new_ints(&CURVE_ORDER)).is_unity() {
+        return Err(AmclError::InvalidPoint);
+    }
+    Ok(gt)
+}
+
 /*************************************************************************************************
 * Core BLS Functions when signatures are on G1
 *
@@ -813,12 +1102,12 @@ pub fn hash_to_curve_g1(msg: &[u8], dst: &[u8]) -> ECP {
     p
 }
 
-// Simplified SWU for Pairing-Friendly Curves
-//
-// Take a field point and map it to a Curve Point.
-// SSWU - https://tools.ietf.org/html/draft-irtf-cfrg-hash-to-curve-09#section-6.6.2
-// ISO11 - https://tools.ietf.org/html/draft-irtf-cfrg-hash-to-curve-09#appendix-C.2
-fn map_to_curve_g1(u: FP) -> ECP {
+/// Simplified SWU for Pairing-Friendly Curves
+///
+/// Take a field point and map it to a Curve Point, the result is not in the G1 subgroup.
+/// SSWU - https://tools.ietf.org/html/draft-irtf-cfrg-hash-to-curve-09#section-6.6.2
+/// ISO11 - https://tools.ietf.org/html/draft-irtf-cfrg-hash-to-curve-09#appendix-C.2
+pub fn map_to_curve_g1(u: FP) -> ECP {
     let (x, y) = simplified_swu_fp(u);
     iso11_to_ecp(&x, &y)
 }
@@ -841,12 +1130,314 @@ pub fn hash_to_curve_g2(msg: &[u8], dst: &[u8]) -> ECP2 {
     q0
 }
 
-// Simplified SWU for Pairing-Friendly Curves
-//
-// Take a field point and map it to a Curve Point.
-// SSWU - https://tools.ietf.org/html/draft-irtf-cfrg-hash-to-curve-09#section-6.6.2
-// ISO3 - https://tools.ietf.org/html/draft-irtf-cfrg-hash-to-curve-09#appendix-C.3
-fn map_to_curve_g2(u: FP2) -> ECP2 {
+/// Simplified SWU for Pairing-Friendly Curves
+///
+/// Take a field point and map it to a Curve Point, the result is not in the G2 subgroup.
+/// SSWU - https://tools.ietf.org/html/draft-irtf-cfrg-hash-to-curve-09#section-6.6.2
+/// ISO3 - https://tools.ietf.org/html/draft-irtf-cfrg-hash-to-curve-09#appendix-C.3
+pub fn map_to_curve_g2(u: FP2) -> ECP2 {
     let (x, y) = simplified_swu_fp2(u);
     iso3_to_ecp2(&x, &y)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde_json::{self, Value};
+
+    const ARKWORKS: &str = include_str!("../../tests/fixtures/arkworks/serialization.json");
+
+    fn bytes(value: &Value) -> Vec<u8> {
+        hex::decode(value.as_str().unwrap()).unwrap()
+    }
+
+    fn scalar(value: &Value) -> Big {
+        let mut be = [0u8; MODBYTES];
+        be[MODBYTES - 32..].copy_from_slice(&bytes(value));
+        Big::from_bytes(&be)
+    }
+
+    fn vectors() -> Value {
+        serde_json::from_str(ARKWORKS).unwrap()
+    }
+
+    #[test]
+    fn test_g1_arkworks() {
+        let vectors = vectors();
+        let mut y_flags = [false, false];
+        for case in vectors["g1"].as_array().unwrap() {
+            let point = ECP::generator().mul(&scalar(&case["scalar"]));
+            let compressed = bytes(&case["compressed"]);
+            let uncompressed = bytes(&case["uncompressed"]);
+            assert_eq!(serialize_g1_arkworks(&point).to_vec(), compressed);
+            assert_eq!(
+                serialize_uncompressed_g1_arkworks(&point).to_vec(),
+                uncompressed
+            );
+            assert_eq!(deserialize_g1_arkworks(&compressed), Ok(point.clone()));
+            assert_eq!(deserialize_g1_arkworks(&uncompressed), Ok(point));
+            y_flags[(compressed[G1_BYTES - 1] >> 7) as usize] = true;
+        }
+        // Both signs of y are covered
+        assert_eq!(y_flags, [true, true]);
+
+        let infinity = &vectors["g1Infinity"];
+        let compressed = bytes(&infinity["compressed"]);
+        let uncompressed = bytes(&infinity["uncompressed"]);
+        assert_eq!(serialize_g1_arkworks(&ECP::new()).to_vec(), compressed);
+        assert_eq!(
+            serialize_uncompressed_g1_arkworks(&ECP::new()).to_vec(),
+            uncompressed
+        );
+        assert!(deserialize_g1_arkworks(&compressed).unwrap().is_infinity());
+        assert!(deserialize_g1_arkworks(&uncompressed)
+            .unwrap()
+            .is_infinity());
+    }
+
+    #[test]
+    fn test_g2_arkworks() {
+        let vectors = vectors();
+        let mut y_flags = [false, false];
+        for case in vectors["g2"].as_array().unwrap() {
+            let point = ECP2::generator().mul(&scalar(&case["scalar"]));
+            let compressed = bytes(&case["compressed"]);
+            let uncompressed = bytes(&case["uncompressed"]);
+            assert_eq!(serialize_g2_arkworks(&point).to_vec(), compressed);
+            assert_eq!(
+                serialize_uncompressed_g2_arkworks(&point).to_vec(),
+                uncompressed
+            );
+            assert_eq!(deserialize_g2_arkworks(&compressed), Ok(point.clone()));
+            assert_eq!(deserialize_g2_arkworks(&uncompressed), Ok(point));
+            y_flags[(compressed[G2_BYTES - 1] >> 7) as usize] = true;
+        }
+        assert_eq!(y_flags, [true, true]);
+
+        let infinity = &vectors["g2Infinity"];
+        let compressed = bytes(&infinity["compressed"]);
+        let uncompressed = bytes(&infinity["uncompressed"]);
+        assert_eq!(serialize_g2_arkworks(&ECP2::new()).to_vec(), compressed);
+        assert_eq!(
+            serialize_uncompressed_g2_arkworks(&ECP2::new()).to_vec(),
+            uncompressed
+        );
+        assert!(deserialize_g2_arkworks(&compressed).unwrap().is_infinity());
+        assert!(deserialize_g2_arkworks(&uncompressed)
+            .unwrap()
+            .is_infinity());
+    }
+
+    #[test]
+    fn test_scalar_arkworks() {
+        let vectors = vectors();
+        for case in vectors["scalars"].as_array().unwrap() {
+            let mut be = bytes(&case["scalar"]);
+            let fr = Fr::from_bytes(&be).unwrap();
+            let le = bytes(&case["bytes"]);
+            be.reverse();
+            assert_eq!(le, be);
+            assert_eq!(serialize_scalar_arkworks(&fr).to_vec(), le);
+            assert_eq!(deserialize_scalar_arkworks(&le), Ok(fr));
+        }
+
+        let r = bytes(&vectors["scalarModulus"]);
+        assert_eq!(
+            deserialize_scalar_arkworks(&r),
+            Err(AmclError::InvalidScalarRange)
+        );
+        assert_eq!(
+            deserialize_scalar_arkworks(&[0xff; SCALAR_BYTES_ARKWORKS]),
+            Err(AmclError::InvalidScalarRange)
+        );
+        assert_eq!(
+            deserialize_scalar_arkworks(&r[..31]),
+            Err(AmclError::InvalidScalarSize)
+        );
+        assert_eq!(
+            deserialize_scalar_arkworks(&[0u8; 33]),
+            Err(AmclError::InvalidScalarSize)
+        );
+    }
+
+    #[test]
+    fn test_gt_arkworks() {
+        let vectors = vectors();
+        for case in vectors["gt"].as_array().unwrap() {
+            let p = ECP::generator().mul(&scalar(&case["g1Scalar"]));
+            let q = ECP2::generator().mul(&scalar(&case["g2Scalar"]));
+            let gt = pair::fexp(&pair::ate(&q, &p));
+            let expected = bytes(&case["bytes"]);
+            assert_eq!(serialize_gt_arkworks(&gt).to_vec(), expected);
+            assert!(deserialize_gt_arkworks(&expected).unwrap().equals(&gt));
+        }
+
+        let one = bytes(&vectors["gtOne"]);
+        assert_eq!(serialize_gt_arkworks(&FP12::new_int(1)).to_vec(), one);
+        assert!(deserialize_gt_arkworks(&one).unwrap().is_unity());
+    }
+
+    #[test]
+    fn test_g1_arkworks_invalid() {
+        let vectors = vectors();
+        let compressed = bytes(&vectors["g1"][0]["compressed"]);
+        let uncompressed = bytes(&vectors["g1"][0]["uncompressed"]);
+        let modulus = bytes(&vectors["fieldModulus"]);
+
+        // Both flags
+        let mut invalid = compressed.clone();
+        invalid[G1_BYTES - 1] |= ARKWORKS_FLAGS;
+        assert_eq!(
+            deserialize_g1_arkworks(&invalid),
+            Err(AmclError::InvalidPoint)
+        );
+
+        // Infinity with a non-zero x
+        let mut invalid = [0u8; G1_BYTES];
+        invalid[0] = 1;
+        invalid[G1_BYTES - 1] = ARKWORKS_INFINITY_FLAG;
+        assert_eq!(
+            deserialize_g1_arkworks(&invalid),
+            Err(AmclError::InvalidPoint)
+        );
+
+        // Uncompressed infinity with y = 2
+        let mut invalid = bytes(&vectors["g1Infinity"]["uncompressed"]);
+        invalid[G1_BYTES] = 2;
+        assert_eq!(
+            deserialize_g1_arkworks(&invalid),
+            Err(AmclError::InvalidPoint)
+        );
+
+        // Uncompressed with the y flag
+        let mut invalid = uncompressed.clone();
+        invalid[2 * G1_BYTES - 1] |= ARKWORKS_Y_FLAG;
+        assert_eq!(
+            deserialize_g1_arkworks(&invalid),
+            Err(AmclError::InvalidYFlag)
+        );
+
+        // Non-canonical x and y
+        assert_eq!(
+            deserialize_g1_arkworks(&modulus),
+            Err(AmclError::InvalidPoint)
+        );
+        let mut invalid = uncompressed.clone();
+        invalid[G1_BYTES..].copy_from_slice(&modulus);
+        assert_eq!(
+            deserialize_g1_arkworks(&invalid),
+            Err(AmclError::InvalidPoint)
+        );
+
+        // Not on the curve
+        let mut invalid = [0u8; 2 * G1_BYTES];
+        invalid[0] = 1;
+        invalid[G1_BYTES] = 1;
+        assert_eq!(
+            deserialize_g1_arkworks(&invalid),
+            Err(AmclError::InvalidPoint)
+        );
+
+        assert_eq!(
+            deserialize_g1_arkworks(&compressed[1..]),
+            Err(AmclError::InvalidG1Size)
+        );
+        assert_eq!(deserialize_g1_arkworks(&[]), Err(AmclError::InvalidG1Size));
+    }
+
+    #[test]
+    fn test_g2_arkworks_invalid() {
+        let vectors = vectors();
+        let compressed = bytes(&vectors["g2"][0]["compressed"]);
+        let uncompressed = bytes(&vectors["g2"][0]["uncompressed"]);
+        let modulus = bytes(&vectors["fieldModulus"]);
+
+        let mut invalid = compressed.clone();
+        invalid[G2_BYTES - 1] |= ARKWORKS_FLAGS;
+        assert_eq!(
+            deserialize_g2_arkworks(&invalid),
+            Err(AmclError::InvalidPoint)
+        );
+
+        let mut invalid = [0u8; G2_BYTES];
+        invalid[MODBYTES] = 1;
+        invalid[G2_BYTES - 1] = ARKWORKS_INFINITY_FLAG;
+        assert_eq!(
+            deserialize_g2_arkworks(&invalid),
+            Err(AmclError::InvalidPoint)
+        );
+
+        let mut invalid = uncompressed.clone();
+        invalid[2 * G2_BYTES - 1] |= ARKWORKS_Y_FLAG;
+        assert_eq!(
+            deserialize_g2_arkworks(&invalid),
+            Err(AmclError::InvalidYFlag)
+        );
+
+        // Non-canonical c0 and c1 of x
+        let mut invalid = compressed.clone();
+        invalid[..MODBYTES].copy_from_slice(&modulus);
+        assert_eq!(
+            deserialize_g2_arkworks(&invalid),
+            Err(AmclError::InvalidPoint)
+        );
+        let mut invalid = compressed.clone();
+        let flags = invalid[G2_BYTES - 1] & ARKWORKS_FLAGS;
+        invalid[MODBYTES..].copy_from_slice(&modulus);
+        invalid[G2_BYTES - 1] |= flags;
+        assert_eq!(
+            deserialize_g2_arkworks(&invalid),
+            Err(AmclError::InvalidPoint)
+        );
+
+        let mut invalid = [0u8; 2 * G2_BYTES];
+        invalid[0] = 1;
+        invalid[G2_BYTES] = 1;
+        assert_eq!(
+            deserialize_g2_arkworks(&invalid),
+            Err(AmclError::InvalidPoint)
+        );
+
+        assert_eq!(
+            deserialize_g2_arkworks(&compressed[1..]),
+            Err(AmclError::InvalidG2Size)
+        );
+        assert_eq!(
+            deserialize_g2_arkworks(&uncompressed[..G1_BYTES]),
+            Err(AmclError::InvalidG2Size)
+        );
+    }
+
+    #[test]
+    fn test_gt_arkworks_invalid() {
+        let vectors = vectors();
+        let valid = bytes(&vectors["gt"][0]["bytes"]);
+        let modulus = bytes(&vectors["fieldModulus"]);
+
+        // Each non-canonical coefficient
+        for i in 0..12 {
+            let mut invalid = valid.clone();
+            invalid[i * MODBYTES..(i + 1) * MODBYTES].copy_from_slice(&modulus);
+            assert_eq!(
+                deserialize_gt_arkworks(&invalid).err(),
+                Some(AmclError::InvalidPoint)
+            );
+        }
+
+        // Zero and 2, which are not in the subgroup
+        let mut invalid = [0u8; GT_BYTES_ARKWORKS];
+        assert_eq!(
+            deserialize_gt_arkworks(&invalid).err(),
+            Some(AmclError::InvalidPoint)
+        );
+        invalid[0] = 2;
+        assert_eq!(
+            deserialize_gt_arkworks(&invalid).err(),
+            Some(AmclError::InvalidPoint)
+        );
+
+        assert_eq!(
+            deserialize_gt_arkworks(&valid[1..]).err(),
+            Some(AmclError::InvalidEncoding)
+        );
+    }
+}
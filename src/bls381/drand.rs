@@ -0,0 +1,345 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+
+use sgx_tstd as std;
+
+use super::core::{self, G1_BYTES, G2_BYTES};
+use crate::errors::AmclError;
+use crate::hash256::{HASH256, HASH_BYTES};
+use std::vec::Vec;
+
+/// Domain Separation Tag used by drand for signatures on G2.
+pub const DST_G2: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+/// Domain Separation Tag used by drand for RFC 9380 compliant signatures on G1.
+pub const DST_G1: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// drand beacon scheme, as advertised in the chain info `schemeID`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Scheme {
+    /// `pedersen-bls-chained`: public key on G1, signature on G2 over H(previous_signature || round).
+    PedersenBlsChained,
+    /// `pedersen-bls-unchained`: public key on G1, signature on G2 over H(round).
+    PedersenBlsUnchained,
+    /// `bls-unchained-on-g1`: public key on G2, signature on G1 over H(round).
+    ///
+    /// Note: this scheme hashes to G1 with the G2 DST, which is kept for compatibility.
+    BlsUnchainedOnG1,
+    /// `bls-unchained-g1-rfc9380`: public key on G2, signature on G1 over H(round).
+    BlsUnchainedG1Rfc9380,
+}
+
+impl Scheme {
+    /// Parse a drand scheme ID.
+    pub fn from_id(id: &str) -> Result<Scheme, AmclError> {
+        match id {
+            "pedersen-bls-chained" => Ok(Scheme::PedersenBlsChained),
+            "pedersen-bls-unchained" => Ok(Scheme::PedersenBlsUnchained),
+            "bls-unchained-on-g1" => Ok(Scheme::BlsUnchainedOnG1),
+            "bls-unchained-g1-rfc9380" => Ok(Scheme::BlsUnchainedG1Rfc9380),
+            _ => Err(AmclError::InvalidSchemeId),
+        }
+    }
+
+    /// The drand scheme ID.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Scheme::PedersenBlsChained => "pedersen-bls-chained",
+            Scheme::PedersenBlsUnchained => "pedersen-bls-unchained",
+            Scheme::BlsUnchainedOnG1 => "bls-unchained-on-g1",
+            Scheme::BlsUnchainedG1Rfc9380 => "bls-unchained-g1-rfc9380",
+        }
+    }
+
+    /// True if the signed message includes the previous signature.
+    pub fn is_chained(&self) -> bool {
+        *self == Scheme::PedersenBlsChained
+    }
+
+    /// True if beacon signatures are on G1 (and the group public key on G2).
+    pub fn signature_on_g1(&self) -> bool {
+        match self {
+            Scheme::PedersenBlsChained | Scheme::PedersenBlsUnchained => false,
+            Scheme::BlsUnchainedOnG1 | Scheme::BlsUnchainedG1Rfc9380 => true,
+        }
+    }
+
+    /// Domain Separation Tag used when hashing the message to the curve.
+    pub fn dst(&self) -> &'static [u8] {
+        match self {
+            Scheme::BlsUnchainedG1Rfc9380 => DST_G1,
+            _ => DST_G2,
+        }
+    }
+}
+
+/// The parts of a drand chain info required to verify beacons.
+#[derive(Debug, Clone)]
+pub struct ChainInfo {
+    pub public_key: Vec<u8>,
+    pub scheme: Scheme,
+}
+
+impl ChainInfo {
+    /// Create chain info from the group public key and scheme ID.
+    ///
+    /// The public key must be a compressed G1 point for the pedersen schemes
+    /// and a compressed G2 point for the schemes with signatures on G1.
+    pub fn new(public_key: &[u8], scheme_id: &str) -> Result<ChainInfo, AmclError> {
+        let scheme = Scheme::from_id(scheme_id)?;
+        if scheme.signature_on_g1() {
+            if public_key.len() != G2_BYTES {
+                return Err(AmclError::InvalidG2Size);
+            }
+        } else if public_key.len() != G1_BYTES {
+            return Err(AmclError::InvalidG1Size);
+        }
+
+        Ok(ChainInfo {
+            public_key: public_key.to_vec(),
+            scheme,
+        })
+    }
+}
+
+/// Message
+///
+/// Compute the message signed for `round`.
+/// Chained: SHA-256(previous_signature || round), unchained: SHA-256(round),
+/// where round is encoded as a big-endian u64.
+pub fn message(scheme: Scheme, round: u64, previous_signature: &[u8]) -> [u8; HASH_BYTES] {
+    let mut hash256 = HASH256::new();
+    hash256.init();
+    if scheme.is_chained() {
+        hash256.process_array(previous_signature);
+    }
+    hash256.process_array(&round.to_be_bytes());
+    hash256.hash()
+}
+
+/// Verify Beacon
+///
+/// Verify the signature of `round` against the chain's group public key.
+/// `previous_signature` is ignored for unchained schemes.
+pub fn verify_beacon(
+    info: &ChainInfo,
+    round: u64,
+    previous_signature: &[u8],
+    signature: &[u8],
+) -> bool {
+    let msg = message(info.scheme, round, previous_signature);
+
+    if info.scheme.signature_on_g1() {
+        core::core_verify_g1(&info.public_key, &msg, signature, info.scheme.dst())
+    } else {
+        core::core_verify_g2(&info.public_key, &msg, signature, info.scheme.dst())
+    }
+}
+
+/// Randomness
+///
+/// Derive the beacon randomness as SHA-256(signature).
+pub fn randomness(signature: &[u8]) -> [u8; HASH_BYTES] {
+    let mut hash256 = HASH256::new();
+    hash256.init();
+    hash256.process_array(signature);
+    hash256.hash()
+}
+
+/// Verify a beacon and return its randomness.
+pub fn verify_and_derive_randomness(
+    info: &ChainInfo,
+    round: u64,
+    previous_signature: &[u8],
+    signature: &[u8],
+) -> Result<[u8; HASH_BYTES], AmclError> {
+    if !verify_beacon(info, round, previous_signature, signature) {
+        return Err(AmclError::InvalidSignature);
+    }
+    Ok(randomness(signature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde_json::{self, Value};
+
+    const BEACONS: &str = include_str!("../../tests/fixtures/drand/beacons.json");
+
+    struct Beacon {
+        info: ChainInfo,
+        round: u64,
+        previous_signature: Vec<u8>,
+        signature: Vec<u8>,
+        randomness: Vec<u8>,
+    }
+
+    fn beacons() -> Vec<Beacon> {
+        let vectors: Value = serde_json::from_str(BEACONS).unwrap();
+        let bytes = |value: &Value| hex::decode(value.as_str().unwrap()).unwrap();
+        vectors["beacons"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|beacon| Beacon {
+                info: ChainInfo::new(
+                    &bytes(&beacon["public_key"]),
+                    beacon["scheme"].as_str().unwrap(),
+                )
+                .unwrap(),
+                round: beacon["round"].as_u64().unwrap(),
+                previous_signature: bytes(&beacon["previous_signature"]),
+                signature: bytes(&beacon["signature"]),
+                randomness: bytes(&beacon["randomness"]),
+            })
+            .collect()
+    }
+
+    fn verify(beacon: &Beacon, info: &ChainInfo) -> bool {
+        verify_beacon(
+            info,
+            beacon.round,
+            &beacon.previous_signature,
+            &beacon.signature,
+        )
+    }
+
+    #[test]
+    fn test_beacons() {
+        let beacons = beacons();
+        for scheme in [
+            Scheme::PedersenBlsChained,
+            Scheme::PedersenBlsUnchained,
+            Scheme::BlsUnchainedOnG1,
+            Scheme::BlsUnchainedG1Rfc9380,
+        ]
+        .iter()
+        {
+            assert!(beacons.iter().any(|beacon| beacon.info.scheme == *scheme));
+        }
+
+        for beacon in beacons.iter() {
+            assert!(verify(beacon, &beacon.info));
+            let derived = verify_and_derive_randomness(
+                &beacon.info,
+                beacon.round,
+                &beacon.previous_signature,
+                &beacon.signature,
+            )
+            .unwrap();
+            assert_eq!(derived.to_vec(), beacon.randomness);
+            assert_eq!(randomness(&beacon.signature).to_vec(), beacon.randomness);
+        }
+    }
+
+    #[test]
+    fn test_tampered_beacons() {
+        for beacon in beacons().iter() {
+            for round in [beacon.round - 1, beacon.round + 1].iter() {
+                assert!(!verify_beacon(
+                    &beacon.info,
+                    *round,
+                    &beacon.previous_signature,
+                    &beacon.signature
+                ));
+                assert_eq!(
+                    verify_and_derive_randomness(
+                        &beacon.info,
+                        *round,
+                        &beacon.previous_signature,
+                        &beacon.signature
+                    ),
+                    Err(AmclError::InvalidSignature)
+                );
+            }
+
+            let mut signature = beacon.signature.clone();
+            let last = signature.len() - 1;
+            signature[last] ^= 1;
+            assert!(!verify_beacon(
+                &beacon.info,
+                beacon.round,
+                &beacon.previous_signature,
+                &signature
+            ));
+        }
+    }
+
+    #[test]
+    fn test_previous_signature() {
+        for beacon in beacons().iter() {
+            let mut previous_signature = beacon.previous_signature.clone();
+            previous_signature.push(0);
+            let valid = verify_beacon(
+                &beacon.info,
+                beacon.round,
+                &previous_signature,
+                &beacon.signature,
+            );
+            // Only the chained scheme signs the previous signature
+            assert_eq!(valid, !beacon.info.scheme.is_chained());
+        }
+    }
+
+    #[test]
+    fn test_g1_dst() {
+        // The signatures on G1 only verify under their own scheme's DST
+        for beacon in beacons()
+            .iter()
+            .filter(|beacon| beacon.info.scheme.signature_on_g1())
+        {
+            let mut info = beacon.info.clone();
+            info.scheme = match info.scheme {
+                Scheme::BlsUnchainedOnG1 => Scheme::BlsUnchainedG1Rfc9380,
+                _ => Scheme::BlsUnchainedOnG1,
+            };
+            assert!(!verify(beacon, &info));
+        }
+        assert_eq!(Scheme::BlsUnchainedOnG1.dst(), DST_G2);
+        assert_eq!(Scheme::BlsUnchainedG1Rfc9380.dst(), DST_G1);
+    }
+
+    #[test]
+    fn test_chain_info() {
+        for scheme in [
+            Scheme::PedersenBlsChained,
+            Scheme::PedersenBlsUnchained,
+            Scheme::BlsUnchainedOnG1,
+            Scheme::BlsUnchainedG1Rfc9380,
+        ]
+        .iter()
+        {
+            assert_eq!(Scheme::from_id(scheme.id()), Ok(*scheme));
+        }
+        assert_eq!(
+            Scheme::from_id("bls-bn254-unchained-on-g1").unwrap_err(),
+            AmclError::InvalidSchemeId
+        );
+
+        let g1 = [0u8; G1_BYTES];
+        let g2 = [0u8; G2_BYTES];
+        assert_eq!(
+            ChainInfo::new(&g2, "pedersen-bls-chained").unwrap_err(),
+            AmclError::InvalidG1Size
+        );
+        assert_eq!(
+            ChainInfo::new(&g1, "bls-unchained-g1-rfc9380").unwrap_err(),
+            AmclError::InvalidG2Size
+        );
+    }
+}
@@ -0,0 +1,886 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::core::{
+    deserialize_g1, deserialize_g2, serialize_g1, subgroup_check_g1, subgroup_check_g2,
+};
+use super::light_client::SyncAggregate;
+use super::proof_of_possession;
+use crate::errors::AmclError;
+use crate::ssz::{
+    hash_tree_root_basic_list, hash_tree_root_container, hash_tree_root_list,
+    hash_tree_root_vector, Bitlist, Bitvector, HashTreeRoot, Root,
+};
+
+use std::vec::Vec;
+
+// Re-export constants from core.
+pub use super::core::{G1_BYTES, G2_BYTES, SECRET_KEY_BYTES};
+pub use super::proof_of_possession::DST_G2 as DST;
+
+/// The compressed G2 point at infinity, the signature of an empty set of participants.
+pub const G2_POINT_AT_INFINITY: [u8; G2_BYTES] = {
+    let mut point = [0u8; G2_BYTES];
+    point[0] = 0xc0;
+    point
+};
+
+/*************************************************************************************************
+* Ethereum consensus layer BLS signatures
+*
+* The proof of possession ciphersuite with public keys on G1 and signatures on G2.
+* https://github.com/ethereum/consensus-specs/blob/dev/specs/phase0/beacon-chain.md#bls-signatures
+* https://github.com/ethereum/consensus-specs/blob/dev/specs/altair/bls.md
+*************************************************************************************************/
+
+/// SkToPk
+pub fn secret_key_to_public_key(secret_key: &[u8]) -> Result<[u8; G1_BYTES], AmclError> {
+    proof_of_possession::secret_key_to_public_key_g2(secret_key)
+}
+
+/// Sign
+pub fn sign(secret_key: &[u8], msg: &[u8]) -> Result<[u8; G2_BYTES], AmclError> {
+    proof_of_possession::sign_g2(secret_key, msg)
+}
+
+/// Verify
+pub fn verify(public_key: &[u8], msg: &[u8], signature: &[u8]) -> bool {
+    proof_of_possession::verify_g2(public_key, msg, signature)
+}
+
+/// Aggregate
+///
+/// Fails for an empty list or if any signature is not a valid G2 point in the subgroup.
+pub fn aggregate(signatures: &[&[u8]]) -> Result<[u8; G2_BYTES], AmclError> {
+    for signature in signatures {
+        if !subgroup_check_g2(&deserialize_g2(signature)?) {
+            return Err(AmclError::InvalidPoint);
+        }
+    }
+    proof_of_possession::aggregate_g2(signatures)
+}
+
+/// FastAggregateVerify
+///
+/// False for an empty list of public keys.
+pub fn fast_aggregate_verify(public_keys: &[&[u8]], msg: &[u8], signature: &[u8]) -> bool {
+    proof_of_possession::fast_aggregate_verify_g2(public_keys, msg, signature)
+}
+
+/// AggregateVerify
+///
+/// Messages need not be distinct under the proof of possession ciphersuite.
+pub fn aggregate_verify(public_keys: &[&[u8]], msgs: &[&[u8]], signature: &[u8]) -> bool {
+    proof_of_possession::aggregate_verify_g2(public_keys, msgs, signature)
+}
+
+/// KeyValidate
+///
+/// A public key is valid if it is a G1 point in the subgroup other than the identity.
+pub fn key_validate(public_key: &[u8]) -> bool {
+    match deserialize_g1(public_key) {
+        Ok(point) => !point.is_infinity() && subgroup_check_g1(&point),
+        Err(_) => false,
+    }
+}
+
+/// eth_aggregate_pubkeys
+///
+/// Sum of the public keys, each of which must pass KeyValidate. Fails for an empty list.
+pub fn eth_aggregate_pubkeys(public_keys: &[&[u8]]) -> Result<[u8; G1_BYTES], AmclError> {
+    if public_keys.is_empty() {
+        return Err(AmclError::AggregateEmptyPoints);
+    }
+
+    let mut aggregate = deserialize_g1(public_keys[0])?;
+    if aggregate.is_infinity() || !subgroup_check_g1(&aggregate) {
+        return Err(AmclError::InvalidPoint);
+    }
+    for public_key in public_keys.iter().skip(1) {
+        let public_key = deserialize_g1(public_key)?;
+        if public_key.is_infinity() || !subgroup_check_g1(&public_key) {
+            return Err(AmclError::InvalidPoint);
+        }
+        aggregate.add(&public_key);
+    }
+
+    Ok(serialize_g1(&aggregate))
+}
+
+/// eth_fast_aggregate_verify
+///
+/// As FastAggregateVerify, except that an empty set of public keys with the G2 point at
+/// infinity as the signature is valid.
+pub fn eth_fast_aggregate_verify(public_keys: &[&[u8]], msg: &[u8], signature: &[u8]) -> bool {
+    if public_keys.is_empty() && signature == &G2_POINT_AT_INFINITY[..] {
+        return true;
+    }
+    fast_aggregate_verify(public_keys, msg, signature)
+}
+
+/*************************************************************************************************
+* Signing roots
+*
+* https://github.com/ethereum/consensus-specs/blob/dev/specs/phase0/beacon-chain.md#compute_domain
+*************************************************************************************************/
+
+/// DomainType
+pub type DomainType = [u8; 4];
+/// Version
+pub type Version = [u8; 4];
+/// Domain
+pub type Domain = [u8; 32];
+
+pub const DOMAIN_BEACON_PROPOSER: DomainType = [0x00, 0x00, 0x00, 0x00];
+pub const DOMAIN_BEACON_ATTESTER: DomainType = [0x01, 0x00, 0x00, 0x00];
+pub const DOMAIN_RANDAO: DomainType = [0x02, 0x00, 0x00, 0x00];
+pub const DOMAIN_DEPOSIT: DomainType = [0x03, 0x00, 0x00, 0x00];
+pub const DOMAIN_VOLUNTARY_EXIT: DomainType = [0x04, 0x00, 0x00, 0x00];
+pub const DOMAIN_SELECTION_PROOF: DomainType = [0x05, 0x00, 0x00, 0x00];
+pub const DOMAIN_AGGREGATE_AND_PROOF: DomainType = [0x06, 0x00, 0x00, 0x00];
+pub const DOMAIN_SYNC_COMMITTEE: DomainType = [0x07, 0x00, 0x00, 0x00];
+pub const DOMAIN_SYNC_COMMITTEE_SELECTION_PROOF: DomainType = [0x08, 0x00, 0x00, 0x00];
+pub const DOMAIN_CONTRIBUTION_AND_PROOF: DomainType = [0x09, 0x00, 0x00, 0x00];
+pub const DOMAIN_APPLICATION_MASK: DomainType = [0x00, 0x00, 0x00, 0x01];
+pub const DOMAIN_APPLICATION_BUILDER: DomainType = [0x00, 0x00, 0x00, 0x01];
+
+/// SLOTS_PER_EPOCH of the mainnet preset
+pub const SLOTS_PER_EPOCH: u64 = 32;
+/// MAX_VALIDATORS_PER_COMMITTEE
+pub const MAX_VALIDATORS_PER_COMMITTEE: usize = 2048;
+/// SYNC_COMMITTEE_SIZE / SYNC_COMMITTEE_SUBNET_COUNT
+pub const SYNC_SUBCOMMITTEE_SIZE: usize = 128;
+/// MAX_PROPOSER_SLASHINGS
+pub const MAX_PROPOSER_SLASHINGS: usize = 16;
+/// MAX_ATTESTER_SLASHINGS
+pub const MAX_ATTESTER_SLASHINGS: usize = 2;
+/// MAX_ATTESTATIONS
+pub const MAX_ATTESTATIONS: usize = 128;
+/// MAX_DEPOSITS
+pub const MAX_DEPOSITS: usize = 16;
+/// MAX_VOLUNTARY_EXITS
+pub const MAX_VOLUNTARY_EXITS: usize = 16;
+/// DEPOSIT_CONTRACT_TREE_DEPTH + 1, the length of a deposit proof
+pub const DEPOSIT_PROOF_LENGTH: usize = 33;
+
+/// Checkpoint
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Checkpoint {
+    pub epoch: u64,
+    pub root: Root,
+}
+
+/// AttestationData
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AttestationData {
+    pub slot: u64,
+    pub index: u64,
+    pub beacon_block_root: Root,
+    pub source: Checkpoint,
+    pub target: Checkpoint,
+}
+
+/// BeaconBlockHeader
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BeaconBlockHeader {
+    pub slot: u64,
+    pub proposer_index: u64,
+    pub parent_root: Root,
+    pub state_root: Root,
+    pub body_root: Root,
+}
+
+/// DepositMessage
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepositMessage {
+    pub pubkey: [u8; G1_BYTES],
+    pub withdrawal_credentials: Root,
+    pub amount: u64,
+}
+
+/// VoluntaryExit
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VoluntaryExit {
+    pub epoch: u64,
+    pub validator_index: u64,
+}
+
+/// Attestation
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attestation {
+    pub aggregation_bits: Bitlist,
+    pub data: AttestationData,
+    pub signature: [u8; G2_BYTES],
+}
+
+/// AggregateAndProof
+#[derive(Clone, Debug, PartialEq)]
+pub struct AggregateAndProof {
+    pub aggregator_index: u64,
+    pub aggregate: Attestation,
+    pub selection_proof: [u8; G2_BYTES],
+}
+
+/// SyncAggregatorSelectionData
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SyncAggregatorSelectionData {
+    pub slot: u64,
+    pub subcommittee_index: u64,
+}
+
+/// SyncCommitteeContribution
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyncCommitteeContribution {
+    pub slot: u64,
+    pub beacon_block_root: Root,
+    pub subcommittee_index: u64,
+    pub aggregation_bits: Bitvector,
+    pub signature: [u8; G2_BYTES],
+}
+
+/// ContributionAndProof
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContributionAndProof {
+    pub aggregator_index: u64,
+    pub contribution: SyncCommitteeContribution,
+    pub selection_proof: [u8; G2_BYTES],
+}
+
+/// ValidatorRegistrationV1 of the builder API
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidatorRegistration {
+    pub fee_recipient: [u8; 20],
+    pub gas_limit: u64,
+    pub timestamp: u64,
+    pub pubkey: [u8; G1_BYTES],
+}
+
+/// ForkData
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ForkData {
+    pub current_version: Version,
+    pub genesis_validators_root: Root,
+}
+
+/// SigningData
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SigningData {
+    pub object_root: Root,
+    pub domain: Domain,
+}
+
+/// Eth1Data
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Eth1Data {
+    pub deposit_root: Root,
+    pub deposit_count: u64,
+    pub block_hash: Root,
+}
+
+/// SignedBeaconBlockHeader
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedBeaconBlockHeader {
+    pub message: BeaconBlockHeader,
+    pub signature: [u8; G2_BYTES],
+}
+
+/// ProposerSlashing
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProposerSlashing {
+    pub signed_header_1: SignedBeaconBlockHeader,
+    pub signed_header_2: SignedBeaconBlockHeader,
+}
+
+/// IndexedAttestation
+///
+/// At most MAX_VALIDATORS_PER_COMMITTEE attesting indices.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexedAttestation {
+    pub attesting_indices: Vec<u64>,
+    pub data: AttestationData,
+    pub signature: [u8; G2_BYTES],
+}
+
+/// AttesterSlashing
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttesterSlashing {
+    pub attestation_1: IndexedAttestation,
+    pub attestation_2: IndexedAttestation,
+}
+
+/// DepositData
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepositData {
+    pub pubkey: [u8; G1_BYTES],
+    pub withdrawal_credentials: Root,
+    pub amount: u64,
+    pub signature: [u8; G2_BYTES],
+}
+
+/// Deposit
+///
+/// The proof holds DEPOSIT_PROOF_LENGTH roots.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deposit {
+    pub proof: Vec<Root>,
+    pub data: DepositData,
+}
+
+/// SignedVoluntaryExit
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedVoluntaryExit {
+    pub message: VoluntaryExit,
+    pub signature: [u8; G2_BYTES],
+}
+
+/// BeaconBlockBody of Phase 0, or of Altair when it has a sync aggregate.
+///
+/// The lists must be within their MAX_* limits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BeaconBlockBody {
+    pub randao_reveal: [u8; G2_BYTES],
+    pub eth1_data: Eth1Data,
+    pub graffiti: Root,
+    pub proposer_slashings: Vec<ProposerSlashing>,
+    pub attester_slashings: Vec<AttesterSlashing>,
+    pub attestations: Vec<Attestation>,
+    pub deposits: Vec<Deposit>,
+    pub voluntary_exits: Vec<SignedVoluntaryExit>,
+    pub sync_aggregate: Option<SyncAggregate>,
+}
+
+impl HashTreeRoot for Checkpoint {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[self.epoch.hash_tree_root(), self.root])
+    }
+}
+
+impl HashTreeRoot for AttestationData {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.slot.hash_tree_root(),
+            self.index.hash_tree_root(),
+            self.beacon_block_root,
+            self.source.hash_tree_root(),
+            self.target.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for BeaconBlockHeader {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.slot.hash_tree_root(),
+            self.proposer_index.hash_tree_root(),
+            self.parent_root,
+            self.state_root,
+            self.body_root,
+        ])
+    }
+}
+
+impl HashTreeRoot for DepositMessage {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.pubkey.hash_tree_root(),
+            self.withdrawal_credentials,
+            self.amount.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for VoluntaryExit {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.epoch.hash_tree_root(),
+            self.validator_index.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for Attestation {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.aggregation_bits.hash_tree_root(),
+            self.data.hash_tree_root(),
+            self.signature.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for AggregateAndProof {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.aggregator_index.hash_tree_root(),
+            self.aggregate.hash_tree_root(),
+            self.selection_proof.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for SyncAggregatorSelectionData {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.slot.hash_tree_root(),
+            self.subcommittee_index.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for SyncCommitteeContribution {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.slot.hash_tree_root(),
+            self.beacon_block_root,
+            self.subcommittee_index.hash_tree_root(),
+            self.aggregation_bits.hash_tree_root(),
+            self.signature.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for ContributionAndProof {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.aggregator_index.hash_tree_root(),
+            self.contribution.hash_tree_root(),
+            self.selection_proof.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for ValidatorRegistration {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.fee_recipient.hash_tree_root(),
+            self.gas_limit.hash_tree_root(),
+            self.timestamp.hash_tree_root(),
+            self.pubkey.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for ForkData {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.current_version.hash_tree_root(),
+            self.genesis_validators_root,
+        ])
+    }
+}
+
+impl HashTreeRoot for SigningData {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[self.object_root, self.domain])
+    }
+}
+
+impl HashTreeRoot for Eth1Data {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.deposit_root,
+            self.deposit_count.hash_tree_root(),
+            self.block_hash,
+        ])
+    }
+}
+
+impl HashTreeRoot for SignedBeaconBlockHeader {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.message.hash_tree_root(),
+            self.signature.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for ProposerSlashing {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.signed_header_1.hash_tree_root(),
+            self.signed_header_2.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for IndexedAttestation {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            hash_tree_root_basic_list(&self.attesting_indices, MAX_VALIDATORS_PER_COMMITTEE)
+                .expect("length is within the limit"),
+            self.data.hash_tree_root(),
+            self.signature.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for AttesterSlashing {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.attestation_1.hash_tree_root(),
+            self.attestation_2.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for DepositData {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.pubkey.hash_tree_root(),
+            self.withdrawal_credentials,
+            self.amount.hash_tree_root(),
+            self.signature.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for Deposit {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            hash_tree_root_vector(&self.proof),
+            self.data.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for SignedVoluntaryExit {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.message.hash_tree_root(),
+            self.signature.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for BeaconBlockBody {
+    fn hash_tree_root(&self) -> Root {
+        let expect = "length is within the limit";
+        let mut field_roots = vec![
+            self.randao_reveal.hash_tree_root(),
+            self.eth1_data.hash_tree_root(),
+            self.graffiti,
+            hash_tree_root_list(&self.proposer_slashings, MAX_PROPOSER_SLASHINGS).expect(expect),
+            hash_tree_root_list(&self.attester_slashings, MAX_ATTESTER_SLASHINGS).expect(expect),
+            hash_tree_root_list(&self.attestations, MAX_ATTESTATIONS).expect(expect),
+            hash_tree_root_list(&self.deposits, MAX_DEPOSITS).expect(expect),
+            hash_tree_root_list(&self.voluntary_exits, MAX_VOLUNTARY_EXITS).expect(expect),
+        ];
+        if let Some(sync_aggregate) = &self.sync_aggregate {
+            field_roots.push(sync_aggregate.hash_tree_root());
+        }
+        hash_tree_root_container(&field_roots)
+    }
+}
+
+/// compute_fork_data_root
+pub fn compute_fork_data_root(current_version: &Version, genesis_validators_root: &Root) -> Root {
+    ForkData {
+        current_version: *current_version,
+        genesis_validators_root: *genesis_validators_root,
+    }
+    .hash_tree_root()
+}
+
+/// compute_domain
+///
+/// domain_type || fork_data_root[..28]
+pub fn compute_domain(
+    domain_type: &DomainType,
+    fork_version: &Version,
+    genesis_validators_root: &Root,
+) -> Domain {
+    let fork_data_root = compute_fork_data_root(fork_version, genesis_validators_root);
+    let mut domain = [0u8; 32];
+    domain[..4].copy_from_slice(domain_type);
+    domain[4..].copy_from_slice(&fork_data_root[..28]);
+    domain
+}
+
+/// compute_signing_root
+pub fn compute_signing_root<T: HashTreeRoot>(object: &T, domain: &Domain) -> Root {
+    SigningData {
+        object_root: object.hash_tree_root(),
+        domain: *domain,
+    }
+    .hash_tree_root()
+}
+
+/// Sign the signing root of `object` under `domain`.
+pub fn sign_object<T: HashTreeRoot>(
+    secret_key: &[u8],
+    object: &T,
+    domain: &Domain,
+) -> Result<[u8; G2_BYTES], AmclError> {
+    sign(secret_key, &compute_signing_root(object, domain))
+}
+
+/// Verify a signature over the signing root of `object` under `domain`.
+pub fn verify_object<T: HashTreeRoot>(
+    public_key: &[u8],
+    object: &T,
+    domain: &Domain,
+    signature: &[u8],
+) -> bool {
+    verify(public_key, &compute_signing_root(object, domain), signature)
+}
+
+/// Sign a block header with DOMAIN_BEACON_PROPOSER.
+pub fn sign_beacon_block_header(
+    secret_key: &[u8],
+    header: &BeaconBlockHeader,
+    fork_version: &Version,
+    genesis_validators_root: &Root,
+) -> Result<[u8; G2_BYTES], AmclError> {
+    let domain = compute_domain(
+        &DOMAIN_BEACON_PROPOSER,
+        fork_version,
+        genesis_validators_root,
+    );
+    sign_object(secret_key, header, &domain)
+}
+
+/// Sign attestation data with DOMAIN_BEACON_ATTESTER.
+pub fn sign_attestation_data(
+    secret_key: &[u8],
+    data: &AttestationData,
+    fork_version: &Version,
+    genesis_validators_root: &Root,
+) -> Result<[u8; G2_BYTES], AmclError> {
+    let domain = compute_domain(
+        &DOMAIN_BEACON_ATTESTER,
+        fork_version,
+        genesis_validators_root,
+    );
+    sign_object(secret_key, data, &domain)
+}
+
+/// Sign a voluntary exit with DOMAIN_VOLUNTARY_EXIT.
+pub fn sign_voluntary_exit(
+    secret_key: &[u8],
+    exit: &VoluntaryExit,
+    fork_version: &Version,
+    genesis_validators_root: &Root,
+) -> Result<[u8; G2_BYTES], AmclError> {
+    let domain = compute_domain(
+        &DOMAIN_VOLUNTARY_EXIT,
+        fork_version,
+        genesis_validators_root,
+    );
+    sign_object(secret_key, exit, &domain)
+}
+
+/// Sign a sync committee message, the block root, with DOMAIN_SYNC_COMMITTEE.
+pub fn sign_sync_committee_message(
+    secret_key: &[u8],
+    beacon_block_root: &Root,
+    fork_version: &Version,
+    genesis_validators_root: &Root,
+) -> Result<[u8; G2_BYTES], AmclError> {
+    let domain = compute_domain(
+        &DOMAIN_SYNC_COMMITTEE,
+        fork_version,
+        genesis_validators_root,
+    );
+    sign_object(secret_key, beacon_block_root, &domain)
+}
+
+/// Sign a deposit message with DOMAIN_DEPOSIT.
+///
+/// Deposits are valid across forks so the domain uses the genesis fork version and a zero
+/// genesis validators root.
+pub fn sign_deposit_message(
+    secret_key: &[u8],
+    message: &DepositMessage,
+    genesis_fork_version: &Version,
+) -> Result<[u8; G2_BYTES], AmclError> {
+    let domain = compute_domain(&DOMAIN_DEPOSIT, genesis_fork_version, &[0u8; 32]);
+    sign_object(secret_key, message, &domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde_json::{self, Value};
+    use std::vec::Vec;
+
+    const VECTORS: &str = include_str!("../../tests/fixtures/eth/bls_vectors.json");
+
+    fn bytes(value: &Value) -> Vec<u8> {
+        hex::decode(value.as_str().unwrap().trim_start_matches("0x")).unwrap()
+    }
+
+    fn bytes_list(value: &Value) -> Vec<Vec<u8>> {
+        value.as_array().unwrap().iter().map(bytes).collect()
+    }
+
+    fn cases<'a>(vectors: &'a Value, name: &str) -> &'a Vec<Value> {
+        let cases = vectors[name].as_array().unwrap();
+        assert!(cases.len() > 0);
+        cases
+    }
+
+    // Compare a result to the expected output, which is null on error.
+    fn check(case: &Value, result: Result<Vec<u8>, AmclError>) {
+        let output = &case["output"];
+        match result {
+            Ok(result) => assert_eq!(result, bytes(output), "{}", case["name"]),
+            Err(_) => assert!(output.is_null(), "{}", case["name"]),
+        }
+    }
+
+    fn refs(list: &[Vec<u8>]) -> Vec<&[u8]> {
+        list.iter().map(|x| &x[..]).collect()
+    }
+
+    #[test]
+    fn test_sign() {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        for case in cases(&vectors, "sign") {
+            let input = &case["input"];
+            let result = sign(&bytes(&input["privkey"]), &bytes(&input["message"]));
+            check(case, result.map(|s| s.to_vec()));
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        for case in cases(&vectors, "verify") {
+            let input = &case["input"];
+            let result = verify(
+                &bytes(&input["pubkey"]),
+                &bytes(&input["message"]),
+                &bytes(&input["signature"]),
+            );
+            assert_eq!(Value::Bool(result), case["output"], "{}", case["name"]);
+        }
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        for case in cases(&vectors, "aggregate") {
+            let signatures = bytes_list(&case["input"]);
+            check(case, aggregate(&refs(&signatures)).map(|s| s.to_vec()));
+        }
+    }
+
+    #[test]
+    fn test_fast_aggregate_verify() {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        for case in cases(&vectors, "fast_aggregate_verify") {
+            let input = &case["input"];
+            let public_keys = bytes_list(&input["pubkeys"]);
+            let result = fast_aggregate_verify(
+                &refs(&public_keys),
+                &bytes(&input["message"]),
+                &bytes(&input["signature"]),
+            );
+            assert_eq!(Value::Bool(result), case["output"], "{}", case["name"]);
+        }
+    }
+
+    #[test]
+    fn test_aggregate_verify() {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        for case in cases(&vectors, "aggregate_verify") {
+            let input = &case["input"];
+            let public_keys = bytes_list(&input["pubkeys"]);
+            let msgs = bytes_list(&input["messages"]);
+            let result = aggregate_verify(
+                &refs(&public_keys),
+                &refs(&msgs),
+                &bytes(&input["signature"]),
+            );
+            assert_eq!(Value::Bool(result), case["output"], "{}", case["name"]);
+        }
+    }
+
+    #[test]
+    fn test_eth_aggregate_pubkeys() {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        for case in cases(&vectors, "eth_aggregate_pubkeys") {
+            let public_keys = bytes_list(&case["input"]);
+            let result = eth_aggregate_pubkeys(&refs(&public_keys));
+            check(case, result.map(|pk| pk.to_vec()));
+        }
+    }
+
+    #[test]
+    fn test_eth_fast_aggregate_verify() {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        for case in cases(&vectors, "eth_fast_aggregate_verify") {
+            let input = &case["input"];
+            let public_keys = bytes_list(&input["pubkeys"]);
+            let result = eth_fast_aggregate_verify(
+                &refs(&public_keys),
+                &bytes(&input["message"]),
+                &bytes(&input["signature"]),
+            );
+            assert_eq!(Value::Bool(result), case["output"], "{}", case["name"]);
+        }
+    }
+
+    #[test]
+    fn test_key_validate() {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        for case in cases(&vectors, "sign") {
+            let secret_key = bytes(&case["input"]["privkey"]);
+            if let Ok(public_key) = secret_key_to_public_key(&secret_key) {
+                assert!(key_validate(&public_key));
+            }
+        }
+        let mut infinity = [0u8; G1_BYTES];
+        infinity[0] = 0xc0;
+        assert!(!key_validate(&infinity));
+        assert!(!key_validate(&infinity[..G1_BYTES - 1]));
+    }
+
+    #[test]
+    fn test_compute_domain() {
+        // The mainnet deposit domain
+        let domain = compute_domain(&DOMAIN_DEPOSIT, &[0u8; 4], &[0u8; 32]);
+        assert_eq!(
+            hex::encode(domain),
+            "03000000f5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a9"
+        );
+
+        // Voluntary exits on the mainnet Bellatrix fork
+        let genesis_validators_root =
+            hex::decode("4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95")
+                .unwrap();
+        let mut root = [0u8; 32];
+        root.copy_from_slice(&genesis_validators_root);
+        let domain = compute_domain(&DOMAIN_VOLUNTARY_EXIT, &[0x02, 0, 0, 0], &root);
+        assert_eq!(
+            hex::encode(domain),
+            "040000004a26c58b08add8089b75caa540848881a8d4f0af0be83417a85c0f45"
+        );
+
+        let exit = VoluntaryExit {
+            epoch: 194048,
+            validator_index: 42,
+        };
+        assert_eq!(
+            hex::encode(compute_signing_root(&exit, &domain)),
+            "9dec04ef81139d03a70f1242b6cd4974951cc08cd00ebb2cb1871ffa0ae59ab3"
+        );
+    }
+}
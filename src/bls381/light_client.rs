@@ -0,0 +1,698 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::eth::{
+    self, compute_domain, compute_signing_root, BeaconBlockHeader, Version, DOMAIN_SYNC_COMMITTEE,
+    G1_BYTES, G2_BYTES, SLOTS_PER_EPOCH,
+};
+use crate::errors::AmclError;
+use crate::ssz::{
+    hash_tree_root_container, hash_tree_root_vector, is_valid_merkle_branch, Bitvector,
+    HashTreeRoot, Root,
+};
+
+use std::vec::Vec;
+
+/// SYNC_COMMITTEE_SIZE
+pub const SYNC_COMMITTEE_SIZE: usize = 512;
+/// MIN_SYNC_COMMITTEE_PARTICIPANTS
+pub const MIN_SYNC_COMMITTEE_PARTICIPANTS: usize = 1;
+/// EPOCHS_PER_SYNC_COMMITTEE_PERIOD
+pub const EPOCHS_PER_SYNC_COMMITTEE_PERIOD: u64 = 256;
+
+/// Generalized index of finalized_checkpoint.root in the BeaconState
+pub const FINALIZED_ROOT_GINDEX: u64 = 105;
+/// Generalized index of current_sync_committee in the BeaconState
+pub const CURRENT_SYNC_COMMITTEE_GINDEX: u64 = 54;
+/// Generalized index of next_sync_committee in the BeaconState
+pub const NEXT_SYNC_COMMITTEE_GINDEX: u64 = 55;
+
+/// floorlog2(FINALIZED_ROOT_GINDEX)
+pub const FINALITY_BRANCH_DEPTH: usize = 6;
+/// floorlog2(NEXT_SYNC_COMMITTEE_GINDEX)
+pub const SYNC_COMMITTEE_BRANCH_DEPTH: usize = 5;
+
+/*************************************************************************************************
+* Light client sync protocol
+*
+* https://github.com/ethereum/consensus-specs/blob/dev/specs/altair/light-client/sync-protocol.md
+*
+* Headers are the Altair BeaconBlockHeader and the generalized indexes those of the Altair to
+* Deneb BeaconState. An update is only accepted with a supermajority of the sync committee, so
+* the store never follows a header that less than 2/3 of the committee signed.
+*************************************************************************************************/
+
+/// SyncCommittee
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyncCommittee {
+    pub pubkeys: Vec<[u8; G1_BYTES]>,
+    pub aggregate_pubkey: [u8; G1_BYTES],
+}
+
+/// SyncAggregate
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyncAggregate {
+    pub sync_committee_bits: Bitvector,
+    pub sync_committee_signature: [u8; G2_BYTES],
+}
+
+/// LightClientUpdate
+///
+/// The next sync committee and the finalized header are optional, an update without them only
+/// proves that the committee signed the attested header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LightClientUpdate {
+    pub attested_header: BeaconBlockHeader,
+    pub next_sync_committee: Option<(SyncCommittee, [Root; SYNC_COMMITTEE_BRANCH_DEPTH])>,
+    pub finalized_header: Option<(BeaconBlockHeader, [Root; FINALITY_BRANCH_DEPTH])>,
+    pub sync_aggregate: SyncAggregate,
+    pub signature_slot: u64,
+}
+
+/// LightClientStore
+#[derive(Clone, Debug, PartialEq)]
+pub struct LightClientStore {
+    pub finalized_header: BeaconBlockHeader,
+    pub current_sync_committee: SyncCommittee,
+    pub next_sync_committee: Option<SyncCommittee>,
+    pub genesis_validators_root: Root,
+}
+
+impl HashTreeRoot for SyncCommittee {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            hash_tree_root_vector(&self.pubkeys),
+            self.aggregate_pubkey.hash_tree_root(),
+        ])
+    }
+}
+
+impl HashTreeRoot for SyncAggregate {
+    fn hash_tree_root(&self) -> Root {
+        hash_tree_root_container(&[
+            self.sync_committee_bits.hash_tree_root(),
+            self.sync_committee_signature.hash_tree_root(),
+        ])
+    }
+}
+
+/// compute_sync_committee_period_at_slot
+pub fn compute_sync_committee_period_at_slot(slot: u64) -> u64 {
+    slot / SLOTS_PER_EPOCH / EPOCHS_PER_SYNC_COMMITTEE_PERIOD
+}
+
+/// Check that the sync committee is at NEXT_SYNC_COMMITTEE_GINDEX of the state with `state_root`.
+pub fn verify_next_sync_committee(
+    sync_committee: &SyncCommittee,
+    branch: &[Root],
+    state_root: &Root,
+) -> bool {
+    is_valid_gindex_branch(
+        &sync_committee.hash_tree_root(),
+        branch,
+        NEXT_SYNC_COMMITTEE_GINDEX,
+        state_root,
+    )
+}
+
+/// Check that the sync committee is at CURRENT_SYNC_COMMITTEE_GINDEX of the state with
+/// `state_root`, as in a LightClientBootstrap.
+pub fn verify_current_sync_committee(
+    sync_committee: &SyncCommittee,
+    branch: &[Root],
+    state_root: &Root,
+) -> bool {
+    is_valid_gindex_branch(
+        &sync_committee.hash_tree_root(),
+        branch,
+        CURRENT_SYNC_COMMITTEE_GINDEX,
+        state_root,
+    )
+}
+
+/// Check that the header is the finalized checkpoint of the state with `state_root`.
+pub fn verify_finalized_header(
+    finalized_header: &BeaconBlockHeader,
+    branch: &[Root],
+    state_root: &Root,
+) -> bool {
+    // The genesis block is finalized with a zero root and must be the default header
+    let finalized_root = if finalized_header.slot == 0 {
+        if *finalized_header != BeaconBlockHeader::default() {
+            return false;
+        }
+        [0u8; 32]
+    } else {
+        finalized_header.hash_tree_root()
+    };
+    is_valid_gindex_branch(&finalized_root, branch, FINALIZED_ROOT_GINDEX, state_root)
+}
+
+/// Verify the sync committee signature over `attested_header`.
+///
+/// The participants are selected from the committee by the 512 bit bitfield and at least 2/3 of
+/// the committee must have signed. `fork_version` is the fork at `signature_slot - 1`.
+pub fn verify_sync_aggregate(
+    sync_committee: &SyncCommittee,
+    sync_aggregate: &SyncAggregate,
+    attested_header: &BeaconBlockHeader,
+    fork_version: &Version,
+    genesis_validators_root: &Root,
+) -> Result<(), AmclError> {
+    let bits = &sync_aggregate.sync_committee_bits.bits;
+    if bits.len() != SYNC_COMMITTEE_SIZE || sync_committee.pubkeys.len() != SYNC_COMMITTEE_SIZE {
+        return Err(AmclError::InvalidSszLength);
+    }
+
+    let participants = sync_aggregate.sync_committee_bits.count_ones();
+    if participants < MIN_SYNC_COMMITTEE_PARTICIPANTS || participants * 3 < SYNC_COMMITTEE_SIZE * 2
+    {
+        return Err(AmclError::InsufficientParticipation);
+    }
+
+    let public_keys: Vec<&[u8]> = sync_committee
+        .pubkeys
+        .iter()
+        .zip(bits.iter())
+        .filter(|(_, bit)| **bit)
+        .map(|(public_key, _)| &public_key[..])
+        .collect();
+
+    let domain = compute_domain(
+        &DOMAIN_SYNC_COMMITTEE,
+        fork_version,
+        genesis_validators_root,
+    );
+    let signing_root = compute_signing_root(attested_header, &domain);
+    if !eth::eth_fast_aggregate_verify(
+        &public_keys,
+        &signing_root,
+        &sync_aggregate.sync_committee_signature,
+    ) {
+        return Err(AmclError::InvalidSignature);
+    }
+    Ok(())
+}
+
+impl LightClientStore {
+    /// Initialize from a trusted LightClientBootstrap.
+    ///
+    /// `header` must hash to the trusted block root and commit to the current sync committee.
+    pub fn bootstrap(
+        trusted_block_root: &Root,
+        header: BeaconBlockHeader,
+        current_sync_committee: SyncCommittee,
+        current_sync_committee_branch: &[Root],
+        genesis_validators_root: Root,
+    ) -> Result<LightClientStore, AmclError> {
+        if header.hash_tree_root() != *trusted_block_root
+            || !verify_current_sync_committee(
+                &current_sync_committee,
+                current_sync_committee_branch,
+                &header.state_root,
+            )
+        {
+            return Err(AmclError::InvalidLightClientUpdate);
+        }
+
+        Ok(LightClientStore {
+            finalized_header: header,
+            current_sync_committee,
+            next_sync_committee: None,
+            genesis_validators_root,
+        })
+    }
+
+    /// validate_light_client_update
+    ///
+    /// `fork_version` is the fork version at `update.signature_slot - 1`.
+    pub fn validate_update(
+        &self,
+        update: &LightClientUpdate,
+        fork_version: &Version,
+    ) -> Result<(), AmclError> {
+        let attested_slot = update.attested_header.slot;
+        if update.signature_slot <= attested_slot {
+            return Err(AmclError::InvalidLightClientUpdate);
+        }
+        if let Some((finalized_header, _)) = &update.finalized_header {
+            if attested_slot < finalized_header.slot {
+                return Err(AmclError::InvalidLightClientUpdate);
+            }
+        }
+
+        // The signature must be from the current or, if known, the next sync committee period
+        let store_period = compute_sync_committee_period_at_slot(self.finalized_header.slot);
+        let signature_period = compute_sync_committee_period_at_slot(update.signature_slot);
+        let expected = signature_period == store_period
+            || (self.next_sync_committee.is_some() && signature_period == store_period + 1);
+        if !expected {
+            return Err(AmclError::InvalidLightClientUpdate);
+        }
+
+        // The update must advance the store
+        let attested_period = compute_sync_committee_period_at_slot(attested_slot);
+        let learns_next_sync_committee = self.next_sync_committee.is_none()
+            && update.next_sync_committee.is_some()
+            && attested_period == store_period;
+        if attested_slot <= self.finalized_header.slot && !learns_next_sync_committee {
+            return Err(AmclError::InvalidLightClientUpdate);
+        }
+
+        if let Some((finalized_header, branch)) = &update.finalized_header {
+            if !verify_finalized_header(
+                finalized_header,
+                branch,
+                &update.attested_header.state_root,
+            ) {
+                return Err(AmclError::InvalidLightClientUpdate);
+            }
+        }
+
+        if let Some((next_sync_committee, branch)) = &update.next_sync_committee {
+            if attested_period == store_period {
+                if let Some(known) = &self.next_sync_committee {
+                    if known != next_sync_committee {
+                        return Err(AmclError::InvalidLightClientUpdate);
+                    }
+                }
+            }
+            if !verify_next_sync_committee(
+                next_sync_committee,
+                branch,
+                &update.attested_header.state_root,
+            ) {
+                return Err(AmclError::InvalidLightClientUpdate);
+            }
+        }
+
+        let sync_committee = if signature_period == store_period {
+            &self.current_sync_committee
+        } else {
+            self.next_sync_committee
+                .as_ref()
+                .ok_or(AmclError::InvalidLightClientUpdate)?
+        };
+        verify_sync_aggregate(
+            sync_committee,
+            &update.sync_aggregate,
+            &update.attested_header,
+            fork_version,
+            &self.genesis_validators_root,
+        )
+    }
+
+    /// Validate an update and apply it to the store.
+    ///
+    /// The finalized header advances, rotating the sync committees when it enters the next
+    /// period, and the next sync committee is learned from updates within the current period.
+    /// The store is only modified if the whole update applies.
+    pub fn process_update(
+        &mut self,
+        update: &LightClientUpdate,
+        fork_version: &Version,
+    ) -> Result<(), AmclError> {
+        self.validate_update(update, fork_version)?;
+
+        let store_period = compute_sync_committee_period_at_slot(self.finalized_header.slot);
+        let attested_period = compute_sync_committee_period_at_slot(update.attested_header.slot);
+        let mut next_sync_committee = self.next_sync_committee.clone();
+        if next_sync_committee.is_none() && attested_period == store_period {
+            if let Some((next, _)) = &update.next_sync_committee {
+                next_sync_committee = Some(next.clone());
+            }
+        }
+
+        // The new finalized header and, when it enters the next period, current sync committee
+        let mut finalized = None;
+        if let Some((finalized_header, _)) = &update.finalized_header {
+            if finalized_header.slot > self.finalized_header.slot {
+                let finalized_period = compute_sync_committee_period_at_slot(finalized_header.slot);
+                let mut current_sync_committee = None;
+                if finalized_period == store_period + 1 {
+                    current_sync_committee = Some(
+                        next_sync_committee
+                            .take()
+                            .ok_or(AmclError::InvalidLightClientUpdate)?,
+                    );
+                    // The next committee of the new period is proven by the attested state
+                    next_sync_committee = match &update.next_sync_committee {
+                        Some((next, _)) if attested_period == finalized_period => {
+                            Some(next.clone())
+                        }
+                        _ => None,
+                    };
+                } else if finalized_period != store_period {
+                    return Err(AmclError::InvalidLightClientUpdate);
+                }
+                finalized = Some((finalized_header.clone(), current_sync_committee));
+            }
+        }
+
+        self.next_sync_committee = next_sync_committee;
+        if let Some((finalized_header, current_sync_committee)) = finalized {
+            self.finalized_header = finalized_header;
+            if let Some(current_sync_committee) = current_sync_committee {
+                self.current_sync_committee = current_sync_committee;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Branch check for a generalized index, the depth is its bit length minus one.
+fn is_valid_gindex_branch(leaf: &Root, branch: &[Root], gindex: u64, root: &Root) -> bool {
+    let depth = 63 - gindex.leading_zeros() as usize;
+    let index = gindex - (1 << depth);
+    is_valid_merkle_branch(leaf, branch, depth, index, root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::fr::Fr;
+    use super::super::proof_of_possession::key_generate;
+    use super::*;
+
+    const FORK_VERSION: Version = [0x01, 0x00, 0x00, 0x00];
+    const GENESIS_VALIDATORS_ROOT: Root = [0x4b; 32];
+    const SLOTS_PER_PERIOD: u64 = SLOTS_PER_EPOCH * EPOCHS_PER_SYNC_COMMITTEE_PERIOD;
+
+    struct Committee {
+        secret_keys: Vec<[u8; 32]>,
+        sync_committee: SyncCommittee,
+    }
+
+    fn committee(seed: u16) -> Committee {
+        let secret_keys: Vec<[u8; 32]> = (0..SYNC_COMMITTEE_SIZE as u16)
+            .map(|i| key_generate(&[seed.to_be_bytes(), i.to_be_bytes()].concat(), &[]))
+            .collect();
+        let pubkeys: Vec<[u8; G1_BYTES]> = secret_keys
+            .iter()
+            .map(|sk| eth::secret_key_to_public_key(sk).unwrap())
+            .collect();
+        let keys: Vec<&[u8]> = pubkeys.iter().map(|pk| &pk[..]).collect();
+        let aggregate_pubkey = eth::eth_aggregate_pubkeys(&keys).unwrap();
+        Committee {
+            secret_keys,
+            sync_committee: SyncCommittee {
+                pubkeys,
+                aggregate_pubkey,
+            },
+        }
+    }
+
+    // The first `participants` members sign the header, with the sum of their secret keys as
+    // every signature is over the same message.
+    fn sign(
+        committee: &Committee,
+        participants: usize,
+        header: &BeaconBlockHeader,
+    ) -> SyncAggregate {
+        let mut bits = Bitvector::new(SYNC_COMMITTEE_SIZE);
+        let mut secret_key = Fr::new_int(0);
+        for i in 0..participants {
+            bits.bits[i] = true;
+            secret_key.add(&Fr::from_bytes(&committee.secret_keys[i]).unwrap());
+        }
+        let domain = compute_domain(
+            &DOMAIN_SYNC_COMMITTEE,
+            &FORK_VERSION,
+            &GENESIS_VALIDATORS_ROOT,
+        );
+        let signing_root = compute_signing_root(header, &domain);
+        SyncAggregate {
+            sync_committee_bits: bits,
+            sync_committee_signature: eth::sign(&secret_key.to_bytes(), &signing_root).unwrap(),
+        }
+    }
+
+    // A state root with the given leaves at their generalized indexes and their branches.
+    fn state(leaves: &[(u64, Root)]) -> (Root, Vec<Vec<Root>>) {
+        let mut nodes = [[0u8; 32]; 128];
+        for (gindex, leaf) in leaves.iter().filter(|(g, _)| *g >= 64) {
+            nodes[*gindex as usize] = *leaf;
+        }
+        for i in (1..64).rev() {
+            nodes[i] = match leaves.iter().find(|(g, _)| *g == i as u64) {
+                Some((_, leaf)) => *leaf,
+                None => crate::ssz::hash(&nodes[2 * i], &nodes[2 * i + 1]),
+            };
+        }
+        let branches = leaves
+            .iter()
+            .map(|(gindex, _)| {
+                let mut branch = vec![];
+                let mut g = *gindex as usize;
+                while g > 1 {
+                    branch.push(nodes[g ^ 1]);
+                    g /= 2;
+                }
+                branch
+            })
+            .collect();
+        (nodes[1], branches)
+    }
+
+    fn header(slot: u64, state_root: Root) -> BeaconBlockHeader {
+        BeaconBlockHeader {
+            slot,
+            proposer_index: slot % 7,
+            parent_root: [slot as u8; 32],
+            state_root,
+            body_root: [0x0b; 32],
+        }
+    }
+
+    fn bootstrap(slot: u64, current: &Committee) -> LightClientStore {
+        let leaf = current.sync_committee.hash_tree_root();
+        let (state_root, branches) = state(&[(CURRENT_SYNC_COMMITTEE_GINDEX, leaf)]);
+        let header = header(slot, state_root);
+        LightClientStore::bootstrap(
+            &header.hash_tree_root(),
+            header,
+            current.sync_committee.clone(),
+            &branches[0],
+            GENESIS_VALIDATORS_ROOT,
+        )
+        .unwrap()
+    }
+
+    // An update attesting to a state with the finalized header and next sync committee.
+    fn update(
+        signers: &Committee,
+        participants: usize,
+        attested_slot: u64,
+        finalized_slot: u64,
+        next: Option<&Committee>,
+    ) -> LightClientUpdate {
+        let finalized_header = header(finalized_slot, [0x0f; 32]);
+        let mut leaves = vec![(FINALIZED_ROOT_GINDEX, finalized_header.hash_tree_root())];
+        if let Some(next) = next {
+            leaves.push((
+                NEXT_SYNC_COMMITTEE_GINDEX,
+                next.sync_committee.hash_tree_root(),
+            ));
+        }
+        let (state_root, branches) = state(&leaves);
+        let attested_header = header(attested_slot, state_root);
+
+        let mut finality_branch = [[0u8; 32]; FINALITY_BRANCH_DEPTH];
+        finality_branch.copy_from_slice(&branches[0]);
+        let next_sync_committee = next.map(|next| {
+            let mut branch = [[0u8; 32]; SYNC_COMMITTEE_BRANCH_DEPTH];
+            branch.copy_from_slice(&branches[1]);
+            (next.sync_committee.clone(), branch)
+        });
+        LightClientUpdate {
+            sync_aggregate: sign(signers, participants, &attested_header),
+            attested_header,
+            next_sync_committee,
+            finalized_header: Some((finalized_header, finality_branch)),
+            signature_slot: attested_slot + 1,
+        }
+    }
+
+    #[test]
+    fn test_supermajority_boundary() {
+        let current = committee(0);
+        let next = committee(1);
+        let mut store = bootstrap(100, &current);
+        let initial = store.clone();
+
+        // 341 of 512 is just below 2/3
+        let rejected = update(&current, 341, 200, 150, Some(&next));
+        assert_eq!(
+            store.process_update(&rejected, &FORK_VERSION),
+            Err(AmclError::InsufficientParticipation)
+        );
+        assert_eq!(store, initial);
+
+        let accepted = update(&current, 342, 200, 150, Some(&next));
+        store.process_update(&accepted, &FORK_VERSION).unwrap();
+        assert_eq!(store.finalized_header.slot, 150);
+        assert_eq!(store.current_sync_committee, current.sync_committee);
+        assert_eq!(store.next_sync_committee, Some(next.sync_committee.clone()));
+
+        // Signed by the wrong committee
+        let mut store = initial.clone();
+        let update = update(&next, SYNC_COMMITTEE_SIZE, 200, 150, None);
+        assert_eq!(
+            store.process_update(&update, &FORK_VERSION),
+            Err(AmclError::InvalidSignature)
+        );
+        assert_eq!(store, initial);
+    }
+
+    #[test]
+    fn test_committee_rotation() {
+        let current = committee(0);
+        let next = committee(1);
+        let after = committee(2);
+        let mut store = bootstrap(100, &current);
+
+        // The next period cannot be followed before its committee is known
+        let period_1 = SLOTS_PER_PERIOD;
+        let rotation = update(&next, 400, period_1 + 10, period_1 + 1, Some(&after));
+        let initial = store.clone();
+        assert_eq!(
+            store.process_update(&rotation, &FORK_VERSION),
+            Err(AmclError::InvalidLightClientUpdate)
+        );
+        assert_eq!(store, initial);
+
+        // Learn the next committee without finalizing anything new
+        let learn = update(&current, 400, 200, 100, Some(&next));
+        store.process_update(&learn, &FORK_VERSION).unwrap();
+        assert_eq!(store.finalized_header.slot, 100);
+        assert_eq!(store.next_sync_committee, Some(next.sync_committee.clone()));
+
+        // A conflicting next committee is refused
+        let conflict = update(&current, 400, 300, 250, Some(&after));
+        assert_eq!(
+            store.process_update(&conflict, &FORK_VERSION),
+            Err(AmclError::InvalidLightClientUpdate)
+        );
+
+        // Finalizing a header of the next period rotates the committees
+        store.process_update(&rotation, &FORK_VERSION).unwrap();
+        assert_eq!(store.finalized_header.slot, period_1 + 1);
+        assert_eq!(store.current_sync_committee, next.sync_committee);
+        assert_eq!(
+            store.next_sync_committee,
+            Some(after.sync_committee.clone())
+        );
+
+        // The old committee no longer signs
+        let stale = update(&current, 400, period_1 + 20, period_1 + 15, None);
+        assert_eq!(
+            store.process_update(&stale, &FORK_VERSION),
+            Err(AmclError::InvalidSignature)
+        );
+        let fresh = update(&next, 400, period_1 + 20, period_1 + 15, None);
+        store.process_update(&fresh, &FORK_VERSION).unwrap();
+        assert_eq!(store.finalized_header.slot, period_1 + 15);
+    }
+
+    #[test]
+    fn test_tampered_branches() {
+        let current = committee(0);
+        let next = committee(1);
+        let store = bootstrap(100, &current);
+        let valid = update(&current, 400, 200, 150, Some(&next));
+
+        let mut tampered = valid.clone();
+        if let Some((_, branch)) = &mut tampered.finalized_header {
+            branch[3][0] ^= 1;
+        }
+        let mut updated = store.clone();
+        assert_eq!(
+            updated.process_update(&tampered, &FORK_VERSION),
+            Err(AmclError::InvalidLightClientUpdate)
+        );
+        assert_eq!(updated, store);
+
+        // A different finalized header under the same branch
+        let mut tampered = valid.clone();
+        if let Some((header, _)) = &mut tampered.finalized_header {
+            header.body_root[0] ^= 1;
+        }
+        assert_eq!(
+            updated.process_update(&tampered, &FORK_VERSION),
+            Err(AmclError::InvalidLightClientUpdate)
+        );
+        assert_eq!(updated, store);
+
+        let mut tampered = valid.clone();
+        if let Some((_, branch)) = &mut tampered.next_sync_committee {
+            branch[0][31] ^= 0x80;
+        }
+        assert_eq!(
+            updated.process_update(&tampered, &FORK_VERSION),
+            Err(AmclError::InvalidLightClientUpdate)
+        );
+        assert_eq!(updated, store);
+
+        // A different next committee under the same branch
+        let mut tampered = valid.clone();
+        if let Some((next_sync_committee, _)) = &mut tampered.next_sync_committee {
+            *next_sync_committee = committee(2).sync_committee;
+        }
+        assert_eq!(
+            updated.process_update(&tampered, &FORK_VERSION),
+            Err(AmclError::InvalidLightClientUpdate)
+        );
+        assert_eq!(updated, store);
+
+        updated.process_update(&valid, &FORK_VERSION).unwrap();
+        assert_eq!(updated.finalized_header.slot, 150);
+    }
+
+    #[test]
+    fn test_genesis_finalized_header() {
+        // The genesis checkpoint is a zero root
+        let (state_root, branches) = state(&[(FINALIZED_ROOT_GINDEX, [0u8; 32])]);
+        let genesis = BeaconBlockHeader::default();
+        assert!(verify_finalized_header(&genesis, &branches[0], &state_root));
+
+        // A slot 0 header that is not the default header is refused
+        let mut other = genesis.clone();
+        other.body_root = [0x0b; 32];
+        assert!(!verify_finalized_header(&other, &branches[0], &state_root));
+
+        // Even when its root is the one in the state
+        let other = header(0, [0x0f; 32]);
+        let (state_root, branches) = state(&[(FINALIZED_ROOT_GINDEX, other.hash_tree_root())]);
+        assert!(!verify_finalized_header(&other, &branches[0], &state_root));
+
+        // Later headers are checked by their root
+        let finalized = header(1, [0x0f; 32]);
+        let (state_root, branches) = state(&[(FINALIZED_ROOT_GINDEX, finalized.hash_tree_root())]);
+        assert!(verify_finalized_header(
+            &finalized,
+            &branches[0],
+            &state_root
+        ));
+        assert!(!verify_finalized_header(
+            &genesis,
+            &branches[0],
+            &state_root
+        ));
+    }
+}
@@ -24,9 +24,19 @@ under the License.
 ///
 #[no_std]
 
+pub mod aggregate;
+pub mod asn1;
 pub mod basic;
+pub mod blind;
+pub mod drand;
+pub mod eth;
+pub mod light_client;
 pub mod message_augmentation;
 pub mod proof_of_possession;
+pub mod serialization;
+pub mod slashing_protection;
+pub mod vrf;
+pub mod web3signer;
 
 // Expose helper functions for external libraries.
 pub mod utils;
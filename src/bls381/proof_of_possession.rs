@@ -161,19 +161,27 @@ pub fn fast_aggregate_verify_g1(public_keys: &[&[u8]], msg: &[u8], signature: &[
         return false;
     }
     let signature = signature.unwrap();
+    if !subgroup_check_g1(&signature) {
+        return false;
+    }
 
     let hash = hash_to_curve_g1(msg, DST_G1);
     let mut g = ECP2::generator();
     g.neg();
 
-    let mut aggregate_public_key = ECP2::from_bytes(&public_keys[0]);
-    for public_key in public_keys.iter().skip(1) {
+    let mut aggregate_public_key = ECP2::new();
+    for public_key in public_keys.iter() {
         let public_key = deserialize_g2(public_key);
         if public_key.is_err() {
             return false;
         }
         let public_key = public_key.unwrap();
 
+        // KeyValidate each public key
+        if !subgroup_check_g2(&public_key) || public_key.is_infinity() {
+            return false;
+        }
+
         aggregate_public_key.add(&public_key);
     }
 
@@ -295,19 +303,27 @@ pub fn fast_aggregate_verify_g2(public_keys: &[&[u8]], msg: &[u8], signature: &[
         return false;
     }
     let signature = signature.unwrap();
+    if !subgroup_check_g2(&signature) {
+        return false;
+    }
 
     let hash = hash_to_curve_g2(msg, DST_G2);
     let mut g = ECP::generator();
     g.neg();
 
-    let mut aggregate_public_key = ECP::from_bytes(&public_keys[0]);
-    for public_key in public_keys.iter().skip(1) {
+    let mut aggregate_public_key = ECP::new();
+    for public_key in public_keys.iter() {
         let public_key = deserialize_g1(public_key);
         if public_key.is_err() {
             return false;
         }
         let public_key = public_key.unwrap();
 
+        // KeyValidate each public key
+        if !subgroup_check_g1(&public_key) || public_key.is_infinity() {
+            return false;
+        }
+
         aggregate_public_key.add(&public_key);
     }
 
@@ -321,3 +337,179 @@ pub fn fast_aggregate_verify_g2(public_keys: &[&[u8]], msg: &[u8], signature: &[
     // True if pairing output is 1
     v.is_unity()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    const MSG: &[u8] = b"fast aggregate verify";
+
+    // Compressed infinity for G1 or G2
+    fn infinity(len: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; len];
+        bytes[0] = 0xc0;
+        bytes
+    }
+
+    // A compressed point on the curve but outside the prime order subgroup.
+    fn non_subgroup_g1() -> [u8; G1_BYTES] {
+        for x in 1u8..=255 {
+            let mut bytes = [0u8; G1_BYTES];
+            bytes[0] = 0x80;
+            bytes[G1_BYTES - 1] = x;
+            if let Ok(point) = deserialize_g1(&bytes) {
+                if !subgroup_check_g1(&point) {
+                    return bytes;
+                }
+            }
+        }
+        unreachable!()
+    }
+
+    fn non_subgroup_g2() -> [u8; G2_BYTES] {
+        for x in 1u8..=255 {
+            let mut bytes = [0u8; G2_BYTES];
+            bytes[0] = 0x80;
+            bytes[G2_BYTES - 1] = x;
+            if let Ok(point) = deserialize_g2(&bytes) {
+                if !subgroup_check_g2(&point) {
+                    return bytes;
+                }
+            }
+        }
+        unreachable!()
+    }
+
+    fn keys_g2(n: u8) -> (Vec<[u8; SECRET_KEY_BYTES]>, Vec<[u8; G1_BYTES]>) {
+        let secret_keys: Vec<_> = (1..=n).map(|i| key_generate(&[i; 32], &[])).collect();
+        let public_keys = secret_keys
+            .iter()
+            .map(|sk| secret_key_to_public_key_g2(sk).unwrap())
+            .collect();
+        (secret_keys, public_keys)
+    }
+
+    fn keys_g1(n: u8) -> (Vec<[u8; SECRET_KEY_BYTES]>, Vec<[u8; G2_BYTES]>) {
+        let secret_keys: Vec<_> = (1..=n).map(|i| key_generate(&[i; 32], &[])).collect();
+        let public_keys = secret_keys
+            .iter()
+            .map(|sk| secret_key_to_public_key_g1(sk).unwrap())
+            .collect();
+        (secret_keys, public_keys)
+    }
+
+    // The first key used to be decoded with ECP::from_bytes, which does not read the
+    // compressed format, so valid aggregates were rejected.
+    #[test]
+    fn test_fast_aggregate_verify_g2_valid() {
+        let (secret_keys, public_keys) = keys_g2(3);
+        let signatures: Vec<_> = secret_keys
+            .iter()
+            .map(|sk| sign_g2(sk, MSG).unwrap())
+            .collect();
+        let signatures: Vec<&[u8]> = signatures.iter().map(|s| &s[..]).collect();
+        let signature = aggregate_g2(&signatures).unwrap();
+        let public_keys: Vec<&[u8]> = public_keys.iter().map(|pk| &pk[..]).collect();
+
+        assert!(fast_aggregate_verify_g2(&public_keys, MSG, &signature));
+        assert!(fast_aggregate_verify_g2(
+            &public_keys[..1],
+            MSG,
+            signatures[0]
+        ));
+        assert!(!fast_aggregate_verify_g2(
+            &public_keys[..2],
+            MSG,
+            &signature
+        ));
+        assert!(!fast_aggregate_verify_g2(&[], MSG, &signature));
+    }
+
+    #[test]
+    fn test_fast_aggregate_verify_g1_valid() {
+        let (secret_keys, public_keys) = keys_g1(3);
+        let signatures: Vec<_> = secret_keys
+            .iter()
+            .map(|sk| sign_g1(sk, MSG).unwrap())
+            .collect();
+        let signatures: Vec<&[u8]> = signatures.iter().map(|s| &s[..]).collect();
+        let signature = aggregate_g1(&signatures).unwrap();
+        let public_keys: Vec<&[u8]> = public_keys.iter().map(|pk| &pk[..]).collect();
+
+        assert!(fast_aggregate_verify_g1(&public_keys, MSG, &signature));
+        assert!(fast_aggregate_verify_g1(
+            &public_keys[..1],
+            MSG,
+            signatures[0]
+        ));
+        assert!(!fast_aggregate_verify_g1(
+            &public_keys[..2],
+            MSG,
+            &signature
+        ));
+    }
+
+    // Identity keys must fail KeyValidate, otherwise the identity signature verifies and an
+    // identity key can be added to any aggregate.
+    #[test]
+    fn test_fast_aggregate_verify_rejects_infinity_public_key() {
+        let infinity_g1 = infinity(G1_BYTES);
+        let infinity_g2 = infinity(G2_BYTES);
+        assert!(!fast_aggregate_verify_g2(
+            &[&infinity_g1],
+            MSG,
+            &infinity_g2
+        ));
+        assert!(!fast_aggregate_verify_g1(
+            &[&infinity_g2],
+            MSG,
+            &infinity_g1
+        ));
+
+        let (secret_keys, public_keys) = keys_g2(1);
+        let signature = sign_g2(&secret_keys[0], MSG).unwrap();
+        assert!(!fast_aggregate_verify_g2(
+            &[&public_keys[0], &infinity_g1],
+            MSG,
+            &signature
+        ));
+
+        let (secret_keys, public_keys) = keys_g1(1);
+        let signature = sign_g1(&secret_keys[0], MSG).unwrap();
+        assert!(!fast_aggregate_verify_g1(
+            &[&public_keys[0], &infinity_g2],
+            MSG,
+            &signature
+        ));
+    }
+
+    #[test]
+    fn test_fast_aggregate_verify_rejects_points_outside_subgroup() {
+        let (secret_keys, public_keys) = keys_g2(1);
+        let signature = sign_g2(&secret_keys[0], MSG).unwrap();
+        assert!(!fast_aggregate_verify_g2(
+            &[&public_keys[0]],
+            MSG,
+            &non_subgroup_g2()
+        ));
+        assert!(!fast_aggregate_verify_g2(
+            &[&public_keys[0], &non_subgroup_g1()],
+            MSG,
+            &signature
+        ));
+
+        let (secret_keys, public_keys) = keys_g1(1);
+        let signature = sign_g1(&secret_keys[0], MSG).unwrap();
+        assert!(!fast_aggregate_verify_g1(
+            &[&public_keys[0]],
+            MSG,
+            &non_subgroup_g1()
+        ));
+        assert!(!fast_aggregate_verify_g1(
+            &[&public_keys[0], &non_subgroup_g2()],
+            MSG,
+            &signature
+        ));
+    }
+}
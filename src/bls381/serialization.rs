@@ -0,0 +1,680 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::super::big::{Big, MODBYTES};
+use super::super::ecp::ECP;
+use super::super::ecp2::ECP2;
+use super::super::fp12::FP12;
+use super::super::fr::Fr;
+use super::super::rom::CURVE_ORDER;
+use super::core::{
+    self, deserialize_g1, deserialize_g2, secret_key_from_bytes, serialize_g1, serialize_g2,
+    subgroup_check_g1, subgroup_check_g2, G1_BYTES, G2_BYTES, SECRET_KEY_BYTES,
+};
+use super::proof_of_possession;
+use crate::errors::AmclError;
+use crate::serde::de::{self, Visitor};
+use crate::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use std::fmt;
+use std::ptr;
+use std::string::String;
+use std::vec::Vec;
+
+/// The number of bytes of a serialized FP12
+pub const FP12_BYTES: usize = MODBYTES * 12;
+
+/*************************************************************************************************
+* Serde
+*
+* Points use the compressed ZCash encodings of `core`, scalars 32 big-endian bytes and Gt
+* elements `FP12::to_bytes`. Human readable formats such as JSON get a 0x prefixed hex string,
+* other formats the raw bytes.
+*
+* Deserialization validates: points must be in the prime order subgroup, public keys must not
+* be the identity, Gt elements must be in the order r subgroup of Fp12 and every encoding must
+* be canonical. Secret keys only implement serde with the `serde_secret_keys` feature.
+*************************************************************************************************/
+
+/// A public key on G1, used when signatures are on G2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey([u8; G1_BYTES]);
+
+/// A signature on G2, used when public keys are on G1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature([u8; G2_BYTES]);
+
+/// A public key on G2, used when signatures are on G1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKeyG2([u8; G2_BYTES]);
+
+/// A signature on G1, used when public keys are on G2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SignatureG1([u8; G1_BYTES]);
+
+/// A secret key, overwritten with zeros when dropped.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretKey([u8; SECRET_KEY_BYTES]);
+
+impl PublicKey {
+    /// KeyValidate a compressed or uncompressed G1 public key.
+    pub fn from_bytes(bytes: &[u8]) -> Result<PublicKey, AmclError> {
+        let point = deserialize_g1(bytes)?;
+        if point.is_infinity() || !subgroup_check_g1(&point) {
+            return Err(AmclError::InvalidPoint);
+        }
+        Ok(PublicKey(serialize_g1(&point)))
+    }
+
+    /// The compressed public key
+    pub fn to_bytes(&self) -> [u8; G1_BYTES] {
+        self.0
+    }
+}
+
+impl Signature {
+    /// Validate a compressed or uncompressed G2 signature.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Signature, AmclError> {
+        let point = deserialize_g2(bytes)?;
+        if !subgroup_check_g2(&point) {
+            return Err(AmclError::InvalidPoint);
+        }
+        Ok(Signature(serialize_g2(&point)))
+    }
+
+    /// The compressed signature
+    pub fn to_bytes(&self) -> [u8; G2_BYTES] {
+        self.0
+    }
+}
+
+impl PublicKeyG2 {
+    /// KeyValidate a compressed or uncompressed G2 public key.
+    pub fn from_bytes(bytes: &[u8]) -> Result<PublicKeyG2, AmclError> {
+        let point = deserialize_g2(bytes)?;
+        if point.is_infinity() || !subgroup_check_g2(&point) {
+            return Err(AmclError::InvalidPoint);
+        }
+        Ok(PublicKeyG2(serialize_g2(&point)))
+    }
+
+    /// The compressed public key
+    pub fn to_bytes(&self) -> [u8; G2_BYTES] {
+        self.0
+    }
+}
+
+impl SignatureG1 {
+    /// Validate a compressed or uncompressed G1 signature.
+    pub fn from_bytes(bytes: &[u8]) -> Result<SignatureG1, AmclError> {
+        let point = deserialize_g1(bytes)?;
+        if !subgroup_check_g1(&point) {
+            return Err(AmclError::InvalidPoint);
+        }
+        Ok(SignatureG1(serialize_g1(&point)))
+    }
+
+    /// The compressed signature
+    pub fn to_bytes(&self) -> [u8; G1_BYTES] {
+        self.0
+    }
+}
+
+impl SecretKey {
+    /// A secret key from 32 big-endian bytes, which must be in the range [1, r).
+    pub fn from_bytes(bytes: &[u8]) -> Result<SecretKey, AmclError> {
+        secret_key_from_bytes(bytes)?;
+        let mut key = [0u8; SECRET_KEY_BYTES];
+        key.copy_from_slice(bytes);
+        Ok(SecretKey(key))
+    }
+
+    /// The secret key bytes
+    pub fn to_bytes(&self) -> [u8; SECRET_KEY_BYTES] {
+        self.0
+    }
+
+    /// The public key when signatures are on G2.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(
+            core::secret_key_to_public_key_g2(&self.0)
+                .expect("secret key was validated on creation"),
+        )
+    }
+
+    /// The public key when signatures are on G1.
+    pub fn public_key_g2(&self) -> PublicKeyG2 {
+        PublicKeyG2(
+            core::secret_key_to_public_key_g1(&self.0)
+                .expect("secret key was validated on creation"),
+        )
+    }
+
+    /// Sign `msg` with the proof of possession scheme and signatures on G2, as Ethereum does.
+    ///
+    /// Signs from the held bytes so no unzeroized copy of the key is made.
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        Signature(
+            proof_of_possession::sign_g2(&self.0, msg)
+                .expect("secret key was validated on creation"),
+        )
+    }
+}
+
+// Never print the key material
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretKey(..)")
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        for byte in self.0.iter_mut() {
+            // Volatile so the write is not optimised away
+            unsafe { ptr::write_volatile(byte, 0) };
+        }
+    }
+}
+
+/*************************************************************************************************
+* Serialize and Deserialize implementations
+*************************************************************************************************/
+
+macro_rules! impl_serde {
+    ($t:ty, $to_bytes:expr, $from_bytes:expr) => {
+        impl Serialize for $t {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let to_bytes: fn(&$t) -> Vec<u8> = $to_bytes;
+                serialize_bytes(&to_bytes(self), serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $t {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<$t, D::Error> {
+                let from_bytes: fn(&[u8]) -> Result<$t, AmclError> = $from_bytes;
+                let bytes = deserialize_bytes(deserializer)?;
+                from_bytes(&bytes).map_err(|e| de::Error::custom(format!("{:?}", e)))
+            }
+        }
+    };
+}
+
+impl_serde!(PublicKey, |k| k.0.to_vec(), PublicKey::from_bytes);
+impl_serde!(Signature, |s| s.0.to_vec(), Signature::from_bytes);
+impl_serde!(PublicKeyG2, |k| k.0.to_vec(), PublicKeyG2::from_bytes);
+impl_serde!(SignatureG1, |s| s.0.to_vec(), SignatureG1::from_bytes);
+#[cfg(feature = "serde_secret_keys")]
+impl_serde!(SecretKey, |k| k.0.to_vec(), SecretKey::from_bytes);
+impl_serde!(ECP, |p| serialize_g1(p).to_vec(), ecp_from_bytes);
+impl_serde!(ECP2, |p| serialize_g2(p).to_vec(), ecp2_from_bytes);
+impl_serde!(Fr, |s| s.to_bytes().to_vec(), Fr::from_bytes);
+impl_serde!(FP12, fp12_to_bytes, fp12_from_bytes);
+
+// A compressed G1 point in the subgroup, the identity is allowed.
+fn ecp_from_bytes(bytes: &[u8]) -> Result<ECP, AmclError> {
+    if bytes.len() != G1_BYTES {
+        return Err(AmclError::InvalidG1Size);
+    }
+    let point = deserialize_g1(bytes)?;
+    if !subgroup_check_g1(&point) {
+        return Err(AmclError::InvalidPoint);
+    }
+    Ok(point)
+}
+
+// A compressed G2 point in the subgroup, the identity is allowed.
+fn ecp2_from_bytes(bytes: &[u8]) -> Result<ECP2, AmclError> {
+    if bytes.len() != G2_BYTES {
+        return Err(AmclError::InvalidG2Size);
+    }
+    let point = deserialize_g2(bytes)?;
+    if !subgroup_check_g2(&point) {
+        return Err(AmclError::InvalidPoint);
+    }
+    Ok(point)
+}
+
+fn fp12_to_bytes(gt: &FP12) -> Vec<u8> {
+    let mut bytes = vec![0u8; FP12_BYTES];
+    gt.to_bytes(&mut bytes);
+    bytes
+}
+
+// An element of the order r subgroup of Fp12 with every coefficient less than the modulus.
+fn fp12_from_bytes(bytes: &[u8]) -> Result<FP12, AmclError> {
+    if bytes.len() != FP12_BYTES {
+        return Err(AmclError::InvalidEncoding);
+    }
+    let gt = FP12::from_bytes(bytes);
+
+    // Coefficients are reduced on conversion so a non-canonical encoding changes
+    if fp12_to_bytes(&gt) != bytes {
+        return Err(AmclError::InvalidEncoding);
+    }
+    if gt.is_zilch() || !gt.pow(&Big::new_ints(&CURVE_ORDER)).is_unity() {
+        return Err(AmclError::InvalidPoint);
+    }
+    Ok(gt)
+}
+
+fn serialize_bytes<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        let mut s = String::from("0x");
+        s.push_str(&hex::encode(bytes));
+        serializer.serialize_str(&s)
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+fn deserialize_bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(HexVisitor)
+    } else {
+        deserializer.deserialize_bytes(BytesVisitor)
+    }
+}
+
+struct HexVisitor;
+
+impl<'de> Visitor<'de> for HexVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a 0x prefixed hex string")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Vec<u8>, E> {
+        let s = s
+            .strip_prefix("0x")
+            .ok_or_else(|| E::custom("missing 0x prefix"))?;
+        hex::decode(s).map_err(|_| E::custom("invalid hex"))
+    }
+}
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a byte array")
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(bytes.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(bytes)
+    }
+
+    // Formats without a byte type encode bytes as a sequence
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::pair;
+    use super::super::super::rom::MODULUS;
+    use super::super::core::{serialize_uncompressed_g1, serialize_uncompressed_g2};
+    use super::super::proof_of_possession::{key_generate, secret_key_to_public_key_g2};
+    use super::*;
+    use crate::serde::de::value::{Error, SeqDeserializer};
+    use crate::serde::ser::{self, Impossible};
+    use crate::serde_json;
+    use std::marker::PhantomData;
+
+    // A minimal binary format, bytes are written as they are and read either as bytes or as a
+    // sequence of u8 for formats without a byte type.
+    struct BinarySerializer;
+
+    macro_rules! unsupported {
+        ($($method:ident($($arg:ty),*) -> $ok:ty;)*) => {
+            $(fn $method(self, $(_: $arg),*) -> Result<$ok, Error> {
+                Err(ser::Error::custom("unsupported"))
+            })*
+        };
+    }
+
+    impl Serializer for BinarySerializer {
+        type Ok = Vec<u8>;
+        type Error = Error;
+        type SerializeSeq = Impossible<Vec<u8>, Error>;
+        type SerializeTuple = Impossible<Vec<u8>, Error>;
+        type SerializeTupleStruct = Impossible<Vec<u8>, Error>;
+        type SerializeTupleVariant = Impossible<Vec<u8>, Error>;
+        type SerializeMap = Impossible<Vec<u8>, Error>;
+        type SerializeStruct = Impossible<Vec<u8>, Error>;
+        type SerializeStructVariant = Impossible<Vec<u8>, Error>;
+
+        fn is_human_readable(&self) -> bool {
+            false
+        }
+
+        fn serialize_bytes(self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+            Ok(bytes.to_vec())
+        }
+
+        fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> Result<Vec<u8>, Error> {
+            Err(ser::Error::custom("unsupported"))
+        }
+
+        fn serialize_newtype_struct<T: ?Sized + Serialize>(
+            self,
+            _: &'static str,
+            _: &T,
+        ) -> Result<Vec<u8>, Error> {
+            Err(ser::Error::custom("unsupported"))
+        }
+
+        fn serialize_newtype_variant<T: ?Sized + Serialize>(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: &T,
+        ) -> Result<Vec<u8>, Error> {
+            Err(ser::Error::custom("unsupported"))
+        }
+
+        unsupported! {
+            serialize_bool(bool) -> Vec<u8>;
+            serialize_i8(i8) -> Vec<u8>;
+            serialize_i16(i16) -> Vec<u8>;
+            serialize_i32(i32) -> Vec<u8>;
+            serialize_i64(i64) -> Vec<u8>;
+            serialize_u8(u8) -> Vec<u8>;
+            serialize_u16(u16) -> Vec<u8>;
+            serialize_u32(u32) -> Vec<u8>;
+            serialize_u64(u64) -> Vec<u8>;
+            serialize_f32(f32) -> Vec<u8>;
+            serialize_f64(f64) -> Vec<u8>;
+            serialize_char(char) -> Vec<u8>;
+            serialize_str(&str) -> Vec<u8>;
+            serialize_none() -> Vec<u8>;
+            serialize_unit() -> Vec<u8>;
+            serialize_unit_struct(&'static str) -> Vec<u8>;
+            serialize_unit_variant(&'static str, u32, &'static str) -> Vec<u8>;
+            serialize_seq(Option<usize>) -> Self::SerializeSeq;
+            serialize_tuple(usize) -> Self::SerializeTuple;
+            serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+            serialize_tuple_variant(&'static str, u32, &'static str, usize)
+                -> Self::SerializeTupleVariant;
+            serialize_map(Option<usize>) -> Self::SerializeMap;
+            serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+            serialize_struct_variant(&'static str, u32, &'static str, usize)
+                -> Self::SerializeStructVariant;
+        }
+    }
+
+    struct BinaryDeserializer<'a> {
+        bytes: &'a [u8],
+        as_seq: bool,
+    }
+
+    impl<'de, 'a> Deserializer<'de> for BinaryDeserializer<'a> {
+        type Error = Error;
+
+        fn is_human_readable(&self) -> bool {
+            false
+        }
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            if self.as_seq {
+                visitor.visit_seq(SeqDeserializer::new(self.bytes.iter().cloned()))
+            } else {
+                visitor.visit_bytes(self.bytes)
+            }
+        }
+
+        crate::serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+            option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum
+            identifier ignored_any
+        }
+    }
+
+    fn from_binary<'de, T: Deserialize<'de>>(bytes: &[u8], as_seq: bool) -> Result<T, Error> {
+        T::deserialize(BinaryDeserializer { bytes, as_seq })
+    }
+
+    fn json_hex(bytes: &[u8]) -> String {
+        format!("\"0x{}\"", hex::encode(bytes))
+    }
+
+    // Both formats encode `value` as `bytes` and decode it back.
+    fn check_round_trip<T>(value: &T, bytes: &[u8], equals: fn(&T, &T) -> bool)
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+    {
+        let json = serde_json::to_string(value).unwrap();
+        assert_eq!(json, json_hex(bytes));
+        assert!(equals(&serde_json::from_str(&json).unwrap(), value));
+
+        assert_eq!(value.serialize(BinarySerializer).unwrap(), bytes);
+        assert!(equals(&from_binary(bytes, false).unwrap(), value));
+        assert!(equals(&from_binary(bytes, true).unwrap(), value));
+    }
+
+    fn check_rejected<T: for<'de> Deserialize<'de>>(bytes: &[u8]) {
+        assert!(serde_json::from_str::<T>(&json_hex(bytes)).is_err());
+        assert!(from_binary::<T>(bytes, false).is_err());
+        assert!(from_binary::<T>(bytes, true).is_err());
+    }
+
+    // Compressed points of E(Fp) and E'(Fp2) that are not in the prime order subgroup.
+    fn non_subgroup_g1() -> [u8; G1_BYTES] {
+        (1u8..)
+            .map(|x| {
+                let mut bytes = [0u8; G1_BYTES];
+                bytes[0] = 0x80;
+                bytes[G1_BYTES - 1] = x;
+                bytes
+            })
+            .find(|bytes| deserialize_g1(bytes).map_or(false, |p| !subgroup_check_g1(&p)))
+            .unwrap()
+    }
+
+    fn non_subgroup_g2() -> [u8; G2_BYTES] {
+        (1u8..)
+            .map(|x| {
+                let mut bytes = [0u8; G2_BYTES];
+                bytes[0] = 0x80;
+                bytes[G2_BYTES - 1] = x;
+                bytes
+            })
+            .find(|bytes| deserialize_g2(bytes).map_or(false, |p| !subgroup_check_g2(&p)))
+            .unwrap()
+    }
+
+    fn identity_g1() -> [u8; G1_BYTES] {
+        serialize_g1(&ECP::new())
+    }
+
+    fn identity_g2() -> [u8; G2_BYTES] {
+        serialize_g2(&ECP2::new())
+    }
+
+    #[test]
+    fn test_public_key_and_signature_serde() {
+        let secret_key = key_generate(&[49; 32], &[]);
+        let public_key =
+            PublicKey::from_bytes(&secret_key_to_public_key_g2(&secret_key).unwrap()).unwrap();
+        check_round_trip(&public_key, &public_key.to_bytes(), PartialEq::eq);
+        let signature =
+            Signature::from_bytes(&proof_of_possession::sign_g2(&secret_key, b"serde").unwrap())
+                .unwrap();
+        check_round_trip(&signature, &signature.to_bytes(), PartialEq::eq);
+        let held = SecretKey::from_bytes(&secret_key).unwrap();
+        assert_eq!(held.sign(b"serde"), signature);
+        assert_eq!(held.public_key(), public_key);
+
+        let public_key_g2 = PublicKeyG2::from_bytes(&serialize_g2(&ECP2::generator())).unwrap();
+        check_round_trip(&public_key_g2, &public_key_g2.to_bytes(), PartialEq::eq);
+        let signature_g1 = SignatureG1::from_bytes(&serialize_g1(&ECP::generator())).unwrap();
+        check_round_trip(&signature_g1, &signature_g1.to_bytes(), PartialEq::eq);
+
+        // Identity public keys are refused, identity signatures are valid points
+        check_rejected::<PublicKey>(&identity_g1());
+        check_rejected::<PublicKeyG2>(&identity_g2());
+        let identity = SignatureG1::from_bytes(&identity_g1()).unwrap();
+        check_round_trip(&identity, &identity_g1(), PartialEq::eq);
+
+        check_rejected::<PublicKey>(&non_subgroup_g1());
+        check_rejected::<SignatureG1>(&non_subgroup_g1());
+        check_rejected::<PublicKeyG2>(&non_subgroup_g2());
+        check_rejected::<Signature>(&non_subgroup_g2());
+
+        check_rejected::<PublicKey>(&public_key.to_bytes()[1..]);
+        check_rejected::<Signature>(&signature.to_bytes()[1..]);
+    }
+
+    #[test]
+    fn test_point_serde() {
+        let g1 = ECP::generator().mul(&Big::new_int(49));
+        check_round_trip(&g1, &serialize_g1(&g1), ECP::equals);
+        check_round_trip(&ECP::new(), &identity_g1(), ECP::equals);
+        let g2 = ECP2::generator().mul(&Big::new_int(49));
+        check_round_trip(&g2, &serialize_g2(&g2), ECP2::equals);
+        check_round_trip(&ECP2::new(), &identity_g2(), ECP2::equals);
+
+        check_rejected::<ECP>(&non_subgroup_g1());
+        check_rejected::<ECP2>(&non_subgroup_g2());
+
+        // Only the compressed encodings
+        check_rejected::<ECP>(&serialize_uncompressed_g1(&g1));
+        check_rejected::<ECP2>(&serialize_uncompressed_g2(&g2));
+    }
+
+    #[test]
+    fn test_fr_serde() {
+        let scalar = Fr::new_int(49);
+        check_round_trip(&scalar, &scalar.to_bytes(), PartialEq::eq);
+        let mut minus_one = Fr::new_int(1);
+        minus_one.neg();
+        check_round_trip(&minus_one, &minus_one.to_bytes(), PartialEq::eq);
+
+        // r and 2^256 - 1 are out of range
+        let mut r = [0u8; MODBYTES];
+        Big::new_ints(&CURVE_ORDER).to_bytes(&mut r);
+        check_rejected::<Fr>(&r[MODBYTES - 32..]);
+        check_rejected::<Fr>(&[0xff; 32]);
+        check_rejected::<Fr>(&scalar.to_bytes()[1..]);
+    }
+
+    #[test]
+    fn test_gt_serde() {
+        let gt = pair::fexp(&pair::ate(&ECP2::generator(), &ECP::generator()));
+        let bytes = fp12_to_bytes(&gt);
+        check_round_trip(&gt, &bytes, FP12::equals);
+        let one = FP12::new_int(1);
+        check_round_trip(&one, &fp12_to_bytes(&one), FP12::equals);
+
+        // Adding the modulus to a coefficient gives the same element non-canonically
+        let mut coefficient = Big::from_bytes(&bytes[..MODBYTES]);
+        coefficient.add(&Big::new_ints(&MODULUS));
+        coefficient.norm();
+        let mut non_canonical = bytes.clone();
+        coefficient.to_bytes(&mut non_canonical[..MODBYTES]);
+        assert!(FP12::from_bytes(&non_canonical).equals(&gt));
+        assert_eq!(
+            fp12_from_bytes(&non_canonical).err(),
+            Some(AmclError::InvalidEncoding)
+        );
+        check_rejected::<FP12>(&non_canonical);
+
+        // Zero and 2 are not in the order r subgroup
+        check_rejected::<FP12>(&fp12_to_bytes(&FP12::new()));
+        check_rejected::<FP12>(&fp12_to_bytes(&FP12::new_int(2)));
+        check_rejected::<FP12>(&bytes[1..]);
+    }
+
+    #[test]
+    fn test_hex_strings() {
+        let public_key = serialize_g1(&ECP::generator());
+        let hex = hex::encode(public_key);
+
+        // Missing 0x prefix
+        assert!(serde_json::from_str::<PublicKey>(&format!("\"{}\"", hex)).is_err());
+        assert!(serde_json::from_str::<Fr>(&format!("\"{}\"", "00".repeat(32))).is_err());
+
+        // Invalid hex
+        assert!(serde_json::from_str::<PublicKey>(&format!("\"0x{}zz\"", &hex[2..])).is_err());
+        assert!(serde_json::from_str::<PublicKey>(&format!("\"0x{}0\"", hex)).is_err());
+        assert!(serde_json::from_str::<PublicKey>(&format!("\"0X{}\"", hex)).is_err());
+
+        // Not a string
+        assert!(serde_json::from_str::<PublicKey>("[1, 2, 3]").is_err());
+
+        // Upper case hex is accepted, the output is lower case
+        let public_key: PublicKey =
+            serde_json::from_str(&format!("\"0x{}\"", hex.to_uppercase())).unwrap();
+        assert_eq!(
+            serde_json::to_string(&public_key).unwrap(),
+            json_hex(&public_key.0)
+        );
+    }
+
+    // Resolves to the inherent method when T implements Serialize and Deserialize, otherwise to
+    // the trait method.
+    struct Probe<T>(PhantomData<T>);
+
+    trait NotSerde {
+        fn is_serde(&self) -> bool {
+            false
+        }
+    }
+
+    impl<T> NotSerde for Probe<T> {}
+
+    impl<T: Serialize + for<'de> Deserialize<'de>> Probe<T> {
+        fn is_serde(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_secret_key_serde_needs_feature() {
+        let is_serde = Probe::<SecretKey>(PhantomData).is_serde();
+        assert_eq!(is_serde, cfg!(feature = "serde_secret_keys"));
+        assert!(Probe::<PublicKey>(PhantomData).is_serde());
+    }
+
+    #[cfg(feature = "serde_secret_keys")]
+    #[test]
+    fn test_secret_key_serde() {
+        let secret_key = SecretKey::from_bytes(&key_generate(&[49; 32], &[])).unwrap();
+        check_round_trip(&secret_key, &secret_key.to_bytes(), PartialEq::eq);
+
+        let mut r = [0u8; MODBYTES];
+        Big::new_ints(&CURVE_ORDER).to_bytes(&mut r);
+        check_rejected::<SecretKey>(&r[MODBYTES - 32..]);
+        check_rejected::<SecretKey>(&[0u8; SECRET_KEY_BYTES]);
+        check_rejected::<SecretKey>(&secret_key.to_bytes()[1..]);
+    }
+}
@@ -0,0 +1,665 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::eth::{
+    self, compute_domain, compute_signing_root, AttestationData, BeaconBlockHeader, Version,
+    DOMAIN_BEACON_ATTESTER, DOMAIN_BEACON_PROPOSER, G1_BYTES, G2_BYTES,
+};
+use crate::errors::AmclError;
+use crate::ssz::Root;
+
+use std::collections::BTreeMap;
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+/// The EIP-3076 interchange format version produced and accepted
+pub const INTERCHANGE_FORMAT_VERSION: &str = "5";
+
+/*************************************************************************************************
+* Slashing protection
+*
+* https://eips.ethereum.org/EIPS/eip-3076
+*
+* Signing is refused if it could produce a slashable message or if it would go below the
+* lowest slot or epochs recorded for the validator, which enforces the minimal rules when the
+* history has been pruned or imported from a minimal interchange.
+*************************************************************************************************/
+
+/// Result of a successful slashing protection check.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Safety {
+    /// The message is new and has been recorded.
+    Safe,
+    /// An identical message was signed before, signing it again is not slashable.
+    SameData,
+}
+
+/// A block proposal recorded for a validator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedBlock {
+    pub slot: u64,
+    pub signing_root: Option<Root>,
+}
+
+/// An attestation recorded for a validator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedAttestation {
+    pub source_epoch: u64,
+    pub target_epoch: u64,
+    pub signing_root: Option<Root>,
+}
+
+/// Signing history of a single validator.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidatorHistory {
+    pub signed_blocks: Vec<SignedBlock>,
+    pub signed_attestations: Vec<SignedAttestation>,
+}
+
+/// Slashing protection records for the validators of one chain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SlashingDatabase {
+    pub genesis_validators_root: Root,
+    pub validators: BTreeMap<[u8; G1_BYTES], ValidatorHistory>,
+}
+
+impl SlashingDatabase {
+    /// New
+    ///
+    /// An empty database for the chain identified by `genesis_validators_root`.
+    pub fn new(genesis_validators_root: Root) -> SlashingDatabase {
+        SlashingDatabase {
+            genesis_validators_root,
+            validators: BTreeMap::new(),
+        }
+    }
+
+    /// Check a block proposal and record it if it is safe to sign.
+    ///
+    /// Refuses a second block at a slot already signed with a different or unknown signing root,
+    /// and any block at or below the lowest recorded slot other than an identical repeat.
+    pub fn check_and_insert_block(
+        &mut self,
+        public_key: &[u8; G1_BYTES],
+        slot: u64,
+        signing_root: &Root,
+    ) -> Result<Safety, AmclError> {
+        let history = self.validators.entry(*public_key).or_default();
+
+        if let Some(existing) = history.signed_blocks.iter().find(|b| b.slot == slot) {
+            if existing.signing_root == Some(*signing_root) {
+                return Ok(Safety::SameData);
+            }
+            return Err(AmclError::DoubleBlockProposal);
+        }
+        if let Some(min_slot) = history.signed_blocks.iter().map(|b| b.slot).min() {
+            if slot <= min_slot {
+                return Err(AmclError::BelowSlashingWatermark);
+            }
+        }
+
+        history.signed_blocks.push(SignedBlock {
+            slot,
+            signing_root: Some(*signing_root),
+        });
+        Ok(Safety::Safe)
+    }
+
+    /// Check an attestation and record it if it is safe to sign.
+    ///
+    /// Refuses double votes, surrounding and surrounded votes, a source epoch below the lowest
+    /// recorded source and a target epoch at or below the lowest recorded target.
+    pub fn check_and_insert_attestation(
+        &mut self,
+        public_key: &[u8; G1_BYTES],
+        source_epoch: u64,
+        target_epoch: u64,
+        signing_root: &Root,
+    ) -> Result<Safety, AmclError> {
+        if source_epoch > target_epoch {
+            return Err(AmclError::InvalidAttestationEpochs);
+        }
+        let history = self.validators.entry(*public_key).or_default();
+
+        for existing in history.signed_attestations.iter() {
+            if existing.target_epoch == target_epoch {
+                if existing.source_epoch == source_epoch
+                    && existing.signing_root == Some(*signing_root)
+                {
+                    return Ok(Safety::SameData);
+                }
+                return Err(AmclError::DoubleVote);
+            }
+            let surrounding =
+                source_epoch < existing.source_epoch && target_epoch > existing.target_epoch;
+            let surrounded =
+                source_epoch > existing.source_epoch && target_epoch < existing.target_epoch;
+            if surrounding || surrounded {
+                return Err(AmclError::SurroundVote);
+            }
+        }
+
+        let attestations = &history.signed_attestations;
+        if let Some(min_source) = attestations.iter().map(|a| a.source_epoch).min() {
+            if source_epoch < min_source {
+                return Err(AmclError::BelowSlashingWatermark);
+            }
+        }
+        if let Some(min_target) = attestations.iter().map(|a| a.target_epoch).min() {
+            if target_epoch <= min_target {
+                return Err(AmclError::BelowSlashingWatermark);
+            }
+        }
+
+        history.signed_attestations.push(SignedAttestation {
+            source_epoch,
+            target_epoch,
+            signing_root: Some(*signing_root),
+        });
+        Ok(Safety::Safe)
+    }
+
+    /// Sign a block header with DOMAIN_BEACON_PROPOSER after checking it is safe.
+    pub fn sign_beacon_block_header(
+        &mut self,
+        secret_key: &[u8],
+        header: &BeaconBlockHeader,
+        fork_version: &Version,
+    ) -> Result<[u8; G2_BYTES], AmclError> {
+        let public_key = eth::secret_key_to_public_key(secret_key)?;
+        let domain = compute_domain(
+            &DOMAIN_BEACON_PROPOSER,
+            fork_version,
+            &self.genesis_validators_root,
+        );
+        let signing_root = compute_signing_root(header, &domain);
+
+        self.check_and_insert_block(&public_key, header.slot, &signing_root)?;
+        eth::sign(secret_key, &signing_root)
+    }
+
+    /// Sign attestation data with DOMAIN_BEACON_ATTESTER after checking it is safe.
+    pub fn sign_attestation_data(
+        &mut self,
+        secret_key: &[u8],
+        data: &AttestationData,
+        fork_version: &Version,
+    ) -> Result<[u8; G2_BYTES], AmclError> {
+        let public_key = eth::secret_key_to_public_key(secret_key)?;
+        let domain = compute_domain(
+            &DOMAIN_BEACON_ATTESTER,
+            fork_version,
+            &self.genesis_validators_root,
+        );
+        let signing_root = compute_signing_root(data, &domain);
+
+        self.check_and_insert_attestation(
+            &public_key,
+            data.source.epoch,
+            data.target.epoch,
+            &signing_root,
+        )?;
+        eth::sign(secret_key, &signing_root)
+    }
+
+    /// Import an EIP-3076 interchange.
+    ///
+    /// The records are merged with the existing history, the genesis validators root must match.
+    pub fn import_interchange(&mut self, json: &str) -> Result<(), AmclError> {
+        let interchange: Interchange =
+            serde_json::from_str(json).map_err(|_| AmclError::InvalidEncoding)?;
+
+        if interchange.metadata.interchange_format_version != INTERCHANGE_FORMAT_VERSION {
+            return Err(AmclError::InvalidEncoding);
+        }
+        if decode_root(&interchange.metadata.genesis_validators_root)?
+            != self.genesis_validators_root
+        {
+            return Err(AmclError::InvalidGenesisValidatorsRoot);
+        }
+
+        // Decode everything before modifying the database
+        let mut imported = Vec::with_capacity(interchange.data.len());
+        for validator in interchange.data.iter() {
+            let public_key = decode_hex(&validator.pubkey)?;
+            if public_key.len() != G1_BYTES {
+                return Err(AmclError::InvalidG1Size);
+            }
+            let mut key = [0u8; G1_BYTES];
+            key.copy_from_slice(&public_key);
+
+            let mut history = ValidatorHistory::default();
+            for block in validator.signed_blocks.iter() {
+                history.signed_blocks.push(SignedBlock {
+                    slot: decode_u64(&block.slot)?,
+                    signing_root: decode_optional_root(&block.signing_root)?,
+                });
+            }
+            for attestation in validator.signed_attestations.iter() {
+                history.signed_attestations.push(SignedAttestation {
+                    source_epoch: decode_u64(&attestation.source_epoch)?,
+                    target_epoch: decode_u64(&attestation.target_epoch)?,
+                    signing_root: decode_optional_root(&attestation.signing_root)?,
+                });
+            }
+            imported.push((key, history));
+        }
+
+        for (key, history) in imported {
+            let existing = self.validators.entry(key).or_default();
+            for block in history.signed_blocks {
+                if !existing.signed_blocks.contains(&block) {
+                    existing.signed_blocks.push(block);
+                }
+            }
+            for attestation in history.signed_attestations {
+                if !existing.signed_attestations.contains(&attestation) {
+                    existing.signed_attestations.push(attestation);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Export the complete history as an EIP-3076 interchange.
+    pub fn export_interchange(&self) -> Result<String, AmclError> {
+        let interchange = Interchange {
+            metadata: InterchangeMetadata {
+                interchange_format_version: INTERCHANGE_FORMAT_VERSION.to_string(),
+                genesis_validators_root: encode_hex(&self.genesis_validators_root),
+            },
+            data: self
+                .validators
+                .iter()
+                .map(|(public_key, history)| InterchangeData {
+                    pubkey: encode_hex(public_key),
+                    signed_blocks: history
+                        .signed_blocks
+                        .iter()
+                        .map(|b| InterchangeBlock {
+                            slot: b.slot.to_string(),
+                            signing_root: b.signing_root.as_ref().map(|r| encode_hex(r)),
+                        })
+                        .collect(),
+                    signed_attestations: history
+                        .signed_attestations
+                        .iter()
+                        .map(|a| InterchangeAttestation {
+                            source_epoch: a.source_epoch.to_string(),
+                            target_epoch: a.target_epoch.to_string(),
+                            signing_root: a.signing_root.as_ref().map(|r| encode_hex(r)),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        };
+
+        serde_json::to_string(&interchange).map_err(|_| AmclError::InvalidEncoding)
+    }
+}
+
+/*************************************************************************************************
+* EIP-3076 JSON, integers are decimal strings and byte strings 0x prefixed hex
+*************************************************************************************************/
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+struct Interchange {
+    metadata: InterchangeMetadata,
+    data: Vec<InterchangeData>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+struct InterchangeMetadata {
+    interchange_format_version: String,
+    genesis_validators_root: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+struct InterchangeData {
+    pubkey: String,
+    #[serde(default)]
+    signed_blocks: Vec<InterchangeBlock>,
+    #[serde(default)]
+    signed_attestations: Vec<InterchangeAttestation>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+struct InterchangeBlock {
+    slot: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signing_root: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+struct InterchangeAttestation {
+    source_epoch: String,
+    target_epoch: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signing_root: Option<String>,
+}
+
+pub(super) fn encode_hex(bytes: &[u8]) -> String {
+    let mut s = String::from("0x");
+    s.push_str(&hex::encode(bytes));
+    s
+}
+
+pub(super) fn decode_hex(s: &str) -> Result<Vec<u8>, AmclError> {
+    let s = s.strip_prefix("0x").ok_or(AmclError::InvalidEncoding)?;
+    hex::decode(s).map_err(|_| AmclError::InvalidEncoding)
+}
+
+pub(super) fn decode_root(s: &str) -> Result<Root, AmclError> {
+    let bytes = decode_hex(s)?;
+    if bytes.len() != 32 {
+        return Err(AmclError::InvalidEncoding);
+    }
+    let mut root = [0u8; 32];
+    root.copy_from_slice(&bytes);
+    Ok(root)
+}
+
+fn decode_optional_root(s: &Option<String>) -> Result<Option<Root>, AmclError> {
+    match s {
+        Some(s) => decode_root(s).map(Some),
+        None => Ok(None),
+    }
+}
+
+pub(super) fn decode_u64(s: &str) -> Result<u64, AmclError> {
+    s.parse().map_err(|_| AmclError::InvalidEncoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENESIS_VALIDATORS_ROOT: Root = [0x04; 32];
+    const PUBLIC_KEY: [u8; G1_BYTES] = [0xa1; G1_BYTES];
+    const ROOT_A: Root = [0x0a; 32];
+    const ROOT_B: Root = [0x0b; 32];
+
+    // A minimal interchange with no signing roots as produced by pruned databases.
+    fn minimal_interchange(genesis_validators_root: &Root) -> String {
+        format!(
+            r#"{{
+                "metadata": {{
+                    "interchange_format_version": "5",
+                    "genesis_validators_root": "{}"
+                }},
+                "data": [
+                    {{
+                        "pubkey": "{}",
+                        "signed_blocks": [{{ "slot": "100" }}],
+                        "signed_attestations": [
+                            {{ "source_epoch": "10", "target_epoch": "20" }}
+                        ]
+                    }}
+                ]
+            }}"#,
+            encode_hex(genesis_validators_root),
+            encode_hex(&PUBLIC_KEY)
+        )
+    }
+
+    #[test]
+    fn test_double_block() {
+        let mut db = SlashingDatabase::new(GENESIS_VALIDATORS_ROOT);
+        assert_eq!(
+            db.check_and_insert_block(&PUBLIC_KEY, 10, &ROOT_A),
+            Ok(Safety::Safe)
+        );
+        assert_eq!(
+            db.check_and_insert_block(&PUBLIC_KEY, 10, &ROOT_A),
+            Ok(Safety::SameData)
+        );
+        assert_eq!(
+            db.check_and_insert_block(&PUBLIC_KEY, 10, &ROOT_B),
+            Err(AmclError::DoubleBlockProposal)
+        );
+        assert_eq!(
+            db.check_and_insert_block(&PUBLIC_KEY, 11, &ROOT_B),
+            Ok(Safety::Safe)
+        );
+        assert_eq!(db.validators[&PUBLIC_KEY].signed_blocks.len(), 2);
+
+        // Other validators are independent
+        assert_eq!(
+            db.check_and_insert_block(&[0xa2; G1_BYTES], 10, &ROOT_B),
+            Ok(Safety::Safe)
+        );
+    }
+
+    #[test]
+    fn test_double_vote() {
+        let mut db = SlashingDatabase::new(GENESIS_VALIDATORS_ROOT);
+        assert_eq!(
+            db.check_and_insert_attestation(&PUBLIC_KEY, 1, 2, &ROOT_A),
+            Ok(Safety::Safe)
+        );
+        assert_eq!(
+            db.check_and_insert_attestation(&PUBLIC_KEY, 1, 2, &ROOT_A),
+            Ok(Safety::SameData)
+        );
+        assert_eq!(
+            db.check_and_insert_attestation(&PUBLIC_KEY, 1, 2, &ROOT_B),
+            Err(AmclError::DoubleVote)
+        );
+        assert_eq!(
+            db.check_and_insert_attestation(&PUBLIC_KEY, 0, 2, &ROOT_A),
+            Err(AmclError::DoubleVote)
+        );
+        assert_eq!(
+            db.check_and_insert_attestation(&PUBLIC_KEY, 2, 1, &ROOT_A),
+            Err(AmclError::InvalidAttestationEpochs)
+        );
+        assert_eq!(db.validators[&PUBLIC_KEY].signed_attestations.len(), 1);
+    }
+
+    #[test]
+    fn test_surround_votes() {
+        let mut db = SlashingDatabase::new(GENESIS_VALIDATORS_ROOT);
+        db.check_and_insert_attestation(&PUBLIC_KEY, 0, 1, &ROOT_A)
+            .unwrap();
+        db.check_and_insert_attestation(&PUBLIC_KEY, 3, 4, &ROOT_A)
+            .unwrap();
+
+        // Surrounding (3, 4)
+        assert_eq!(
+            db.check_and_insert_attestation(&PUBLIC_KEY, 2, 5, &ROOT_B),
+            Err(AmclError::SurroundVote)
+        );
+        db.check_and_insert_attestation(&PUBLIC_KEY, 4, 10, &ROOT_A)
+            .unwrap();
+        // Surrounded by (4, 10)
+        assert_eq!(
+            db.check_and_insert_attestation(&PUBLIC_KEY, 5, 9, &ROOT_B),
+            Err(AmclError::SurroundVote)
+        );
+        // Sharing a source or target epoch is not a surround
+        assert_eq!(
+            db.check_and_insert_attestation(&PUBLIC_KEY, 4, 11, &ROOT_B),
+            Ok(Safety::Safe)
+        );
+        assert_eq!(
+            db.check_and_insert_attestation(&PUBLIC_KEY, 11, 12, &ROOT_B),
+            Ok(Safety::Safe)
+        );
+    }
+
+    #[test]
+    fn test_minimal_import_watermarks() {
+        let mut db = SlashingDatabase::new(GENESIS_VALIDATORS_ROOT);
+        db.import_interchange(&minimal_interchange(&GENESIS_VALIDATORS_ROOT))
+            .unwrap();
+
+        // Without a signing root even a repeat is refused
+        assert_eq!(
+            db.check_and_insert_block(&PUBLIC_KEY, 100, &ROOT_A),
+            Err(AmclError::DoubleBlockProposal)
+        );
+        assert_eq!(
+            db.check_and_insert_block(&PUBLIC_KEY, 99, &ROOT_A),
+            Err(AmclError::BelowSlashingWatermark)
+        );
+        assert_eq!(
+            db.check_and_insert_block(&PUBLIC_KEY, 101, &ROOT_A),
+            Ok(Safety::Safe)
+        );
+
+        assert_eq!(
+            db.check_and_insert_attestation(&PUBLIC_KEY, 10, 20, &ROOT_A),
+            Err(AmclError::DoubleVote)
+        );
+        assert_eq!(
+            db.check_and_insert_attestation(&PUBLIC_KEY, 10, 19, &ROOT_A),
+            Err(AmclError::BelowSlashingWatermark)
+        );
+        assert_eq!(
+            db.check_and_insert_attestation(&PUBLIC_KEY, 9, 25, &ROOT_A),
+            Err(AmclError::SurroundVote)
+        );
+        assert_eq!(
+            db.check_and_insert_attestation(&PUBLIC_KEY, 10, 21, &ROOT_A),
+            Ok(Safety::Safe)
+        );
+    }
+
+    #[test]
+    fn test_import_export_round_trip() {
+        let mut db = SlashingDatabase::new(GENESIS_VALIDATORS_ROOT);
+        db.check_and_insert_block(&PUBLIC_KEY, 1, &ROOT_A).unwrap();
+        db.check_and_insert_block(&PUBLIC_KEY, 2, &ROOT_B).unwrap();
+        db.check_and_insert_attestation(&PUBLIC_KEY, 0, 1, &ROOT_A)
+            .unwrap();
+        db.check_and_insert_attestation(&[0xa2; G1_BYTES], 1, 3, &ROOT_B)
+            .unwrap();
+
+        let json = db.export_interchange().unwrap();
+        let mut imported = SlashingDatabase::new(GENESIS_VALIDATORS_ROOT);
+        imported.import_interchange(&json).unwrap();
+        assert_eq!(imported, db);
+        assert_eq!(imported.export_interchange().unwrap(), json);
+
+        // Importing the same records again changes nothing
+        imported.import_interchange(&json).unwrap();
+        assert_eq!(imported, db);
+
+        // A minimal interchange survives the round trip without signing roots
+        let mut minimal = SlashingDatabase::new(GENESIS_VALIDATORS_ROOT);
+        minimal
+            .import_interchange(&minimal_interchange(&GENESIS_VALIDATORS_ROOT))
+            .unwrap();
+        let mut imported = SlashingDatabase::new(GENESIS_VALIDATORS_ROOT);
+        imported
+            .import_interchange(&minimal.export_interchange().unwrap())
+            .unwrap();
+        assert_eq!(imported, minimal);
+        assert_eq!(
+            imported.validators[&PUBLIC_KEY].signed_blocks[0].signing_root,
+            None
+        );
+
+        // Merging keeps the existing records
+        db.import_interchange(&minimal_interchange(&GENESIS_VALIDATORS_ROOT))
+            .unwrap();
+        assert_eq!(db.validators[&PUBLIC_KEY].signed_blocks.len(), 3);
+        assert_eq!(db.validators[&PUBLIC_KEY].signed_attestations.len(), 2);
+    }
+
+    #[test]
+    fn test_import_invalid() {
+        let mut db = SlashingDatabase::new(GENESIS_VALIDATORS_ROOT);
+        assert_eq!(
+            db.import_interchange(&minimal_interchange(&[0x05; 32])),
+            Err(AmclError::InvalidGenesisValidatorsRoot)
+        );
+        let json = minimal_interchange(&GENESIS_VALIDATORS_ROOT);
+        assert_eq!(
+            db.import_interchange(&json.replace(r#""5""#, r#""4""#)),
+            Err(AmclError::InvalidEncoding)
+        );
+        assert_eq!(
+            db.import_interchange(&json.replace(r#""100""#, r#""-1""#)),
+            Err(AmclError::InvalidEncoding)
+        );
+        assert_eq!(
+            db.import_interchange(&json.replace(&encode_hex(&PUBLIC_KEY), "0xa1a1")),
+            Err(AmclError::InvalidG1Size)
+        );
+        assert_eq!(
+            db.import_interchange(&json[..json.len() - 1]),
+            Err(AmclError::InvalidEncoding)
+        );
+        assert_eq!(db, SlashingDatabase::new(GENESIS_VALIDATORS_ROOT));
+    }
+
+    #[test]
+    fn test_sign() {
+        let secret_key = [0x11; 32];
+        let fork_version = [0x01, 0x00, 0x00, 0x00];
+        let mut db = SlashingDatabase::new(GENESIS_VALIDATORS_ROOT);
+
+        let mut header = BeaconBlockHeader {
+            slot: 5,
+            ..Default::default()
+        };
+        let signature = db
+            .sign_beacon_block_header(&secret_key, &header, &fork_version)
+            .unwrap();
+        assert_eq!(
+            db.sign_beacon_block_header(&secret_key, &header, &fork_version),
+            Ok(signature)
+        );
+        header.body_root = ROOT_A;
+        assert_eq!(
+            db.sign_beacon_block_header(&secret_key, &header, &fork_version)
+                .err(),
+            Some(AmclError::DoubleBlockProposal)
+        );
+
+        let mut data = AttestationData::default();
+        data.source.epoch = 1;
+        data.target.epoch = 2;
+        let signature = db
+            .sign_attestation_data(&secret_key, &data, &fork_version)
+            .unwrap();
+        let domain = compute_domain(
+            &DOMAIN_BEACON_ATTESTER,
+            &fork_version,
+            &GENESIS_VALIDATORS_ROOT,
+        );
+        let public_key = eth::secret_key_to_public_key(&secret_key).unwrap();
+        assert!(eth::verify_object(&public_key, &data, &domain, &signature));
+
+        data.beacon_block_root = ROOT_B;
+        assert_eq!(
+            db.sign_attestation_data(&secret_key, &data, &fork_version)
+                .err(),
+            Some(AmclError::DoubleVote)
+        );
+    }
+}
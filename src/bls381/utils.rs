@@ -19,7 +19,11 @@ under the License.
 
 
 pub use super::core::{
-    deserialize_g1, deserialize_g2, hash_to_curve_g1, hash_to_curve_g2, secret_key_from_bytes,
-    secret_key_to_bytes, serialize_g1, serialize_g2, serialize_uncompressed_g1,
-    serialize_uncompressed_g2, subgroup_check_g1, subgroup_check_g2,
+    deserialize_g1, deserialize_g1_arkworks, deserialize_g2, deserialize_g2_arkworks,
+    deserialize_gt_arkworks, deserialize_scalar_arkworks, hash_to_curve_g1, hash_to_curve_g2,
+    map_to_curve_g1, map_to_curve_g2, secret_key_from_bytes, secret_key_to_bytes, serialize_g1,
+    serialize_g1_arkworks, serialize_g2, serialize_g2_arkworks, serialize_gt_arkworks,
+    serialize_scalar_arkworks, serialize_uncompressed_g1, serialize_uncompressed_g1_arkworks,
+    serialize_uncompressed_g2, serialize_uncompressed_g2_arkworks, subgroup_check_g1,
+    subgroup_check_g2, GT_BYTES_ARKWORKS, SCALAR_BYTES_ARKWORKS,
 };
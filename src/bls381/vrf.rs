@@ -0,0 +1,261 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+
+use super::super::transcript::Transcript;
+use super::core::{
+    self, deserialize_g1, deserialize_g2, serialize_g1, serialize_g2, G1_BYTES, G2_BYTES,
+};
+use crate::errors::AmclError;
+
+/// Domain Separation Tag for VRF proofs on G1
+pub const DST_G1: &[u8] = b"BLS_VRF_BLS12381G1_XMD:SHA-256_SSWU_RO_";
+/// Domain Separation Tag for VRF proofs on G2
+pub const DST_G2: &[u8] = b"BLS_VRF_BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+/// Domain used when hashing a proof to the VRF output.
+const OUTPUT_DOMAIN: &[u8] = b"BLS_VRF_OUTPUT_";
+
+/// The number of bytes of a VRF output
+pub const OUTPUT_BYTES: usize = 64;
+
+// Hash a canonically serialized proof to the VRF output.
+fn hash_proof(proof: &[u8]) -> [u8; OUTPUT_BYTES] {
+    let mut transcript = Transcript::new(OUTPUT_DOMAIN);
+    transcript.append_message(b"proof", proof);
+    let output = transcript
+        .challenge_bytes(b"output", OUTPUT_BYTES)
+        .expect("output length is in range");
+
+    let mut bytes = [0u8; OUTPUT_BYTES];
+    bytes.copy_from_slice(&output);
+    bytes
+}
+
+/*************************************************************************************************
+* VRF with proofs on G1 (public keys on G2)
+*
+* The proof is the unique BLS signature of alpha, the output is a hash of the proof.
+*************************************************************************************************/
+
+/// VRF Prove
+///
+/// Returns the proof for `alpha`, a signature on G1 under `DST_G1`.
+pub fn prove_g1(secret_key: &[u8], alpha: &[u8]) -> Result<[u8; G1_BYTES], AmclError> {
+    core::core_sign_g1(secret_key, alpha, DST_G1)
+}
+
+/// VRF Proof To Hash
+///
+/// Derive the VRF output from a proof on G1 without verifying it.
+pub fn proof_to_hash_g1(proof: &[u8]) -> Result<[u8; OUTPUT_BYTES], AmclError> {
+    let proof = deserialize_g1(proof)?;
+    Ok(hash_proof(&serialize_g1(&proof)))
+}
+
+/// VRF Verify
+///
+/// Verify a proof on G1 for `alpha` and return the VRF output.
+pub fn verify_g1(
+    public_key: &[u8],
+    alpha: &[u8],
+    proof: &[u8],
+) -> Result<[u8; OUTPUT_BYTES], AmclError> {
+    if !core::core_verify_g1(public_key, alpha, proof, DST_G1) {
+        return Err(AmclError::InvalidSignature);
+    }
+    proof_to_hash_g1(proof)
+}
+
+/*************************************************************************************************
+* VRF with proofs on G2 (public keys on G1)
+*
+* The proof is the unique BLS signature of alpha, the output is a hash of the proof.
+*************************************************************************************************/
+
+/// VRF Prove
+///
+/// Returns the proof for `alpha`, a signature on G2 under `DST_G2`.
+pub fn prove_g2(secret_key: &[u8], alpha: &[u8]) -> Result<[u8; G2_BYTES], AmclError> {
+    core::core_sign_g2(secret_key, alpha, DST_G2)
+}
+
+/// VRF Proof To Hash
+///
+/// Derive the VRF output from a proof on G2 without verifying it.
+pub fn proof_to_hash_g2(proof: &[u8]) -> Result<[u8; OUTPUT_BYTES], AmclError> {
+    let proof = deserialize_g2(proof)?;
+    Ok(hash_proof(&serialize_g2(&proof)))
+}
+
+/// VRF Verify
+///
+/// Verify a proof on G2 for `alpha` and return the VRF output.
+pub fn verify_g2(
+    public_key: &[u8],
+    alpha: &[u8],
+    proof: &[u8],
+) -> Result<[u8; OUTPUT_BYTES], AmclError> {
+    if !core::core_verify_g2(public_key, alpha, proof, DST_G2) {
+        return Err(AmclError::InvalidSignature);
+    }
+    proof_to_hash_g2(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::fp::FP;
+    use super::super::super::fp2::FP2;
+    use super::super::basic::{
+        key_generate, secret_key_to_public_key_g1, secret_key_to_public_key_g2,
+    };
+    use super::super::core::{map_to_curve_g1, map_to_curve_g2};
+    use super::*;
+
+    const ALPHA: &[u8] = b"sample alpha";
+
+    fn secret_keys() -> ([u8; 32], [u8; 32]) {
+        (key_generate(&[1u8; 32], &[]), key_generate(&[2u8; 32], &[]))
+    }
+
+    #[test]
+    fn test_vrf_g1() {
+        let (secret_key, other_key) = secret_keys();
+        let public_key = secret_key_to_public_key_g1(&secret_key).unwrap();
+        let other_public_key = secret_key_to_public_key_g1(&other_key).unwrap();
+
+        let proof = prove_g1(&secret_key, ALPHA).unwrap();
+        let output = verify_g1(&public_key, ALPHA, &proof).unwrap();
+        assert_eq!(output, proof_to_hash_g1(&proof).unwrap());
+
+        // One proof and output per key and alpha
+        assert_eq!(
+            prove_g1(&secret_key, ALPHA).unwrap().to_vec(),
+            proof.to_vec()
+        );
+        let other_alpha = prove_g1(&secret_key, b"other alpha").unwrap();
+        let other_proof = prove_g1(&other_key, ALPHA).unwrap();
+        assert_ne!(other_alpha.to_vec(), proof.to_vec());
+        assert_ne!(other_proof.to_vec(), proof.to_vec());
+        assert_ne!(
+            proof_to_hash_g1(&other_alpha).unwrap().to_vec(),
+            output.to_vec()
+        );
+        assert_ne!(
+            proof_to_hash_g1(&other_proof).unwrap().to_vec(),
+            output.to_vec()
+        );
+
+        assert_eq!(
+            verify_g1(&other_public_key, ALPHA, &proof),
+            Err(AmclError::InvalidSignature)
+        );
+        assert_eq!(
+            verify_g1(&public_key, b"other alpha", &proof),
+            Err(AmclError::InvalidSignature)
+        );
+        assert_eq!(
+            verify_g1(&public_key, ALPHA, &other_proof),
+            Err(AmclError::InvalidSignature)
+        );
+
+        // The same signature under another DST is not a proof
+        let signature = core::core_sign_g1(&secret_key, ALPHA, DST_G2).unwrap();
+        assert_eq!(
+            verify_g1(&public_key, ALPHA, &signature),
+            Err(AmclError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_vrf_g2() {
+        let (secret_key, other_key) = secret_keys();
+        let public_key = secret_key_to_public_key_g2(&secret_key).unwrap();
+        let other_public_key = secret_key_to_public_key_g2(&other_key).unwrap();
+
+        let proof = prove_g2(&secret_key, ALPHA).unwrap();
+        let output = verify_g2(&public_key, ALPHA, &proof).unwrap();
+        assert_eq!(output, proof_to_hash_g2(&proof).unwrap());
+
+        assert_eq!(
+            prove_g2(&secret_key, ALPHA).unwrap().to_vec(),
+            proof.to_vec()
+        );
+        let other_alpha = prove_g2(&secret_key, b"other alpha").unwrap();
+        let other_proof = prove_g2(&other_key, ALPHA).unwrap();
+        assert_ne!(other_alpha.to_vec(), proof.to_vec());
+        assert_ne!(other_proof.to_vec(), proof.to_vec());
+        assert_ne!(
+            proof_to_hash_g2(&other_alpha).unwrap().to_vec(),
+            output.to_vec()
+        );
+        assert_ne!(
+            proof_to_hash_g2(&other_proof).unwrap().to_vec(),
+            output.to_vec()
+        );
+
+        assert_eq!(
+            verify_g2(&other_public_key, ALPHA, &proof),
+            Err(AmclError::InvalidSignature)
+        );
+        assert_eq!(
+            verify_g2(&public_key, b"other alpha", &proof),
+            Err(AmclError::InvalidSignature)
+        );
+        assert_eq!(
+            verify_g2(&public_key, ALPHA, &other_proof),
+            Err(AmclError::InvalidSignature)
+        );
+
+        let signature = core::core_sign_g2(&secret_key, ALPHA, DST_G1).unwrap();
+        assert_eq!(
+            verify_g2(&public_key, ALPHA, &signature),
+            Err(AmclError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_non_subgroup_proof() {
+        let (secret_key, _) = secret_keys();
+
+        // Points on the curves outside the prime order subgroups
+        let proof = serialize_g1(&map_to_curve_g1(FP::new_int(1)));
+        assert!(proof_to_hash_g1(&proof).is_ok());
+        let public_key = secret_key_to_public_key_g1(&secret_key).unwrap();
+        assert_eq!(
+            verify_g1(&public_key, ALPHA, &proof),
+            Err(AmclError::InvalidSignature)
+        );
+
+        let proof = serialize_g2(&map_to_curve_g2(FP2::new_ints(1, 2)));
+        assert!(proof_to_hash_g2(&proof).is_ok());
+        let public_key = secret_key_to_public_key_g2(&secret_key).unwrap();
+        assert_eq!(
+            verify_g2(&public_key, ALPHA, &proof),
+            Err(AmclError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_invalid_encodings() {
+        assert!(proof_to_hash_g1(&[0u8; G1_BYTES - 1]).is_err());
+        assert!(proof_to_hash_g2(&[0u8; G2_BYTES + 1]).is_err());
+        assert!(prove_g1(&[0u8; 31], ALPHA).is_err());
+        assert!(prove_g2(&[0u8; 31], ALPHA).is_err());
+    }
+}
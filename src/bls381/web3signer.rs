@@ -0,0 +1,1179 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::eth::{
+    self, compute_domain, compute_signing_root, Domain, DomainType, Version, DEPOSIT_PROOF_LENGTH,
+    DOMAIN_AGGREGATE_AND_PROOF, DOMAIN_APPLICATION_BUILDER, DOMAIN_BEACON_ATTESTER,
+    DOMAIN_BEACON_PROPOSER, DOMAIN_CONTRIBUTION_AND_PROOF, DOMAIN_DEPOSIT, DOMAIN_RANDAO,
+    DOMAIN_SELECTION_PROOF, DOMAIN_SYNC_COMMITTEE, DOMAIN_SYNC_COMMITTEE_SELECTION_PROOF,
+    DOMAIN_VOLUNTARY_EXIT, G1_BYTES, G2_BYTES, MAX_ATTESTATIONS, MAX_ATTESTER_SLASHINGS,
+    MAX_DEPOSITS, MAX_PROPOSER_SLASHINGS, MAX_VALIDATORS_PER_COMMITTEE, MAX_VOLUNTARY_EXITS,
+    SLOTS_PER_EPOCH, SYNC_SUBCOMMITTEE_SIZE,
+};
+use super::light_client::{self, SYNC_COMMITTEE_SIZE};
+use super::serialization::SecretKey;
+use super::slashing_protection::{
+    decode_hex, decode_root, decode_u64, encode_hex, SlashingDatabase,
+};
+use crate::errors::AmclError;
+use crate::ssz::{Bitlist, Bitvector, HashTreeRoot, Root};
+
+use std::boxed::Box;
+use std::collections::BTreeMap;
+use std::string::String;
+use std::vec::Vec;
+
+/*************************************************************************************************
+* Remote signing
+*
+* Request and response types of the Web3Signer Eth2 signing API,
+* POST /api/v1/eth2/sign/{identifier}, and a transport independent handler.
+* https://consensys.github.io/web3signer/web3signer-eth2.html
+*
+* The handler recomputes the signing root from the request, rejects requests whose optional
+* `signingRoot` disagrees with it and signs with the Ethereum proof of possession ciphersuite.
+* Integers are decimal strings and byte strings 0x prefixed hex.
+*************************************************************************************************/
+
+/// Fork
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct Fork {
+    pub previous_version: String,
+    pub current_version: String,
+    pub epoch: String,
+}
+
+/// Fork and chain of the message being signed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct ForkInfo {
+    pub fork: Fork,
+    pub genesis_validators_root: String,
+}
+
+/// Checkpoint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct Checkpoint {
+    pub epoch: String,
+    pub root: String,
+}
+
+/// AttestationData
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct AttestationData {
+    pub slot: String,
+    pub index: String,
+    pub beacon_block_root: String,
+    pub source: Checkpoint,
+    pub target: Checkpoint,
+}
+
+/// BeaconBlockHeader
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct BeaconBlockHeader {
+    pub slot: String,
+    pub proposer_index: String,
+    pub parent_root: String,
+    pub state_root: String,
+    pub body_root: String,
+}
+
+/// Eth1Data
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct Eth1Data {
+    pub deposit_root: String,
+    pub deposit_count: String,
+    pub block_hash: String,
+}
+
+/// SignedBeaconBlockHeader
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct SignedBeaconBlockHeader {
+    pub message: BeaconBlockHeader,
+    pub signature: String,
+}
+
+/// ProposerSlashing
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct ProposerSlashing {
+    pub signed_header_1: SignedBeaconBlockHeader,
+    pub signed_header_2: SignedBeaconBlockHeader,
+}
+
+/// IndexedAttestation
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct IndexedAttestation {
+    pub attesting_indices: Vec<String>,
+    pub data: AttestationData,
+    pub signature: String,
+}
+
+/// AttesterSlashing
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct AttesterSlashing {
+    pub attestation_1: IndexedAttestation,
+    pub attestation_2: IndexedAttestation,
+}
+
+/// DepositData
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct DepositData {
+    pub pubkey: String,
+    pub withdrawal_credentials: String,
+    pub amount: String,
+    pub signature: String,
+}
+
+/// Deposit of a block body
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct BlockDeposit {
+    pub proof: Vec<String>,
+    pub data: DepositData,
+}
+
+/// SignedVoluntaryExit
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct SignedVoluntaryExit {
+    pub message: VoluntaryExit,
+    pub signature: String,
+}
+
+/// SyncAggregate
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct SyncAggregate {
+    pub sync_committee_bits: String,
+    pub sync_committee_signature: String,
+}
+
+/// BeaconBlockBody of PHASE0, or of ALTAIR with the sync aggregate.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct BeaconBlockBody {
+    pub randao_reveal: String,
+    pub eth1_data: Eth1Data,
+    pub graffiti: String,
+    pub proposer_slashings: Vec<ProposerSlashing>,
+    pub attester_slashings: Vec<AttesterSlashing>,
+    pub attestations: Vec<Attestation>,
+    pub deposits: Vec<BlockDeposit>,
+    pub voluntary_exits: Vec<SignedVoluntaryExit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_aggregate: Option<SyncAggregate>,
+}
+
+/// A full PHASE0 or ALTAIR block.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct Block {
+    pub slot: String,
+    pub proposer_index: String,
+    pub parent_root: String,
+    pub state_root: String,
+    pub body: BeaconBlockBody,
+}
+
+/// The block of a BLOCK_V2 request.
+///
+/// PHASE0 and ALTAIR requests carry the full `block`, later forks the `block_header`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct BeaconBlock {
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block: Option<Box<Block>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_header: Option<BeaconBlockHeader>,
+}
+
+/// AggregationSlot
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct AggregationSlot {
+    pub slot: String,
+}
+
+/// Attestation
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct Attestation {
+    pub aggregation_bits: String,
+    pub data: AttestationData,
+    pub signature: String,
+}
+
+/// AggregateAndProof
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct AggregateAndProof {
+    pub aggregator_index: String,
+    pub aggregate: Attestation,
+    pub selection_proof: String,
+}
+
+/// RandaoReveal
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct RandaoReveal {
+    pub epoch: String,
+}
+
+/// VoluntaryExit
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct VoluntaryExit {
+    pub epoch: String,
+    pub validator_index: String,
+}
+
+/// Deposit
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct Deposit {
+    pub pubkey: String,
+    pub withdrawal_credentials: String,
+    pub amount: String,
+    pub genesis_fork_version: String,
+}
+
+/// SyncCommitteeMessage
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct SyncCommitteeMessage {
+    pub beacon_block_root: String,
+    pub slot: String,
+}
+
+/// SyncAggregatorSelectionData
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct SyncAggregatorSelectionData {
+    pub slot: String,
+    pub subcommittee_index: String,
+}
+
+/// SyncCommitteeContribution
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct SyncCommitteeContribution {
+    pub slot: String,
+    pub beacon_block_root: String,
+    pub subcommittee_index: String,
+    pub aggregation_bits: String,
+    pub signature: String,
+}
+
+/// ContributionAndProof
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct ContributionAndProof {
+    pub aggregator_index: String,
+    pub selection_proof: String,
+    pub contribution: SyncCommitteeContribution,
+}
+
+/// ValidatorRegistration
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct ValidatorRegistration {
+    pub fee_recipient: String,
+    pub gas_limit: String,
+    pub timestamp: String,
+    pub pubkey: String,
+}
+
+/// The message of a signing request, selected by its `type`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(
+    crate = "crate::serde",
+    tag = "type",
+    rename_all = "SCREAMING_SNAKE_CASE"
+)]
+pub enum SigningMessage {
+    #[serde(rename = "BLOCK_V2")]
+    BlockV2 {
+        beacon_block: BeaconBlock,
+    },
+    Attestation {
+        attestation: AttestationData,
+    },
+    AggregationSlot {
+        aggregation_slot: AggregationSlot,
+    },
+    AggregateAndProof {
+        aggregate_and_proof: AggregateAndProof,
+    },
+    RandaoReveal {
+        randao_reveal: RandaoReveal,
+    },
+    VoluntaryExit {
+        voluntary_exit: VoluntaryExit,
+    },
+    Deposit {
+        deposit: Deposit,
+    },
+    SyncCommitteeMessage {
+        sync_committee_message: SyncCommitteeMessage,
+    },
+    SyncCommitteeSelectionProof {
+        sync_aggregator_selection_data: SyncAggregatorSelectionData,
+    },
+    SyncCommitteeContributionAndProof {
+        contribution_and_proof: ContributionAndProof,
+    },
+    ValidatorRegistration {
+        validator_registration: ValidatorRegistration,
+    },
+}
+
+/// Body of a signing request.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct SigningRequest {
+    #[serde(flatten)]
+    pub message: SigningMessage,
+    /// Required for every type except DEPOSIT and VALIDATOR_REGISTRATION.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fork_info: Option<ForkInfo>,
+    #[serde(
+        rename = "signingRoot",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub signing_root: Option<String>,
+}
+
+/// Body of a successful response.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct SigningResponse {
+    pub signature: String,
+}
+
+impl SigningRequest {
+    /// Parse a JSON request body.
+    pub fn from_json(json: &str) -> Result<SigningRequest, AmclError> {
+        serde_json::from_str(json).map_err(|_| AmclError::InvalidEncoding)
+    }
+
+    /// Serialize to a JSON request body.
+    pub fn to_json(&self) -> Result<String, AmclError> {
+        serde_json::to_string(self).map_err(|_| AmclError::InvalidEncoding)
+    }
+}
+
+impl SigningResponse {
+    /// Parse a JSON response body.
+    pub fn from_json(json: &str) -> Result<SigningResponse, AmclError> {
+        serde_json::from_str(json).map_err(|_| AmclError::InvalidEncoding)
+    }
+
+    /// Serialize to a JSON response body.
+    pub fn to_json(&self) -> Result<String, AmclError> {
+        serde_json::to_string(self).map_err(|_| AmclError::InvalidEncoding)
+    }
+
+    /// The decoded signature.
+    pub fn signature(&self) -> Result<[u8; G2_BYTES], AmclError> {
+        let mut signature = [0u8; G2_BYTES];
+        decode_into(&self.signature, &mut signature)?;
+        Ok(signature)
+    }
+}
+
+/*************************************************************************************************
+* Handler
+*************************************************************************************************/
+
+/// Signs Web3Signer requests with the keys it holds.
+///
+/// Blocks and attestations are always checked against the slashing protection database.
+pub struct Web3SignerHandler {
+    keys: BTreeMap<[u8; G1_BYTES], SecretKey>,
+    slashing_database: SlashingDatabase,
+    genesis_fork_version: Version,
+}
+
+impl Web3SignerHandler {
+    /// New
+    ///
+    /// A handler without keys for the chain with `genesis_fork_version`, used by DEPOSIT and
+    /// VALIDATOR_REGISTRATION requests, which records blocks and attestations in
+    /// `slashing_database`. Epochs are computed with SLOTS_PER_EPOCH of the mainnet preset.
+    pub fn new(
+        genesis_fork_version: Version,
+        slashing_database: SlashingDatabase,
+    ) -> Web3SignerHandler {
+        Web3SignerHandler {
+            keys: BTreeMap::new(),
+            slashing_database,
+            genesis_fork_version,
+        }
+    }
+
+    /// The slashing protection database.
+    pub fn slashing_database(&self) -> &SlashingDatabase {
+        &self.slashing_database
+    }
+
+    /// Add a secret key, returning its public key.
+    pub fn add_key(&mut self, secret_key: &[u8]) -> Result<[u8; G1_BYTES], AmclError> {
+        let secret_key = SecretKey::from_bytes(secret_key)?;
+        let public_key = secret_key.public_key().to_bytes();
+        self.keys.insert(public_key, secret_key);
+        Ok(public_key)
+    }
+
+    /// Remove a key, returning whether it was held.
+    pub fn remove_key(&mut self, public_key: &[u8; G1_BYTES]) -> bool {
+        self.keys.remove(public_key).is_some()
+    }
+
+    /// Public keys of the held keys, the response of GET /api/v1/eth2/publicKeys.
+    pub fn public_keys(&self) -> Vec<String> {
+        self.keys.keys().map(|k| encode_hex(k)).collect()
+    }
+
+    /// Handle a request to POST /api/v1/eth2/sign/{identifier}.
+    ///
+    /// `identifier` is the 0x prefixed hex public key and `body` the JSON request.
+    pub fn handle(&mut self, identifier: &str, body: &str) -> Result<SigningResponse, AmclError> {
+        let request = SigningRequest::from_json(body)?;
+        self.sign(identifier, &request)
+    }
+
+    /// Sign a parsed request with the key identified by `identifier`.
+    pub fn sign(
+        &mut self,
+        identifier: &str,
+        request: &SigningRequest,
+    ) -> Result<SigningResponse, AmclError> {
+        let public_key = decode_public_key(identifier)?;
+        if !self.keys.contains_key(&public_key) {
+            return Err(AmclError::UnknownPublicKey);
+        }
+
+        let signing_root = self.check_signing_root(&public_key, request)?;
+        let signature = self.keys[&public_key].sign(&signing_root);
+        Ok(SigningResponse {
+            signature: encode_hex(&signature.to_bytes()),
+        })
+    }
+
+    /// Signing root of a request, recording blocks and attestations for slashing protection.
+    fn check_signing_root(
+        &mut self,
+        public_key: &[u8; G1_BYTES],
+        request: &SigningRequest,
+    ) -> Result<Root, AmclError> {
+        let signing_root = self.compute_signing_root(request)?;
+        if let Some(expected) = &request.signing_root {
+            if decode_root(expected)? != signing_root {
+                return Err(AmclError::SigningRootMismatch);
+            }
+        }
+
+        let database = &mut self.slashing_database;
+        match &request.message {
+            SigningMessage::BlockV2 { beacon_block } => {
+                let header = decode_block_header(beacon_block)?;
+                check_chain(database, request)?;
+                database.check_and_insert_block(public_key, header.slot, &signing_root)?;
+            }
+            SigningMessage::Attestation { attestation } => {
+                let data = decode_attestation_data(attestation)?;
+                check_chain(database, request)?;
+                database.check_and_insert_attestation(
+                    public_key,
+                    data.source.epoch,
+                    data.target.epoch,
+                    &signing_root,
+                )?;
+            }
+            _ => {}
+        }
+
+        Ok(signing_root)
+    }
+
+    /// Signing root of the message of a request.
+    pub fn compute_signing_root(&self, request: &SigningRequest) -> Result<Root, AmclError> {
+        match &request.message {
+            SigningMessage::BlockV2 { beacon_block } => {
+                let header = decode_block_header(beacon_block)?;
+                let epoch = self.epoch_at_slot(header.slot);
+                self.fork_signing_root(request, &header, DOMAIN_BEACON_PROPOSER, epoch)
+            }
+            SigningMessage::Attestation { attestation } => {
+                let data = decode_attestation_data(attestation)?;
+                let epoch = data.target.epoch;
+                self.fork_signing_root(request, &data, DOMAIN_BEACON_ATTESTER, epoch)
+            }
+            SigningMessage::AggregationSlot { aggregation_slot } => {
+                let slot = decode_u64(&aggregation_slot.slot)?;
+                let epoch = self.epoch_at_slot(slot);
+                self.fork_signing_root(request, &slot, DOMAIN_SELECTION_PROOF, epoch)
+            }
+            SigningMessage::AggregateAndProof {
+                aggregate_and_proof,
+            } => {
+                let aggregate = decode_attestation(&aggregate_and_proof.aggregate)?;
+                let epoch = self.epoch_at_slot(aggregate.data.slot);
+                let message = eth::AggregateAndProof {
+                    aggregator_index: decode_u64(&aggregate_and_proof.aggregator_index)?,
+                    aggregate,
+                    selection_proof: decode_signature(&aggregate_and_proof.selection_proof)?,
+                };
+                self.fork_signing_root(request, &message, DOMAIN_AGGREGATE_AND_PROOF, epoch)
+            }
+            SigningMessage::RandaoReveal { randao_reveal } => {
+                let epoch = decode_u64(&randao_reveal.epoch)?;
+                self.fork_signing_root(request, &epoch, DOMAIN_RANDAO, epoch)
+            }
+            SigningMessage::VoluntaryExit { voluntary_exit } => {
+                let exit = eth::VoluntaryExit {
+                    epoch: decode_u64(&voluntary_exit.epoch)?,
+                    validator_index: decode_u64(&voluntary_exit.validator_index)?,
+                };
+                let epoch = exit.epoch;
+                self.fork_signing_root(request, &exit, DOMAIN_VOLUNTARY_EXIT, epoch)
+            }
+            SigningMessage::Deposit { deposit } => {
+                let mut pubkey = [0u8; G1_BYTES];
+                decode_into(&deposit.pubkey, &mut pubkey)?;
+                let mut genesis_fork_version = [0u8; 4];
+                decode_into(&deposit.genesis_fork_version, &mut genesis_fork_version)?;
+                let message = eth::DepositMessage {
+                    pubkey,
+                    withdrawal_credentials: decode_root(&deposit.withdrawal_credentials)?,
+                    amount: decode_u64(&deposit.amount)?,
+                };
+                let domain = compute_domain(&DOMAIN_DEPOSIT, &genesis_fork_version, &[0u8; 32]);
+                Ok(compute_signing_root(&message, &domain))
+            }
+            SigningMessage::SyncCommitteeMessage {
+                sync_committee_message,
+            } => {
+                let root = decode_root(&sync_committee_message.beacon_block_root)?;
+                let epoch = self.epoch_at_slot(decode_u64(&sync_committee_message.slot)?);
+                self.fork_signing_root(request, &root, DOMAIN_SYNC_COMMITTEE, epoch)
+            }
+            SigningMessage::SyncCommitteeSelectionProof {
+                sync_aggregator_selection_data,
+            } => {
+                let data = eth::SyncAggregatorSelectionData {
+                    slot: decode_u64(&sync_aggregator_selection_data.slot)?,
+                    subcommittee_index: decode_u64(
+                        &sync_aggregator_selection_data.subcommittee_index,
+                    )?,
+                };
+                let epoch = self.epoch_at_slot(data.slot);
+                let domain = DOMAIN_SYNC_COMMITTEE_SELECTION_PROOF;
+                self.fork_signing_root(request, &data, domain, epoch)
+            }
+            SigningMessage::SyncCommitteeContributionAndProof {
+                contribution_and_proof,
+            } => {
+                let contribution = &contribution_and_proof.contribution;
+                let bits = decode_hex(&contribution.aggregation_bits)?;
+                let message = eth::ContributionAndProof {
+                    aggregator_index: decode_u64(&contribution_and_proof.aggregator_index)?,
+                    contribution: eth::SyncCommitteeContribution {
+                        slot: decode_u64(&contribution.slot)?,
+                        beacon_block_root: decode_root(&contribution.beacon_block_root)?,
+                        subcommittee_index: decode_u64(&contribution.subcommittee_index)?,
+                        aggregation_bits: Bitvector::from_bytes(&bits, SYNC_SUBCOMMITTEE_SIZE)?,
+                        signature: decode_signature(&contribution.signature)?,
+                    },
+                    selection_proof: decode_signature(&contribution_and_proof.selection_proof)?,
+                };
+                let epoch = self.epoch_at_slot(message.contribution.slot);
+                let domain = DOMAIN_CONTRIBUTION_AND_PROOF;
+                self.fork_signing_root(request, &message, domain, epoch)
+            }
+            SigningMessage::ValidatorRegistration {
+                validator_registration,
+            } => {
+                let mut fee_recipient = [0u8; 20];
+                decode_into(&validator_registration.fee_recipient, &mut fee_recipient)?;
+                let mut pubkey = [0u8; G1_BYTES];
+                decode_into(&validator_registration.pubkey, &mut pubkey)?;
+                let message = eth::ValidatorRegistration {
+                    fee_recipient,
+                    gas_limit: decode_u64(&validator_registration.gas_limit)?,
+                    timestamp: decode_u64(&validator_registration.timestamp)?,
+                    pubkey,
+                };
+                let domain = compute_domain(
+                    &DOMAIN_APPLICATION_BUILDER,
+                    &self.genesis_fork_version,
+                    &[0u8; 32],
+                );
+                Ok(compute_signing_root(&message, &domain))
+            }
+        }
+    }
+
+    fn epoch_at_slot(&self, slot: u64) -> u64 {
+        slot / SLOTS_PER_EPOCH
+    }
+
+    /// Signing root under the fork of `fork_info` that is active at `epoch`.
+    fn fork_signing_root<T: HashTreeRoot>(
+        &self,
+        request: &SigningRequest,
+        object: &T,
+        domain_type: DomainType,
+        epoch: u64,
+    ) -> Result<Root, AmclError> {
+        let domain = fork_domain(request, domain_type, epoch)?;
+        Ok(compute_signing_root(object, &domain))
+    }
+}
+
+/*************************************************************************************************
+* Decoding of the JSON types
+*************************************************************************************************/
+
+fn fork_domain(
+    request: &SigningRequest,
+    domain_type: DomainType,
+    epoch: u64,
+) -> Result<Domain, AmclError> {
+    let fork_info = request
+        .fork_info
+        .as_ref()
+        .ok_or(AmclError::UnsupportedSigningRequest)?;
+
+    let mut fork_version = [0u8; 4];
+    if epoch < decode_u64(&fork_info.fork.epoch)? {
+        decode_into(&fork_info.fork.previous_version, &mut fork_version)?;
+    } else {
+        decode_into(&fork_info.fork.current_version, &mut fork_version)?;
+    }
+    let genesis_validators_root = decode_root(&fork_info.genesis_validators_root)?;
+
+    Ok(compute_domain(
+        &domain_type,
+        &fork_version,
+        &genesis_validators_root,
+    ))
+}
+
+// The request must be for the chain the slashing protection database records.
+fn check_chain(database: &SlashingDatabase, request: &SigningRequest) -> Result<(), AmclError> {
+    let fork_info = request
+        .fork_info
+        .as_ref()
+        .ok_or(AmclError::UnsupportedSigningRequest)?;
+    if decode_root(&fork_info.genesis_validators_root)? != database.genesis_validators_root {
+        return Err(AmclError::InvalidGenesisValidatorsRoot);
+    }
+    Ok(())
+}
+
+fn decode_into(s: &str, out: &mut [u8]) -> Result<(), AmclError> {
+    let bytes = decode_hex(s)?;
+    if bytes.len() != out.len() {
+        return Err(AmclError::InvalidEncoding);
+    }
+    out.copy_from_slice(&bytes);
+    Ok(())
+}
+
+fn decode_public_key(s: &str) -> Result<[u8; G1_BYTES], AmclError> {
+    let bytes = decode_hex(s)?;
+    if bytes.len() != G1_BYTES {
+        return Err(AmclError::InvalidG1Size);
+    }
+    let mut public_key = [0u8; G1_BYTES];
+    public_key.copy_from_slice(&bytes);
+    Ok(public_key)
+}
+
+fn decode_signature(s: &str) -> Result<[u8; G2_BYTES], AmclError> {
+    let bytes = decode_hex(s)?;
+    if bytes.len() != G2_BYTES {
+        return Err(AmclError::InvalidG2Size);
+    }
+    let mut signature = [0u8; G2_BYTES];
+    signature.copy_from_slice(&bytes);
+    Ok(signature)
+}
+
+// A full PHASE0 or ALTAIR block is reduced to its header by computing the body root.
+fn decode_block_header(block: &BeaconBlock) -> Result<eth::BeaconBlockHeader, AmclError> {
+    let full = match (&block.block, &block.block_header) {
+        (None, Some(header)) => return decode_header(header),
+        (Some(full), None) => full,
+        _ => return Err(AmclError::UnsupportedSigningRequest),
+    };
+    let altair = match block.version.as_str() {
+        "PHASE0" => false,
+        "ALTAIR" => true,
+        _ => return Err(AmclError::UnsupportedSigningRequest),
+    };
+
+    Ok(eth::BeaconBlockHeader {
+        slot: decode_u64(&full.slot)?,
+        proposer_index: decode_u64(&full.proposer_index)?,
+        parent_root: decode_root(&full.parent_root)?,
+        state_root: decode_root(&full.state_root)?,
+        body_root: decode_block_body(&full.body, altair)?.hash_tree_root(),
+    })
+}
+
+fn decode_header(header: &BeaconBlockHeader) -> Result<eth::BeaconBlockHeader, AmclError> {
+    Ok(eth::BeaconBlockHeader {
+        slot: decode_u64(&header.slot)?,
+        proposer_index: decode_u64(&header.proposer_index)?,
+        parent_root: decode_root(&header.parent_root)?,
+        state_root: decode_root(&header.state_root)?,
+        body_root: decode_root(&header.body_root)?,
+    })
+}
+
+// Each list must be within its limit and the sync aggregate is present exactly for ALTAIR.
+fn decode_block_body(
+    body: &BeaconBlockBody,
+    altair: bool,
+) -> Result<eth::BeaconBlockBody, AmclError> {
+    if body.proposer_slashings.len() > MAX_PROPOSER_SLASHINGS
+        || body.attester_slashings.len() > MAX_ATTESTER_SLASHINGS
+        || body.attestations.len() > MAX_ATTESTATIONS
+        || body.deposits.len() > MAX_DEPOSITS
+        || body.voluntary_exits.len() > MAX_VOLUNTARY_EXITS
+    {
+        return Err(AmclError::InvalidSszLength);
+    }
+    let sync_aggregate = match (&body.sync_aggregate, altair) {
+        (Some(sync_aggregate), true) => {
+            let bits = decode_hex(&sync_aggregate.sync_committee_bits)?;
+            Some(light_client::SyncAggregate {
+                sync_committee_bits: Bitvector::from_bytes(&bits, SYNC_COMMITTEE_SIZE)?,
+                sync_committee_signature: decode_signature(
+                    &sync_aggregate.sync_committee_signature,
+                )?,
+            })
+        }
+        (None, false) => None,
+        _ => return Err(AmclError::InvalidEncoding),
+    };
+
+    Ok(eth::BeaconBlockBody {
+        randao_reveal: decode_signature(&body.randao_reveal)?,
+        eth1_data: eth::Eth1Data {
+            deposit_root: decode_root(&body.eth1_data.deposit_root)?,
+            deposit_count: decode_u64(&body.eth1_data.deposit_count)?,
+            block_hash: decode_root(&body.eth1_data.block_hash)?,
+        },
+        graffiti: decode_root(&body.graffiti)?,
+        proposer_slashings: body
+            .proposer_slashings
+            .iter()
+            .map(|slashing| {
+                Ok(eth::ProposerSlashing {
+                    signed_header_1: decode_signed_header(&slashing.signed_header_1)?,
+                    signed_header_2: decode_signed_header(&slashing.signed_header_2)?,
+                })
+            })
+            .collect::<Result<_, AmclError>>()?,
+        attester_slashings: body
+            .attester_slashings
+            .iter()
+            .map(|slashing| {
+                Ok(eth::AttesterSlashing {
+                    attestation_1: decode_indexed_attestation(&slashing.attestation_1)?,
+                    attestation_2: decode_indexed_attestation(&slashing.attestation_2)?,
+                })
+            })
+            .collect::<Result<_, AmclError>>()?,
+        attestations: body
+            .attestations
+            .iter()
+            .map(decode_attestation)
+            .collect::<Result<_, AmclError>>()?,
+        deposits: body
+            .deposits
+            .iter()
+            .map(decode_deposit)
+            .collect::<Result<_, AmclError>>()?,
+        voluntary_exits: body
+            .voluntary_exits
+            .iter()
+            .map(|exit| {
+                Ok(eth::SignedVoluntaryExit {
+                    message: eth::VoluntaryExit {
+                        epoch: decode_u64(&exit.message.epoch)?,
+                        validator_index: decode_u64(&exit.message.validator_index)?,
+                    },
+                    signature: decode_signature(&exit.signature)?,
+                })
+            })
+            .collect::<Result<_, AmclError>>()?,
+        sync_aggregate,
+    })
+}
+
+fn decode_signed_header(
+    header: &SignedBeaconBlockHeader,
+) -> Result<eth::SignedBeaconBlockHeader, AmclError> {
+    Ok(eth::SignedBeaconBlockHeader {
+        message: decode_header(&header.message)?,
+        signature: decode_signature(&header.signature)?,
+    })
+}
+
+fn decode_indexed_attestation(
+    attestation: &IndexedAttestation,
+) -> Result<eth::IndexedAttestation, AmclError> {
+    if attestation.attesting_indices.len() > MAX_VALIDATORS_PER_COMMITTEE {
+        return Err(AmclError::InvalidSszLength);
+    }
+    Ok(eth::IndexedAttestation {
+        attesting_indices: attestation
+            .attesting_indices
+            .iter()
+            .map(|index| decode_u64(index))
+            .collect::<Result<_, AmclError>>()?,
+        data: decode_attestation_data(&attestation.data)?,
+        signature: decode_signature(&attestation.signature)?,
+    })
+}
+
+fn decode_deposit(deposit: &BlockDeposit) -> Result<eth::Deposit, AmclError> {
+    if deposit.proof.len() != DEPOSIT_PROOF_LENGTH {
+        return Err(AmclError::InvalidSszLength);
+    }
+    let mut pubkey = [0u8; G1_BYTES];
+    decode_into(&deposit.data.pubkey, &mut pubkey)?;
+    Ok(eth::Deposit {
+        proof: deposit
+            .proof
+            .iter()
+            .map(|root| decode_root(root))
+            .collect::<Result<_, AmclError>>()?,
+        data: eth::DepositData {
+            pubkey,
+            withdrawal_credentials: decode_root(&deposit.data.withdrawal_credentials)?,
+            amount: decode_u64(&deposit.data.amount)?,
+            signature: decode_signature(&deposit.data.signature)?,
+        },
+    })
+}
+
+fn decode_checkpoint(checkpoint: &Checkpoint) -> Result<eth::Checkpoint, AmclError> {
+    Ok(eth::Checkpoint {
+        epoch: decode_u64(&checkpoint.epoch)?,
+        root: decode_root(&checkpoint.root)?,
+    })
+}
+
+fn decode_attestation_data(data: &AttestationData) -> Result<eth::AttestationData, AmclError> {
+    Ok(eth::AttestationData {
+        slot: decode_u64(&data.slot)?,
+        index: decode_u64(&data.index)?,
+        beacon_block_root: decode_root(&data.beacon_block_root)?,
+        source: decode_checkpoint(&data.source)?,
+        target: decode_checkpoint(&data.target)?,
+    })
+}
+
+fn decode_attestation(attestation: &Attestation) -> Result<eth::Attestation, AmclError> {
+    let bits = decode_hex(&attestation.aggregation_bits)?;
+    Ok(eth::Attestation {
+        aggregation_bits: Bitlist::from_bytes(&bits, MAX_VALIDATORS_PER_COMMITTEE)?,
+        data: decode_attestation_data(&attestation.data)?,
+        signature: decode_signature(&attestation.signature)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde_json::Value;
+    use std::string::ToString;
+
+    const VECTORS: &str = include_str!("../../tests/fixtures/web3signer/signing_requests.json");
+
+    fn vectors() -> Value {
+        serde_json::from_str(VECTORS).unwrap()
+    }
+
+    fn case<'a>(vectors: &'a Value, name: &str) -> &'a Value {
+        vectors["cases"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|case| case["name"] == name)
+            .unwrap()
+    }
+
+    fn genesis_validators_root(vectors: &Value) -> Root {
+        let request = &case(vectors, "attestation")["request"];
+        decode_root(
+            request["fork_info"]["genesis_validators_root"]
+                .as_str()
+                .unwrap(),
+        )
+        .unwrap()
+    }
+
+    // A handler holding the fixture key with an empty slashing protection database.
+    fn new_handler(vectors: &Value) -> (Web3SignerHandler, String) {
+        let mut genesis_fork_version = [0u8; 4];
+        let version = vectors["genesis_fork_version"].as_str().unwrap();
+        decode_into(version, &mut genesis_fork_version).unwrap();
+        let database = SlashingDatabase::new(genesis_validators_root(vectors));
+        let mut handler = Web3SignerHandler::new(genesis_fork_version, database);
+
+        let secret_key = decode_hex(vectors["secret_key"].as_str().unwrap()).unwrap();
+        let public_key = handler.add_key(&secret_key).unwrap();
+        assert_eq!(encode_hex(&public_key), vectors["public_key"]);
+        (handler, encode_hex(&public_key))
+    }
+
+    fn request(value: &Value) -> SigningRequest {
+        SigningRequest::from_json(&value.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_signing_request_fixtures() {
+        let vectors = vectors();
+        let cases = vectors["cases"].as_array().unwrap();
+        assert_eq!(cases.len(), 13);
+
+        for case in cases {
+            let name = &case["name"];
+            let (mut handler, identifier) = new_handler(&vectors);
+            let body = case["request"].to_string();
+            let request = SigningRequest::from_json(&body).unwrap();
+            let signing_root = encode_hex(&handler.compute_signing_root(&request).unwrap());
+            assert_eq!(signing_root, case["signing_root"], "{}", name);
+
+            let response = handler.handle(&identifier, &body).unwrap();
+            let expected = SigningResponse::from_json(&case["response"].to_string()).unwrap();
+            assert_eq!(response, expected, "{}", name);
+            let json: Value = serde_json::from_str(&response.to_json().unwrap()).unwrap();
+            assert_eq!(json, case["response"], "{}", name);
+
+            // Requests survive serialization unchanged
+            let json: Value = serde_json::from_str(&request.to_json().unwrap()).unwrap();
+            assert_eq!(json, case["request"], "{}", name);
+
+            // The optional signing root must agree with the message
+            let mut request = request;
+            request.signing_root = Some(signing_root);
+            let (mut handler, _) = new_handler(&vectors);
+            assert_eq!(
+                handler.sign(&identifier, &request),
+                Ok(expected),
+                "{}",
+                name
+            );
+            request.signing_root = Some(encode_hex(&[0u8; 32]));
+            assert_eq!(
+                handler.sign(&identifier, &request),
+                Err(AmclError::SigningRootMismatch),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_full_block_body_root() {
+        let vectors = vectors();
+        for name in ["block_v2_phase0", "block_v2_altair"].iter() {
+            let case = case(&vectors, name);
+            let request = request(&case["request"]);
+            let beacon_block = match &request.message {
+                SigningMessage::BlockV2 { beacon_block } => beacon_block,
+                _ => unreachable!(),
+            };
+            let header = decode_block_header(beacon_block).unwrap();
+            assert_eq!(encode_hex(&header.body_root), case["body_root"], "{}", name);
+
+            // The header form of the same block has the same signing root
+            let block = beacon_block.block.as_ref().unwrap();
+            let header_request = SigningRequest {
+                message: SigningMessage::BlockV2 {
+                    beacon_block: BeaconBlock {
+                        version: String::from("BELLATRIX"),
+                        block: None,
+                        block_header: Some(BeaconBlockHeader {
+                            slot: block.slot.clone(),
+                            proposer_index: block.proposer_index.clone(),
+                            parent_root: block.parent_root.clone(),
+                            state_root: block.state_root.clone(),
+                            body_root: encode_hex(&header.body_root),
+                        }),
+                    },
+                },
+                ..request.clone()
+            };
+            let (handler, _) = new_handler(&vectors);
+            assert_eq!(
+                handler.compute_signing_root(&header_request),
+                handler.compute_signing_root(&request)
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_blocks() {
+        let vectors = vectors();
+        let (handler, _) = new_handler(&vectors);
+        let phase0 = case(&vectors, "block_v2_phase0")["request"].clone();
+        let altair = case(&vectors, "block_v2_altair")["request"].clone();
+
+        let check = |request: &Value, error: AmclError| {
+            assert_eq!(
+                handler.compute_signing_root(&self::request(request)),
+                Err(error)
+            );
+        };
+
+        // The sync aggregate is present exactly for ALTAIR
+        let mut request = phase0.clone();
+        request["beacon_block"]["version"] = Value::from("ALTAIR");
+        check(&request, AmclError::InvalidEncoding);
+        let mut request = altair.clone();
+        request["beacon_block"]["version"] = Value::from("PHASE0");
+        check(&request, AmclError::InvalidEncoding);
+
+        // Later forks only carry the header
+        let mut request = altair.clone();
+        request["beacon_block"]["version"] = Value::from("BELLATRIX");
+        check(&request, AmclError::UnsupportedSigningRequest);
+        let mut request = phase0.clone();
+        request["beacon_block"]["block_header"] = case(&vectors, "block_v2_bellatrix_header")
+            ["request"]["beacon_block"]["block_header"]
+            .clone();
+        check(&request, AmclError::UnsupportedSigningRequest);
+        let mut request = phase0.clone();
+        request["beacon_block"]["block"] = Value::Null;
+        check(&request, AmclError::UnsupportedSigningRequest);
+
+        // Fixed lengths and list limits
+        let mut request = phase0.clone();
+        let proof = &mut request["beacon_block"]["block"]["body"]["deposits"][0]["proof"];
+        proof.as_array_mut().unwrap().pop();
+        check(&request, AmclError::InvalidSszLength);
+        let mut request = phase0.clone();
+        let exits = &mut request["beacon_block"]["block"]["body"]["voluntary_exits"];
+        let exit = exits[0].clone();
+        *exits = Value::Array(vec![exit; MAX_VOLUNTARY_EXITS + 1]);
+        check(&request, AmclError::InvalidSszLength);
+        let mut request = altair.clone();
+        let sync_aggregate = &mut request["beacon_block"]["block"]["body"]["sync_aggregate"];
+        sync_aggregate["sync_committee_bits"] = Value::from(encode_hex(&[0xff; 32]));
+        check(&request, AmclError::InvalidSszLength);
+    }
+
+    #[test]
+    fn test_slashing_protection() {
+        let vectors = vectors();
+        let (mut handler, identifier) = new_handler(&vectors);
+
+        // Repeats of the same block and attestation are signed again
+        for name in ["block_v2_phase0", "attestation"].iter() {
+            let request = request(&case(&vectors, name)["request"]);
+            let signature = handler.sign(&identifier, &request).unwrap();
+            assert_eq!(handler.sign(&identifier, &request), Ok(signature));
+        }
+        let history = &handler
+            .slashing_database()
+            .validators
+            .values()
+            .next()
+            .unwrap();
+        assert_eq!(history.signed_blocks.len(), 1);
+        assert_eq!(history.signed_attestations.len(), 1);
+
+        let mut request = case(&vectors, "block_v2_phase0")["request"].clone();
+        request["beacon_block"]["block"]["proposer_index"] = Value::from("8");
+        assert_eq!(
+            handler.handle(&identifier, &request.to_string()),
+            Err(AmclError::DoubleBlockProposal)
+        );
+        let mut request = case(&vectors, "attestation")["request"].clone();
+        request["attestation"]["index"] = Value::from("4");
+        assert_eq!(
+            handler.handle(&identifier, &request.to_string()),
+            Err(AmclError::DoubleVote)
+        );
+
+        // Blocks and attestations must be for the chain of the database
+        let mut request = case(&vectors, "block_v2_bellatrix_header")["request"].clone();
+        request["fork_info"]["genesis_validators_root"] = Value::from(encode_hex(&[0u8; 32]));
+        assert_eq!(
+            handler.handle(&identifier, &request.to_string()),
+            Err(AmclError::InvalidGenesisValidatorsRoot)
+        );
+        let mut request = case(&vectors, "attestation")["request"].clone();
+        request["fork_info"] = Value::Null;
+        assert_eq!(
+            handler.handle(&identifier, &request.to_string()),
+            Err(AmclError::UnsupportedSigningRequest)
+        );
+    }
+
+    #[test]
+    fn test_keys() {
+        let vectors = vectors();
+        let (mut handler, identifier) = new_handler(&vectors);
+        assert_eq!(handler.public_keys(), vec![identifier.clone()]);
+        assert_eq!(
+            handler.add_key(&[1u8; 31]),
+            Err(AmclError::InvalidSecretKeySize)
+        );
+        assert_eq!(
+            handler.add_key(&[0u8; 32]),
+            Err(AmclError::InvalidSecretKeyRange)
+        );
+
+        let body = case(&vectors, "randao_reveal")["request"].to_string();
+        assert_eq!(
+            handler.handle("0xa491", &body),
+            Err(AmclError::InvalidG1Size)
+        );
+        let public_key = decode_public_key(&identifier).unwrap();
+        assert!(handler.remove_key(&public_key));
+        assert!(!handler.remove_key(&public_key));
+        assert_eq!(
+            handler.handle(&identifier, &body),
+            Err(AmclError::UnknownPublicKey)
+        );
+        assert!(handler.public_keys().is_empty());
+    }
+}
@@ -0,0 +1,949 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::big::Big;
+use super::bls381::utils::{deserialize_g1, serialize_g1, subgroup_check_g1};
+use super::ecp::ECP;
+use super::fr::{Fr, FR_BYTES};
+use super::pedersen::{multi_scalar_mul, nums_generators, PedersenGens};
+use super::transcript::Transcript;
+use crate::errors::AmclError;
+use crate::rand::RAND;
+use std::vec::Vec;
+
+/*************************************************************************************************
+* Bulletproofs range proofs
+*
+* https://eprint.iacr.org/2017/1066
+*
+* Aggregated proofs that each of m Pedersen commitments V_j = v_j * B + gamma_j * B_blinding
+* opens to a value in [0, 2^n), with logarithmic size inner product arguments.
+*************************************************************************************************/
+
+/// Transcript domain of range proofs
+pub const RANGE_PROOF_DOMAIN: &[u8] = b"BLS12381_BULLETPROOFS_RANGE_PROOF_V1_";
+/// Label from which all generators are derived
+pub const GENERATORS_LABEL: &[u8] = b"BLS12381_BULLETPROOFS";
+/// The largest supported bit size
+pub const MAX_BITS: usize = 64;
+
+// Bytes of a compressed G1 point
+const G1_BYTES: usize = 48;
+
+/// Generators for range proofs.
+///
+/// `pedersen` holds the value base B as its only `g_vec` entry and B_blinding as `h`.
+/// `g_vec` and `h_vec` are the vector generators used by the inner product argument.
+#[derive(Clone)]
+pub struct BulletproofGens {
+    pub pedersen: PedersenGens,
+    pub g_vec: Vec<ECP>,
+    pub h_vec: Vec<ECP>,
+}
+
+/// Proof that <a, b> = c for committed vectors a and b.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InnerProductProof {
+    pub l_vec: Vec<ECP>,
+    pub r_vec: Vec<ECP>,
+    pub a: Fr,
+    pub b: Fr,
+}
+
+/// Aggregated range proof.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RangeProof {
+    pub a: ECP,
+    pub s: ECP,
+    pub t_1: ECP,
+    pub t_2: ECP,
+    pub t_x: Fr,
+    pub t_x_blinding: Fr,
+    pub e_blinding: Fr,
+    pub ipp: InnerProductProof,
+}
+
+impl BulletproofGens {
+    /// New
+    ///
+    /// Generators for proving `parties` values of up to `MAX_BITS` bits each.
+    pub fn new(parties: usize) -> BulletproofGens {
+        let capacity = MAX_BITS * parties;
+        let mut g_label = GENERATORS_LABEL.to_vec();
+        g_label.extend_from_slice(b"_G_VEC");
+        let mut h_label = GENERATORS_LABEL.to_vec();
+        h_label.extend_from_slice(b"_H_VEC");
+
+        BulletproofGens {
+            pedersen: PedersenGens::new(GENERATORS_LABEL, 1),
+            g_vec: nums_generators(&g_label, capacity),
+            h_vec: nums_generators(&h_label, capacity),
+        }
+    }
+
+    /// Commit
+    ///
+    /// V = value * B + blinding * B_blinding
+    pub fn commit(&self, value: u64, blinding: &Fr) -> ECP {
+        multi_scalar_mul(
+            &[self.pedersen.g_vec[0].clone(), self.pedersen.h.clone()],
+            &[fr_from_u64(value), blinding.clone()],
+        )
+    }
+}
+
+impl RangeProof {
+    /// Prove
+    ///
+    /// Proves each value is in [0, 2^bits) and returns the proof with the value commitments.
+    /// `bits` must be 8, 16, 32 or 64 and the number of values a power of two.
+    pub fn prove(
+        gens: &BulletproofGens,
+        values: &[u64],
+        blindings: &[Fr],
+        bits: usize,
+        rng: &mut RAND,
+    ) -> Result<(RangeProof, Vec<ECP>), AmclError> {
+        let m = values.len();
+        check_sizes(gens, bits, m)?;
+        if blindings.len() != m {
+            return Err(AmclError::InvalidMessageCount);
+        }
+        if bits < MAX_BITS && values.iter().any(|v| v >> bits != 0) {
+            return Err(AmclError::InvalidScalarRange);
+        }
+
+        let nm = bits * m;
+        let b = &gens.pedersen.g_vec[0];
+        let b_blinding = &gens.pedersen.h;
+        let g_vec = &gens.g_vec[..nm];
+        let h_vec = &gens.h_vec[..nm];
+
+        let commitments: Vec<ECP> = values
+            .iter()
+            .zip(blindings.iter())
+            .map(|(v, gamma)| gens.commit(*v, gamma))
+            .collect();
+
+        let mut transcript = range_proof_transcript(bits, &commitments);
+
+        // a_L are the bits of the values, a_R = a_L - 1
+        let mut a_l = Vec::with_capacity(nm);
+        let mut a_r = Vec::with_capacity(nm);
+        for value in values {
+            for i in 0..bits {
+                let bit = Fr::new_int(((value >> i) & 1) as isize);
+                let mut bit_minus_one = bit.clone();
+                bit_minus_one.sub(&Fr::one());
+                a_l.push(bit);
+                a_r.push(bit_minus_one);
+            }
+        }
+
+        // A = alpha * B_blinding + <a_L, G> + <a_R, H>
+        let alpha = Fr::random(rng);
+        let a = vector_commit(b_blinding, &alpha, g_vec, &a_l, h_vec, &a_r);
+
+        // S = rho * B_blinding + <s_L, G> + <s_R, H>
+        let s_l: Vec<Fr> = (0..nm).map(|_| Fr::random(rng)).collect();
+        let s_r: Vec<Fr> = (0..nm).map(|_| Fr::random(rng)).collect();
+        let rho = Fr::random(rng);
+        let s = vector_commit(b_blinding, &rho, g_vec, &s_l, h_vec, &s_r);
+
+        transcript.append_g1(b"A", &a);
+        transcript.append_g1(b"S", &s);
+        let y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+
+        // l(X) = (a_L - z) + s_L * X
+        // r(X) = y^i * (a_R + z + s_R * X) + z^(2 + j) * 2^k for i = j * bits + k
+        let y_powers = powers(&y, nm);
+        let two_powers = powers(&Fr::new_int(2), bits);
+        let z_powers = powers(&z, m + 2);
+
+        let mut l_0 = Vec::with_capacity(nm);
+        let mut r_0 = Vec::with_capacity(nm);
+        let mut r_1 = Vec::with_capacity(nm);
+        for i in 0..nm {
+            let mut l = a_l[i].clone();
+            l.sub(&z);
+            l_0.push(l);
+
+            let mut r = a_r[i].clone();
+            r.add(&z);
+            r.mul(&y_powers[i]);
+            let mut z_two = z_powers[2 + i / bits].clone();
+            z_two.mul(&two_powers[i % bits]);
+            r.add(&z_two);
+            r_0.push(r);
+
+            let mut r = s_r[i].clone();
+            r.mul(&y_powers[i]);
+            r_1.push(r);
+        }
+
+        // t(X) = <l(X), r(X)> = t_0 + t_1 * X + t_2 * X^2
+        let mut t_1 = inner_product(&l_0, &r_1);
+        t_1.add(&inner_product(&s_l, &r_0));
+        let t_2 = inner_product(&s_l, &r_1);
+
+        let tau_1 = Fr::random(rng);
+        let tau_2 = Fr::random(rng);
+        let t_1_commitment =
+            multi_scalar_mul(&[b.clone(), b_blinding.clone()], &[t_1, tau_1.clone()]);
+        let t_2_commitment =
+            multi_scalar_mul(&[b.clone(), b_blinding.clone()], &[t_2, tau_2.clone()]);
+
+        transcript.append_g1(b"T_1", &t_1_commitment);
+        transcript.append_g1(b"T_2", &t_2_commitment);
+        let x = transcript.challenge_scalar(b"x");
+
+        // tau_x = tau_2 * x^2 + tau_1 * x + sum(z^(2 + j) * gamma_j)
+        let mut x_squared = x.clone();
+        x_squared.sqr();
+        let mut t_x_blinding = tau_2;
+        t_x_blinding.mul(&x_squared);
+        let mut tau_1_x = tau_1;
+        tau_1_x.mul(&x);
+        t_x_blinding.add(&tau_1_x);
+        for (j, gamma) in blindings.iter().enumerate() {
+            let mut term = z_powers[2 + j].clone();
+            term.mul(gamma);
+            t_x_blinding.add(&term);
+        }
+
+        // mu = alpha + rho * x
+        let mut e_blinding = rho;
+        e_blinding.mul(&x);
+        e_blinding.add(&alpha);
+
+        let l_vec: Vec<Fr> = l_0
+            .iter()
+            .zip(s_l.iter())
+            .map(|(l, s)| linear(l, s, &x))
+            .collect();
+        let r_vec: Vec<Fr> = r_0
+            .iter()
+            .zip(r_1.iter())
+            .map(|(r, s)| linear(r, s, &x))
+            .collect();
+        let t_x = inner_product(&l_vec, &r_vec);
+
+        transcript.append_scalar(b"t_x", &t_x);
+        transcript.append_scalar(b"t_x_blinding", &t_x_blinding);
+        transcript.append_scalar(b"e_blinding", &e_blinding);
+        let w = transcript.challenge_scalar(b"w");
+        let q = b.mul(&w.redc());
+
+        // H'_i = y^-i * H_i
+        let h_prime = h_prime(h_vec, &y);
+        let ipp = InnerProductProof::prove(&mut transcript, &q, g_vec, &h_prime, l_vec, r_vec);
+
+        Ok((
+            RangeProof {
+                a,
+                s,
+                t_1: t_1_commitment,
+                t_2: t_2_commitment,
+                t_x,
+                t_x_blinding,
+                e_blinding,
+                ipp,
+            },
+            commitments,
+        ))
+    }
+
+    /// Verify
+    ///
+    /// Verifies that every commitment opens to a value in [0, 2^bits).
+    pub fn verify(&self, gens: &BulletproofGens, commitments: &[ECP], bits: usize) -> bool {
+        let m = commitments.len();
+        if check_sizes(gens, bits, m).is_err() {
+            return false;
+        }
+        if commitments
+            .iter()
+            .chain([&self.a, &self.s, &self.t_1, &self.t_2].iter().cloned())
+            .any(|p| !subgroup_check_g1(p))
+        {
+            return false;
+        }
+
+        let nm = bits * m;
+        let b = &gens.pedersen.g_vec[0];
+        let b_blinding = &gens.pedersen.h;
+        let g_vec = &gens.g_vec[..nm];
+        let h_vec = &gens.h_vec[..nm];
+
+        let mut transcript = range_proof_transcript(bits, commitments);
+        transcript.append_g1(b"A", &self.a);
+        transcript.append_g1(b"S", &self.s);
+        let y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+        transcript.append_g1(b"T_1", &self.t_1);
+        transcript.append_g1(b"T_2", &self.t_2);
+        let x = transcript.challenge_scalar(b"x");
+        transcript.append_scalar(b"t_x", &self.t_x);
+        transcript.append_scalar(b"t_x_blinding", &self.t_x_blinding);
+        transcript.append_scalar(b"e_blinding", &self.e_blinding);
+        let w = transcript.challenge_scalar(b"w");
+
+        let y_powers = powers(&y, nm);
+        let two_powers = powers(&Fr::new_int(2), bits);
+        let z_powers = powers(&z, m + 3);
+        let mut x_squared = x.clone();
+        x_squared.sqr();
+
+        // delta(y, z) = (z - z^2) * <1, y^nm> - sum(z^(3 + j)) * <1, 2^n>
+        let mut delta = z.clone();
+        delta.sub(&z_powers[2]);
+        delta.mul(&sum(&y_powers));
+        let mut z_cubed_sum = sum(&z_powers[3..3 + m]);
+        z_cubed_sum.mul(&sum(&two_powers));
+        delta.sub(&z_cubed_sum);
+
+        // t_x * B + t_x_blinding * B_blinding
+        //     == sum(z^(2 + j) * V_j) + delta * B + x * T_1 + x^2 * T_2
+        let mut points = commitments.to_vec();
+        let mut scalars = z_powers[2..2 + m].to_vec();
+        let mut b_scalar = delta;
+        b_scalar.sub(&self.t_x);
+        let mut b_blinding_scalar = self.t_x_blinding.clone();
+        b_blinding_scalar.neg();
+        points.extend_from_slice(&[
+            b.clone(),
+            b_blinding.clone(),
+            self.t_1.clone(),
+            self.t_2.clone(),
+        ]);
+        scalars.extend_from_slice(&[b_scalar, b_blinding_scalar, x.clone(), x_squared]);
+        if !multi_scalar_mul(&points, &scalars).is_infinity() {
+            return false;
+        }
+
+        // P = A + x * S - z * <1, G> + <z * y^i + z^(2 + j) * 2^k, H'> - mu * B_blinding + t_x * Q
+        let q = b.mul(&w.redc());
+        let h_prime = h_prime(h_vec, &y);
+        let mut minus_z = z.clone();
+        minus_z.neg();
+        let mut points = Vec::with_capacity(2 * nm + 4);
+        let mut scalars = Vec::with_capacity(2 * nm + 4);
+        for i in 0..nm {
+            points.push(g_vec[i].clone());
+            scalars.push(minus_z.clone());
+
+            let mut h_scalar = z.clone();
+            h_scalar.mul(&y_powers[i]);
+            let mut z_two = z_powers[2 + i / bits].clone();
+            z_two.mul(&two_powers[i % bits]);
+            h_scalar.add(&z_two);
+            points.push(h_prime[i].clone());
+            scalars.push(h_scalar);
+        }
+        let mut minus_mu = self.e_blinding.clone();
+        minus_mu.neg();
+        points.extend_from_slice(&[
+            self.a.clone(),
+            self.s.clone(),
+            b_blinding.clone(),
+            q.clone(),
+        ]);
+        scalars.extend_from_slice(&[Fr::one(), x, minus_mu, self.t_x.clone()]);
+        let p = multi_scalar_mul(&points, &scalars);
+
+        self.ipp.verify(&mut transcript, &q, g_vec, &h_prime, &p)
+    }
+
+    /// Serialize as A || S || T_1 || T_2 || t_x || t_x_blinding || e_blinding followed by the
+    /// inner product proof.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for point in [&self.a, &self.s, &self.t_1, &self.t_2].iter() {
+            bytes.extend_from_slice(&serialize_g1(point));
+        }
+        for scalar in [&self.t_x, &self.t_x_blinding, &self.e_blinding].iter() {
+            bytes.extend_from_slice(&scalar.to_bytes());
+        }
+        bytes.extend_from_slice(&self.ipp.to_bytes());
+        bytes
+    }
+
+    /// Deserialize from the format of `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<RangeProof, AmclError> {
+        let header = 4 * G1_BYTES + 3 * FR_BYTES;
+        if bytes.len() < header {
+            return Err(AmclError::InvalidProof);
+        }
+
+        let point = |i: usize| deserialize_g1(&bytes[i * G1_BYTES..(i + 1) * G1_BYTES]);
+        let scalar = |i: usize| {
+            let start = 4 * G1_BYTES + i * FR_BYTES;
+            Fr::from_bytes(&bytes[start..start + FR_BYTES])
+        };
+
+        Ok(RangeProof {
+            a: point(0)?,
+            s: point(1)?,
+            t_1: point(2)?,
+            t_2: point(3)?,
+            t_x: scalar(0)?,
+            t_x_blinding: scalar(1)?,
+            e_blinding: scalar(2)?,
+            ipp: InnerProductProof::from_bytes(&bytes[header..])?,
+        })
+    }
+}
+
+impl InnerProductProof {
+    /// Prove
+    ///
+    /// Proves P = <a, G> + <b, H> + <a, b> * Q, the lengths must be equal and a power of two.
+    pub fn prove(
+        transcript: &mut Transcript,
+        q: &ECP,
+        g_vec: &[ECP],
+        h_vec: &[ECP],
+        mut a: Vec<Fr>,
+        mut b: Vec<Fr>,
+    ) -> InnerProductProof {
+        let mut g = g_vec.to_vec();
+        let mut h = h_vec.to_vec();
+        let mut l_vec = Vec::new();
+        let mut r_vec = Vec::new();
+        transcript.append_u64(b"n", a.len() as u64);
+
+        while a.len() > 1 {
+            let half = a.len() / 2;
+            let (a_lo, a_hi) = a.split_at(half);
+            let (b_lo, b_hi) = b.split_at(half);
+            let (g_lo, g_hi) = g.split_at(half);
+            let (h_lo, h_hi) = h.split_at(half);
+
+            // L = <a_lo, G_hi> + <b_hi, H_lo> + <a_lo, b_hi> * Q
+            let l = ipp_cross_term(a_lo, g_hi, b_hi, h_lo, q);
+            // R = <a_hi, G_lo> + <b_lo, H_hi> + <a_hi, b_lo> * Q
+            let r = ipp_cross_term(a_hi, g_lo, b_lo, h_hi, q);
+
+            transcript.append_g1(b"L", &l);
+            transcript.append_g1(b"R", &r);
+            let u = transcript.challenge_scalar(b"u");
+            let mut u_inverse = u.clone();
+            u_inverse.inverse();
+
+            // a = a_lo * u + a_hi * u^-1, b = b_lo * u^-1 + b_hi * u
+            // G = G_lo * u^-1 + G_hi * u, H = H_lo * u + H_hi * u^-1
+            let a_next = fold_scalars(a_lo, a_hi, &u, &u_inverse);
+            let b_next = fold_scalars(b_lo, b_hi, &u_inverse, &u);
+            let g_next = fold_points(g_lo, g_hi, &u_inverse, &u);
+            let h_next = fold_points(h_lo, h_hi, &u, &u_inverse);
+
+            a = a_next;
+            b = b_next;
+            g = g_next;
+            h = h_next;
+            l_vec.push(l);
+            r_vec.push(r);
+        }
+
+        InnerProductProof {
+            l_vec,
+            r_vec,
+            a: a.remove(0),
+            b: b.remove(0),
+        }
+    }
+
+    /// Verify
+    ///
+    /// Verifies the proof for P = <a, G> + <b, H> + <a, b> * Q.
+    pub fn verify(
+        &self,
+        transcript: &mut Transcript,
+        q: &ECP,
+        g_vec: &[ECP],
+        h_vec: &[ECP],
+        p: &ECP,
+    ) -> bool {
+        let n = g_vec.len();
+        if !n.is_power_of_two()
+            || h_vec.len() != n
+            || self.l_vec.len() != n.trailing_zeros() as usize
+            || self.r_vec.len() != self.l_vec.len()
+        {
+            return false;
+        }
+        if self
+            .l_vec
+            .iter()
+            .chain(self.r_vec.iter())
+            .any(|p| !subgroup_check_g1(p))
+        {
+            return false;
+        }
+
+        let mut g = g_vec.to_vec();
+        let mut h = h_vec.to_vec();
+        let mut p = p.clone();
+        transcript.append_u64(b"n", n as u64);
+
+        for (l, r) in self.l_vec.iter().zip(self.r_vec.iter()) {
+            transcript.append_g1(b"L", l);
+            transcript.append_g1(b"R", r);
+            let u = transcript.challenge_scalar(b"u");
+            let mut u_inverse = u.clone();
+            u_inverse.inverse();
+
+            // P = u^2 * L + P + u^-2 * R
+            let mut u_squared = u.clone();
+            u_squared.sqr();
+            let mut u_inverse_squared = u_inverse.clone();
+            u_inverse_squared.sqr();
+            p.add(&multi_scalar_mul(
+                &[l.clone(), r.clone()],
+                &[u_squared, u_inverse_squared],
+            ));
+
+            let half = g.len() / 2;
+            let g_next = fold_points(&g[..half], &g[half..], &u_inverse, &u);
+            let h_next = fold_points(&h[..half], &h[half..], &u, &u_inverse);
+            g = g_next;
+            h = h_next;
+        }
+
+        // P == a * G + b * H + a * b * Q
+        let mut ab = self.a.clone();
+        ab.mul(&self.b);
+        let expected = multi_scalar_mul(
+            &[g[0].clone(), h[0].clone(), q.clone()],
+            &[self.a.clone(), self.b.clone(), ab],
+        );
+        expected.equals(&p)
+    }
+
+    /// Serialize as (L_i || R_i) for each round followed by a || b.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.l_vec.len() * 2 * G1_BYTES + 2 * FR_BYTES);
+        for (l, r) in self.l_vec.iter().zip(self.r_vec.iter()) {
+            bytes.extend_from_slice(&serialize_g1(l));
+            bytes.extend_from_slice(&serialize_g1(r));
+        }
+        bytes.extend_from_slice(&self.a.to_bytes());
+        bytes.extend_from_slice(&self.b.to_bytes());
+        bytes
+    }
+
+    /// Deserialize from the format of `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<InnerProductProof, AmclError> {
+        if bytes.len() < 2 * FR_BYTES || (bytes.len() - 2 * FR_BYTES) % (2 * G1_BYTES) != 0 {
+            return Err(AmclError::InvalidProof);
+        }
+
+        let (points, scalars) = bytes.split_at(bytes.len() - 2 * FR_BYTES);
+        let mut l_vec = Vec::with_capacity(points.len() / (2 * G1_BYTES));
+        let mut r_vec = Vec::with_capacity(points.len() / (2 * G1_BYTES));
+        for round in points.chunks(2 * G1_BYTES) {
+            l_vec.push(deserialize_g1(&round[..G1_BYTES])?);
+            r_vec.push(deserialize_g1(&round[G1_BYTES..])?);
+        }
+
+        Ok(InnerProductProof {
+            l_vec,
+            r_vec,
+            a: Fr::from_bytes(&scalars[..FR_BYTES])?,
+            b: Fr::from_bytes(&scalars[FR_BYTES..])?,
+        })
+    }
+}
+
+// Ensures the bit size and number of parties are supported by the generators.
+fn check_sizes(gens: &BulletproofGens, bits: usize, parties: usize) -> Result<(), AmclError> {
+    if !(bits == 8 || bits == 16 || bits == 32 || bits == 64) {
+        return Err(AmclError::InvalidScalarSize);
+    }
+    if parties == 0 || !parties.is_power_of_two() || bits * parties > gens.g_vec.len() {
+        return Err(AmclError::InvalidMessageCount);
+    }
+    Ok(())
+}
+
+fn range_proof_transcript(bits: usize, commitments: &[ECP]) -> Transcript {
+    let mut transcript = Transcript::new(RANGE_PROOF_DOMAIN);
+    transcript.append_u64(b"bits", bits as u64);
+    transcript.append_u64(b"parties", commitments.len() as u64);
+    for commitment in commitments {
+        transcript.append_g1(b"V", commitment);
+    }
+    transcript
+}
+
+// blinding * base + <a, G> + <b, H>
+fn vector_commit(base: &ECP, blinding: &Fr, g: &[ECP], a: &[Fr], h: &[ECP], b: &[Fr]) -> ECP {
+    let mut points = Vec::with_capacity(g.len() + h.len() + 1);
+    let mut scalars = Vec::with_capacity(g.len() + h.len() + 1);
+    points.push(base.clone());
+    scalars.push(blinding.clone());
+    points.extend_from_slice(g);
+    scalars.extend_from_slice(a);
+    points.extend_from_slice(h);
+    scalars.extend_from_slice(b);
+    multi_scalar_mul(&points, &scalars)
+}
+
+// <a, G> + <b, H> + <a, b> * Q
+fn ipp_cross_term(a: &[Fr], g: &[ECP], b: &[Fr], h: &[ECP], q: &ECP) -> ECP {
+    let mut points = g.to_vec();
+    points.extend_from_slice(h);
+    points.push(q.clone());
+    let mut scalars = a.to_vec();
+    scalars.extend_from_slice(b);
+    scalars.push(inner_product(a, b));
+    multi_scalar_mul(&points, &scalars)
+}
+
+// H'_i = y^-i * H_i
+fn h_prime(h_vec: &[ECP], y: &Fr) -> Vec<ECP> {
+    let mut y_inverse = y.clone();
+    y_inverse.inverse();
+    h_vec
+        .iter()
+        .zip(powers(&y_inverse, h_vec.len()).iter())
+        .map(|(h, y_inverse_i)| h.mul(&y_inverse_i.redc()))
+        .collect()
+}
+
+// lo[i] * x + hi[i] * y
+fn fold_scalars(lo: &[Fr], hi: &[Fr], x: &Fr, y: &Fr) -> Vec<Fr> {
+    lo.iter()
+        .zip(hi.iter())
+        .map(|(lo, hi)| {
+            let mut result = lo.clone();
+            result.mul(x);
+            let mut hi = hi.clone();
+            hi.mul(y);
+            result.add(&hi);
+            result
+        })
+        .collect()
+}
+
+// lo[i] * x + hi[i] * y
+fn fold_points(lo: &[ECP], hi: &[ECP], x: &Fr, y: &Fr) -> Vec<ECP> {
+    let (x, y) = (x.redc(), y.redc());
+    lo.iter()
+        .zip(hi.iter())
+        .map(|(lo, hi)| lo.mul2(&x, hi, &y))
+        .collect()
+}
+
+// a + b * x
+fn linear(a: &Fr, b: &Fr, x: &Fr) -> Fr {
+    let mut result = b.clone();
+    result.mul(x);
+    result.add(a);
+    result
+}
+
+fn inner_product(a: &[Fr], b: &[Fr]) -> Fr {
+    let mut result = Fr::new();
+    for (a, b) in a.iter().zip(b.iter()) {
+        let mut term = a.clone();
+        term.mul(b);
+        result.add(&term);
+    }
+    result
+}
+
+fn sum(values: &[Fr]) -> Fr {
+    let mut result = Fr::new();
+    for value in values {
+        result.add(value);
+    }
+    result
+}
+
+// Returns [x^0, x^1, ..., x^(n - 1)].
+fn powers(x: &Fr, n: usize) -> Vec<Fr> {
+    let mut powers = Vec::with_capacity(n);
+    let mut current = Fr::one();
+    for _ in 0..n {
+        powers.push(current.clone());
+        current.mul(x);
+    }
+    powers
+}
+
+fn fr_from_u64(value: u64) -> Fr {
+    Fr::new_big(&Big::from_bytes(&value.to_be_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rng() -> RAND {
+        let mut rng = RAND::new();
+        rng.seed(32, &[39u8; 32]);
+        rng
+    }
+
+    fn blindings(m: usize, rng: &mut RAND) -> Vec<Fr> {
+        (0..m).map(|_| Fr::random(rng)).collect()
+    }
+
+    // Adds the generator to a point.
+    fn shift(point: &ECP) -> ECP {
+        let mut shifted = point.clone();
+        shifted.add(&ECP::generator());
+        shifted
+    }
+
+    fn plus_one(scalar: &Fr) -> Fr {
+        let mut result = scalar.clone();
+        result.add(&Fr::one());
+        result
+    }
+
+    #[test]
+    fn test_prove_verify() {
+        let mut rng = rng();
+        let gens = BulletproofGens::new(4);
+        let value_sets: [&[u64]; 3] = [
+            &[u64::MAX],
+            &[0, 1 << 63],
+            &[7, u64::MAX - 1, 1 << 32, 123_456_789],
+        ];
+        for values in value_sets.iter() {
+            let blindings = blindings(values.len(), &mut rng);
+            let (proof, commitments) =
+                RangeProof::prove(&gens, values, &blindings, 64, &mut rng).unwrap();
+            assert!(proof.verify(&gens, &commitments, 64));
+            assert_eq!(
+                proof.ipp.l_vec.len(),
+                (64 * values.len()).trailing_zeros() as usize
+            );
+
+            for (commitment, (value, blinding)) in
+                commitments.iter().zip(values.iter().zip(blindings.iter()))
+            {
+                assert!(commitment.equals(&gens.commit(*value, blinding)));
+            }
+
+            // Serialization round trip
+            let bytes = proof.to_bytes();
+            let decoded = RangeProof::from_bytes(&bytes).unwrap();
+            assert_eq!(decoded, proof);
+            assert_eq!(decoded.to_bytes(), bytes);
+            assert!(decoded.verify(&gens, &commitments, 64));
+        }
+    }
+
+    #[test]
+    fn test_smaller_bit_sizes() {
+        let mut rng = rng();
+        let gens = BulletproofGens::new(2);
+        for bits in [8, 16, 32].iter() {
+            let max = (1u64 << *bits) - 1;
+            let blindings = blindings(2, &mut rng);
+            let (proof, commitments) =
+                RangeProof::prove(&gens, &[0, max], &blindings, *bits, &mut rng).unwrap();
+            assert!(proof.verify(&gens, &commitments, *bits));
+            // Checked against another bit size the argument does not fit
+            assert!(!proof.verify(&gens, &commitments, 64));
+        }
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let mut rng = rng();
+        let gens = BulletproofGens::new(2);
+        for (value, bits) in [(256, 8), (1 << 16, 16), (u64::MAX, 32)].iter() {
+            assert_eq!(
+                RangeProof::prove(&gens, &[*value], &blindings(1, &mut rng), *bits, &mut rng).err(),
+                Some(AmclError::InvalidScalarRange)
+            );
+        }
+
+        // A 16 bit proof of 300 is not an 8 bit proof of 300
+        let (proof, commitments) =
+            RangeProof::prove(&gens, &[300], &blindings(1, &mut rng), 16, &mut rng).unwrap();
+        assert!(proof.verify(&gens, &commitments, 16));
+        assert!(!proof.verify(&gens, &commitments, 8));
+    }
+
+    #[test]
+    fn test_tampered_proofs() {
+        let mut rng = rng();
+        let gens = BulletproofGens::new(2);
+        let (proof, commitments) =
+            RangeProof::prove(&gens, &[10, 20], &blindings(2, &mut rng), 64, &mut rng).unwrap();
+        assert!(proof.verify(&gens, &commitments, 64));
+
+        let mut tampered_proofs = Vec::new();
+        let mut tampered = proof.clone();
+        tampered.t_x = plus_one(&proof.t_x);
+        tampered_proofs.push(tampered);
+        let mut tampered = proof.clone();
+        tampered.t_x_blinding = plus_one(&proof.t_x_blinding);
+        tampered_proofs.push(tampered);
+        // mu
+        let mut tampered = proof.clone();
+        tampered.e_blinding = plus_one(&proof.e_blinding);
+        tampered_proofs.push(tampered);
+        for point in 0..4 {
+            let mut tampered = proof.clone();
+            match point {
+                0 => tampered.a = shift(&proof.a),
+                1 => tampered.s = shift(&proof.s),
+                2 => tampered.t_1 = shift(&proof.t_1),
+                _ => tampered.t_2 = shift(&proof.t_2),
+            }
+            tampered_proofs.push(tampered);
+        }
+        for round in [0, proof.ipp.l_vec.len() - 1].iter() {
+            let mut tampered = proof.clone();
+            tampered.ipp.l_vec[*round] = shift(&proof.ipp.l_vec[*round]);
+            tampered_proofs.push(tampered);
+            let mut tampered = proof.clone();
+            tampered.ipp.r_vec[*round] = shift(&proof.ipp.r_vec[*round]);
+            tampered_proofs.push(tampered);
+        }
+        let mut tampered = proof.clone();
+        tampered.ipp.l_vec.swap(0, 1);
+        tampered_proofs.push(tampered);
+        let mut tampered = proof.clone();
+        tampered.ipp.a = plus_one(&proof.ipp.a);
+        tampered_proofs.push(tampered);
+        let mut tampered = proof.clone();
+        tampered.ipp.b = plus_one(&proof.ipp.b);
+        tampered_proofs.push(tampered);
+        // A round missing
+        let mut tampered = proof.clone();
+        tampered.ipp.l_vec.pop();
+        tampered.ipp.r_vec.pop();
+        tampered_proofs.push(tampered);
+
+        for tampered in tampered_proofs.iter() {
+            assert!(!tampered.verify(&gens, &commitments, 64));
+        }
+    }
+
+    #[test]
+    fn test_wrong_commitments() {
+        let mut rng = rng();
+        let gens = BulletproofGens::new(4);
+        let blindings = blindings(2, &mut rng);
+        let (proof, commitments) =
+            RangeProof::prove(&gens, &[10, 20], &blindings, 64, &mut rng).unwrap();
+
+        let mut swapped = commitments.clone();
+        swapped.swap(0, 1);
+        assert!(!proof.verify(&gens, &swapped, 64));
+
+        // Commitment to another value with the same blinding
+        let mut other = commitments.clone();
+        other[1] = gens.commit(21, &blindings[1]);
+        assert!(!proof.verify(&gens, &other, 64));
+
+        // A prefix or an extension of the committed values
+        assert!(!proof.verify(&gens, &commitments[..1], 64));
+        let mut extended = commitments.clone();
+        extended.extend(commitments.iter().cloned());
+        assert!(!proof.verify(&gens, &extended, 64));
+    }
+
+    #[test]
+    fn test_non_power_of_two_parties() {
+        let mut rng = rng();
+        let gens = BulletproofGens::new(4);
+        assert_eq!(
+            RangeProof::prove(&gens, &[1, 2, 3], &blindings(3, &mut rng), 64, &mut rng).err(),
+            Some(AmclError::InvalidMessageCount)
+        );
+        assert_eq!(
+            RangeProof::prove(&gens, &[], &[], 64, &mut rng).err(),
+            Some(AmclError::InvalidMessageCount)
+        );
+
+        // More values than the generators support
+        let small = BulletproofGens::new(1);
+        assert_eq!(
+            RangeProof::prove(&small, &[1, 2], &blindings(2, &mut rng), 64, &mut rng).err(),
+            Some(AmclError::InvalidMessageCount)
+        );
+        assert_eq!(
+            RangeProof::prove(&gens, &[1], &blindings(1, &mut rng), 12, &mut rng).err(),
+            Some(AmclError::InvalidScalarSize)
+        );
+
+        let (proof, commitments) =
+            RangeProof::prove(&gens, &[1, 2, 3, 4], &blindings(4, &mut rng), 64, &mut rng).unwrap();
+        assert!(!proof.verify(&gens, &commitments[..3], 64));
+    }
+
+    #[test]
+    fn test_from_bytes_invalid() {
+        let mut rng = rng();
+        let gens = BulletproofGens::new(1);
+        let (proof, commitments) =
+            RangeProof::prove(&gens, &[5], &blindings(1, &mut rng), 8, &mut rng).unwrap();
+        let bytes = proof.to_bytes();
+
+        // Truncated, a partial round and a trailing byte
+        for len in [0, 4 * G1_BYTES, bytes.len() - 1, bytes.len() - G1_BYTES].iter() {
+            assert!(RangeProof::from_bytes(&bytes[..*len]).is_err());
+        }
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert!(RangeProof::from_bytes(&extended).is_err());
+
+        // t_x = r is not canonical
+        let mut non_canonical = bytes.clone();
+        let r = hex::decode("73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001")
+            .unwrap();
+        non_canonical[4 * G1_BYTES..4 * G1_BYTES + FR_BYTES].copy_from_slice(&r);
+        assert_eq!(
+            RangeProof::from_bytes(&non_canonical).err(),
+            Some(AmclError::InvalidScalarRange)
+        );
+
+        // A point without the compression flag, and one outside the subgroup that decodes but
+        // does not verify
+        let mut uncompressed = bytes.clone();
+        uncompressed[0] &= 0x7f;
+        assert!(RangeProof::from_bytes(&uncompressed).is_err());
+        let mut outside = bytes.clone();
+        let point = hex::decode(
+            "8123456789abcdef0123456789abcdef0123456789abcdef\
+             0123456789abcdef0123456789abcdef0123456789abcdef",
+        )
+        .unwrap();
+        outside[..G1_BYTES].copy_from_slice(&point);
+        let outside = RangeProof::from_bytes(&outside).unwrap();
+        assert!(!outside.verify(&gens, &commitments, 8));
+
+        let ipp_bytes = proof.ipp.to_bytes();
+        assert_eq!(
+            InnerProductProof::from_bytes(&ipp_bytes).unwrap(),
+            proof.ipp
+        );
+        assert!(InnerProductProof::from_bytes(&ipp_bytes[1..]).is_err());
+    }
+}
@@ -0,0 +1,937 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::big::Big;
+use super::bls381::proof_of_possession;
+use super::bls381::utils::{
+    deserialize_g1, deserialize_g2, serialize_g1, serialize_g2, subgroup_check_g1,
+    subgroup_check_g2,
+};
+use super::ecp::ECP;
+use super::ecp2::ECP2;
+use super::fr::Fr;
+use super::kzg::pairing_product_is_unity;
+use super::pair;
+use crate::errors::AmclError;
+use crate::rand::RAND;
+
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+/*************************************************************************************************
+* Powers-of-tau ceremony
+*
+* https://github.com/ethereum/kzg-ceremony-specs
+*************************************************************************************************/
+
+/// Powers of tau in G1 and G2, [tau^i]G1 and [tau^i]G2.
+#[derive(Clone)]
+pub struct PowersOfTau {
+    pub g1_powers: Vec<ECP>,
+    pub g2_powers: Vec<ECP2>,
+}
+
+/// One participant's update to a set of powers of tau.
+///
+/// `pot_pubkey` is [tau]G2 for the participant's secret tau and `bls_signature` is an optional
+/// BLS signature over their identity with tau as the secret key.
+#[derive(Clone)]
+pub struct Contribution {
+    pub powers: PowersOfTau,
+    pub pot_pubkey: ECP2,
+    pub bls_signature: Option<ECP>,
+}
+
+/// A contribution to every transcript of a ceremony at once.
+#[derive(Clone)]
+pub struct BatchContribution {
+    pub contributions: Vec<Contribution>,
+    pub ecdsa_signature: Option<String>,
+}
+
+/// Witness that a transcript is the product of its contributions.
+///
+/// Entry 0 is the generator for the initial powers, entry i + 1 is from the i-th contribution.
+/// The running products are [tau_1 * ... * tau_i]G1.
+#[derive(Clone)]
+pub struct Witness {
+    pub running_products: Vec<ECP>,
+    pub pot_pubkeys: Vec<ECP2>,
+    pub bls_signatures: Vec<Option<ECP>>,
+}
+
+/// The current powers of tau and the witness of how they were produced.
+#[derive(Clone)]
+pub struct Transcript {
+    pub powers: PowersOfTau,
+    pub witness: Witness,
+}
+
+/// The set of transcripts of a ceremony along with the identities of the participants.
+///
+/// `participant_ids[i]` is the identity for witness entry i.
+#[derive(Clone)]
+pub struct BatchTranscript {
+    pub transcripts: Vec<Transcript>,
+    pub participant_ids: Vec<String>,
+    pub participant_ecdsa_signatures: Vec<String>,
+}
+
+impl PowersOfTau {
+    /// Initial powers for a new ceremony, every power is the generator.
+    pub fn new(num_g1_powers: usize, num_g2_powers: usize) -> PowersOfTau {
+        PowersOfTau {
+            g1_powers: vec![ECP::generator(); num_g1_powers],
+            g2_powers: vec![ECP2::generator(); num_g2_powers],
+        }
+    }
+
+    /// Check the powers are well formed.
+    ///
+    /// Every point is in the correct subgroup and not the identity, the first powers are the
+    /// generators and consecutive powers share a common ratio. The ratios are checked in one
+    /// pairing equation per group using random linear combinations.
+    pub fn verify(&self, rng: &mut RAND) -> Result<(), AmclError> {
+        if self.g1_powers.len() < 2 || self.g2_powers.len() < 2 {
+            return Err(AmclError::InvalidTranscript);
+        }
+        if self
+            .g1_powers
+            .iter()
+            .any(|p| p.is_infinity() || !subgroup_check_g1(p))
+            || self
+                .g2_powers
+                .iter()
+                .any(|p| p.is_infinity() || !subgroup_check_g2(p))
+        {
+            return Err(AmclError::InvalidPoint);
+        }
+        if !self.g1_powers[0].equals(&ECP::generator())
+            || !self.g2_powers[0].equals(&ECP2::generator())
+        {
+            return Err(AmclError::InvalidTranscript);
+        }
+
+        // e(sum rho^i [tau^i]G1, [tau]G2) == e(sum rho^i [tau^(i+1)]G1, G2)
+        let rho = random_powers(self.g1_powers.len() - 1, rng);
+        let mut lhs = ECP::new();
+        let mut rhs = ECP::new();
+        for (i, rho_i) in rho.iter().enumerate() {
+            lhs.add(&pair::g1mul(&self.g1_powers[i], rho_i));
+            rhs.add(&pair::g1mul(&self.g1_powers[i + 1], rho_i));
+        }
+        lhs.neg();
+        if !pairing_product_is_unity(&[(&self.g2_powers[1], &lhs), (&self.g2_powers[0], &rhs)]) {
+            return Err(AmclError::InvalidTranscript);
+        }
+
+        // e([tau]G1, sum rho^i [tau^i]G2) == e(G1, sum rho^i [tau^(i+1)]G2)
+        let rho = random_powers(self.g2_powers.len() - 1, rng);
+        let mut lhs = ECP2::new();
+        let mut rhs = ECP2::new();
+        for (i, rho_i) in rho.iter().enumerate() {
+            lhs.add(&pair::g2mul(&self.g2_powers[i], rho_i));
+            rhs.add(&pair::g2mul(&self.g2_powers[i + 1], rho_i));
+        }
+        lhs.neg();
+        if !pairing_product_is_unity(&[(&lhs, &self.g1_powers[1]), (&rhs, &self.g1_powers[0])]) {
+            return Err(AmclError::InvalidTranscript);
+        }
+
+        Ok(())
+    }
+
+    // Multiply the i-th powers by tau^i.
+    fn update(&mut self, tau: &Fr) {
+        let mut tau_i = Fr::one();
+        for i in 0..core::cmp::max(self.g1_powers.len(), self.g2_powers.len()) {
+            let scalar = tau_i.redc();
+            if i < self.g1_powers.len() {
+                self.g1_powers[i] = pair::g1mul(&self.g1_powers[i], &scalar);
+            }
+            if i < self.g2_powers.len() {
+                self.g2_powers[i] = pair::g2mul(&self.g2_powers[i], &scalar);
+            }
+            tau_i.mul(tau);
+        }
+    }
+}
+
+impl Contribution {
+    /// Contribute
+    ///
+    /// Multiplies the powers by a fresh secret tau from `rng`, sets the proof of contribution
+    /// [tau]G2 and, if `identity` is given, signs it with tau. tau is discarded afterwards.
+    pub fn contribute(&mut self, identity: Option<&[u8]>, rng: &mut RAND) -> Result<(), AmclError> {
+        let mut tau = Fr::random(rng);
+        while tau.is_zilch() {
+            tau = Fr::random(rng);
+        }
+
+        self.powers.update(&tau);
+        self.pot_pubkey = pair::g2mul(&ECP2::generator(), &tau.redc());
+        self.bls_signature = match identity {
+            Some(identity) => Some(deserialize_g1(&proof_of_possession::sign_g1(
+                &tau.to_bytes(),
+                identity,
+            )?)?),
+            None => None,
+        };
+
+        Ok(())
+    }
+
+    /// Verify the contribution was built on `previous`.
+    ///
+    /// Checks the new powers are well formed and e([tau']G1, G2) == e([tau]G1, [x]G2),
+    /// where tau' = x * tau for the participant's secret x.
+    pub fn verify(&self, previous: &PowersOfTau, rng: &mut RAND) -> Result<(), AmclError> {
+        if self.powers.g1_powers.len() != previous.g1_powers.len()
+            || self.powers.g2_powers.len() != previous.g2_powers.len()
+        {
+            return Err(AmclError::InvalidTranscript);
+        }
+        self.powers.verify(rng)?;
+        check_pot_pubkey(&self.pot_pubkey)?;
+
+        let mut previous_tau = previous.g1_powers[1].clone();
+        previous_tau.neg();
+        if !pairing_product_is_unity(&[
+            (&ECP2::generator(), &self.powers.g1_powers[1]),
+            (&self.pot_pubkey, &previous_tau),
+        ]) {
+            return Err(AmclError::InvalidTranscript);
+        }
+
+        Ok(())
+    }
+}
+
+impl BatchContribution {
+    /// Contribute to every transcript, each with its own fresh tau.
+    pub fn contribute(&mut self, identity: Option<&[u8]>, rng: &mut RAND) -> Result<(), AmclError> {
+        for contribution in self.contributions.iter_mut() {
+            contribution.contribute(identity, rng)?;
+        }
+        Ok(())
+    }
+
+    /// Parse from the ceremony's BatchContribution JSON.
+    pub fn from_json(json: &str) -> Result<BatchContribution, AmclError> {
+        let json: JsonBatchContribution =
+            serde_json::from_str(json).map_err(|_| AmclError::InvalidEncoding)?;
+
+        let mut contributions = Vec::with_capacity(json.contributions.len());
+        for contribution in json.contributions.iter() {
+            let powers = contribution
+                .powers_of_tau
+                .decode(contribution.num_g1_powers, contribution.num_g2_powers)?;
+            contributions.push(Contribution {
+                powers,
+                pot_pubkey: decode_g2(&contribution.pot_pubkey)?,
+                bls_signature: decode_optional_g1(&contribution.bls_signature)?,
+            });
+        }
+
+        Ok(BatchContribution {
+            contributions,
+            ecdsa_signature: json.ecdsa_signature,
+        })
+    }
+
+    /// Serialize to the ceremony's BatchContribution JSON.
+    pub fn to_json(&self) -> Result<String, AmclError> {
+        let json = JsonBatchContribution {
+            contributions: self
+                .contributions
+                .iter()
+                .map(|c| JsonContribution {
+                    num_g1_powers: c.powers.g1_powers.len(),
+                    num_g2_powers: c.powers.g2_powers.len(),
+                    powers_of_tau: JsonPowersOfTau::encode(&c.powers),
+                    pot_pubkey: encode_g2(&c.pot_pubkey),
+                    bls_signature: encode_optional_g1(&c.bls_signature),
+                })
+                .collect(),
+            ecdsa_signature: self.ecdsa_signature.clone(),
+        };
+
+        serde_json::to_string(&json).map_err(|_| AmclError::InvalidEncoding)
+    }
+}
+
+impl Transcript {
+    /// Verify the transcript.
+    ///
+    /// Checks the powers are well formed, every witness point is in the correct subgroup, each
+    /// running product is the previous one multiplied by that contribution's tau and the final
+    /// running product is the current [tau]G1.
+    pub fn verify(&self, rng: &mut RAND) -> Result<(), AmclError> {
+        self.powers.verify(rng)?;
+
+        let witness = &self.witness;
+        let length = witness.running_products.len();
+        if length == 0
+            || witness.pot_pubkeys.len() != length
+            || witness.bls_signatures.len() != length
+        {
+            return Err(AmclError::InvalidTranscript);
+        }
+        if !witness.running_products[0].equals(&ECP::generator())
+            || !witness.pot_pubkeys[0].equals(&ECP2::generator())
+        {
+            return Err(AmclError::InvalidTranscript);
+        }
+        if witness
+            .running_products
+            .iter()
+            .any(|p| p.is_infinity() || !subgroup_check_g1(p))
+        {
+            return Err(AmclError::InvalidPoint);
+        }
+        for pot_pubkey in witness.pot_pubkeys.iter() {
+            check_pot_pubkey(pot_pubkey)?;
+        }
+
+        // e(running_products[i + 1], G2) == e(running_products[i], pot_pubkeys[i + 1])
+        let g2 = ECP2::generator();
+        for i in 0..length - 1 {
+            let mut previous = witness.running_products[i].clone();
+            previous.neg();
+            if !pairing_product_is_unity(&[
+                (&g2, &witness.running_products[i + 1]),
+                (&witness.pot_pubkeys[i + 1], &previous),
+            ]) {
+                return Err(AmclError::InvalidTranscript);
+            }
+        }
+
+        if !witness.running_products[length - 1].equals(&self.powers.g1_powers[1]) {
+            return Err(AmclError::InvalidTranscript);
+        }
+
+        Ok(())
+    }
+}
+
+impl BatchTranscript {
+    /// Initial transcripts for a new ceremony with the given (G1, G2) numbers of powers.
+    pub fn new(sizes: &[(usize, usize)]) -> BatchTranscript {
+        BatchTranscript {
+            transcripts: sizes
+                .iter()
+                .map(|&(num_g1_powers, num_g2_powers)| Transcript {
+                    powers: PowersOfTau::new(num_g1_powers, num_g2_powers),
+                    witness: Witness {
+                        running_products: vec![ECP::generator()],
+                        pot_pubkeys: vec![ECP2::generator()],
+                        bls_signatures: vec![None],
+                    },
+                })
+                .collect(),
+            participant_ids: vec![String::new()],
+            participant_ecdsa_signatures: vec![String::new()],
+        }
+    }
+
+    /// Verify every transcript.
+    pub fn verify(&self, rng: &mut RAND) -> Result<(), AmclError> {
+        for transcript in self.transcripts.iter() {
+            transcript.verify(rng)?;
+            if transcript.witness.running_products.len() != self.participant_ids.len() {
+                return Err(AmclError::InvalidTranscript);
+            }
+        }
+        Ok(())
+    }
+
+    /// Verify the BLS signatures present in the witnesses over the participant identities.
+    pub fn verify_bls_signatures(&self) -> Result<(), AmclError> {
+        for transcript in self.transcripts.iter() {
+            let witness = &transcript.witness;
+            for (i, signature) in witness.bls_signatures.iter().enumerate() {
+                if let Some(signature) = signature {
+                    let identity = self
+                        .participant_ids
+                        .get(i)
+                        .ok_or(AmclError::InvalidTranscript)?;
+                    if !proof_of_possession::verify_g1(
+                        &serialize_g2(&witness.pot_pubkeys[i]),
+                        identity.as_bytes(),
+                        &serialize_g1(signature),
+                    ) {
+                        return Err(AmclError::InvalidSignature);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The current state of the ceremony for the next participant to contribute to.
+    pub fn contribution(&self) -> BatchContribution {
+        BatchContribution {
+            contributions: self
+                .transcripts
+                .iter()
+                .map(|t| Contribution {
+                    powers: t.powers.clone(),
+                    pot_pubkey: ECP2::generator(),
+                    bls_signature: None,
+                })
+                .collect(),
+            ecdsa_signature: None,
+        }
+    }
+
+    /// Verify a contribution against the current state and append it to the transcripts.
+    pub fn apply(
+        &mut self,
+        contribution: &BatchContribution,
+        participant_id: &str,
+        rng: &mut RAND,
+    ) -> Result<(), AmclError> {
+        if contribution.contributions.len() != self.transcripts.len() {
+            return Err(AmclError::InvalidTranscript);
+        }
+        for (transcript, contribution) in self
+            .transcripts
+            .iter()
+            .zip(contribution.contributions.iter())
+        {
+            contribution.verify(&transcript.powers, rng)?;
+        }
+
+        for (transcript, contribution) in self
+            .transcripts
+            .iter_mut()
+            .zip(contribution.contributions.iter())
+        {
+            transcript.powers = contribution.powers.clone();
+            let witness = &mut transcript.witness;
+            witness
+                .running_products
+                .push(contribution.powers.g1_powers[1].clone());
+            witness.pot_pubkeys.push(contribution.pot_pubkey.clone());
+            witness
+                .bls_signatures
+                .push(contribution.bls_signature.clone());
+        }
+        self.participant_ids.push(participant_id.to_string());
+        self.participant_ecdsa_signatures
+            .push(contribution.ecdsa_signature.clone().unwrap_or_default());
+
+        Ok(())
+    }
+
+    /// Parse from the ceremony's BatchTranscript JSON.
+    pub fn from_json(json: &str) -> Result<BatchTranscript, AmclError> {
+        let json: JsonBatchTranscript =
+            serde_json::from_str(json).map_err(|_| AmclError::InvalidEncoding)?;
+
+        let mut transcripts = Vec::with_capacity(json.transcripts.len());
+        for transcript in json.transcripts.iter() {
+            let powers = transcript
+                .powers_of_tau
+                .decode(transcript.num_g1_powers, transcript.num_g2_powers)?;
+
+            let witness = &transcript.witness;
+            transcripts.push(Transcript {
+                powers,
+                witness: Witness {
+                    running_products: witness
+                        .running_products
+                        .iter()
+                        .map(|p| decode_g1(p))
+                        .collect::<Result<_, _>>()?,
+                    pot_pubkeys: witness
+                        .pot_pubkeys
+                        .iter()
+                        .map(|p| decode_g2(p))
+                        .collect::<Result<_, _>>()?,
+                    bls_signatures: witness
+                        .bls_signatures
+                        .iter()
+                        .map(|p| decode_optional_g1(p))
+                        .collect::<Result<_, _>>()?,
+                },
+            });
+        }
+
+        Ok(BatchTranscript {
+            transcripts,
+            participant_ids: json.participant_ids,
+            participant_ecdsa_signatures: json.participant_ecdsa_signatures,
+        })
+    }
+
+    /// Serialize to the ceremony's BatchTranscript JSON.
+    pub fn to_json(&self) -> Result<String, AmclError> {
+        let json = JsonBatchTranscript {
+            transcripts: self
+                .transcripts
+                .iter()
+                .map(|t| JsonTranscript {
+                    num_g1_powers: t.powers.g1_powers.len(),
+                    num_g2_powers: t.powers.g2_powers.len(),
+                    powers_of_tau: JsonPowersOfTau::encode(&t.powers),
+                    witness: JsonWitness {
+                        running_products: t
+                            .witness
+                            .running_products
+                            .iter()
+                            .map(encode_g1)
+                            .collect(),
+                        pot_pubkeys: t.witness.pot_pubkeys.iter().map(encode_g2).collect(),
+                        bls_signatures: t
+                            .witness
+                            .bls_signatures
+                            .iter()
+                            .map(encode_optional_g1)
+                            .collect(),
+                    },
+                })
+                .collect(),
+            participant_ids: self.participant_ids.clone(),
+            participant_ecdsa_signatures: self.participant_ecdsa_signatures.clone(),
+        };
+
+        serde_json::to_string(&json).map_err(|_| AmclError::InvalidEncoding)
+    }
+}
+
+// Ensures a proof of contribution is a non-identity G2 point in the subgroup.
+fn check_pot_pubkey(pot_pubkey: &ECP2) -> Result<(), AmclError> {
+    if pot_pubkey.is_infinity() || !subgroup_check_g2(pot_pubkey) {
+        return Err(AmclError::InvalidPoint);
+    }
+    Ok(())
+}
+
+// Returns [1, rho, rho^2, ...] for a random rho.
+fn random_powers(n: usize, rng: &mut RAND) -> Vec<Big> {
+    let rho = Fr::random(rng);
+    let mut powers = Vec::with_capacity(n);
+    let mut current = Fr::one();
+    for _ in 0..n {
+        powers.push(current.redc());
+        current.mul(&rho);
+    }
+    powers
+}
+
+/*************************************************************************************************
+* JSON encoding, points are 0x prefixed hex of the compressed serialization
+*************************************************************************************************/
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+struct JsonPowersOfTau {
+    #[serde(rename = "G1Powers")]
+    g1_powers: Vec<String>,
+    #[serde(rename = "G2Powers")]
+    g2_powers: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+#[serde(rename_all = "camelCase")]
+struct JsonContribution {
+    #[serde(rename = "numG1Powers")]
+    num_g1_powers: usize,
+    #[serde(rename = "numG2Powers")]
+    num_g2_powers: usize,
+    powers_of_tau: JsonPowersOfTau,
+    pot_pubkey: String,
+    #[serde(default)]
+    bls_signature: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+#[serde(rename_all = "camelCase")]
+struct JsonBatchContribution {
+    contributions: Vec<JsonContribution>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ecdsa_signature: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+#[serde(rename_all = "camelCase")]
+struct JsonWitness {
+    running_products: Vec<String>,
+    pot_pubkeys: Vec<String>,
+    bls_signatures: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+#[serde(rename_all = "camelCase")]
+struct JsonTranscript {
+    #[serde(rename = "numG1Powers")]
+    num_g1_powers: usize,
+    #[serde(rename = "numG2Powers")]
+    num_g2_powers: usize,
+    powers_of_tau: JsonPowersOfTau,
+    witness: JsonWitness,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "crate::serde")]
+#[serde(rename_all = "camelCase")]
+struct JsonBatchTranscript {
+    transcripts: Vec<JsonTranscript>,
+    participant_ids: Vec<String>,
+    participant_ecdsa_signatures: Vec<String>,
+}
+
+impl JsonPowersOfTau {
+    fn encode(powers: &PowersOfTau) -> JsonPowersOfTau {
+        JsonPowersOfTau {
+            g1_powers: powers.g1_powers.iter().map(encode_g1).collect(),
+            g2_powers: powers.g2_powers.iter().map(encode_g2).collect(),
+        }
+    }
+
+    fn decode(&self, num_g1_powers: usize, num_g2_powers: usize) -> Result<PowersOfTau, AmclError> {
+        if self.g1_powers.len() != num_g1_powers || self.g2_powers.len() != num_g2_powers {
+            return Err(AmclError::InvalidTranscript);
+        }
+
+        Ok(PowersOfTau {
+            g1_powers: self
+                .g1_powers
+                .iter()
+                .map(|p| decode_g1(p))
+                .collect::<Result<_, _>>()?,
+            g2_powers: self
+                .g2_powers
+                .iter()
+                .map(|p| decode_g2(p))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+fn encode_g1(point: &ECP) -> String {
+    let mut s = String::from("0x");
+    s.push_str(&hex::encode(&serialize_g1(point)[..]));
+    s
+}
+
+fn encode_g2(point: &ECP2) -> String {
+    let mut s = String::from("0x");
+    s.push_str(&hex::encode(&serialize_g2(point)[..]));
+    s
+}
+
+fn encode_optional_g1(point: &Option<ECP>) -> String {
+    point.as_ref().map(encode_g1).unwrap_or_default()
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, AmclError> {
+    let s = s.strip_prefix("0x").ok_or(AmclError::InvalidEncoding)?;
+    hex::decode(s).map_err(|_| AmclError::InvalidEncoding)
+}
+
+fn decode_g1(s: &str) -> Result<ECP, AmclError> {
+    deserialize_g1(&decode_hex(s)?)
+}
+
+fn decode_g2(s: &str) -> Result<ECP2, AmclError> {
+    deserialize_g2(&decode_hex(s)?)
+}
+
+// An empty string encodes a missing signature.
+fn decode_optional_g1(s: &str) -> Result<Option<ECP>, AmclError> {
+    if s.is_empty() {
+        return Ok(None);
+    }
+    decode_g1(s).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde_json::{self, Value};
+
+    const TRANSCRIPTS: &str = include_str!("../tests/fixtures/ceremony/transcripts.json");
+
+    fn fixture() -> Value {
+        serde_json::from_str(TRANSCRIPTS).unwrap()
+    }
+
+    fn rng() -> RAND {
+        let mut rng = RAND::new();
+        rng.seed(32, &[37u8; 32]);
+        rng
+    }
+
+    fn transcript(name: &str) -> BatchTranscript {
+        BatchTranscript::from_json(&fixture()[name].to_string()).unwrap()
+    }
+
+    fn contribution() -> BatchContribution {
+        BatchContribution::from_json(&fixture()["contribution"].to_string()).unwrap()
+    }
+
+    fn to_value(json: &str) -> Value {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_initial_transcript() {
+        let initial = BatchTranscript::new(&[(4, 2), (8, 3)]);
+        assert_eq!(
+            to_value(&initial.to_json().unwrap()),
+            fixture()["initialTranscript"]
+        );
+        initial.verify(&mut rng()).unwrap();
+    }
+
+    #[test]
+    fn test_transcript_json_round_trip() {
+        let mut rng = rng();
+        for name in ["initialTranscript", "transcript", "updatedTranscript"].iter() {
+            let transcript = transcript(name);
+            transcript.verify(&mut rng).unwrap();
+            transcript.verify_bls_signatures().unwrap();
+            assert_eq!(to_value(&transcript.to_json().unwrap()), fixture()[*name]);
+        }
+
+        let contribution = contribution();
+        assert_eq!(
+            to_value(&contribution.to_json().unwrap()),
+            fixture()["contribution"]
+        );
+    }
+
+    #[test]
+    fn test_apply_contribution() {
+        let mut rng = rng();
+        let fixture = fixture();
+        let mut transcript = transcript("transcript");
+        let participant_id = fixture["participantId"].as_str().unwrap();
+        transcript
+            .apply(&contribution(), participant_id, &mut rng)
+            .unwrap();
+        assert_eq!(
+            to_value(&transcript.to_json().unwrap()),
+            fixture["updatedTranscript"]
+        );
+    }
+
+    #[test]
+    fn test_contribute_verify() {
+        let mut rng = rng();
+        let mut transcript = transcript("updatedTranscript");
+        let identity = "git|7654321|participant";
+
+        let mut contribution = transcript.contribution();
+        contribution
+            .contribute(Some(identity.as_bytes()), &mut rng)
+            .unwrap();
+        let contribution = BatchContribution::from_json(&contribution.to_json().unwrap()).unwrap();
+        for (c, t) in contribution
+            .contributions
+            .iter()
+            .zip(transcript.transcripts.iter())
+        {
+            c.verify(&t.powers, &mut rng).unwrap();
+            assert!(!c.powers.g1_powers[1].equals(&t.powers.g1_powers[1]));
+        }
+
+        transcript.apply(&contribution, identity, &mut rng).unwrap();
+        transcript.verify(&mut rng).unwrap();
+        transcript.verify_bls_signatures().unwrap();
+        assert_eq!(transcript.participant_ids.len(), 4);
+
+        // The signature is over the identity it was made for
+        transcript.participant_ids[3] = "git|1|other".to_string();
+        assert_eq!(
+            transcript.verify_bls_signatures(),
+            Err(AmclError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_contribution_tampered_powers() {
+        let mut rng = rng();
+        let previous = &transcript("transcript").transcripts[1].powers;
+        let contribution = &contribution().contributions[1];
+        contribution.verify(previous, &mut rng).unwrap();
+
+        // A G1 power out of sequence and one off by a factor of 2
+        for i in [2, 7].iter() {
+            let mut tampered = contribution.clone();
+            tampered.powers.g1_powers[*i] = contribution.powers.g1_powers[*i - 1].clone();
+            assert_eq!(
+                tampered.verify(previous, &mut rng),
+                Err(AmclError::InvalidTranscript)
+            );
+            tampered.powers.g1_powers[*i] = contribution.powers.g1_powers[*i].clone();
+            tampered.powers.g1_powers[*i].dbl();
+            assert_eq!(
+                tampered.verify(previous, &mut rng),
+                Err(AmclError::InvalidTranscript)
+            );
+        }
+
+        // A wrong g2^tau
+        let mut tampered = contribution.clone();
+        tampered.powers.g2_powers[1] = pair::g2mul(&ECP2::generator(), &Big::new_int(5));
+        assert_eq!(
+            tampered.verify(previous, &mut rng),
+            Err(AmclError::InvalidTranscript)
+        );
+
+        // The generators must stay first
+        let mut tampered = contribution.clone();
+        tampered.powers.g1_powers[0].dbl();
+        assert_eq!(
+            tampered.verify(previous, &mut rng),
+            Err(AmclError::InvalidTranscript)
+        );
+    }
+
+    #[test]
+    fn test_contribution_not_built_on_previous() {
+        let mut rng = rng();
+        let contribution = &contribution().contributions[0];
+
+        // Well formed powers, but not the previous powers times the secret of pot_pubkey
+        let initial = PowersOfTau::new(4, 2);
+        assert_eq!(
+            contribution.verify(&initial, &mut rng),
+            Err(AmclError::InvalidTranscript)
+        );
+
+        let previous = &transcript("transcript").transcripts[0].powers;
+        let mut tampered = contribution.clone();
+        tampered.pot_pubkey = pair::g2mul(&ECP2::generator(), &Big::new_int(5));
+        assert_eq!(
+            tampered.verify(previous, &mut rng),
+            Err(AmclError::InvalidTranscript)
+        );
+
+        // A contribution to a transcript of another size
+        let other = &transcript("transcript").transcripts[1].powers;
+        assert_eq!(
+            contribution.verify(other, &mut rng),
+            Err(AmclError::InvalidTranscript)
+        );
+    }
+
+    #[test]
+    fn test_identity_contribution() {
+        let mut rng = rng();
+        let previous = &transcript("transcript").transcripts[0].powers;
+
+        // tau = 0 sends every power but the generators to the identity
+        let mut zero = Contribution {
+            powers: previous.clone(),
+            pot_pubkey: ECP2::new(),
+            bls_signature: None,
+        };
+        zero.powers.update(&Fr::new());
+        assert_eq!(
+            zero.verify(previous, &mut rng),
+            Err(AmclError::InvalidPoint)
+        );
+
+        // An identity proof of contribution with valid powers
+        let mut contribution = contribution().contributions[0].clone();
+        contribution.pot_pubkey = ECP2::new();
+        assert_eq!(
+            contribution.verify(previous, &mut rng),
+            Err(AmclError::InvalidPoint)
+        );
+
+        let mut transcript = transcript("transcript");
+        let mut batch = self::contribution();
+        batch.contributions[0] = zero;
+        assert_eq!(
+            transcript.apply(&batch, "eth|0x0", &mut rng),
+            Err(AmclError::InvalidPoint)
+        );
+        assert_eq!(transcript.participant_ids.len(), 2);
+    }
+
+    #[test]
+    fn test_broken_witness_chain() {
+        let mut rng = rng();
+        let updated = transcript("updatedTranscript");
+
+        // A running product that is not the previous one times the contribution's tau
+        let mut broken = updated.clone();
+        broken.transcripts[0].witness.running_products[1].dbl();
+        assert_eq!(broken.verify(&mut rng), Err(AmclError::InvalidTranscript));
+
+        // Proofs of contribution swapped between participants
+        let mut broken = updated.clone();
+        broken.transcripts[1].witness.pot_pubkeys.swap(1, 2);
+        assert_eq!(broken.verify(&mut rng), Err(AmclError::InvalidTranscript));
+
+        // A final running product that is not the current [tau]G1
+        let mut broken = updated.clone();
+        let witness = &mut broken.transcripts[0].witness;
+        witness.running_products.pop();
+        witness.pot_pubkeys.pop();
+        witness.bls_signatures.pop();
+        assert_eq!(broken.verify(&mut rng), Err(AmclError::InvalidTranscript));
+
+        // An identity proof of contribution in the witness
+        let mut broken = updated.clone();
+        broken.transcripts[0].witness.pot_pubkeys[2] = ECP2::new();
+        assert_eq!(broken.verify(&mut rng), Err(AmclError::InvalidPoint));
+
+        // Witness entries without a participant
+        let mut broken = updated.clone();
+        broken.participant_ids.pop();
+        assert_eq!(broken.verify(&mut rng), Err(AmclError::InvalidTranscript));
+    }
+
+    #[test]
+    fn test_from_json_invalid() {
+        let json = fixture()["transcript"].to_string();
+
+        // Points without the 0x prefix and a count that does not match the powers
+        let point =
+            fixture()["transcript"]["transcripts"][0]["witness"]["runningProducts"][1].clone();
+        let point = point.as_str().unwrap();
+        let unprefixed = json.replacen(point, &point[2..], 1);
+        assert_ne!(unprefixed, json);
+        assert_eq!(
+            BatchTranscript::from_json(&unprefixed).err(),
+            Some(AmclError::InvalidEncoding)
+        );
+        let miscounted = json.replacen("\"numG1Powers\":4", "\"numG1Powers\":5", 1);
+        assert_ne!(miscounted, json);
+        assert_eq!(
+            BatchTranscript::from_json(&miscounted).err(),
+            Some(AmclError::InvalidTranscript)
+        );
+        assert_eq!(
+            BatchTranscript::from_json("{}").err(),
+            Some(AmclError::InvalidEncoding)
+        );
+    }
+}
@@ -0,0 +1,546 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::big::Big;
+use super::bls381::utils::{
+    map_to_curve_g1, map_to_curve_g2, subgroup_check_g1, subgroup_check_g2,
+};
+use super::ecp::ECP;
+use super::ecp2::ECP2;
+use super::fp::FP;
+use super::fp2::FP2;
+use super::fr::Fr;
+use super::kzg::pairing_product_is_unity;
+use super::pair;
+use super::pedersen::multi_scalar_mul;
+use super::rom::{CURVE_ORDER, H_EFF_G1, MODBYTES, MODULUS};
+use crate::errors::AmclError;
+use std::vec::Vec;
+
+/// Precompile addresses
+pub const BLS12_G1ADD: u8 = 0x0b;
+pub const BLS12_G1MSM: u8 = 0x0c;
+pub const BLS12_G2ADD: u8 = 0x0d;
+pub const BLS12_G2MSM: u8 = 0x0e;
+pub const BLS12_PAIRING_CHECK: u8 = 0x0f;
+pub const BLS12_MAP_FP_TO_G1: u8 = 0x10;
+pub const BLS12_MAP_FP2_TO_G2: u8 = 0x11;
+
+/// The number of bytes of an encoded base field element
+pub const FP_BYTES: usize = 64;
+/// The number of bytes of an encoded extension field element
+pub const FP2_BYTES: usize = 2 * FP_BYTES;
+/// The number of bytes of an encoded G1 point
+pub const G1_POINT_BYTES: usize = 2 * FP_BYTES;
+/// The number of bytes of an encoded G2 point
+pub const G2_POINT_BYTES: usize = 2 * FP2_BYTES;
+/// The number of bytes of an encoded scalar
+pub const SCALAR_BYTES: usize = 32;
+
+// Zero padding in front of the 48 byte big-endian field element
+const FP_PADDING: usize = FP_BYTES - MODBYTES;
+
+/*************************************************************************************************
+* EIP-2537: Precompile for BLS12-381 curve operations
+*
+* https://eips.ethereum.org/EIPS/eip-2537
+*
+* Field elements are 64 bytes, big-endian with 16 zero bytes of padding, and must be less than
+* the modulus. An Fp2 element c0 + c1 * v is encoded as c0 || c1. Points are x || y without
+* flags and the point at infinity is all zeros. Scalars are 32 byte big-endian integers which
+* need not be reduced.
+*
+* Points must be on the curve, and for the MSM and pairing operations in the prime order
+* subgroup. Gas is left to the caller.
+*************************************************************************************************/
+
+/// Run the precompile at `address` on `input`.
+pub fn run(address: u8, input: &[u8]) -> Result<Vec<u8>, AmclError> {
+    match address {
+        BLS12_G1ADD => g1_add(input).map(|r| r.to_vec()),
+        BLS12_G1MSM => g1_msm(input).map(|r| r.to_vec()),
+        BLS12_G2ADD => g2_add(input).map(|r| r.to_vec()),
+        BLS12_G2MSM => g2_msm(input).map(|r| r.to_vec()),
+        BLS12_PAIRING_CHECK => pairing_check(input).map(|r| r.to_vec()),
+        BLS12_MAP_FP_TO_G1 => map_fp_to_g1(input).map(|r| r.to_vec()),
+        BLS12_MAP_FP2_TO_G2 => map_fp2_to_g2(input).map(|r| r.to_vec()),
+        _ => Err(AmclError::InvalidPrecompileInput),
+    }
+}
+
+/// BLS12_G1ADD
+///
+/// Input two G1 points, output their sum. No subgroup check is performed.
+pub fn g1_add(input: &[u8]) -> Result<[u8; G1_POINT_BYTES], AmclError> {
+    if input.len() != 2 * G1_POINT_BYTES {
+        return Err(AmclError::InvalidPrecompileInput);
+    }
+    let mut p = decode_g1(&input[..G1_POINT_BYTES])?;
+    let q = decode_g1(&input[G1_POINT_BYTES..])?;
+    p.add(&q);
+    Ok(encode_g1(&p))
+}
+
+/// BLS12_G1MSM
+///
+/// Input k > 0 pairs of a G1 point and a scalar, output the sum of the products.
+pub fn g1_msm(input: &[u8]) -> Result<[u8; G1_POINT_BYTES], AmclError> {
+    let pair_bytes = G1_POINT_BYTES + SCALAR_BYTES;
+    if input.is_empty() || input.len() % pair_bytes != 0 {
+        return Err(AmclError::InvalidPrecompileInput);
+    }
+
+    let mut points = Vec::with_capacity(input.len() / pair_bytes);
+    let mut scalars = Vec::with_capacity(input.len() / pair_bytes);
+    for chunk in input.chunks(pair_bytes) {
+        let point = decode_g1(&chunk[..G1_POINT_BYTES])?;
+        if !subgroup_check_g1(&point) {
+            return Err(AmclError::InvalidPoint);
+        }
+        points.push(point);
+        scalars.push(Fr::new_big(&decode_scalar(&chunk[G1_POINT_BYTES..])));
+    }
+
+    Ok(encode_g1(&multi_scalar_mul(&points, &scalars)))
+}
+
+/// BLS12_G2ADD
+///
+/// Input two G2 points, output their sum. No subgroup check is performed.
+pub fn g2_add(input: &[u8]) -> Result<[u8; G2_POINT_BYTES], AmclError> {
+    if input.len() != 2 * G2_POINT_BYTES {
+        return Err(AmclError::InvalidPrecompileInput);
+    }
+    let mut p = decode_g2(&input[..G2_POINT_BYTES])?;
+    let q = decode_g2(&input[G2_POINT_BYTES..])?;
+    p.add(&q);
+    Ok(encode_g2(&p))
+}
+
+/// BLS12_G2MSM
+///
+/// Input k > 0 pairs of a G2 point and a scalar, output the sum of the products.
+pub fn g2_msm(input: &[u8]) -> Result<[u8; G2_POINT_BYTES], AmclError> {
+    let pair_bytes = G2_POINT_BYTES + SCALAR_BYTES;
+    if input.is_empty() || input.len() % pair_bytes != 0 {
+        return Err(AmclError::InvalidPrecompileInput);
+    }
+
+    let r = Big::new_ints(&CURVE_ORDER);
+    let mut result = ECP2::new();
+    for chunk in input.chunks(pair_bytes) {
+        let point = decode_g2(&chunk[..G2_POINT_BYTES])?;
+        if !subgroup_check_g2(&point) {
+            return Err(AmclError::InvalidPoint);
+        }
+        // Points are in the subgroup so the scalar may be reduced modulo r
+        let mut scalar = decode_scalar(&chunk[G2_POINT_BYTES..]);
+        scalar.rmod(&r);
+        if !scalar.is_zilch() {
+            result.add(&pair::g2mul(&point, &scalar));
+        }
+    }
+
+    Ok(encode_g2(&result))
+}
+
+/// BLS12_PAIRING_CHECK
+///
+/// Input k > 0 pairs of a G1 and a G2 point, output 1 as a 32 byte big-endian integer if the
+/// product of their pairings is the identity and 0 otherwise.
+pub fn pairing_check(input: &[u8]) -> Result<[u8; 32], AmclError> {
+    let pair_bytes = G1_POINT_BYTES + G2_POINT_BYTES;
+    if input.is_empty() || input.len() % pair_bytes != 0 {
+        return Err(AmclError::InvalidPrecompileInput);
+    }
+
+    let mut g1_points = Vec::with_capacity(input.len() / pair_bytes);
+    let mut g2_points = Vec::with_capacity(input.len() / pair_bytes);
+    for chunk in input.chunks(pair_bytes) {
+        let p = decode_g1(&chunk[..G1_POINT_BYTES])?;
+        let q = decode_g2(&chunk[G1_POINT_BYTES..])?;
+        if !subgroup_check_g1(&p) || !subgroup_check_g2(&q) {
+            return Err(AmclError::InvalidPoint);
+        }
+        g1_points.push(p);
+        g2_points.push(q);
+    }
+
+    let pairs: Vec<(&ECP2, &ECP)> = g2_points.iter().zip(g1_points.iter()).collect();
+    let mut output = [0u8; 32];
+    if pairing_product_is_unity(&pairs) {
+        output[31] = 1;
+    }
+    Ok(output)
+}
+
+/// BLS12_MAP_FP_TO_G1
+///
+/// Input a base field element, output its image under the simplified SWU map with the cofactor
+/// cleared.
+pub fn map_fp_to_g1(input: &[u8]) -> Result<[u8; G1_POINT_BYTES], AmclError> {
+    if input.len() != FP_BYTES {
+        return Err(AmclError::InvalidPrecompileInput);
+    }
+    let u = decode_fp(input)?;
+    let point = map_to_curve_g1(u).mul(&Big::new_ints(&H_EFF_G1));
+    Ok(encode_g1(&point))
+}
+
+/// BLS12_MAP_FP2_TO_G2
+///
+/// Input an extension field element, output its image under the simplified SWU map with the
+/// cofactor cleared.
+pub fn map_fp2_to_g2(input: &[u8]) -> Result<[u8; G2_POINT_BYTES], AmclError> {
+    if input.len() != FP2_BYTES {
+        return Err(AmclError::InvalidPrecompileInput);
+    }
+    let u = decode_fp2(input)?;
+    let mut point = map_to_curve_g2(u);
+    point.clear_cofactor();
+    Ok(encode_g2(&point))
+}
+
+/*************************************************************************************************
+* Encoding
+*************************************************************************************************/
+
+fn decode_fp(bytes: &[u8]) -> Result<FP, AmclError> {
+    if bytes[..FP_PADDING].iter().any(|b| *b != 0) {
+        return Err(AmclError::InvalidEncoding);
+    }
+    let x = Big::from_bytes(&bytes[FP_PADDING..FP_BYTES]);
+    if x >= Big::new_ints(&MODULUS) {
+        return Err(AmclError::InvalidEncoding);
+    }
+    Ok(FP::new_big(x))
+}
+
+fn encode_fp(x: &FP, bytes: &mut [u8]) {
+    x.redc().to_bytes(&mut bytes[FP_PADDING..FP_BYTES]);
+}
+
+fn decode_fp2(bytes: &[u8]) -> Result<FP2, AmclError> {
+    let c0 = decode_fp(&bytes[..FP_BYTES])?;
+    let c1 = decode_fp(&bytes[FP_BYTES..FP2_BYTES])?;
+    Ok(FP2::new_fps(c0, c1))
+}
+
+fn encode_fp2(x: &FP2, bytes: &mut [u8]) {
+    x.geta().to_bytes(&mut bytes[FP_PADDING..FP_BYTES]);
+    x.getb()
+        .to_bytes(&mut bytes[FP_BYTES + FP_PADDING..FP2_BYTES]);
+}
+
+// A point on the curve, not necessarily in the subgroup.
+fn decode_g1(bytes: &[u8]) -> Result<ECP, AmclError> {
+    if bytes.iter().all(|b| *b == 0) {
+        return Ok(ECP::new());
+    }
+    let x = decode_fp(&bytes[..FP_BYTES])?;
+    let y = decode_fp(&bytes[FP_BYTES..G1_POINT_BYTES])?;
+    let point = ECP::new_fps(x, y);
+    if point.is_infinity() {
+        return Err(AmclError::InvalidPoint);
+    }
+    Ok(point)
+}
+
+fn encode_g1(point: &ECP) -> [u8; G1_POINT_BYTES] {
+    let mut bytes = [0u8; G1_POINT_BYTES];
+    if !point.is_infinity() {
+        let mut point = point.clone();
+        point.affine();
+        encode_fp(&point.getpx(), &mut bytes[..FP_BYTES]);
+        encode_fp(&point.getpy(), &mut bytes[FP_BYTES..]);
+    }
+    bytes
+}
+
+// A point on the twist, not necessarily in the subgroup.
+fn decode_g2(bytes: &[u8]) -> Result<ECP2, AmclError> {
+    if bytes.iter().all(|b| *b == 0) {
+        return Ok(ECP2::new());
+    }
+    let x = decode_fp2(&bytes[..FP2_BYTES])?;
+    let y = decode_fp2(&bytes[FP2_BYTES..G2_POINT_BYTES])?;
+    let point = ECP2::new_fp2s(x, y);
+    if point.is_infinity() {
+        return Err(AmclError::InvalidPoint);
+    }
+    Ok(point)
+}
+
+fn encode_g2(point: &ECP2) -> [u8; G2_POINT_BYTES] {
+    let mut bytes = [0u8; G2_POINT_BYTES];
+    if !point.is_infinity() {
+        encode_fp2(&point.getx(), &mut bytes[..FP2_BYTES]);
+        encode_fp2(&point.gety(), &mut bytes[FP2_BYTES..]);
+    }
+    bytes
+}
+
+fn decode_scalar(bytes: &[u8]) -> Big {
+    Big::from_bytes(&bytes[..SCALAR_BYTES])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde_json::{self, Value};
+
+    // The EIP's own vectors, a null output means the input must be rejected.
+    const VECTORS: &str = include_str!("../tests/fixtures/eip2537/vectors.json");
+
+    fn bytes(value: &Value) -> Vec<u8> {
+        hex::decode(value.as_str().unwrap().trim_start_matches("0x")).unwrap()
+    }
+
+    // A G1 and a G2 point on the curve but outside the prime order subgroup
+    fn non_subgroup_points() -> (ECP, ECP2) {
+        let p = map_to_curve_g1(FP::new_int(1));
+        let q = map_to_curve_g2(FP2::new_ints(1, 2));
+        assert!(!subgroup_check_g1(&p));
+        assert!(!subgroup_check_g2(&q));
+        (p, q)
+    }
+
+    fn scalar(n: u8) -> [u8; SCALAR_BYTES] {
+        let mut bytes = [0u8; SCALAR_BYTES];
+        bytes[SCALAR_BYTES - 1] = n;
+        bytes
+    }
+
+    #[test]
+    fn test_vectors() {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        let precompiles = [
+            ("g1_add", BLS12_G1ADD),
+            ("g1_msm", BLS12_G1MSM),
+            ("g2_add", BLS12_G2ADD),
+            ("g2_msm", BLS12_G2MSM),
+            ("pairing_check", BLS12_PAIRING_CHECK),
+            ("map_fp_to_g1", BLS12_MAP_FP_TO_G1),
+            ("map_fp2_to_g2", BLS12_MAP_FP2_TO_G2),
+        ];
+        for (name, address) in precompiles.iter() {
+            let cases = vectors[*name].as_array().unwrap();
+            assert!(cases.iter().any(|case| case["output"].is_null()));
+            assert!(cases.iter().any(|case| !case["output"].is_null()));
+            for case in cases {
+                let result = run(*address, &bytes(&case["input"]));
+                match result {
+                    Ok(output) => assert_eq!(output, bytes(&case["output"]), "{}", case["name"]),
+                    Err(_) => assert!(case["output"].is_null(), "{}", case["name"]),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_address() {
+        assert_eq!(
+            run(0x0a, &[0u8; FP_BYTES]),
+            Err(AmclError::InvalidPrecompileInput)
+        );
+        assert_eq!(
+            run(0x12, &[0u8; FP_BYTES]),
+            Err(AmclError::InvalidPrecompileInput)
+        );
+    }
+
+    #[test]
+    fn test_padding() {
+        let generator = encode_g1(&ECP::generator());
+        let input = [generator, generator].concat();
+        assert!(g1_add(&input).is_ok());
+        for i in [0, FP_PADDING - 1, FP_BYTES, G1_POINT_BYTES].iter() {
+            let mut padded = input.clone();
+            padded[*i] = 1;
+            assert_eq!(g1_add(&padded), Err(AmclError::InvalidEncoding));
+        }
+
+        // Every padding byte of an extension field element
+        let mut input = [0u8; FP2_BYTES];
+        encode_fp2(&FP2::new_ints(1, 2), &mut input);
+        assert!(map_fp2_to_g2(&input).is_ok());
+        for i in (0..FP_PADDING).chain(FP_BYTES..FP_BYTES + FP_PADDING) {
+            let mut padded = input;
+            padded[i] = 0x80;
+            assert_eq!(map_fp2_to_g2(&padded), Err(AmclError::InvalidEncoding));
+        }
+    }
+
+    #[test]
+    fn test_field_element_range() {
+        let mut modulus = [0u8; FP_BYTES];
+        Big::new_ints(&MODULUS).to_bytes(&mut modulus[FP_PADDING..]);
+        assert_eq!(map_fp_to_g1(&modulus), Err(AmclError::InvalidEncoding));
+
+        // p - 1 is the largest element
+        let mut largest = modulus;
+        largest[FP_BYTES - 1] -= 1;
+        assert!(map_fp_to_g1(&largest).is_ok());
+
+        // x = p as a point coordinate
+        let mut point = encode_g1(&ECP::generator()).to_vec();
+        point[..FP_BYTES].copy_from_slice(&modulus);
+        assert_eq!(
+            g1_add(&[&point[..], &[0u8; G1_POINT_BYTES][..]].concat()),
+            Err(AmclError::InvalidEncoding)
+        );
+        let mut fp2 = [0u8; FP2_BYTES];
+        fp2[FP_BYTES..].copy_from_slice(&modulus);
+        assert_eq!(map_fp2_to_g2(&fp2), Err(AmclError::InvalidEncoding));
+    }
+
+    #[test]
+    fn test_not_on_curve() {
+        let mut point = encode_g1(&ECP::generator());
+        point[G1_POINT_BYTES - 1] ^= 1;
+        let infinity = [0u8; G1_POINT_BYTES];
+        assert_eq!(
+            g1_add(&[point, infinity].concat()),
+            Err(AmclError::InvalidPoint)
+        );
+        assert_eq!(
+            g1_msm(&[&point[..], &scalar(1)[..]].concat()),
+            Err(AmclError::InvalidPoint)
+        );
+
+        let mut point = encode_g2(&ECP2::generator());
+        point[G2_POINT_BYTES - 1] ^= 1;
+        let infinity = [0u8; G2_POINT_BYTES];
+        assert_eq!(
+            g2_add(&[point, infinity].concat()),
+            Err(AmclError::InvalidPoint)
+        );
+        assert_eq!(
+            pairing_check(&[&encode_g1(&ECP::generator())[..], &point[..]].concat()),
+            Err(AmclError::InvalidPoint)
+        );
+    }
+
+    #[test]
+    fn test_subgroup() {
+        let (p, q) = non_subgroup_points();
+        let g1 = encode_g1(&ECP::generator());
+        let g2 = encode_g2(&ECP2::generator());
+
+        // Addition does not check the subgroup
+        assert!(g1_add(&[encode_g1(&p), g1].concat()).is_ok());
+        assert!(g2_add(&[encode_g2(&q), g2].concat()).is_ok());
+
+        assert_eq!(
+            g1_msm(&[&g1[..], &scalar(1)[..], &encode_g1(&p)[..], &scalar(0)[..]].concat()),
+            Err(AmclError::InvalidPoint)
+        );
+        assert_eq!(
+            g2_msm(&[&g2[..], &scalar(1)[..], &encode_g2(&q)[..], &scalar(0)[..]].concat()),
+            Err(AmclError::InvalidPoint)
+        );
+        assert_eq!(
+            pairing_check(&[&encode_g1(&p)[..], &g2[..]].concat()),
+            Err(AmclError::InvalidPoint)
+        );
+        assert_eq!(
+            pairing_check(&[&g1[..], &encode_g2(&q)[..]].concat()),
+            Err(AmclError::InvalidPoint)
+        );
+    }
+
+    #[test]
+    fn test_lengths() {
+        let g1 = encode_g1(&ECP::generator());
+        let g2 = encode_g2(&ECP2::generator());
+        let g1_pair = [&g1[..], &scalar(2)[..]].concat();
+        let g2_pair = [&g2[..], &scalar(2)[..]].concat();
+        let pairing = [&g1[..], &g2[..]].concat();
+
+        for (address, input) in [
+            (BLS12_G1MSM, &g1_pair),
+            (BLS12_G2MSM, &g2_pair),
+            (BLS12_PAIRING_CHECK, &pairing),
+        ]
+        .iter()
+        {
+            assert_eq!(run(*address, &[]), Err(AmclError::InvalidPrecompileInput));
+            assert!(run(*address, input).is_ok());
+            assert!(run(*address, &input.repeat(3)).is_ok());
+            assert_eq!(
+                run(*address, &input[1..]),
+                Err(AmclError::InvalidPrecompileInput)
+            );
+            assert_eq!(
+                run(*address, &[&input[..], &[0u8][..]].concat()),
+                Err(AmclError::InvalidPrecompileInput)
+            );
+        }
+
+        // An MSM of k equal pairs is the single product k times
+        let double = g1_msm(&g1_pair).unwrap();
+        let mut expected = decode_g1(&double).unwrap();
+        expected.add(&decode_g1(&double).unwrap());
+        assert_eq!(
+            g1_msm(&g1_pair.repeat(2)).unwrap().to_vec(),
+            encode_g1(&expected).to_vec()
+        );
+    }
+
+    #[test]
+    fn test_infinity() {
+        let g1 = encode_g1(&ECP::generator());
+        let g2 = encode_g2(&ECP2::generator());
+        let infinity_g1 = [0u8; G1_POINT_BYTES];
+        let infinity_g2 = [0u8; G2_POINT_BYTES];
+
+        assert_eq!(
+            g1_add(&[g1, infinity_g1].concat()).unwrap().to_vec(),
+            g1.to_vec()
+        );
+        assert_eq!(
+            g2_add(&[infinity_g2, g2].concat()).unwrap().to_vec(),
+            g2.to_vec()
+        );
+        assert_eq!(
+            g1_msm(&[&infinity_g1[..], &scalar(5)[..]].concat())
+                .unwrap()
+                .to_vec(),
+            infinity_g1.to_vec()
+        );
+        assert_eq!(
+            g2_msm(&[&g2[..], &scalar(0)[..]].concat())
+                .unwrap()
+                .to_vec(),
+            infinity_g2.to_vec()
+        );
+
+        // e(P, O) = e(O, Q) = 1
+        assert_eq!(
+            pairing_check(&[&g1[..], &infinity_g2[..]].concat()),
+            Ok(scalar(1))
+        );
+        assert_eq!(
+            pairing_check(&[&infinity_g1[..], &g2[..]].concat()),
+            Ok(scalar(1))
+        );
+        assert_eq!(pairing_check(&[&g1[..], &g2[..]].concat()), Ok(scalar(0)));
+    }
+}
@@ -0,0 +1,696 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::big::Big;
+use super::bls381::utils::{
+    deserialize_g1, deserialize_g2, serialize_g1, subgroup_check_g1, subgroup_check_g2,
+};
+use super::ecp::ECP;
+use super::ecp2::ECP2;
+use super::kzg::{
+    g1_linear_combination, pairing_product_is_unity, parse_trusted_setup_file, scalar_add,
+    scalar_from_bytes, scalar_inverse, scalar_mul, scalar_sub, scalar_to_bytes, G1_POINT_BYTES,
+    G2_POINT_BYTES,
+};
+use super::pair;
+use super::rom::CURVE_ORDER;
+use crate::errors::AmclError;
+use crate::hash256::HASH256;
+use std::vec::Vec;
+
+/// The number of field elements in a blob
+pub const FIELD_ELEMENTS_PER_BLOB: usize = 4096;
+/// The number of bytes of a serialized field element
+pub const BYTES_PER_FIELD_ELEMENT: usize = 32;
+/// The number of bytes in a blob
+pub const BYTES_PER_BLOB: usize = FIELD_ELEMENTS_PER_BLOB * BYTES_PER_FIELD_ELEMENT;
+/// The number of bytes of a KZG commitment
+pub const BYTES_PER_COMMITMENT: usize = G1_POINT_BYTES;
+/// The number of bytes of a KZG proof
+pub const BYTES_PER_PROOF: usize = G1_POINT_BYTES;
+
+/// Domain for the Fiat-Shamir evaluation challenge of a blob
+pub const FIAT_SHAMIR_PROTOCOL_DOMAIN: &[u8] = b"FSBLOBVERIFY_V1_";
+/// Domain for the random challenge of batch verification
+pub const RANDOM_CHALLENGE_KZG_BATCH_DOMAIN: &[u8] = b"RCKZGBATCH___V1_";
+/// Primitive root of unity of the scalar field
+pub const PRIMITIVE_ROOT_OF_UNITY: isize = 7;
+
+/// KZG settings for EIP-4844
+///
+/// Holds the G1 Lagrange basis and the roots of unity in bit-reversed order,
+/// and the G2 monomial setup.
+#[derive(Clone)]
+pub struct KzgSettings {
+    pub g1_lagrange_brp: Vec<ECP>,
+    pub g2_monomial: Vec<ECP2>,
+    pub roots_of_unity_brp: Vec<Big>,
+}
+
+/// Load Trusted Setup
+///
+/// `g1_lagrange_bytes` holds 4096 compressed G1 points of the Lagrange basis in natural order,
+/// `g2_monomial_bytes` at least the compressed G2 points [1]G2 and [tau]G2.
+pub fn load_trusted_setup(
+    g1_lagrange_bytes: &[u8],
+    g2_monomial_bytes: &[u8],
+) -> Result<KzgSettings, AmclError> {
+    if g1_lagrange_bytes.len() != FIELD_ELEMENTS_PER_BLOB * G1_POINT_BYTES {
+        return Err(AmclError::InvalidTrustedSetup);
+    }
+    if g2_monomial_bytes.len() < 2 * G2_POINT_BYTES || g2_monomial_bytes.len() % G2_POINT_BYTES != 0
+    {
+        return Err(AmclError::InvalidTrustedSetup);
+    }
+
+    let mut g1_lagrange = Vec::with_capacity(FIELD_ELEMENTS_PER_BLOB);
+    for point in g1_lagrange_bytes.chunks(G1_POINT_BYTES) {
+        let point = deserialize_g1(point)?;
+        if point.is_infinity() || !subgroup_check_g1(&point) {
+            return Err(AmclError::InvalidTrustedSetup);
+        }
+        g1_lagrange.push(point);
+    }
+
+    let mut g2_monomial = Vec::with_capacity(g2_monomial_bytes.len() / G2_POINT_BYTES);
+    for point in g2_monomial_bytes.chunks(G2_POINT_BYTES) {
+        let point = deserialize_g2(point)?;
+        if point.is_infinity() || !subgroup_check_g2(&point) {
+            return Err(AmclError::InvalidTrustedSetup);
+        }
+        g2_monomial.push(point);
+    }
+    if !g2_monomial[0].equals(&ECP2::generator()) {
+        return Err(AmclError::InvalidTrustedSetup);
+    }
+
+    Ok(KzgSettings {
+        g1_lagrange_brp: bit_reversal_permutation(&g1_lagrange),
+        g2_monomial,
+        roots_of_unity_brp: bit_reversal_permutation(&compute_roots_of_unity(
+            FIELD_ELEMENTS_PER_BLOB,
+        )),
+    })
+}
+
+/// Load Trusted Setup File
+///
+/// Parse the text format of `trusted_setup.txt` as shipped with c-kzg-4844, see
+/// `kzg::parse_trusted_setup_file`. The G1 monomial points of newer files are not used.
+pub fn load_trusted_setup_file(contents: &str) -> Result<KzgSettings, AmclError> {
+    let file = parse_trusted_setup_file(contents)?;
+    load_trusted_setup(&file.g1_lagrange, &file.g2_monomial)
+}
+
+/*************************************************************************************************
+* Helper functions
+*
+* https://github.com/ethereum/consensus-specs/blob/dev/specs/deneb/polynomial-commitments.md
+*************************************************************************************************/
+
+// Reverse the lowest `bits` bits of `n`.
+fn reverse_bits(n: usize, bits: usize) -> usize {
+    let mut reversed = 0;
+    for i in 0..bits {
+        reversed |= ((n >> i) & 1) << (bits - 1 - i);
+    }
+    reversed
+}
+
+/// Bit Reversal Permutation
+///
+/// Permute a list of power of two length by reversing the bits of each index.
+pub fn bit_reversal_permutation<T: Clone>(sequence: &[T]) -> Vec<T> {
+    let bits = sequence.len().trailing_zeros() as usize;
+    (0..sequence.len())
+        .map(|i| sequence[reverse_bits(i, bits)].clone())
+        .collect()
+}
+
+/// Compute Roots Of Unity
+///
+/// Returns [w^0, w^1, ..., w^(n - 1)] where w = 7^((r - 1) / n), n a power of two.
+pub fn compute_roots_of_unity(n: usize) -> Vec<Big> {
+    let r = Big::new_ints(&CURVE_ORDER);
+
+    // (r - 1) / n
+    let mut exponent = r.clone();
+    exponent.dec(1);
+    exponent.norm();
+    exponent.shr(n.trailing_zeros() as usize);
+
+    let root = Big::new_int(PRIMITIVE_ROOT_OF_UNITY).powmod(&exponent, &r);
+    compute_powers(&root, n)
+}
+
+// Returns [x^0, x^1, ..., x^(n - 1)].
+fn compute_powers(x: &Big, n: usize) -> Vec<Big> {
+    let mut powers = Vec::with_capacity(n);
+    let mut current = Big::new_int(1);
+    for _ in 0..n {
+        powers.push(current.clone());
+        current = scalar_mul(&current, x);
+    }
+    powers
+}
+
+// Montgomery batch inversion, all inputs must be non-zero.
+fn batch_inverse(values: &[Big]) -> Vec<Big> {
+    let mut prefix = Vec::with_capacity(values.len());
+    let mut accumulator = Big::new_int(1);
+    for value in values.iter() {
+        prefix.push(accumulator.clone());
+        accumulator = scalar_mul(&accumulator, value);
+    }
+
+    let mut inverse = scalar_inverse(&accumulator);
+    let mut result = vec![Big::new(); values.len()];
+    for i in (0..values.len()).rev() {
+        result[i] = scalar_mul(&inverse, &prefix[i]);
+        inverse = scalar_mul(&inverse, &values[i]);
+    }
+    result
+}
+
+// Hash To BLS Field
+//
+// Interpret SHA-256(data) as a big-endian integer mod r.
+fn hash_to_bls_field(data: &[u8]) -> Big {
+    let mut hash256 = HASH256::new();
+    hash256.init();
+    hash256.process_array(data);
+    let mut x = Big::from_bytes(&hash256.hash());
+    x.rmod(&Big::new_ints(&CURVE_ORDER));
+    x
+}
+
+// Validate KZG G1
+//
+// Deserialize a compressed commitment or proof, the point at infinity is allowed.
+fn bytes_to_kzg_g1(bytes: &[u8]) -> Result<ECP, AmclError> {
+    if bytes.len() != G1_POINT_BYTES {
+        return Err(AmclError::InvalidG1Size);
+    }
+    let point = deserialize_g1(bytes)?;
+    if !subgroup_check_g1(&point) {
+        return Err(AmclError::InvalidPoint);
+    }
+    Ok(point)
+}
+
+// Blob To Polynomial
+//
+// Convert a blob to its polynomial in evaluation form.
+fn blob_to_polynomial(blob: &[u8]) -> Result<Vec<Big>, AmclError> {
+    if blob.len() != BYTES_PER_BLOB {
+        return Err(AmclError::InvalidBlobSize);
+    }
+
+    let mut polynomial = Vec::with_capacity(FIELD_ELEMENTS_PER_BLOB);
+    for field_element in blob.chunks(BYTES_PER_FIELD_ELEMENT) {
+        polynomial.push(scalar_from_bytes(field_element)?);
+    }
+    Ok(polynomial)
+}
+
+// Compute Challenge
+//
+// Fiat-Shamir evaluation challenge of a blob and its commitment.
+fn compute_challenge(blob: &[u8], commitment: &[u8]) -> Big {
+    let mut data = FIAT_SHAMIR_PROTOCOL_DOMAIN.to_vec();
+    // Degree of the polynomial as a 16 byte big-endian integer
+    data.extend_from_slice(&(FIELD_ELEMENTS_PER_BLOB as u128).to_be_bytes());
+    data.extend_from_slice(blob);
+    data.extend_from_slice(commitment);
+    hash_to_bls_field(&data)
+}
+
+// Evaluate Polynomial In Evaluation Form
+//
+// Evaluate a polynomial in bit-reversed Lagrange form at `z` with the barycentric formula.
+fn evaluate_polynomial_in_evaluation_form(
+    settings: &KzgSettings,
+    polynomial: &[Big],
+    z: &Big,
+) -> Big {
+    let roots = &settings.roots_of_unity_brp;
+
+    // If z is a root of unity the evaluation is given directly
+    if let Some(index) = roots.iter().position(|root| root == z) {
+        return polynomial[index].clone();
+    }
+
+    let denominators: Vec<Big> = roots.iter().map(|root| scalar_sub(z, root)).collect();
+    let inverses = batch_inverse(&denominators);
+
+    // sum(p[i] * w[i] / (z - w[i]))
+    let mut result = Big::new();
+    for i in 0..FIELD_ELEMENTS_PER_BLOB {
+        let term = scalar_mul(&scalar_mul(&polynomial[i], &roots[i]), &inverses[i]);
+        result = scalar_add(&result, &term);
+    }
+
+    // result * (z^n - 1) / n
+    let r = Big::new_ints(&CURVE_ORDER);
+    let mut z_pow = z
+        .clone()
+        .powmod(&Big::new_int(FIELD_ELEMENTS_PER_BLOB as isize), &r);
+    z_pow.norm();
+    let z_pow_minus_one = scalar_sub(&z_pow, &Big::new_int(1));
+    let inverse_width = scalar_inverse(&Big::new_int(FIELD_ELEMENTS_PER_BLOB as isize));
+
+    scalar_mul(&scalar_mul(&result, &z_pow_minus_one), &inverse_width)
+}
+
+// Compute Quotient Evaluation Within Domain
+//
+// The quotient (p(x) - y) / (x - z) evaluated at z, where z is in the domain.
+fn compute_quotient_eval_within_domain(
+    settings: &KzgSettings,
+    z: &Big,
+    polynomial: &[Big],
+    y: &Big,
+) -> Big {
+    let mut result = Big::new();
+    for (i, omega) in settings.roots_of_unity_brp.iter().enumerate() {
+        if omega == z {
+            continue;
+        }
+        // (p[i] - y) * w[i] / (z * (z - w[i]))
+        let numerator = scalar_mul(&scalar_sub(&polynomial[i], y), omega);
+        let denominator = scalar_mul(z, &scalar_sub(z, omega));
+        result = scalar_add(
+            &result,
+            &scalar_mul(&numerator, &scalar_inverse(&denominator)),
+        );
+    }
+    result
+}
+
+// Compute KZG Proof Impl
+//
+// Returns the proof and p(z) of a polynomial in evaluation form.
+fn compute_kzg_proof_impl(settings: &KzgSettings, polynomial: &[Big], z: &Big) -> (ECP, Big) {
+    let roots = &settings.roots_of_unity_brp;
+    let y = evaluate_polynomial_in_evaluation_form(settings, polynomial, z);
+
+    let mut quotient = vec![Big::new(); FIELD_ELEMENTS_PER_BLOB];
+    let mut in_domain = None;
+    let mut denominators = Vec::with_capacity(FIELD_ELEMENTS_PER_BLOB);
+    for (i, root) in roots.iter().enumerate() {
+        let denominator = scalar_sub(root, z);
+        if denominator.is_zilch() {
+            // Replace by 1 for batch inversion, computed separately below
+            in_domain = Some(i);
+            denominators.push(Big::new_int(1));
+        } else {
+            denominators.push(denominator);
+        }
+    }
+    let inverses = batch_inverse(&denominators);
+
+    for i in 0..FIELD_ELEMENTS_PER_BLOB {
+        quotient[i] = scalar_mul(&scalar_sub(&polynomial[i], &y), &inverses[i]);
+    }
+    if let Some(i) = in_domain {
+        quotient[i] = compute_quotient_eval_within_domain(settings, &roots[i], polynomial, &y);
+    }
+
+    (
+        g1_linear_combination(&settings.g1_lagrange_brp, &quotient),
+        y,
+    )
+}
+
+// Verify KZG Proof Impl
+//
+// Checks e(P - [y]G1, -G2) * e(proof, [tau - z]G2) == 1.
+fn verify_kzg_proof_impl(
+    settings: &KzgSettings,
+    commitment: &ECP,
+    z: &Big,
+    y: &Big,
+    proof: &ECP,
+) -> bool {
+    // [tau - z]G2
+    let mut x_minus_z = pair::g2mul(&ECP2::generator(), z);
+    x_minus_z.neg();
+    x_minus_z.add(&settings.g2_monomial[1]);
+
+    // P - [y]G1
+    let mut p_minus_y = pair::g1mul(&ECP::generator(), y);
+    p_minus_y.neg();
+    p_minus_y.add(commitment);
+
+    let mut g2 = ECP2::generator();
+    g2.neg();
+
+    pairing_product_is_unity(&[(&g2, &p_minus_y), (&x_minus_z, proof)])
+}
+
+// Verify KZG Proof Batch
+//
+// Verify multiple proofs with a random linear combination.
+fn verify_kzg_proof_batch(
+    settings: &KzgSettings,
+    commitments: &[ECP],
+    zs: &[Big],
+    ys: &[Big],
+    proofs: &[ECP],
+) -> bool {
+    let n = commitments.len();
+
+    // Random challenge, hashed exactly as the consensus specs do rather than with a Transcript
+    let mut data = RANDOM_CHALLENGE_KZG_BATCH_DOMAIN.to_vec();
+    data.extend_from_slice(&(FIELD_ELEMENTS_PER_BLOB as u64).to_be_bytes());
+    data.extend_from_slice(&(n as u64).to_be_bytes());
+    for i in 0..n {
+        data.extend_from_slice(&serialize_g1(&commitments[i]));
+        data.extend_from_slice(&scalar_to_bytes(&zs[i]));
+        data.extend_from_slice(&scalar_to_bytes(&ys[i]));
+        data.extend_from_slice(&serialize_g1(&proofs[i]));
+    }
+    let r = hash_to_bls_field(&data);
+    let r_powers = compute_powers(&r, n);
+
+    // sum(r^i * proof[i])
+    let proof_lincomb = g1_linear_combination(proofs, &r_powers);
+
+    // sum(r^i * z[i] * proof[i])
+    let r_times_z: Vec<Big> = (0..n).map(|i| scalar_mul(&r_powers[i], &zs[i])).collect();
+    let proof_z_lincomb = g1_linear_combination(proofs, &r_times_z);
+
+    // sum(r^i * (C[i] - [y[i]]G1))
+    let g1 = ECP::generator();
+    let c_minus_ys: Vec<ECP> = (0..n)
+        .map(|i| {
+            let mut c_minus_y = pair::g1mul(&g1, &ys[i]);
+            c_minus_y.neg();
+            c_minus_y.add(&commitments[i]);
+            c_minus_y
+        })
+        .collect();
+    let mut rhs = g1_linear_combination(&c_minus_ys, &r_powers);
+    rhs.add(&proof_z_lincomb);
+
+    let mut tau_g2 = settings.g2_monomial[1].clone();
+    tau_g2.neg();
+
+    pairing_product_is_unity(&[(&tau_g2, &proof_lincomb), (&ECP2::generator(), &rhs)])
+}
+
+/*************************************************************************************************
+* Public API
+*
+* https://github.com/ethereum/consensus-specs/blob/dev/specs/deneb/polynomial-commitments.md
+*************************************************************************************************/
+
+/// Blob To KZG Commitment
+pub fn blob_to_kzg_commitment(
+    settings: &KzgSettings,
+    blob: &[u8],
+) -> Result<[u8; BYTES_PER_COMMITMENT], AmclError> {
+    let polynomial = blob_to_polynomial(blob)?;
+    let commitment = g1_linear_combination(&settings.g1_lagrange_brp, &polynomial);
+    Ok(serialize_g1(&commitment))
+}
+
+/// Compute KZG Proof
+///
+/// Returns the proof and the evaluation y = p(z) of the blob's polynomial.
+pub fn compute_kzg_proof(
+    settings: &KzgSettings,
+    blob: &[u8],
+    z_bytes: &[u8],
+) -> Result<([u8; BYTES_PER_PROOF], [u8; BYTES_PER_FIELD_ELEMENT]), AmclError> {
+    let polynomial = blob_to_polynomial(blob)?;
+    let z = scalar_from_bytes(z_bytes)?;
+    let (proof, y) = compute_kzg_proof_impl(settings, &polynomial, &z);
+    Ok((serialize_g1(&proof), scalar_to_bytes(&y)))
+}
+
+/// Compute Blob KZG Proof
+///
+/// Returns the proof of the blob's polynomial at the Fiat-Shamir challenge.
+pub fn compute_blob_kzg_proof(
+    settings: &KzgSettings,
+    blob: &[u8],
+    commitment_bytes: &[u8],
+) -> Result<[u8; BYTES_PER_PROOF], AmclError> {
+    bytes_to_kzg_g1(commitment_bytes)?;
+    let polynomial = blob_to_polynomial(blob)?;
+    let challenge = compute_challenge(blob, commitment_bytes);
+    let (proof, _) = compute_kzg_proof_impl(settings, &polynomial, &challenge);
+    Ok(serialize_g1(&proof))
+}
+
+/// Verify KZG Proof
+///
+/// Verify that p(z) = y for the polynomial committed to.
+pub fn verify_kzg_proof(
+    settings: &KzgSettings,
+    commitment_bytes: &[u8],
+    z_bytes: &[u8],
+    y_bytes: &[u8],
+    proof_bytes: &[u8],
+) -> Result<bool, AmclError> {
+    let commitment = bytes_to_kzg_g1(commitment_bytes)?;
+    let z = scalar_from_bytes(z_bytes)?;
+    let y = scalar_from_bytes(y_bytes)?;
+    let proof = bytes_to_kzg_g1(proof_bytes)?;
+
+    Ok(verify_kzg_proof_impl(settings, &commitment, &z, &y, &proof))
+}
+
+/// Verify Blob KZG Proof
+///
+/// Verify a blob against its commitment and proof.
+pub fn verify_blob_kzg_proof(
+    settings: &KzgSettings,
+    blob: &[u8],
+    commitment_bytes: &[u8],
+    proof_bytes: &[u8],
+) -> Result<bool, AmclError> {
+    let commitment = bytes_to_kzg_g1(commitment_bytes)?;
+    let polynomial = blob_to_polynomial(blob)?;
+    let challenge = compute_challenge(blob, commitment_bytes);
+    let y = evaluate_polynomial_in_evaluation_form(settings, &polynomial, &challenge);
+    let proof = bytes_to_kzg_g1(proof_bytes)?;
+
+    Ok(verify_kzg_proof_impl(
+        settings,
+        &commitment,
+        &challenge,
+        &y,
+        &proof,
+    ))
+}
+
+/// Verify Blob KZG Proof Batch
+///
+/// Verify multiple blobs against their commitments and proofs, true if the batch is empty.
+pub fn verify_blob_kzg_proof_batch(
+    settings: &KzgSettings,
+    blobs: &[&[u8]],
+    commitments_bytes: &[&[u8]],
+    proofs_bytes: &[&[u8]],
+) -> Result<bool, AmclError> {
+    if blobs.len() != commitments_bytes.len() || blobs.len() != proofs_bytes.len() {
+        return Err(AmclError::InvalidBatchSize);
+    }
+    if blobs.len() == 0 {
+        return Ok(true);
+    }
+
+    let mut commitments = Vec::with_capacity(blobs.len());
+    let mut challenges = Vec::with_capacity(blobs.len());
+    let mut ys = Vec::with_capacity(blobs.len());
+    let mut proofs = Vec::with_capacity(blobs.len());
+    for i in 0..blobs.len() {
+        let commitment = bytes_to_kzg_g1(commitments_bytes[i])?;
+        let polynomial = blob_to_polynomial(blobs[i])?;
+        let challenge = compute_challenge(blobs[i], commitments_bytes[i]);
+        ys.push(evaluate_polynomial_in_evaluation_form(
+            settings,
+            &polynomial,
+            &challenge,
+        ));
+        challenges.push(challenge);
+        commitments.push(commitment);
+        proofs.push(bytes_to_kzg_g1(proofs_bytes[i])?);
+    }
+
+    Ok(verify_kzg_proof_batch(
+        settings,
+        &commitments,
+        &challenges,
+        &ys,
+        &proofs,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde_json::{self, Value};
+
+    // The c-kzg-4844 reference cases in its input/output schema, a null output means the inputs
+    // must be rejected.
+    const VECTORS: &str = include_str!("../tests/fixtures/eip4844/kzg_vectors.json");
+    const TRUSTED_SETUP: &str = include_str!("../tests/fixtures/eip4844/trusted_setup.txt");
+
+    fn bytes(value: &Value) -> Vec<u8> {
+        hex::decode(value.as_str().unwrap().trim_start_matches("0x")).unwrap()
+    }
+
+    fn blob(vectors: &Value, name: &Value) -> Vec<u8> {
+        bytes(&vectors["blobs"][name.as_str().unwrap()])
+    }
+
+    fn list<T, F: Fn(&Value) -> T>(value: &Value, f: F) -> Vec<T> {
+        value.as_array().unwrap().iter().map(f).collect()
+    }
+
+    fn slices(list: &[Vec<u8>]) -> Vec<&[u8]> {
+        list.iter().map(|item| &item[..]).collect()
+    }
+
+    fn cases<'a>(vectors: &'a Value, name: &str) -> &'a Vec<Value> {
+        let cases = vectors[name].as_array().unwrap();
+        assert!(cases.iter().any(|case| case["output"].is_null()));
+        assert!(cases.iter().any(|case| !case["output"].is_null()));
+        cases
+    }
+
+    // Compare a result to the expected output, which is null on error.
+    fn check<T, F: Fn(&T) -> Value>(case: &Value, result: Result<T, AmclError>, to_value: F) {
+        let output = &case["output"];
+        match result {
+            Ok(result) => assert_eq!(&to_value(&result), output, "{}", case["name"]),
+            Err(_) => assert!(output.is_null(), "{}", case["name"]),
+        }
+    }
+
+    fn hex_value(bytes: &[u8]) -> Value {
+        Value::String(format!("0x{}", hex::encode(bytes)))
+    }
+
+    #[test]
+    fn test_reference_vectors() {
+        let settings = load_trusted_setup_file(TRUSTED_SETUP).unwrap();
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+
+        for case in cases(&vectors, "blob_to_kzg_commitment") {
+            let input = &case["input"];
+            let result = blob_to_kzg_commitment(&settings, &blob(&vectors, &input["blob"]));
+            check(case, result, |c| hex_value(c));
+        }
+
+        for case in cases(&vectors, "compute_kzg_proof") {
+            let input = &case["input"];
+            let result = compute_kzg_proof(
+                &settings,
+                &blob(&vectors, &input["blob"]),
+                &bytes(&input["z"]),
+            );
+            check(case, result, |(proof, y)| {
+                Value::Array(vec![hex_value(proof), hex_value(y)])
+            });
+        }
+
+        for case in cases(&vectors, "verify_kzg_proof") {
+            let input = &case["input"];
+            let result = verify_kzg_proof(
+                &settings,
+                &bytes(&input["commitment"]),
+                &bytes(&input["z"]),
+                &bytes(&input["y"]),
+                &bytes(&input["proof"]),
+            );
+            check(case, result, |valid| Value::Bool(*valid));
+        }
+
+        for case in cases(&vectors, "compute_blob_kzg_proof") {
+            let input = &case["input"];
+            let result = compute_blob_kzg_proof(
+                &settings,
+                &blob(&vectors, &input["blob"]),
+                &bytes(&input["commitment"]),
+            );
+            check(case, result, |proof| hex_value(proof));
+        }
+
+        for case in cases(&vectors, "verify_blob_kzg_proof") {
+            let input = &case["input"];
+            let result = verify_blob_kzg_proof(
+                &settings,
+                &blob(&vectors, &input["blob"]),
+                &bytes(&input["commitment"]),
+                &bytes(&input["proof"]),
+            );
+            check(case, result, |valid| Value::Bool(*valid));
+        }
+
+        for case in cases(&vectors, "verify_blob_kzg_proof_batch") {
+            let input = &case["input"];
+            let blobs = list(&input["blobs"], |name| blob(&vectors, name));
+            let commitments = list(&input["commitments"], bytes);
+            let proofs = list(&input["proofs"], bytes);
+            let result = verify_blob_kzg_proof_batch(
+                &settings,
+                &slices(&blobs),
+                &slices(&commitments),
+                &slices(&proofs),
+            );
+            check(case, result, |valid| Value::Bool(*valid));
+        }
+    }
+
+    // A G2 point on the curve but outside the prime order subgroup.
+    fn non_subgroup_g2() -> [u8; G2_POINT_BYTES] {
+        let mut bytes = [0u8; G2_POINT_BYTES];
+        bytes[0] = 0x80;
+        for x in 1..=255u8 {
+            bytes[G2_POINT_BYTES - 1] = x;
+            if let Ok(point) = deserialize_g2(&bytes) {
+                if !subgroup_check_g2(&point) {
+                    return bytes;
+                }
+            }
+        }
+        panic!("no point found");
+    }
+
+    #[test]
+    fn test_load_trusted_setup_rejects_g2_points_outside_subgroup() {
+        let file = parse_trusted_setup_file(TRUSTED_SETUP).unwrap();
+        assert!(load_trusted_setup(&file.g1_lagrange, &file.g2_monomial).is_ok());
+
+        for i in [1, 2, file.g2_monomial.len() / G2_POINT_BYTES - 1].iter() {
+            let mut g2_monomial = file.g2_monomial.clone();
+            g2_monomial[i * G2_POINT_BYTES..(i + 1) * G2_POINT_BYTES]
+                .copy_from_slice(&non_subgroup_g2());
+            assert_eq!(
+                load_trusted_setup(&file.g1_lagrange, &g2_monomial).err(),
+                Some(AmclError::InvalidTrustedSetup)
+            );
+        }
+    }
+}
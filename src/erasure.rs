@@ -0,0 +1,351 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::fr::{Fr, GENERATOR};
+use super::poly::{Domain, Polynomial};
+use crate::errors::AmclError;
+use std::vec::Vec;
+
+// Below this many roots the zero polynomial is built by schoolbook multiplication
+const ZERO_POLY_THRESHOLD: usize = 64;
+
+/*************************************************************************************************
+* Reed-Solomon erasure coding with rate 1/2
+*
+* Data of length n is viewed as the evaluations of a polynomial of degree less than n over the
+* n-th roots of unity. Encoding evaluates that polynomial over the 2n-th roots of unity, so the
+* extended data commits to the same KZG polynomial and any n of the 2n values recover the rest.
+*************************************************************************************************/
+
+/// Encode
+///
+/// Extends `data`, whose length must be a power of two, to twice its length.
+/// The original data sits at the even indexes of the result.
+pub fn encode(data: &[Fr]) -> Result<Vec<Fr>, AmclError> {
+    let domain = Domain::new(data.len())?;
+    let extended_domain = Domain::new(2 * data.len())?;
+
+    let coeffs = domain.ifft(data)?;
+    extended_domain.fft(&coeffs)
+}
+
+/// Recover
+///
+/// Recovers the full extended data from `samples`, where missing values are `None`.
+/// `samples.len()` must be a power of two and at least half of the samples must be present.
+///
+/// Uses the zero polynomial Z(x) = prod (x - w^i) over the missing indexes i: the evaluations
+/// of E(x) * Z(x), with E(x) the data with zeros at the gaps, equal those of D(x) * Z(x) for the
+/// original polynomial D(x). D(x) is then recovered by division over a coset where Z(x) has
+/// no roots.
+pub fn recover(samples: &[Option<Fr>]) -> Result<Vec<Fr>, AmclError> {
+    let domain = Domain::new(samples.len())?;
+    let data_size = samples.len() / 2;
+
+    let missing: Vec<usize> = samples
+        .iter()
+        .enumerate()
+        .filter(|(_, sample)| sample.is_none())
+        .map(|(i, _)| i)
+        .collect();
+
+    if missing.len() > data_size || data_size == 0 {
+        return Err(AmclError::InvalidEvaluationPoints);
+    }
+    if missing.is_empty() {
+        let present: Vec<Fr> = samples.iter().flatten().cloned().collect();
+        check_degree(&domain.ifft(&present)?, data_size)?;
+        return Ok(present);
+    }
+
+    let elements = domain.elements();
+    let roots: Vec<Fr> = missing.iter().map(|&i| elements[i].clone()).collect();
+    let zero_poly = zero_polynomial(&roots)?;
+    let zero_evaluations = domain.fft(zero_poly.coeffs())?;
+
+    // (E * Z)(w^i) = E(w^i) * Z(w^i), zero wherever a sample is missing
+    let product_evaluations: Vec<Fr> = samples
+        .iter()
+        .zip(zero_evaluations.iter())
+        .map(|(sample, z)| match sample {
+            Some(e) => {
+                let mut product = e.clone();
+                product.mul(z);
+                product
+            }
+            None => Fr::new(),
+        })
+        .collect();
+    let product_coeffs = domain.ifft(&product_evaluations)?;
+
+    // D(x) = (D * Z)(x) / Z(x) evaluated over the coset GENERATOR * domain
+    let shift = Fr::new_int(GENERATOR);
+    let mut quotient_evaluations = domain.coset_fft(&product_coeffs, &shift)?;
+    let mut zero_coset_evaluations = domain.coset_fft(zero_poly.coeffs(), &shift)?;
+    batch_inverse(&mut zero_coset_evaluations);
+    for (q, z_inverse) in quotient_evaluations
+        .iter_mut()
+        .zip(zero_coset_evaluations.iter())
+    {
+        q.mul(z_inverse);
+    }
+    let data_coeffs = domain.coset_ifft(&quotient_evaluations, &shift)?;
+
+    // An inconsistent set of samples does not lie on a polynomial of degree less than n
+    check_degree(&data_coeffs, data_size)?;
+
+    let recovered = domain.fft(&data_coeffs)?;
+    for (sample, value) in samples.iter().zip(recovered.iter()) {
+        if let Some(sample) = sample {
+            if sample != value {
+                return Err(AmclError::InvalidCodeword);
+            }
+        }
+    }
+
+    Ok(recovered)
+}
+
+// Ensures coefficients above `degree_bound` are all zero.
+fn check_degree(coeffs: &[Fr], degree_bound: usize) -> Result<(), AmclError> {
+    if coeffs[degree_bound..].iter().any(|c| !c.is_zilch()) {
+        return Err(AmclError::InvalidCodeword);
+    }
+    Ok(())
+}
+
+// Builds prod (x - roots[i]) with a product tree, multiplying large halves using FFTs.
+fn zero_polynomial(roots: &[Fr]) -> Result<Polynomial, AmclError> {
+    if roots.len() <= ZERO_POLY_THRESHOLD {
+        return Ok(Polynomial::from_roots(roots));
+    }
+
+    let (left, right) = roots.split_at(roots.len() / 2);
+    let left = zero_polynomial(left)?;
+    let right = zero_polynomial(right)?;
+
+    let domain = Domain::new((roots.len() + 1).next_power_of_two())?;
+    domain.mul(&left, &right)
+}
+
+// Montgomery batch inversion, all inputs must be non-zero.
+fn batch_inverse(values: &mut [Fr]) {
+    let mut prefix = Vec::with_capacity(values.len());
+    let mut accumulator = Fr::one();
+    for value in values.iter() {
+        prefix.push(accumulator.clone());
+        accumulator.mul(value);
+    }
+
+    accumulator.inverse();
+    for (value, prefix) in values.iter_mut().zip(prefix.iter()).rev() {
+        let mut inverse = accumulator.clone();
+        inverse.mul(prefix);
+        accumulator.mul(value);
+        *value = inverse;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::big::Big;
+    use super::super::ecp::ECP;
+    use super::super::ecp2::ECP2;
+    use super::super::kzg::TrustedSetup;
+    use super::super::pair;
+    use super::*;
+    use crate::rand::RAND;
+
+    fn rng() -> RAND {
+        let mut rng = RAND::new();
+        rng.seed(32, &[36u8; 32]);
+        rng
+    }
+
+    fn random_data(n: usize, rng: &mut RAND) -> Vec<Fr> {
+        (0..n).map(|_| Fr::random(rng)).collect()
+    }
+
+    // Drops `count` distinct indexes chosen at random.
+    fn drop_random(extended: &[Fr], count: usize, rng: &mut RAND) -> Vec<Option<Fr>> {
+        let mut indexes: Vec<usize> = (0..extended.len()).collect();
+        for i in (1..indexes.len()).rev() {
+            let r =
+                u32::from_be_bytes([rng.getbyte(), rng.getbyte(), rng.getbyte(), rng.getbyte()]);
+            indexes.swap(i, r as usize % (i + 1));
+        }
+        let mut samples: Vec<Option<Fr>> = extended.iter().cloned().map(Some).collect();
+        for i in indexes[..count].iter() {
+            samples[*i] = None;
+        }
+        samples
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut rng = rng();
+        let data = random_data(8, &mut rng);
+        let extended = encode(&data).unwrap();
+        assert_eq!(extended.len(), 16);
+        for (i, value) in data.iter().enumerate() {
+            assert_eq!(&extended[2 * i], value);
+        }
+        assert!(encode(&random_data(6, &mut rng)).is_err());
+    }
+
+    #[test]
+    fn test_recover_random_subsets() {
+        let mut rng = rng();
+        for n in [1, 4, 16, 64].iter() {
+            let extended = encode(&random_data(*n, &mut rng)).unwrap();
+            for _ in 0..4 {
+                for missing in [0, 1, *n / 2, *n].iter() {
+                    let samples = drop_random(&extended, *missing, &mut rng);
+                    assert_eq!(recover(&samples).unwrap(), extended);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_recover_with_fft_zero_polynomial() {
+        let mut rng = rng();
+        // More than ZERO_POLY_THRESHOLD missing builds the zero polynomial by FFT
+        let extended = encode(&random_data(128, &mut rng)).unwrap();
+        for missing in [ZERO_POLY_THRESHOLD + 1, 100, 128].iter() {
+            let samples = drop_random(&extended, *missing, &mut rng);
+            assert_eq!(recover(&samples).unwrap(), extended);
+        }
+    }
+
+    #[test]
+    fn test_zero_polynomial() {
+        let mut rng = rng();
+        for count in [1, ZERO_POLY_THRESHOLD, ZERO_POLY_THRESHOLD + 1, 100, 200].iter() {
+            let roots = random_data(*count, &mut rng);
+            assert_eq!(
+                zero_polynomial(&roots).unwrap(),
+                Polynomial::from_roots(&roots)
+            );
+        }
+    }
+
+    #[test]
+    fn test_extension_opens_against_original_commitment() {
+        let mut rng = rng();
+        let n = 8;
+        let data = random_data(n, &mut rng);
+
+        let tau = Fr::random(&mut rng);
+        let mut g1_powers = Vec::new();
+        let mut power = Fr::one();
+        for _ in 0..n {
+            g1_powers.push(pair::g1mul(&ECP::generator(), &power.redc()));
+            power.mul(&tau);
+        }
+        let g2_powers = vec![
+            ECP2::generator(),
+            pair::g2mul(&ECP2::generator(), &tau.redc()),
+        ];
+        let setup = TrustedSetup::new(g1_powers, g2_powers).unwrap();
+
+        let coeffs: Vec<Big> = Domain::new(n)
+            .unwrap()
+            .ifft(&data)
+            .unwrap()
+            .iter()
+            .map(|c| c.redc())
+            .collect();
+        let commitment = setup.commit(&coeffs).unwrap();
+
+        // Every value of the extended and of the recovered data is an opening of the commitment
+        // to the original data at the matching 2n-th root of unity
+        let extended = encode(&data).unwrap();
+        let recovered = recover(&drop_random(&extended, n, &mut rng)).unwrap();
+        let elements = Domain::new(2 * n).unwrap().elements();
+        for ((z, value), recovered) in elements.iter().zip(extended.iter()).zip(recovered.iter()) {
+            let z = z.redc();
+            let (proof, y) = setup.open(&coeffs, &z).unwrap();
+            assert_eq!(y, value.redc());
+            assert_eq!(y, recovered.redc());
+            assert!(setup.verify(&commitment, &z, &y, &proof));
+        }
+
+        // A value that is not an opening does not verify
+        let z = elements[1].redc();
+        let (proof, _) = setup.open(&coeffs, &z).unwrap();
+        let mut wrong = extended[1].clone();
+        wrong.add(&Fr::one());
+        assert!(!setup.verify(&commitment, &z, &wrong.redc(), &proof));
+    }
+
+    #[test]
+    fn test_recover_exactly_half_missing() {
+        let mut rng = rng();
+        let extended = encode(&random_data(16, &mut rng)).unwrap();
+
+        // The original data only, the extension only and a random half
+        let even: Vec<Option<Fr>> = extended
+            .iter()
+            .enumerate()
+            .map(|(i, v)| if i % 2 == 0 { Some(v.clone()) } else { None })
+            .collect();
+        let odd: Vec<Option<Fr>> = extended
+            .iter()
+            .enumerate()
+            .map(|(i, v)| if i % 2 == 1 { Some(v.clone()) } else { None })
+            .collect();
+        for samples in [even, odd, drop_random(&extended, 16, &mut rng)].iter() {
+            assert_eq!(recover(samples).unwrap(), extended);
+        }
+    }
+
+    #[test]
+    fn test_recover_more_than_half_missing() {
+        let mut rng = rng();
+        for n in [1, 4, 16].iter() {
+            let extended = encode(&random_data(*n, &mut rng)).unwrap();
+            for missing in [*n + 1, 2 * *n].iter() {
+                let samples = drop_random(&extended, *missing, &mut rng);
+                assert_eq!(recover(&samples), Err(AmclError::InvalidEvaluationPoints));
+            }
+        }
+    }
+
+    #[test]
+    fn test_recover_inconsistent_samples() {
+        let mut rng = rng();
+        let extended = encode(&random_data(8, &mut rng)).unwrap();
+        // With exactly half missing any samples lie on a polynomial of degree less than n
+        for missing in [0, 3, 7].iter() {
+            let mut samples = drop_random(&extended, *missing, &mut rng);
+            let present = samples.iter().position(|s| s.is_some()).unwrap();
+            samples[present] = Some(Fr::random(&mut rng));
+            assert_eq!(recover(&samples), Err(AmclError::InvalidCodeword));
+        }
+    }
+
+    #[test]
+    fn test_recover_invalid_length() {
+        let samples: Vec<Option<Fr>> = (0..12).map(|i| Some(Fr::new_int(i))).collect();
+        assert!(recover(&samples).is_err());
+        assert!(recover(&[Some(Fr::one())]).is_err());
+    }
+}
@@ -8,4 +8,40 @@ pub enum AmclError {
     InvalidG1Size,
     InvalidG2Size,
     InvalidYFlag,
+    InvalidSchemeId,
+    InvalidSignature,
+    InvalidScalarSize,
+    InvalidScalarRange,
+    InvalidTrustedSetup,
+    InvalidPolynomialDegree,
+    InvalidBlobSize,
+    InvalidBatchSize,
+    InvalidEncoding,
+    InvalidPublicInputs,
+    InvalidProof,
+    InvalidDisclosedIndexes,
+    InvalidMessageCount,
+    InvalidDomainSize,
+    InvalidEvaluationPoints,
+    InvalidCodeword,
+    InvalidTranscript,
+    InvalidSszLength,
+    InvalidGenesisValidatorsRoot,
+    DoubleBlockProposal,
+    DoubleVote,
+    SurroundVote,
+    BelowSlashingWatermark,
+    InvalidAttestationEpochs,
+    UnknownPublicKey,
+    UnsupportedSigningRequest,
+    SigningRootMismatch,
+    InvalidCommitteeIndex,
+    CommitteeSizeMismatch,
+    OverlappingAggregates,
+    InsufficientParticipation,
+    InvalidLightClientUpdate,
+    InvalidPrecompileInput,
+    InvalidDer,
+    InvalidPem,
+    UnsupportedKeyAlgorithm,
 }
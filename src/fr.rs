@@ -0,0 +1,586 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::big;
+use super::big::Big;
+use super::dbig::DBig;
+use super::hash_to_curve::expand_message_xmd;
+use super::rom::{CURVE_ORDER, MODBYTES};
+use crate::arch::Chunk;
+use crate::errors::AmclError;
+use crate::rand::RAND;
+
+use std::fmt;
+
+/// The number of bytes of a canonically serialized scalar
+pub const FR_BYTES: usize = 32;
+
+/// R^2 mod r where R = 2^(NLEN * BASEBITS)
+pub const R2MODR: [Chunk; big::NLEN] = [
+    0x1D72A6F9D63B65C,
+    0x1481CE33B774486,
+    0xD7FC21A828F4E7,
+    0x1FEEC9635E799EB,
+    0x2F585B,
+    0x0,
+    0x0,
+];
+/// -1/r mod 2^BASEBITS
+pub const MCONST_R: Chunk = 0x3FFFFFEFFFFFFFF;
+/// 2-adicity of r - 1
+pub const TWO_ADICITY: usize = 32;
+/// Generator of the multiplicative group, a quadratic non-residue
+pub const GENERATOR: isize = 7;
+
+// Number of uniform bytes reduced to a scalar in hash_to_scalar
+const HASH_TO_SCALAR_BYTES: usize = 48;
+
+/// An element of the scalar field, integers mod r, held in Montgomery form.
+#[derive(Clone)]
+pub struct Fr {
+    x: Big,
+}
+
+impl PartialEq for Fr {
+    fn eq(&self, other: &Fr) -> bool {
+        self.equals(other)
+    }
+}
+
+impl Eq for Fr {}
+
+impl fmt::Display for Fr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Fr: [ {} ]", self.redc().to_string())
+    }
+}
+
+impl fmt::Debug for Fr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Fr: [ {} ]", self.redc().to_string())
+    }
+}
+
+impl Fr {
+    /// New
+    ///
+    /// Creates a new Fr at 0.
+    #[inline(always)]
+    pub fn new() -> Fr {
+        Fr { x: Big::new() }
+    }
+
+    /// New Int
+    ///
+    /// Creates a Fr from a non-negative int.
+    #[inline(always)]
+    pub fn new_int(a: isize) -> Fr {
+        Fr::new_big(&Big::new_int(a))
+    }
+
+    /// New Big
+    ///
+    /// Creates a Fr from a Big, reducing it mod r.
+    #[inline(always)]
+    pub fn new_big(x: &Big) -> Fr {
+        let r = Big::new_ints(&CURVE_ORDER);
+        let mut x = x.clone();
+        x.rmod(&r);
+        let mut d = Big::mul(&x, &Big::new_ints(&R2MODR));
+        Fr {
+            x: Fr::modulo(&mut d),
+        }
+    }
+
+    /// One
+    pub fn one() -> Fr {
+        Fr::new_int(1)
+    }
+
+    // Montgomery reduction of a DBig to a fully reduced Big.
+    fn modulo(d: &mut DBig) -> Big {
+        let r = Big::new_ints(&CURVE_ORDER);
+        let mut x = Big::monty(&r, MCONST_R, d);
+        x.rmod(&r);
+        x
+    }
+
+    /// Reduce
+    ///
+    /// Convert back from Montgomery form to a Big in the range [0, r).
+    pub fn redc(&self) -> Big {
+        let mut d = DBig::new_scopy(&self.x);
+        Fr::modulo(&mut d)
+    }
+
+    /// Is Zilch
+    ///
+    /// Returns true if self is 0.
+    pub fn is_zilch(&self) -> bool {
+        self.x.is_zilch()
+    }
+
+    /// Is Unity
+    ///
+    /// Returns true if self is 1.
+    pub fn is_unity(&self) -> bool {
+        self.equals(&Fr::one())
+    }
+
+    /// Equals
+    ///
+    /// Constant time comparison.
+    pub fn equals(&self, other: &Fr) -> bool {
+        let mut diff: Chunk = 0;
+        for i in 0..big::NLEN {
+            diff |= self.x.w[i] ^ other.x.w[i];
+        }
+        diff == 0
+    }
+
+    /// Conditional Move
+    ///
+    /// Set self to b if d is 1, no branches.
+    pub fn cmove(&mut self, b: &Fr, d: isize) {
+        self.x.cmove(&b.x, d);
+    }
+
+    /// Add
+    ///
+    /// self += b mod r
+    pub fn add(&mut self, b: &Fr) {
+        self.x.add(&b.x);
+        self.x.rmod(&Big::new_ints(&CURVE_ORDER));
+    }
+
+    /// Double
+    ///
+    /// self += self mod r
+    pub fn dbl(&mut self) {
+        let b = self.clone();
+        self.add(&b);
+    }
+
+    /// Negate
+    ///
+    /// self = -self mod r
+    pub fn neg(&mut self) {
+        if self.is_zilch() {
+            return;
+        }
+        let mut r = Big::new_ints(&CURVE_ORDER);
+        r.sub(&self.x);
+        r.norm();
+        self.x = r;
+    }
+
+    /// Subtract
+    ///
+    /// self -= b mod r
+    pub fn sub(&mut self, b: &Fr) {
+        let mut neg_b = b.clone();
+        neg_b.neg();
+        self.add(&neg_b);
+    }
+
+    /// Multiply
+    ///
+    /// self *= b mod r
+    pub fn mul(&mut self, b: &Fr) {
+        let mut d = Big::mul(&self.x, &b.x);
+        self.x = Fr::modulo(&mut d);
+    }
+
+    /// Square
+    ///
+    /// self *= self mod r
+    pub fn sqr(&mut self) {
+        let mut d = Big::sqr(&self.x);
+        self.x = Fr::modulo(&mut d);
+    }
+
+    /// Power
+    ///
+    /// Returns self^e mod r, square and multiply over every bit of e.
+    pub fn pow(&self, e: &Big) -> Fr {
+        let mut e = e.clone();
+        e.norm();
+        let mut result = Fr::one();
+        for i in (0..e.nbits()).rev() {
+            result.sqr();
+            let mut t = result.clone();
+            t.mul(self);
+            result.cmove(&t, e.bit(i));
+        }
+        result
+    }
+
+    /// Inverse
+    ///
+    /// self = 1/self mod r using Fermat's little theorem, 0 maps to 0.
+    pub fn inverse(&mut self) {
+        let mut e = Big::new_ints(&CURVE_ORDER);
+        e.dec(2);
+        e.norm();
+        *self = self.pow(&e);
+    }
+
+    /// Legendre Symbol
+    ///
+    /// Returns 1 for non-zero squares, -1 for non-squares and 0 for 0.
+    pub fn legendre(&self) -> isize {
+        if self.is_zilch() {
+            return 0;
+        }
+        // self^((r - 1) / 2)
+        let mut e = Big::new_ints(&CURVE_ORDER);
+        e.dec(1);
+        e.norm();
+        e.fshr(1);
+        if self.pow(&e).is_unity() {
+            1
+        } else {
+            -1
+        }
+    }
+
+    /// Square Root
+    ///
+    /// Tonelli-Shanks, returns None if self is not a square.
+    pub fn sqrt(&self) -> Option<Fr> {
+        if self.is_zilch() {
+            return Some(Fr::new());
+        }
+        if self.legendre() != 1 {
+            return None;
+        }
+
+        // r - 1 = 2^TWO_ADICITY * t, t odd
+        let mut t = Big::new_ints(&CURVE_ORDER);
+        t.dec(1);
+        t.norm();
+        t.shr(TWO_ADICITY);
+
+        // (t + 1) / 2
+        let mut t_plus_one_half = t.clone();
+        t_plus_one_half.inc(1);
+        t_plus_one_half.norm();
+        t_plus_one_half.fshr(1);
+
+        let mut m = TWO_ADICITY;
+        let mut c = Fr::new_int(GENERATOR).pow(&t);
+        let mut u = self.pow(&t);
+        let mut x = self.pow(&t_plus_one_half);
+
+        while !u.is_unity() {
+            // Find least i such that u^(2^i) = 1
+            let mut i = 0;
+            let mut u2 = u.clone();
+            while !u2.is_unity() {
+                u2.sqr();
+                i += 1;
+            }
+
+            // b = c^(2^(m - i - 1))
+            let mut b = c.clone();
+            for _ in 0..(m - i - 1) {
+                b.sqr();
+            }
+
+            m = i;
+            c = b.clone();
+            c.sqr();
+            u.mul(&c);
+            x.mul(&b);
+        }
+
+        Some(x)
+    }
+
+    /// Random
+    ///
+    /// Uniformly random element mod r.
+    pub fn random(rng: &mut RAND) -> Fr {
+        let r = Big::new_ints(&CURVE_ORDER);
+        Fr::new_big(&Big::randomnum(&r, rng))
+    }
+
+    /// From Bytes
+    ///
+    /// Convert from a canonical 32 byte big-endian integer, which must be less than r.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Fr, AmclError> {
+        if bytes.len() != FR_BYTES {
+            return Err(AmclError::InvalidScalarSize);
+        }
+
+        let mut big_bytes = [0u8; MODBYTES];
+        big_bytes[MODBYTES - FR_BYTES..].copy_from_slice(bytes);
+        let x = Big::from_bytes(&big_bytes);
+        if x >= Big::new_ints(&CURVE_ORDER) {
+            return Err(AmclError::InvalidScalarRange);
+        }
+
+        Ok(Fr::new_big(&x))
+    }
+
+    /// From Bytes Little Endian
+    ///
+    /// Convert from a canonical 32 byte little-endian integer, which must be less than r.
+    pub fn from_bytes_le(bytes: &[u8]) -> Result<Fr, AmclError> {
+        if bytes.len() != FR_BYTES {
+            return Err(AmclError::InvalidScalarSize);
+        }
+
+        let mut big_endian = [0u8; FR_BYTES];
+        big_endian.copy_from_slice(bytes);
+        big_endian.reverse();
+        Fr::from_bytes(&big_endian)
+    }
+
+    /// To Bytes
+    ///
+    /// Convert to a canonical 32 byte big-endian integer.
+    pub fn to_bytes(&self) -> [u8; FR_BYTES] {
+        let mut big_bytes = [0u8; MODBYTES];
+        self.redc().to_bytes(&mut big_bytes);
+        let mut bytes = [0u8; FR_BYTES];
+        bytes.copy_from_slice(&big_bytes[MODBYTES - FR_BYTES..]);
+        bytes
+    }
+
+    /// To Bytes Little Endian
+    ///
+    /// Convert to a canonical 32 byte little-endian integer.
+    pub fn to_bytes_le(&self) -> [u8; FR_BYTES] {
+        let mut bytes = self.to_bytes();
+        bytes.reverse();
+        bytes
+    }
+}
+
+/// Hash To Scalar
+///
+/// OS2IP(expand_message_xmd(msg, dst, 48)) mod r
+pub fn hash_to_scalar(msg: &[u8], dst: &[u8]) -> Result<Fr, AmclError> {
+    let uniform_bytes = expand_message_xmd(msg, HASH_TO_SCALAR_BYTES, dst)?;
+    Ok(Fr::new_big(&Big::from_bytes(&uniform_bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // r - 1
+    const R_MINUS_ONE: &str = "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000000";
+
+    fn fr(s: &str) -> Fr {
+        Fr::from_bytes(&hex::decode(s).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_montgomery_conversion() {
+        assert_eq!(Fr::new_int(5).redc(), Big::new_int(5));
+        assert_eq!(Fr::one().redc(), Big::new_int(1));
+        assert!(Fr::new().is_zilch());
+        assert!(Fr::one().is_unity());
+
+        // new_big reduces mod r
+        let mut r = Big::new_ints(&CURVE_ORDER);
+        assert!(Fr::new_big(&r).is_zilch());
+        r.inc(1);
+        r.norm();
+        assert!(Fr::new_big(&r).is_unity());
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        for s in [
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20",
+            R_MINUS_ONE,
+        ]
+        .iter()
+        {
+            let bytes = hex::decode(s).unwrap();
+            let x = Fr::from_bytes(&bytes).unwrap();
+            assert_eq!(x.to_bytes().to_vec(), bytes);
+
+            let mut le = bytes.clone();
+            le.reverse();
+            assert_eq!(x.to_bytes_le().to_vec(), le);
+            assert_eq!(Fr::from_bytes_le(&le).unwrap(), x);
+        }
+    }
+
+    #[test]
+    fn test_from_bytes_invalid() {
+        // r, r + 1 and 2^256 - 1
+        for s in [
+            "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001",
+            "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000002",
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        ]
+        .iter()
+        {
+            let bytes = hex::decode(s).unwrap();
+            assert_eq!(Fr::from_bytes(&bytes), Err(AmclError::InvalidScalarRange));
+            let mut le = bytes.clone();
+            le.reverse();
+            assert_eq!(Fr::from_bytes_le(&le), Err(AmclError::InvalidScalarRange));
+        }
+
+        for len in [0, 31, 33, 48].iter() {
+            let bytes = vec![0u8; *len];
+            assert_eq!(Fr::from_bytes(&bytes), Err(AmclError::InvalidScalarSize));
+            assert_eq!(Fr::from_bytes_le(&bytes), Err(AmclError::InvalidScalarSize));
+        }
+    }
+
+    #[test]
+    fn test_add_sub_neg() {
+        let r_minus_one = fr(R_MINUS_ONE);
+
+        let mut x = r_minus_one.clone();
+        x.add(&Fr::one());
+        assert!(x.is_zilch());
+
+        let mut x = Fr::new();
+        x.sub(&Fr::one());
+        assert_eq!(x, r_minus_one);
+
+        let mut x = Fr::one();
+        x.neg();
+        assert_eq!(x, r_minus_one);
+        let mut x = Fr::new();
+        x.neg();
+        assert!(x.is_zilch());
+
+        let mut x = r_minus_one.clone();
+        x.dbl();
+        let mut expected = Fr::new_int(2);
+        expected.neg();
+        assert_eq!(x, expected);
+    }
+
+    #[test]
+    fn test_mul_inverse() {
+        // (r - 1)^2 = 1
+        let mut x = fr(R_MINUS_ONE);
+        x.sqr();
+        assert!(x.is_unity());
+
+        let mut two = Fr::new_int(2);
+        two.inverse();
+        assert_eq!(
+            hex::encode(two.to_bytes()),
+            "39f6d3a994cebea4199cec0404d0ec02a9ded2017fff2dff7fffffff80000001"
+        );
+        let mut three = Fr::new_int(3);
+        three.inverse();
+        assert_eq!(
+            hex::encode(three.to_bytes()),
+            "4d491a377113a8daccd13ab0066be558e27e6d5755543d54aaaaaaaa00000001"
+        );
+
+        let mut rng = RAND::new();
+        rng.seed(32, &[34u8; 32]);
+        for x in [fr(R_MINUS_ONE), Fr::one(), Fr::random(&mut rng)].iter() {
+            let mut y = x.clone();
+            y.inverse();
+            y.mul(x);
+            assert!(y.is_unity());
+        }
+
+        // 0 has no inverse and maps to 0
+        let mut zero = Fr::new();
+        zero.inverse();
+        assert!(zero.is_zilch());
+    }
+
+    #[test]
+    fn test_pow() {
+        let five = Fr::new_int(5);
+        assert!(five.pow(&Big::new()).is_unity());
+        assert_eq!(five.pow(&Big::new_int(3)), Fr::new_int(125));
+        assert_eq!(
+            hex::encode(five.pow(&Big::new_int(1000)).to_bytes()),
+            "526d502992c86db03d41ff36adcba2b092587b7bf150c0fba41170401b980a12"
+        );
+
+        // Fermat, x^(r - 1) = 1
+        let r_minus_one = fr(R_MINUS_ONE).redc();
+        assert!(five.pow(&r_minus_one).is_unity());
+        assert!(Fr::new().pow(&r_minus_one).is_zilch());
+    }
+
+    #[test]
+    fn test_legendre() {
+        assert_eq!(Fr::new().legendre(), 0);
+        assert_eq!(Fr::one().legendre(), 1);
+        assert_eq!(Fr::new_int(4).legendre(), 1);
+        assert_eq!(Fr::new_int(11).legendre(), 1);
+        // r = 1 mod 4 so -1 is a square
+        assert_eq!(fr(R_MINUS_ONE).legendre(), 1);
+        assert_eq!(Fr::new_int(5).legendre(), -1);
+        assert_eq!(Fr::new_int(GENERATOR).legendre(), -1);
+    }
+
+    #[test]
+    fn test_sqrt() {
+        assert!(Fr::new().sqrt().unwrap().is_zilch());
+        assert!(Fr::new_int(5).sqrt().is_none());
+        assert!(Fr::new_int(GENERATOR).sqrt().is_none());
+
+        // The square roots of -1 are the primitive 4th roots of unity
+        let root = fr(R_MINUS_ONE).sqrt().unwrap();
+        let roots = [
+            "73eda753299d7d47a5e80b39939ed33467baa40089fb5bfefffeffff00000001",
+            "00000000000000008d51ccce760304d0ec030002760300000001000000000000",
+        ];
+        assert!(roots.contains(&&hex::encode(root.to_bytes())[..]));
+
+        let mut rng = RAND::new();
+        rng.seed(32, &[35u8; 32]);
+        let mut squares = vec![Fr::one(), Fr::new_int(4), Fr::new_int(11)];
+        for _ in 0..8 {
+            let mut x = Fr::random(&mut rng);
+            x.sqr();
+            squares.push(x);
+        }
+        for square in squares.iter() {
+            let mut x = square.sqrt().unwrap();
+            x.sqr();
+            assert_eq!(x, *square);
+        }
+    }
+
+    #[test]
+    fn test_hash_to_scalar() {
+        // draft-irtf-cfrg-bbs-signatures-06, BLS12-381-SHA-256 message 1 to scalar
+        let msg = hex::decode("9872ad089e452c7b6e283dfac2a80d58e8d0ff71cc4d5e310a1debdda4a45f02")
+            .unwrap();
+        let dst = b"BBS_BLS12381G1_XMD:SHA-256_SSWU_RO_H2G_HM2S_MAP_MSG_TO_SCALAR_AS_HASH_";
+        assert_eq!(
+            hex::encode(hash_to_scalar(&msg, dst).unwrap().to_bytes()),
+            "1cb5bb86114b34dc438a911617655a1db595abafac92f47c5001799cf624b430"
+        );
+    }
+}
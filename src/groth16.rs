@@ -0,0 +1,608 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::big::Big;
+use super::bls381::utils::{
+    deserialize_g1, deserialize_g1_arkworks, deserialize_g2, deserialize_g2_arkworks,
+    subgroup_check_g1, subgroup_check_g2,
+};
+use super::ecp::ECP;
+use super::ecp2::ECP2;
+use super::fp12::FP12;
+use super::kzg::{g1_linear_combination, multi_pairing, scalar_add};
+use super::pair;
+use super::rom::MODBYTES;
+use crate::errors::AmclError;
+use crate::rand::RAND;
+use std::vec::Vec;
+
+// Serialization flag of compressed points
+const COMPRESSION_FLAG: u8 = 0b_1000_0000;
+
+// Number of random bytes of each batch verification scalar
+const BATCH_SCALAR_BYTES: usize = 16;
+
+/// Groth16 Verifying Key
+///
+/// `beta_g1` and `delta_g1` are not used by verification and are `None` for keys parsed from
+/// arkworks, which does not serialize them.
+#[derive(Clone)]
+pub struct VerifyingKey {
+    pub alpha_g1: ECP,
+    pub beta_g1: Option<ECP>,
+    pub beta_g2: ECP2,
+    pub gamma_g2: ECP2,
+    pub delta_g1: Option<ECP>,
+    pub delta_g2: ECP2,
+    pub ic: Vec<ECP>,
+}
+
+/// Groth16 Verifying Key prepared for verification
+///
+/// Holds e(alpha, beta) as a Gt value and the negated G2 elements.
+#[derive(Clone)]
+pub struct PreparedVerifyingKey {
+    pub alpha_g1_beta_g2: FP12,
+    pub neg_gamma_g2: ECP2,
+    pub neg_delta_g2: ECP2,
+    pub ic: Vec<ECP>,
+}
+
+/// Groth16 Proof
+#[derive(Clone)]
+pub struct Proof {
+    pub a: ECP,
+    pub b: ECP2,
+    pub c: ECP,
+}
+
+// Reads points from a byte stream where each point may be compressed or uncompressed.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader { bytes, offset: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], AmclError> {
+        if self.offset + len > self.bytes.len() {
+            return Err(AmclError::InvalidEncoding);
+        }
+        let bytes = &self.bytes[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    // Length of the next point given its size when compressed.
+    fn point_len(&self, compressed_len: usize) -> Result<usize, AmclError> {
+        if self.offset >= self.bytes.len() {
+            return Err(AmclError::InvalidEncoding);
+        }
+        if self.bytes[self.offset] & COMPRESSION_FLAG != 0 {
+            Ok(compressed_len)
+        } else {
+            Ok(compressed_len * 2)
+        }
+    }
+
+    fn read_g1(&mut self) -> Result<ECP, AmclError> {
+        let len = self.point_len(MODBYTES)?;
+        let point = deserialize_g1(self.take(len)?)?;
+        if !subgroup_check_g1(&point) {
+            return Err(AmclError::InvalidPoint);
+        }
+        Ok(point)
+    }
+
+    fn read_g2(&mut self) -> Result<ECP2, AmclError> {
+        let len = self.point_len(MODBYTES * 2)?;
+        let point = deserialize_g2(self.take(len)?)?;
+        if !subgroup_check_g2(&point) {
+            return Err(AmclError::InvalidPoint);
+        }
+        Ok(point)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, AmclError> {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(u32::from_be_bytes(bytes))
+    }
+
+    // arkworks points, either all compressed or all uncompressed.
+    fn read_g1_arkworks(&mut self, compressed: bool) -> Result<ECP, AmclError> {
+        let len = if compressed { MODBYTES } else { MODBYTES * 2 };
+        let point = deserialize_g1_arkworks(self.take(len)?)?;
+        if !subgroup_check_g1(&point) {
+            return Err(AmclError::InvalidPoint);
+        }
+        Ok(point)
+    }
+
+    fn read_g2_arkworks(&mut self, compressed: bool) -> Result<ECP2, AmclError> {
+        let len = if compressed {
+            MODBYTES * 2
+        } else {
+            MODBYTES * 4
+        };
+        let point = deserialize_g2_arkworks(self.take(len)?)?;
+        if !subgroup_check_g2(&point) {
+            return Err(AmclError::InvalidPoint);
+        }
+        Ok(point)
+    }
+
+    fn read_u64_le(&mut self) -> Result<u64, AmclError> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn finish(&self) -> Result<(), AmclError> {
+        if self.offset != self.bytes.len() {
+            return Err(AmclError::InvalidEncoding);
+        }
+        Ok(())
+    }
+}
+
+impl VerifyingKey {
+    /// Parse a verifying key in the bellman format.
+    ///
+    /// alpha_g1 || beta_g1 || beta_g2 || gamma_g2 || delta_g1 || delta_g2 || u32 length || ic,
+    /// points may be compressed or uncompressed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<VerifyingKey, AmclError> {
+        let mut reader = Reader::new(bytes);
+        let alpha_g1 = reader.read_g1()?;
+        let beta_g1 = reader.read_g1()?;
+        let beta_g2 = reader.read_g2()?;
+        let gamma_g2 = reader.read_g2()?;
+        let delta_g1 = reader.read_g1()?;
+        let delta_g2 = reader.read_g2()?;
+
+        let ic_len = reader.read_u32()? as usize;
+        if ic_len == 0 {
+            return Err(AmclError::InvalidEncoding);
+        }
+        let mut ic = Vec::new();
+        for _ in 0..ic_len {
+            ic.push(reader.read_g1()?);
+        }
+        reader.finish()?;
+
+        Ok(VerifyingKey {
+            alpha_g1,
+            beta_g1: Some(beta_g1),
+            beta_g2,
+            gamma_g2,
+            delta_g1: Some(delta_g1),
+            delta_g2,
+            ic,
+        })
+    }
+
+    /// Parse a verifying key in the arkworks `CanonicalSerialize` format of ark-groth16.
+    ///
+    /// alpha_g1 || beta_g2 || gamma_g2 || delta_g2 || u64 little-endian length || gamma_abc_g1,
+    /// every point compressed or every point uncompressed in the little-endian encodings of
+    /// ark-bls12-381 up to 0.3, see `deserialize_g1_arkworks`. arkworks keys do not hold beta_g1
+    /// and delta_g1, so they are `None`.
+    pub fn from_arkworks_bytes(bytes: &[u8], compressed: bool) -> Result<VerifyingKey, AmclError> {
+        let mut reader = Reader::new(bytes);
+        let alpha_g1 = reader.read_g1_arkworks(compressed)?;
+        let beta_g2 = reader.read_g2_arkworks(compressed)?;
+        let gamma_g2 = reader.read_g2_arkworks(compressed)?;
+        let delta_g2 = reader.read_g2_arkworks(compressed)?;
+
+        let ic_len = reader.read_u64_le()?;
+        if ic_len == 0 {
+            return Err(AmclError::InvalidEncoding);
+        }
+        let mut ic = Vec::new();
+        for _ in 0..ic_len {
+            ic.push(reader.read_g1_arkworks(compressed)?);
+        }
+        reader.finish()?;
+
+        Ok(VerifyingKey {
+            alpha_g1,
+            beta_g1: None,
+            beta_g2,
+            gamma_g2,
+            delta_g1: None,
+            delta_g2,
+            ic,
+        })
+    }
+
+    /// Prepare Verifying Key
+    ///
+    /// Precompute e(alpha, beta) and negate gamma and delta.
+    pub fn prepare(&self) -> PreparedVerifyingKey {
+        let alpha_g1_beta_g2 = multi_pairing(&[(&self.beta_g2, &self.alpha_g1)]);
+
+        let mut neg_gamma_g2 = self.gamma_g2.clone();
+        neg_gamma_g2.neg();
+        let mut neg_delta_g2 = self.delta_g2.clone();
+        neg_delta_g2.neg();
+
+        PreparedVerifyingKey {
+            alpha_g1_beta_g2,
+            neg_gamma_g2,
+            neg_delta_g2,
+            ic: self.ic.clone(),
+        }
+    }
+}
+
+impl Proof {
+    /// Parse a proof in the bellman format, a || b || c.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Proof, AmclError> {
+        let mut reader = Reader::new(bytes);
+        let a = reader.read_g1()?;
+        let b = reader.read_g2()?;
+        let c = reader.read_g1()?;
+        reader.finish()?;
+
+        Ok(Proof { a, b, c })
+    }
+
+    /// Parse a proof in the arkworks `CanonicalSerialize` format of ark-groth16, a || b || c,
+    /// every point compressed or every point uncompressed as for `VerifyingKey`.
+    pub fn from_arkworks_bytes(bytes: &[u8], compressed: bool) -> Result<Proof, AmclError> {
+        let mut reader = Reader::new(bytes);
+        let a = reader.read_g1_arkworks(compressed)?;
+        let b = reader.read_g2_arkworks(compressed)?;
+        let c = reader.read_g1_arkworks(compressed)?;
+        reader.finish()?;
+
+        Ok(Proof { a, b, c })
+    }
+}
+
+impl PreparedVerifyingKey {
+    // ic[0] + sum(public_inputs[i] * ic[i + 1])
+    fn accumulate_inputs(&self, public_inputs: &[Big]) -> Result<ECP, AmclError> {
+        if public_inputs.len() + 1 != self.ic.len() {
+            return Err(AmclError::InvalidPublicInputs);
+        }
+        let mut acc = g1_linear_combination(&self.ic[1..], public_inputs);
+        acc.add(&self.ic[0]);
+        Ok(acc)
+    }
+
+    /// Verify Proof
+    ///
+    /// Checks e(A, B) * e(acc, -gamma) * e(C, -delta) == e(alpha, beta).
+    pub fn verify(&self, proof: &Proof, public_inputs: &[Big]) -> Result<bool, AmclError> {
+        let acc = self.accumulate_inputs(public_inputs)?;
+
+        let v = multi_pairing(&[
+            (&proof.b, &proof.a),
+            (&self.neg_gamma_g2, &acc),
+            (&self.neg_delta_g2, &proof.c),
+        ]);
+
+        Ok(v.equals(&self.alpha_g1_beta_g2))
+    }
+
+    /// Batch Verify
+    ///
+    /// Verify proofs with a random linear combination using a single final exponentiation.
+    /// prod(e(r_j * A_j, B_j)) * e(sum(r_j * acc_j), -gamma) * e(sum(r_j * C_j), -delta)
+    /// == e(alpha, beta)^sum(r_j)
+    pub fn batch_verify(
+        &self,
+        proofs: &[Proof],
+        public_inputs: &[&[Big]],
+        rng: &mut RAND,
+    ) -> Result<bool, AmclError> {
+        if proofs.len() != public_inputs.len() {
+            return Err(AmclError::InvalidBatchSize);
+        }
+        if proofs.len() == 0 {
+            return Ok(true);
+        }
+
+        let mut r = pair::initmp();
+        let mut acc_sum = ECP::new();
+        let mut c_sum = ECP::new();
+        let mut r_sum = Big::new();
+
+        for (proof, inputs) in proofs.iter().zip(public_inputs.iter()) {
+            let random = random_batch_scalar(rng);
+            let acc = self.accumulate_inputs(inputs)?;
+
+            acc_sum.add(&pair::g1mul(&acc, &random));
+            c_sum.add(&pair::g1mul(&proof.c, &random));
+            r_sum = scalar_add(&r_sum, &random);
+
+            let a = pair::g1mul(&proof.a, &random);
+            if !a.is_infinity() && !proof.b.is_infinity() {
+                pair::another(&mut r, &proof.b, &a);
+            }
+        }
+
+        if !acc_sum.is_infinity() {
+            pair::another(&mut r, &self.neg_gamma_g2, &acc_sum);
+        }
+        if !c_sum.is_infinity() {
+            pair::another(&mut r, &self.neg_delta_g2, &c_sum);
+        }
+        let mut v = pair::miller(&r);
+        v = pair::fexp(&v);
+
+        let expected = pair::gtpow(&self.alpha_g1_beta_g2, &r_sum);
+        Ok(v.equals(&expected))
+    }
+}
+
+// Random non-zero 128 bit scalar.
+fn random_batch_scalar(rng: &mut RAND) -> Big {
+    loop {
+        let mut bytes = [0u8; BATCH_SCALAR_BYTES];
+        for byte in bytes.iter_mut() {
+            *byte = rng.getbyte();
+        }
+        let scalar = Big::from_bytes(&bytes);
+        if !scalar.is_zilch() {
+            return scalar;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::kzg::scalar_from_bytes;
+    use super::*;
+    use crate::serde_json::{self, Value};
+
+    // Key and proofs generated with ark-groth16 0.3
+    const VECTORS: &str = include_str!("../tests/fixtures/groth16/arkworks.json");
+    // Key and proofs in the bellman layout and zcash point encoding
+    const BELLMAN_VECTORS: &str = include_str!("../tests/fixtures/groth16/bellman.json");
+
+    fn bytes(value: &Value) -> Vec<u8> {
+        hex::decode(value.as_str().unwrap().trim_start_matches("0x")).unwrap()
+    }
+
+    fn public_inputs(proof: &Value) -> Vec<Big> {
+        proof["public_inputs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|input| scalar_from_bytes(&bytes(input)).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_arkworks_verify() {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        for (vk_name, proof_name, compressed) in [
+            ("vk_compressed", "proof_compressed", true),
+            ("vk_uncompressed", "proof_uncompressed", false),
+        ]
+        .iter()
+        {
+            let vk = VerifyingKey::from_arkworks_bytes(&bytes(&vectors[vk_name]), *compressed)
+                .unwrap()
+                .prepare();
+            assert_eq!(vk.ic.len(), 3);
+
+            for proof in vectors["proofs"].as_array().unwrap() {
+                let inputs = public_inputs(proof);
+                let parsed =
+                    Proof::from_arkworks_bytes(&bytes(&proof[proof_name]), *compressed).unwrap();
+                assert!(vk.verify(&parsed, &inputs).unwrap());
+
+                // Wrong public input
+                let mut wrong = inputs.clone();
+                wrong[0].inc(1);
+                assert!(!vk.verify(&parsed, &wrong).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_arkworks_batch_verify() {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        let vk = VerifyingKey::from_arkworks_bytes(&bytes(&vectors["vk_compressed"]), true)
+            .unwrap()
+            .prepare();
+        let mut proofs = Vec::new();
+        let mut inputs = Vec::new();
+        for proof in vectors["proofs"].as_array().unwrap() {
+            proofs.push(
+                Proof::from_arkworks_bytes(&bytes(&proof["proof_compressed"]), true).unwrap(),
+            );
+            inputs.push(public_inputs(proof));
+        }
+        let inputs: Vec<&[Big]> = inputs.iter().map(|i| &i[..]).collect();
+
+        let mut rng = RAND::new();
+        rng.seed(32, &[7u8; 32]);
+        assert!(vk.batch_verify(&proofs, &inputs, &mut rng).unwrap());
+
+        // Proofs swapped between statements
+        proofs.swap(0, 1);
+        assert!(!vk.batch_verify(&proofs, &inputs, &mut rng).unwrap());
+    }
+
+    #[test]
+    fn test_arkworks_invalid_encodings() {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        let vk = bytes(&vectors["vk_compressed"]);
+        let proof = bytes(&vectors["proofs"][0]["proof_compressed"]);
+
+        // Wrong compression mode
+        assert!(VerifyingKey::from_arkworks_bytes(&vk, false).is_err());
+        assert!(Proof::from_arkworks_bytes(&proof, false).is_err());
+
+        // Truncated and trailing bytes
+        assert!(VerifyingKey::from_arkworks_bytes(&vk[..vk.len() - 1], true).is_err());
+        assert!(Proof::from_arkworks_bytes(&proof[..proof.len() - 1], true).is_err());
+        let mut long = proof.clone();
+        long.push(0);
+        assert!(Proof::from_arkworks_bytes(&long, true).is_err());
+
+        // gamma_abc_g1 length larger than the points present
+        let mut vk_bad_len = vk.clone();
+        vk_bad_len[MODBYTES * 7] += 1;
+        assert!(VerifyingKey::from_arkworks_bytes(&vk_bad_len, true).is_err());
+
+        // Empty gamma_abc_g1
+        let mut vk_empty = vk[..MODBYTES * 7 + 8].to_vec();
+        for byte in vk_empty[MODBYTES * 7..].iter_mut() {
+            *byte = 0;
+        }
+        assert_eq!(
+            VerifyingKey::from_arkworks_bytes(&vk_empty, true).err(),
+            Some(AmclError::InvalidEncoding)
+        );
+
+        // The bellman parser does not accept arkworks bytes
+        assert!(Proof::from_bytes(&proof).is_err());
+    }
+
+    fn bellman() -> (Value, PreparedVerifyingKey, Vec<Proof>, Vec<Vec<Big>>) {
+        let vectors: Value = serde_json::from_str(BELLMAN_VECTORS).unwrap();
+        let vk = VerifyingKey::from_bytes(&bytes(&vectors["vk"])).unwrap();
+        assert!(vk.beta_g1.is_some() && vk.delta_g1.is_some());
+        let mut proofs = Vec::new();
+        let mut inputs = Vec::new();
+        for proof in vectors["proofs"].as_array().unwrap() {
+            proofs.push(Proof::from_bytes(&bytes(&proof["proof"])).unwrap());
+            inputs.push(public_inputs(proof));
+        }
+        (vectors, vk.prepare(), proofs, inputs)
+    }
+
+    #[test]
+    fn test_bellman_verify() {
+        let (vectors, vk, proofs, inputs) = bellman();
+        assert_eq!(vk.ic.len(), 3);
+        for (i, proof) in proofs.iter().enumerate() {
+            assert!(vk.verify(proof, &inputs[i]).unwrap());
+
+            // The same points uncompressed
+            let uncompressed = bytes(&vectors["proofs"][i]["proof_uncompressed"]);
+            assert!(vk
+                .verify(&Proof::from_bytes(&uncompressed).unwrap(), &inputs[i])
+                .unwrap());
+
+            let mut wrong = inputs[i].clone();
+            wrong[1].inc(1);
+            assert!(!vk.verify(proof, &wrong).unwrap());
+            assert_eq!(
+                vk.verify(proof, &inputs[i][..1]).err(),
+                Some(AmclError::InvalidPublicInputs)
+            );
+        }
+
+        // Proofs of other statements
+        assert!(!vk.verify(&proofs[0], &inputs[1]).unwrap());
+    }
+
+    #[test]
+    fn test_bellman_tampered_proofs() {
+        let (_, vk, proofs, inputs) = bellman();
+        let mut tampered = proofs[0].clone();
+        tampered.a.add(&ECP::generator());
+        assert!(!vk.verify(&tampered, &inputs[0]).unwrap());
+        let mut tampered = proofs[0].clone();
+        tampered.b.add(&ECP2::generator());
+        assert!(!vk.verify(&tampered, &inputs[0]).unwrap());
+        let mut tampered = proofs[0].clone();
+        tampered.c = proofs[1].c.clone();
+        assert!(!vk.verify(&tampered, &inputs[0]).unwrap());
+
+        // A key with another delta rejects every proof
+        let vectors: Value = serde_json::from_str(BELLMAN_VECTORS).unwrap();
+        let mut key = VerifyingKey::from_bytes(&bytes(&vectors["vk"])).unwrap();
+        key.delta_g2.add(&ECP2::generator());
+        assert!(!key.prepare().verify(&proofs[0], &inputs[0]).unwrap());
+    }
+
+    #[test]
+    fn test_bellman_batch_verify() {
+        let (_, vk, mut proofs, inputs) = bellman();
+        let inputs: Vec<&[Big]> = inputs.iter().map(|i| &i[..]).collect();
+        let mut rng = RAND::new();
+        rng.seed(32, &[9u8; 32]);
+        assert!(vk.batch_verify(&proofs, &inputs, &mut rng).unwrap());
+        assert!(vk.batch_verify(&[], &[], &mut rng).unwrap());
+        assert_eq!(
+            vk.batch_verify(&proofs, &inputs[1..], &mut rng).err(),
+            Some(AmclError::InvalidBatchSize)
+        );
+
+        // One tampered proof fails the whole batch
+        proofs[2].c.add(&ECP::generator());
+        assert!(!vk.batch_verify(&proofs, &inputs, &mut rng).unwrap());
+        assert!(vk
+            .batch_verify(&proofs[..2], &inputs[..2], &mut rng)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_bellman_invalid_encodings() {
+        let vectors: Value = serde_json::from_str(BELLMAN_VECTORS).unwrap();
+        let vk = bytes(&vectors["vk"]);
+        let proof = bytes(&vectors["proofs"][0]["proof"]);
+
+        assert!(VerifyingKey::from_bytes(&vk[..vk.len() - 1]).is_err());
+        let mut long = vk.clone();
+        long.push(0);
+        assert!(VerifyingKey::from_bytes(&long).is_err());
+        assert!(Proof::from_bytes(&proof[..proof.len() - 1]).is_err());
+        assert!(Proof::from_bytes(&[]).is_err());
+
+        // ic length past the points present, and an empty ic
+        let ic_len = MODBYTES * 2 * 3 + MODBYTES * 4 * 3;
+        let mut bad_len = vk.clone();
+        bad_len[ic_len + 3] += 1;
+        assert!(VerifyingKey::from_bytes(&bad_len).is_err());
+        let mut empty = vk[..ic_len + 4].to_vec();
+        empty[ic_len + 3] = 0;
+        assert_eq!(
+            VerifyingKey::from_bytes(&empty).err(),
+            Some(AmclError::InvalidEncoding)
+        );
+
+        // A point moved off the curve
+        let mut off_curve = proof.clone();
+        off_curve[MODBYTES - 1] ^= 1;
+        assert!(Proof::from_bytes(&off_curve).is_err());
+
+        // The arkworks parser does not accept bellman bytes
+        assert!(Proof::from_arkworks_bytes(&proof, true).is_err());
+    }
+
+    #[test]
+    fn test_arkworks_key_has_no_beta_and_delta_g1() {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        let vk =
+            VerifyingKey::from_arkworks_bytes(&bytes(&vectors["vk_compressed"]), true).unwrap();
+        assert!(vk.beta_g1.is_none() && vk.delta_g1.is_none());
+    }
+}
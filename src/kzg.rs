@@ -0,0 +1,507 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::big::Big;
+use super::bls381::utils::{
+    deserialize_g1, deserialize_g2, serialize_g1, subgroup_check_g1, subgroup_check_g2,
+};
+use super::ecp::ECP;
+use super::ecp2::ECP2;
+use super::fp12::FP12;
+use super::pair;
+use super::rom::{CURVE_ORDER, MODBYTES};
+use crate::errors::AmclError;
+use std::vec::Vec;
+
+/// The number of bytes of a serialized scalar
+pub const SCALAR_BYTES: usize = 32;
+/// The number of bytes of a compressed G1 point in a serialized setup
+pub const G1_POINT_BYTES: usize = MODBYTES;
+/// The number of bytes of a compressed G2 point in a serialized setup
+pub const G2_POINT_BYTES: usize = MODBYTES * 2;
+
+/// Structured Reference String
+///
+/// Powers of tau in G1, [tau^i]G1 for i in 0..n, and [1]G2, [tau]G2.
+#[derive(Clone)]
+pub struct TrustedSetup {
+    pub g1_powers: Vec<ECP>,
+    pub g2_powers: Vec<ECP2>,
+}
+
+impl TrustedSetup {
+    /// Load a setup from concatenated compressed points.
+    ///
+    /// `g1_bytes` holds n 48 byte G1 points and `g2_bytes` at least two 96 byte G2 points,
+    /// as in the ZCash serialization used by `deserialize_g1` and `deserialize_g2`.
+    pub fn from_bytes(g1_bytes: &[u8], g2_bytes: &[u8]) -> Result<TrustedSetup, AmclError> {
+        if g1_bytes.len() == 0 || g1_bytes.len() % G1_POINT_BYTES != 0 {
+            return Err(AmclError::InvalidTrustedSetup);
+        }
+        if g2_bytes.len() < 2 * G2_POINT_BYTES || g2_bytes.len() % G2_POINT_BYTES != 0 {
+            return Err(AmclError::InvalidTrustedSetup);
+        }
+
+        let mut g1_powers = Vec::with_capacity(g1_bytes.len() / G1_POINT_BYTES);
+        for point in g1_bytes.chunks(G1_POINT_BYTES) {
+            g1_powers.push(deserialize_g1(point)?);
+        }
+        let mut g2_powers = Vec::with_capacity(g2_bytes.len() / G2_POINT_BYTES);
+        for point in g2_bytes.chunks(G2_POINT_BYTES) {
+            g2_powers.push(deserialize_g2(point)?);
+        }
+
+        TrustedSetup::new(g1_powers, g2_powers)
+    }
+
+    /// Load a setup from the text format of `trusted_setup.txt` as shipped with c-kzg-4844.
+    ///
+    /// Uses the G1 monomial points following the G2 points, files without them only hold the
+    /// Lagrange basis and are rejected.
+    pub fn from_file(contents: &str) -> Result<TrustedSetup, AmclError> {
+        let file = parse_trusted_setup_file(contents)?;
+        if file.g1_monomial.is_empty() {
+            return Err(AmclError::InvalidTrustedSetup);
+        }
+
+        TrustedSetup::from_bytes(&file.g1_monomial, &file.g2_monomial)
+    }
+
+    /// Create a setup from points, checking they are in the correct subgroups.
+    pub fn new(g1_powers: Vec<ECP>, g2_powers: Vec<ECP2>) -> Result<TrustedSetup, AmclError> {
+        if g1_powers.len() == 0 || g2_powers.len() < 2 {
+            return Err(AmclError::InvalidTrustedSetup);
+        }
+        if g1_powers
+            .iter()
+            .any(|p| p.is_infinity() || !subgroup_check_g1(p))
+        {
+            return Err(AmclError::InvalidTrustedSetup);
+        }
+        if g2_powers
+            .iter()
+            .any(|p| p.is_infinity() || !subgroup_check_g2(p))
+        {
+            return Err(AmclError::InvalidTrustedSetup);
+        }
+
+        Ok(TrustedSetup {
+            g1_powers,
+            g2_powers,
+        })
+    }
+
+    /// The maximum number of coefficients of a polynomial that can be committed to.
+    pub fn max_coefficients(&self) -> usize {
+        self.g1_powers.len()
+    }
+
+    /// Commit
+    ///
+    /// C = sum(coefficients[i] * [tau^i]G1)
+    pub fn commit(&self, coefficients: &[Big]) -> Result<ECP, AmclError> {
+        if coefficients.len() > self.g1_powers.len() {
+            return Err(AmclError::InvalidPolynomialDegree);
+        }
+
+        Ok(g1_linear_combination(&self.g1_powers, coefficients))
+    }
+
+    /// Open
+    ///
+    /// Returns the proof [q(tau)]G1 where q(x) = (p(x) - p(z)) / (x - z), and y = p(z).
+    pub fn open(&self, coefficients: &[Big], z: &Big) -> Result<(ECP, Big), AmclError> {
+        if coefficients.len() > self.g1_powers.len() {
+            return Err(AmclError::InvalidPolynomialDegree);
+        }
+
+        let (quotient, y) = divide_by_linear(coefficients, z);
+        let proof = g1_linear_combination(&self.g1_powers, &quotient);
+        Ok((proof, y))
+    }
+
+    /// Verify
+    ///
+    /// Checks e(proof, [tau - z]G2) * e(C - [y]G1, -G2) == 1.
+    pub fn verify(&self, commitment: &ECP, z: &Big, y: &Big, proof: &ECP) -> bool {
+        if !subgroup_check_g1(commitment) || !subgroup_check_g1(proof) {
+            return false;
+        }
+
+        // [tau - z]G2
+        let mut x_minus_z = pair::g2mul(&self.g2_powers[0], z);
+        x_minus_z.neg();
+        x_minus_z.add(&self.g2_powers[1]);
+
+        // C - [y]G1
+        let mut p_minus_y = pair::g1mul(&self.g1_powers[0], y);
+        p_minus_y.neg();
+        p_minus_y.add(commitment);
+
+        let mut g2 = self.g2_powers[0].clone();
+        g2.neg();
+
+        pairing_product_is_unity(&[(&x_minus_z, proof), (&g2, &p_minus_y)])
+    }
+}
+
+/// The compressed points of a `trusted_setup.txt` file
+///
+/// Older files end after the G2 points, in which case `g1_monomial` is empty.
+#[derive(Clone, Debug)]
+pub struct TrustedSetupFile {
+    pub g1_lagrange: Vec<u8>,
+    pub g2_monomial: Vec<u8>,
+    pub g1_monomial: Vec<u8>,
+}
+
+/// Parse Trusted Setup File
+///
+/// The number of G1 points n, the number of G2 points, then each point as hex: the n G1 points
+/// of the Lagrange basis, the G2 monomial points and, in newer files, the n G1 monomial points.
+/// Points are only decoded as hex, curve and subgroup checks are left to the caller.
+pub fn parse_trusted_setup_file(contents: &str) -> Result<TrustedSetupFile, AmclError> {
+    let mut lines = contents.split_whitespace();
+
+    let n_g1: usize = lines
+        .next()
+        .and_then(|n| n.parse().ok())
+        .ok_or(AmclError::InvalidTrustedSetup)?;
+    let n_g2: usize = lines
+        .next()
+        .and_then(|n| n.parse().ok())
+        .ok_or(AmclError::InvalidTrustedSetup)?;
+    if n_g1 == 0 {
+        return Err(AmclError::InvalidTrustedSetup);
+    }
+
+    let g1_lagrange = read_points(&mut lines, n_g1, G1_POINT_BYTES)?;
+    let g2_monomial = read_points(&mut lines, n_g2, G2_POINT_BYTES)?;
+    let g1_monomial = match lines.next() {
+        None => Vec::new(),
+        Some(first) => {
+            let mut points = decode_point_hex(first, G1_POINT_BYTES)?;
+            points.extend_from_slice(&read_points(&mut lines, n_g1 - 1, G1_POINT_BYTES)?);
+            points
+        }
+    };
+    if lines.next().is_some() {
+        return Err(AmclError::InvalidTrustedSetup);
+    }
+
+    Ok(TrustedSetupFile {
+        g1_lagrange,
+        g2_monomial,
+        g1_monomial,
+    })
+}
+
+// Read `count` hex encoded points of `size` bytes each.
+//
+// `count` comes from the file header, so nothing is allocated ahead of the points actually read.
+fn read_points<'a, I: Iterator<Item = &'a str>>(
+    lines: &mut I,
+    count: usize,
+    size: usize,
+) -> Result<Vec<u8>, AmclError> {
+    let mut bytes = Vec::new();
+    for _ in 0..count {
+        let line = lines.next().ok_or(AmclError::InvalidTrustedSetup)?;
+        bytes.extend_from_slice(&decode_point_hex(line, size)?);
+    }
+    Ok(bytes)
+}
+
+// Decode a hex point with optional 0x prefix.
+fn decode_point_hex(line: &str, size: usize) -> Result<Vec<u8>, AmclError> {
+    let bytes =
+        hex::decode(line.trim_start_matches("0x")).map_err(|_| AmclError::InvalidTrustedSetup)?;
+    if bytes.len() != size {
+        return Err(AmclError::InvalidTrustedSetup);
+    }
+    Ok(bytes)
+}
+
+/// Scalar From Bytes
+///
+/// Convert a 32 byte big-endian integer to a scalar, requiring it to be less than r.
+pub fn scalar_from_bytes(bytes: &[u8]) -> Result<Big, AmclError> {
+    if bytes.len() != SCALAR_BYTES {
+        return Err(AmclError::InvalidScalarSize);
+    }
+
+    let mut big_bytes = [0u8; MODBYTES];
+    big_bytes[MODBYTES - SCALAR_BYTES..].copy_from_slice(bytes);
+    let scalar = Big::from_bytes(&big_bytes);
+    if scalar >= Big::new_ints(&CURVE_ORDER) {
+        return Err(AmclError::InvalidScalarRange);
+    }
+
+    Ok(scalar)
+}
+
+/// Scalar To Bytes
+///
+/// Convert a scalar to a 32 byte big-endian integer.
+pub fn scalar_to_bytes(scalar: &Big) -> [u8; SCALAR_BYTES] {
+    let mut big_bytes = [0u8; MODBYTES];
+    scalar.to_bytes(&mut big_bytes);
+    let mut bytes = [0u8; SCALAR_BYTES];
+    bytes.copy_from_slice(&big_bytes[MODBYTES - SCALAR_BYTES..]);
+    bytes
+}
+
+/// Commitment To Bytes
+pub fn commitment_to_bytes(commitment: &ECP) -> [u8; G1_POINT_BYTES] {
+    serialize_g1(commitment)
+}
+
+/// Evaluate
+///
+/// Horner evaluation of p(z) mod r.
+pub fn evaluate(coefficients: &[Big], z: &Big) -> Big {
+    let mut y = Big::new();
+    for coefficient in coefficients.iter().rev() {
+        y = scalar_add(&scalar_mul(&y, z), coefficient);
+    }
+    y
+}
+
+// Modular arithmetic helpers on scalars mod r, all returning normalised values in [0, r).
+pub(crate) fn scalar_add(a: &Big, b: &Big) -> Big {
+    let r = Big::new_ints(&CURVE_ORDER);
+    let mut c = a.clone();
+    c.add(b);
+    c.rmod(&r);
+    c
+}
+
+pub(crate) fn scalar_sub(a: &Big, b: &Big) -> Big {
+    let r = Big::new_ints(&CURVE_ORDER);
+    let mut c = Big::modneg(b, &r);
+    c.add(a);
+    c.rmod(&r);
+    c
+}
+
+pub(crate) fn scalar_mul(a: &Big, b: &Big) -> Big {
+    let r = Big::new_ints(&CURVE_ORDER);
+    let mut c = Big::modmul(a, b, &r);
+    c.norm();
+    c
+}
+
+pub(crate) fn scalar_inverse(a: &Big) -> Big {
+    let r = Big::new_ints(&CURVE_ORDER);
+    let mut c = a.clone();
+    c.invmodp(&r);
+    c.norm();
+    c
+}
+
+// Synthetic division of p(x) by (x - z).
+//
+// Returns the quotient and the remainder p(z).
+fn divide_by_linear(coefficients: &[Big], z: &Big) -> (Vec<Big>, Big) {
+    if coefficients.len() == 0 {
+        return (Vec::new(), Big::new());
+    }
+
+    let mut quotient = vec![Big::new(); coefficients.len() - 1];
+    let mut carry = Big::new();
+    for i in (1..coefficients.len()).rev() {
+        // carry = a[i] + z * carry
+        carry = scalar_add(&scalar_mul(&carry, z), &coefficients[i]);
+        quotient[i - 1] = carry.clone();
+    }
+
+    // p(z) = a[0] + z * q[0]
+    let y = scalar_add(&scalar_mul(&carry, z), &coefficients[0]);
+
+    (quotient, y)
+}
+
+// Naive multi-scalar multiplication sum(scalars[i] * points[i]).
+pub(crate) fn g1_linear_combination(points: &[ECP], scalars: &[Big]) -> ECP {
+    let mut result = ECP::new();
+    for (point, scalar) in points.iter().zip(scalars.iter()) {
+        if scalar.is_zilch() {
+            continue;
+        }
+        result.add(&pair::g1mul(point, scalar));
+    }
+    result
+}
+
+// Returns the product of pairings e(P, Q) after final exponentiation.
+//
+// Pairs containing the point at infinity contribute 1 and are skipped.
+pub(crate) fn multi_pairing(pairs: &[(&ECP2, &ECP)]) -> FP12 {
+    let mut r = pair::initmp();
+    for (p, q) in pairs.iter() {
+        if p.is_infinity() || q.is_infinity() {
+            continue;
+        }
+        pair::another(&mut r, p, q);
+    }
+    let v = pair::miller(&r);
+    pair::fexp(&v)
+}
+
+// Returns true if the product of pairings e(P, Q) is 1.
+pub(crate) fn pairing_product_is_unity(pairs: &[(&ECP2, &ECP)]) -> bool {
+    multi_pairing(pairs).is_unity()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bls381::utils::{map_to_curve_g1, serialize_g2};
+    use super::super::fp::FP;
+    use super::*;
+    use std::string::String;
+
+    // A trusted_setup.txt with n G1 and two G2 points for tau = 5, the Lagrange section
+    // repeats the monomial points as only its layout matters here.
+    fn setup_file(n: usize, with_monomial: bool) -> String {
+        let tau = Big::new_int(5);
+        let mut g1 = Vec::new();
+        let mut power = Big::new_int(1);
+        for _ in 0..n {
+            g1.push(hex::encode(serialize_g1(&pair::g1mul(
+                &ECP::generator(),
+                &power,
+            ))));
+            power = scalar_mul(&power, &tau);
+        }
+        let g2 = [ECP2::generator(), pair::g2mul(&ECP2::generator(), &tau)];
+
+        let mut contents = format!("{}\n2\n", n);
+        for point in g1.iter() {
+            contents.push_str(&format!("{}\n", point));
+        }
+        for point in g2.iter() {
+            contents.push_str(&format!("{}\n", hex::encode(&serialize_g2(point)[..])));
+        }
+        if with_monomial {
+            for point in g1.iter() {
+                contents.push_str(&format!("{}\n", point));
+            }
+        }
+        contents
+    }
+
+    #[test]
+    fn test_from_file() {
+        let setup = TrustedSetup::from_file(&setup_file(4, true)).unwrap();
+        assert_eq!(setup.max_coefficients(), 4);
+        assert_eq!(setup.g2_powers.len(), 2);
+
+        let coefficients: Vec<Big> = (1..5).map(Big::new_int).collect();
+        let commitment = setup.commit(&coefficients).unwrap();
+        let z = Big::new_int(3);
+        let (proof, y) = setup.open(&coefficients, &z).unwrap();
+        assert!(setup.verify(&commitment, &z, &y, &proof));
+
+        // 1 + 2*3 + 3*9 + 4*27
+        assert_eq!(y, Big::new_int(142));
+    }
+
+    #[test]
+    fn test_from_file_matches_from_bytes() {
+        let contents = setup_file(3, true);
+        let file = parse_trusted_setup_file(&contents).unwrap();
+        assert_eq!(file.g1_lagrange.len(), 3 * G1_POINT_BYTES);
+        assert_eq!(file.g2_monomial.len(), 2 * G2_POINT_BYTES);
+        assert_eq!(file.g1_monomial, file.g1_lagrange);
+
+        let from_file = TrustedSetup::from_file(&contents).unwrap();
+        let from_bytes = TrustedSetup::from_bytes(&file.g1_monomial, &file.g2_monomial).unwrap();
+        for (a, b) in from_file.g1_powers.iter().zip(from_bytes.g1_powers.iter()) {
+            assert!(a.equals(b));
+        }
+    }
+
+    #[test]
+    fn test_from_file_without_monomial_points() {
+        let contents = setup_file(4, false);
+        assert!(parse_trusted_setup_file(&contents)
+            .unwrap()
+            .g1_monomial
+            .is_empty());
+        assert!(TrustedSetup::from_file(&contents).is_err());
+    }
+
+    #[test]
+    fn test_from_file_invalid() {
+        // Truncated G1 monomial section
+        let mut contents = setup_file(4, true);
+        let end = contents.trim_end().rfind('\n').unwrap();
+        contents.truncate(end);
+        assert!(parse_trusted_setup_file(&contents).is_err());
+
+        // Trailing data
+        let contents = setup_file(2, true) + "00\n";
+        assert!(parse_trusted_setup_file(&contents).is_err());
+
+        // Wrong point size and bad hex
+        let contents = setup_file(2, false).replacen("\n2\n", "\n2\n00", 1);
+        assert!(parse_trusted_setup_file(&contents).is_err());
+        assert!(parse_trusted_setup_file("1\n2\nzz\n").is_err());
+        assert!(parse_trusted_setup_file("0\n2\n").is_err());
+        assert!(parse_trusted_setup_file("").is_err());
+
+        // Point counts far beyond the points present
+        let contents = format!("{}\n2\n", usize::MAX);
+        assert!(parse_trusted_setup_file(&contents).is_err());
+        let contents = setup_file(2, false).replacen("2\n2\n", &format!("2\n{}\n", usize::MAX), 1);
+        assert!(parse_trusted_setup_file(&contents).is_err());
+    }
+
+    #[test]
+    fn test_verify_invalid() {
+        let setup = TrustedSetup::from_file(&setup_file(4, true)).unwrap();
+        let coefficients: Vec<Big> = (1..5).map(Big::new_int).collect();
+        let commitment = setup.commit(&coefficients).unwrap();
+        let z = Big::new_int(3);
+        let (proof, y) = setup.open(&coefficients, &z).unwrap();
+        assert!(setup.verify(&commitment, &z, &y, &proof));
+
+        // Wrong value and wrong point
+        assert!(!setup.verify(&commitment, &z, &scalar_add(&y, &Big::new_int(1)), &proof));
+        assert!(!setup.verify(&commitment, &Big::new_int(4), &y, &proof));
+
+        // Proof and commitment of other polynomials
+        let other: Vec<Big> = (2..6).map(Big::new_int).collect();
+        let (other_proof, _) = setup.open(&other, &z).unwrap();
+        assert!(!setup.verify(&commitment, &z, &y, &other_proof));
+        let other_commitment = setup.commit(&other).unwrap();
+        assert!(!setup.verify(&other_commitment, &z, &y, &proof));
+
+        // Tampered proofs
+        let mut tampered = proof.clone();
+        tampered.add(&ECP::generator());
+        assert!(!setup.verify(&commitment, &z, &y, &tampered));
+        assert!(!setup.verify(&commitment, &z, &y, &ECP::new()));
+        let non_subgroup = map_to_curve_g1(FP::new_int(1));
+        assert!(!subgroup_check_g1(&non_subgroup));
+        assert!(!setup.verify(&commitment, &z, &y, &non_subgroup));
+        assert!(!setup.verify(&non_subgroup, &z, &y, &proof));
+    }
+}
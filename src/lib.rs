@@ -7,12 +7,10 @@ extern crate sgx_tstd as std;
 
 // pub use std::*;
 
-// #[cfg(test)]
-// extern crate serde_json;
-// #[cfg(test)]
-//
-// #[macro_use]
-// extern crate serde_derive;
+extern crate serde_sgx as serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json_sgx as serde_json;
 
 #[cfg(not(target_env = "sgx"))]
 #[macro_use]
@@ -33,6 +31,7 @@ pub mod hash512;
 // pub mod nhs;
 pub mod rand;
 // pub mod sha3;
+pub mod ssz;
 pub mod types;
 
 #[cfg(feature = "bls381")]
@@ -40,16 +39,30 @@ pub mod types;
 pub mod bls381 {
     #[path = "roms/rom_bls381.rs"]
     pub mod rom;
+    pub mod bbs;
     pub mod big;
     pub mod bls381;
+    pub mod bulletproofs;
+    pub mod ceremony;
     pub mod dbig;
     pub mod ecp;
     pub mod ecp2;
+    pub mod eip2537;
+    pub mod eip4844;
+    pub mod erasure;
     pub mod fp;
     pub mod fp12;
     pub mod fp2;
     pub mod fp4;
+    pub mod fr;
+    pub mod groth16;
     pub mod hash_to_curve;
+    pub mod kzg;
     pub mod mpin;
     pub mod pair;
+    pub mod pedersen;
+    pub mod poly;
+    pub mod ps;
+    pub mod sigma;
+    pub mod transcript;
 }
@@ -0,0 +1,272 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::bls381::utils::hash_to_curve_g1;
+use super::ecp::ECP;
+use super::fr::Fr;
+use super::pair;
+use crate::errors::AmclError;
+use std::vec::Vec;
+
+/// Domain separation tag for deriving generators with hash_to_curve_g1
+pub const GENERATORS_DST: &[u8] = b"BLS12381G1_XMD:SHA-256_SSWU_RO_PEDERSEN_GENERATORS_";
+
+// Below this many points multi-scalar multiplication uses double and add per point
+const PIPPENGER_THRESHOLD: usize = 8;
+
+/// Nothing Up My Sleeve Generators
+///
+/// Returns hash_to_curve_g1(label || I2OSP(i, 4)) for i in 0..n. Nobody knows the discrete
+/// log relations between the generators, or between generators with different labels.
+pub fn nums_generators(label: &[u8], n: usize) -> Vec<ECP> {
+    (0..n)
+        .map(|i| {
+            let mut msg = label.to_vec();
+            msg.extend_from_slice(&(i as u32).to_be_bytes());
+            hash_to_curve_g1(&msg, GENERATORS_DST)
+        })
+        .collect()
+}
+
+/// Multi-Scalar Multiplication
+///
+/// Computes sum(scalars[i] * points[i]) with Pippenger's bucket method.
+pub fn multi_scalar_mul(points: &[ECP], scalars: &[Fr]) -> ECP {
+    let n = core::cmp::min(points.len(), scalars.len());
+    if n < PIPPENGER_THRESHOLD {
+        let mut result = ECP::new();
+        for (point, scalar) in points.iter().zip(scalars.iter()) {
+            if !scalar.is_zilch() {
+                result.add(&pair::g1mul(point, &scalar.redc()));
+            }
+        }
+        return result;
+    }
+
+    // Window of roughly log2(n) bits
+    let log_n = (0usize.leading_zeros() - n.leading_zeros()) as usize;
+    let window = core::cmp::min(core::cmp::max(2, log_n), 16);
+    let scalar_bytes: Vec<[u8; 32]> = scalars[..n].iter().map(|s| s.to_bytes()).collect();
+    let digit = |bytes: &[u8; 32], start: usize| -> usize {
+        let mut d = 0;
+        for bit in (start..core::cmp::min(start + window, 256)).rev() {
+            d = (d << 1) | ((bytes[31 - bit / 8] >> (bit % 8)) & 1) as usize;
+        }
+        d
+    };
+
+    let mut result = ECP::new();
+    let windows = (255 + window - 1) / window;
+    for w in (0..windows).rev() {
+        for _ in 0..window {
+            result.dbl();
+        }
+
+        let mut buckets = vec![ECP::new(); (1 << window) - 1];
+        for (point, bytes) in points[..n].iter().zip(scalar_bytes.iter()) {
+            let d = digit(bytes, w * window);
+            if d != 0 {
+                buckets[d - 1].add(point);
+            }
+        }
+
+        // sum(d * bucket[d]) as a sum of running sums
+        let mut running = ECP::new();
+        let mut window_sum = ECP::new();
+        for bucket in buckets.iter().rev() {
+            running.add(bucket);
+            window_sum.add(&running);
+        }
+        result.add(&window_sum);
+    }
+    result
+}
+
+/// Pedersen vector commitment generators, g_vec[i] for each value and h for the blinding.
+#[derive(Clone)]
+pub struct PedersenGens {
+    pub g_vec: Vec<ECP>,
+    pub h: ECP,
+}
+
+impl PedersenGens {
+    /// New
+    ///
+    /// Derives generators for committing to up to `n` values, separated by `label`.
+    pub fn new(label: &[u8], n: usize) -> PedersenGens {
+        let mut h_label = label.to_vec();
+        h_label.extend_from_slice(b"_H");
+        let mut g_label = label.to_vec();
+        g_label.extend_from_slice(b"_G");
+
+        PedersenGens {
+            g_vec: nums_generators(&g_label, n),
+            h: nums_generators(&h_label, 1).remove(0),
+        }
+    }
+
+    /// Commit
+    ///
+    /// C = sum(values[i] * g_vec[i]) + blinding * h
+    pub fn commit(&self, values: &[Fr], blinding: &Fr) -> Result<ECP, AmclError> {
+        if values.len() > self.g_vec.len() {
+            return Err(AmclError::InvalidMessageCount);
+        }
+
+        let mut points = self.g_vec[..values.len()].to_vec();
+        points.push(self.h.clone());
+        let mut scalars = values.to_vec();
+        scalars.push(blinding.clone());
+        Ok(multi_scalar_mul(&points, &scalars))
+    }
+
+    /// Verify Opening
+    ///
+    /// Returns true if `commitment` opens to `values` with `blinding`.
+    pub fn verify_opening(&self, commitment: &ECP, values: &[Fr], blinding: &Fr) -> bool {
+        match self.commit(values, blinding) {
+            Ok(expected) => expected.equals(commitment),
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::big::Big;
+    use super::*;
+    use crate::rand::RAND;
+
+    fn rng() -> RAND {
+        let mut rng = RAND::new();
+        rng.seed(32, &[38u8; 32]);
+        rng
+    }
+
+    fn naive_msm(points: &[ECP], scalars: &[Fr]) -> ECP {
+        let mut result = ECP::new();
+        for (point, scalar) in points.iter().zip(scalars.iter()) {
+            result.add(&pair::g1mul(point, &scalar.redc()));
+        }
+        result
+    }
+
+    // 2^k
+    fn power_of_two(k: isize) -> Fr {
+        Fr::new_int(2).pow(&Big::new_int(k))
+    }
+
+    #[test]
+    fn test_msm_matches_naive() {
+        let mut rng = rng();
+        let generators = nums_generators(b"msm test", 65);
+
+        // Both sides of the threshold and of each change of window size
+        for n in [0, 1, 7, 8, 9, 15, 16, 17, 31, 32, 33, 63, 64, 65].iter() {
+            let points = generators[..*n].to_vec();
+            let scalars: Vec<Fr> = (0..*n).map(|_| Fr::random(&mut rng)).collect();
+            assert!(multi_scalar_mul(&points, &scalars).equals(&naive_msm(&points, &scalars)));
+        }
+    }
+
+    #[test]
+    fn test_msm_edge_scalars_and_points() {
+        let mut rng = rng();
+        let mut r_minus_one = Fr::new();
+        r_minus_one.sub(&Fr::one());
+
+        // Digits at and either side of the window boundaries of every window size
+        let mut scalars = vec![Fr::new(), Fr::one(), r_minus_one, Fr::new()];
+        for window in 2..8 {
+            for k in [window, 2 * window, 252 / window * window].iter() {
+                let mut below = power_of_two(*k);
+                below.sub(&Fr::one());
+                scalars.push(power_of_two(*k));
+                scalars.push(below);
+            }
+        }
+
+        // Identity points, repeated points and a point with its negation
+        let mut points = nums_generators(b"msm test", scalars.len());
+        points[1] = ECP::new();
+        points[5] = ECP::new();
+        points[7] = points[6].clone();
+        points[9] = points[8].clone();
+        points[9].neg();
+
+        for n in [4, 8, 16, 33, scalars.len()].iter() {
+            let (points, scalars) = (&points[..*n], &scalars[..*n]);
+            assert!(multi_scalar_mul(points, scalars).equals(&naive_msm(points, scalars)));
+        }
+
+        // All zero scalars and all identity points
+        let zeros = vec![Fr::new(); 20];
+        assert!(multi_scalar_mul(&points[..20], &zeros).is_infinity());
+        let identities = vec![ECP::new(); 20];
+        let scalars: Vec<Fr> = (0..20).map(|_| Fr::random(&mut rng)).collect();
+        assert!(multi_scalar_mul(&identities, &scalars).is_infinity());
+
+        // Only the shorter of the two lengths is used
+        assert!(multi_scalar_mul(&points[..20], &scalars[..12])
+            .equals(&naive_msm(&points[..12], &scalars[..12])));
+    }
+
+    #[test]
+    fn test_nums_generators() {
+        let generators = nums_generators(b"label", 4);
+        assert_eq!(generators.len(), 4);
+        for (i, g) in generators.iter().enumerate() {
+            assert!(!g.is_infinity());
+            assert!(g.equals(&nums_generators(b"label", 4)[i]));
+            for other in generators[i + 1..].iter() {
+                assert!(!g.equals(other));
+            }
+            assert!(!g.equals(&nums_generators(b"other label", 4)[i]));
+        }
+    }
+
+    #[test]
+    fn test_commit() {
+        let mut rng = rng();
+        let gens = PedersenGens::new(b"commit test", 4);
+        let values: Vec<Fr> = (0..3).map(|_| Fr::random(&mut rng)).collect();
+        let blinding = Fr::random(&mut rng);
+
+        let commitment = gens.commit(&values, &blinding).unwrap();
+        let mut points = gens.g_vec[..3].to_vec();
+        points.push(gens.h.clone());
+        let mut scalars = values.clone();
+        scalars.push(blinding.clone());
+        assert!(commitment.equals(&naive_msm(&points, &scalars)));
+        assert!(gens.verify_opening(&commitment, &values, &blinding));
+
+        let mut other = blinding.clone();
+        other.add(&Fr::one());
+        assert!(!gens.verify_opening(&commitment, &values, &other));
+        assert!(!gens.verify_opening(&commitment, &values[..2], &blinding));
+
+        let too_many = vec![Fr::one(); 5];
+        assert_eq!(
+            gens.commit(&too_many, &blinding).err(),
+            Some(AmclError::InvalidMessageCount)
+        );
+        assert!(!gens.verify_opening(&commitment, &too_many, &blinding));
+    }
+}
@@ -0,0 +1,643 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::big::Big;
+use super::fr::{Fr, GENERATOR, TWO_ADICITY};
+use super::rom::CURVE_ORDER;
+use crate::errors::AmclError;
+use std::vec::Vec;
+
+// Below this many points multipoint evaluation falls back to Horner's method
+const MULTIPOINT_THRESHOLD: usize = 8;
+
+/// Root Of Unity
+///
+/// Returns a primitive root of unity of order `size`, which must be a power of two no greater
+/// than 2^32.
+pub fn root_of_unity(size: usize) -> Result<Fr, AmclError> {
+    if !size.is_power_of_two() || size.trailing_zeros() as usize > TWO_ADICITY {
+        return Err(AmclError::InvalidDomainSize);
+    }
+
+    // GENERATOR^((r - 1) / size)
+    let mut exponent = Big::new_ints(&CURVE_ORDER);
+    exponent.dec(1);
+    exponent.norm();
+    exponent.shr(size.trailing_zeros() as usize);
+
+    Ok(Fr::new_int(GENERATOR).pow(&exponent))
+}
+
+/*************************************************************************************************
+* Dense Polynomials
+*************************************************************************************************/
+
+/// A dense polynomial over the scalar field.
+///
+/// Coefficients are stored lowest degree first, with no trailing zeros.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polynomial {
+    coeffs: Vec<Fr>,
+}
+
+impl Polynomial {
+    /// New
+    ///
+    /// Creates a polynomial from coefficients, lowest degree first.
+    pub fn new(coeffs: Vec<Fr>) -> Polynomial {
+        let mut poly = Polynomial { coeffs };
+        poly.truncate();
+        poly
+    }
+
+    /// Zero
+    ///
+    /// The zero polynomial.
+    pub fn zero() -> Polynomial {
+        Polynomial { coeffs: vec![] }
+    }
+
+    /// From Roots
+    ///
+    /// Returns the monic polynomial (x - roots[0]) * ... * (x - roots[n - 1]).
+    pub fn from_roots(roots: &[Fr]) -> Polynomial {
+        let mut coeffs = Vec::with_capacity(roots.len() + 1);
+        coeffs.push(Fr::one());
+
+        for root in roots {
+            // Multiply by (x - root)
+            coeffs.push(Fr::new());
+            for i in (0..coeffs.len()).rev() {
+                let mut term = coeffs[i].clone();
+                term.mul(root);
+                term.neg();
+                if i > 0 {
+                    term.add(&coeffs[i - 1]);
+                }
+                coeffs[i] = term;
+            }
+        }
+
+        Polynomial::new(coeffs)
+    }
+
+    /// Interpolate
+    ///
+    /// Lagrange interpolation of the unique polynomial of degree less than `xs.len()`
+    /// through the points (xs[i], ys[i]). The xs must be distinct.
+    pub fn interpolate(xs: &[Fr], ys: &[Fr]) -> Result<Polynomial, AmclError> {
+        if xs.len() != ys.len() {
+            return Err(AmclError::InvalidEvaluationPoints);
+        }
+
+        // Z(x) = prod (x - xs[j])
+        let vanishing = Polynomial::from_roots(xs);
+        let mut result = Polynomial::zero();
+
+        for (i, (x_i, y_i)) in xs.iter().zip(ys.iter()).enumerate() {
+            // L_i(x) = Z(x) / (x - x_i) * 1 / prod_{j != i} (x_i - x_j)
+            let mut denominator = Fr::one();
+            for (j, x_j) in xs.iter().enumerate() {
+                if i != j {
+                    let mut difference = x_i.clone();
+                    difference.sub(x_j);
+                    denominator.mul(&difference);
+                }
+            }
+            if denominator.is_zilch() {
+                return Err(AmclError::InvalidEvaluationPoints);
+            }
+            denominator.inverse();
+
+            let mut scale = y_i.clone();
+            scale.mul(&denominator);
+
+            let basis = vanishing.divide_by_linear(x_i);
+            result = result.add(&basis.scale(&scale));
+        }
+
+        Ok(result)
+    }
+
+    // Removes trailing zero coefficients.
+    fn truncate(&mut self) {
+        while self.coeffs.last().map_or(false, |c| c.is_zilch()) {
+            self.coeffs.pop();
+        }
+    }
+
+    /// Coefficients
+    ///
+    /// Lowest degree first, empty for the zero polynomial.
+    pub fn coeffs(&self) -> &[Fr] {
+        &self.coeffs
+    }
+
+    /// Is Zero
+    pub fn is_zero(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+
+    /// Degree
+    ///
+    /// The degree of the polynomial, the zero polynomial is given degree 0.
+    pub fn degree(&self) -> usize {
+        self.coeffs.len().saturating_sub(1)
+    }
+
+    /// Evaluate
+    ///
+    /// Horner's method.
+    pub fn evaluate(&self, x: &Fr) -> Fr {
+        let mut result = Fr::new();
+        for coeff in self.coeffs.iter().rev() {
+            result.mul(x);
+            result.add(coeff);
+        }
+        result
+    }
+
+    /// Evaluate Many
+    ///
+    /// Multipoint evaluation by recursively reducing modulo subproducts of (x - points[i]).
+    pub fn evaluate_many(&self, points: &[Fr]) -> Vec<Fr> {
+        if points.len() <= MULTIPOINT_THRESHOLD {
+            return points.iter().map(|x| self.evaluate(x)).collect();
+        }
+
+        let (left, right) = points.split_at(points.len() / 2);
+        // Divisors are monic so division cannot fail
+        let (_, left_remainder) = self
+            .div_rem(&Polynomial::from_roots(left))
+            .expect("monic divisor");
+        let (_, right_remainder) = self
+            .div_rem(&Polynomial::from_roots(right))
+            .expect("monic divisor");
+
+        let mut evaluations = left_remainder.evaluate_many(left);
+        evaluations.extend(right_remainder.evaluate_many(right));
+        evaluations
+    }
+
+    /// Add
+    pub fn add(&self, other: &Polynomial) -> Polynomial {
+        let (longer, shorter) = if self.coeffs.len() >= other.coeffs.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+
+        let mut coeffs = longer.coeffs.clone();
+        for (c, s) in coeffs.iter_mut().zip(shorter.coeffs.iter()) {
+            c.add(s);
+        }
+        Polynomial::new(coeffs)
+    }
+
+    /// Subtract
+    pub fn sub(&self, other: &Polynomial) -> Polynomial {
+        let mut negated = other.clone();
+        for c in negated.coeffs.iter_mut() {
+            c.neg();
+        }
+        self.add(&negated)
+    }
+
+    /// Multiply
+    ///
+    /// Schoolbook multiplication, see `Domain` for FFT based multiplication of large polynomials.
+    pub fn mul(&self, other: &Polynomial) -> Polynomial {
+        if self.is_zero() || other.is_zero() {
+            return Polynomial::zero();
+        }
+
+        let mut coeffs = vec![Fr::new(); self.coeffs.len() + other.coeffs.len() - 1];
+        for (i, a) in self.coeffs.iter().enumerate() {
+            for (j, b) in other.coeffs.iter().enumerate() {
+                let mut term = a.clone();
+                term.mul(b);
+                coeffs[i + j].add(&term);
+            }
+        }
+        Polynomial::new(coeffs)
+    }
+
+    /// Scale
+    ///
+    /// Multiply every coefficient by `s`.
+    pub fn scale(&self, s: &Fr) -> Polynomial {
+        let coeffs = self
+            .coeffs
+            .iter()
+            .map(|c| {
+                let mut c = c.clone();
+                c.mul(s);
+                c
+            })
+            .collect();
+        Polynomial::new(coeffs)
+    }
+
+    /// Divide With Remainder
+    ///
+    /// Long division returning (quotient, remainder), fails if `divisor` is zero.
+    pub fn div_rem(&self, divisor: &Polynomial) -> Result<(Polynomial, Polynomial), AmclError> {
+        if divisor.is_zero() {
+            return Err(AmclError::InvalidPolynomialDegree);
+        }
+        if self.coeffs.len() < divisor.coeffs.len() {
+            return Ok((Polynomial::zero(), self.clone()));
+        }
+
+        let mut leading_inverse = divisor.coeffs[divisor.coeffs.len() - 1].clone();
+        leading_inverse.inverse();
+
+        let mut remainder = self.coeffs.clone();
+        let mut quotient = vec![Fr::new(); self.coeffs.len() - divisor.coeffs.len() + 1];
+
+        for i in (0..quotient.len()).rev() {
+            let mut q = remainder[i + divisor.coeffs.len() - 1].clone();
+            q.mul(&leading_inverse);
+
+            for (j, d) in divisor.coeffs.iter().enumerate() {
+                let mut term = d.clone();
+                term.mul(&q);
+                remainder[i + j].sub(&term);
+            }
+            quotient[i] = q;
+        }
+
+        remainder.truncate(divisor.coeffs.len() - 1);
+        Ok((Polynomial::new(quotient), Polynomial::new(remainder)))
+    }
+
+    // Synthetic division by (x - z), discarding the remainder.
+    fn divide_by_linear(&self, z: &Fr) -> Polynomial {
+        if self.coeffs.len() < 2 {
+            return Polynomial::zero();
+        }
+
+        let mut quotient = vec![Fr::new(); self.coeffs.len() - 1];
+        let mut carry = Fr::new();
+        for i in (1..self.coeffs.len()).rev() {
+            carry.mul(z);
+            carry.add(&self.coeffs[i]);
+            quotient[i - 1] = carry.clone();
+        }
+        Polynomial::new(quotient)
+    }
+}
+
+/*************************************************************************************************
+* Radix-2 FFT
+*************************************************************************************************/
+
+/// A multiplicative subgroup of the scalar field of power of two size.
+#[derive(Clone, Debug)]
+pub struct Domain {
+    size: usize,
+    omega: Fr,
+    omega_inverse: Fr,
+    size_inverse: Fr,
+}
+
+impl Domain {
+    /// New
+    ///
+    /// The domain of `size`-th roots of unity, `size` must be a power of two no greater than 2^32.
+    pub fn new(size: usize) -> Result<Domain, AmclError> {
+        let omega = root_of_unity(size)?;
+
+        let mut omega_inverse = omega.clone();
+        omega_inverse.inverse();
+
+        let mut size_inverse = Fr::new_int(size as isize);
+        size_inverse.inverse();
+
+        Ok(Domain {
+            size,
+            omega,
+            omega_inverse,
+            size_inverse,
+        })
+    }
+
+    /// Size
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Generator
+    ///
+    /// The primitive root of unity generating the domain.
+    pub fn generator(&self) -> &Fr {
+        &self.omega
+    }
+
+    /// Elements
+    ///
+    /// Returns [omega^0, omega^1, ..., omega^(size - 1)].
+    pub fn elements(&self) -> Vec<Fr> {
+        powers(&self.omega, self.size)
+    }
+
+    /// FFT
+    ///
+    /// Evaluates the polynomial with `coeffs` at every element of the domain.
+    /// Fails if there are more coefficients than the domain size.
+    pub fn fft(&self, coeffs: &[Fr]) -> Result<Vec<Fr>, AmclError> {
+        let mut values = self.pad(coeffs)?;
+        fft_in_place(&mut values, &self.omega);
+        Ok(values)
+    }
+
+    /// Inverse FFT
+    ///
+    /// Interpolates the coefficients of the polynomial taking `evaluations` over the domain.
+    pub fn ifft(&self, evaluations: &[Fr]) -> Result<Vec<Fr>, AmclError> {
+        if evaluations.len() != self.size {
+            return Err(AmclError::InvalidDomainSize);
+        }
+
+        let mut values = evaluations.to_vec();
+        fft_in_place(&mut values, &self.omega_inverse);
+        for value in values.iter_mut() {
+            value.mul(&self.size_inverse);
+        }
+        Ok(values)
+    }
+
+    /// Coset FFT
+    ///
+    /// Evaluates the polynomial with `coeffs` over the coset `shift * domain`.
+    pub fn coset_fft(&self, coeffs: &[Fr], shift: &Fr) -> Result<Vec<Fr>, AmclError> {
+        // p(shift * x) has coefficients coeffs[i] * shift^i
+        let mut values = self.pad(coeffs)?;
+        for (value, power) in values.iter_mut().zip(powers(shift, self.size).iter()) {
+            value.mul(power);
+        }
+        fft_in_place(&mut values, &self.omega);
+        Ok(values)
+    }
+
+    /// Coset Inverse FFT
+    ///
+    /// Interpolates the coefficients of the polynomial taking `evaluations` over the coset
+    /// `shift * domain`, `shift` must be non-zero.
+    pub fn coset_ifft(&self, evaluations: &[Fr], shift: &Fr) -> Result<Vec<Fr>, AmclError> {
+        if shift.is_zilch() {
+            return Err(AmclError::InvalidEvaluationPoints);
+        }
+
+        let mut values = self.ifft(evaluations)?;
+        let mut shift_inverse = shift.clone();
+        shift_inverse.inverse();
+        for (value, power) in values
+            .iter_mut()
+            .zip(powers(&shift_inverse, self.size).iter())
+        {
+            value.mul(power);
+        }
+        Ok(values)
+    }
+
+    /// Multiply
+    ///
+    /// Multiplies two polynomials by pointwise multiplication of their FFTs,
+    /// the product must have fewer coefficients than the domain size.
+    pub fn mul(&self, a: &Polynomial, b: &Polynomial) -> Result<Polynomial, AmclError> {
+        if a.coeffs.len() + b.coeffs.len() > self.size + 1 {
+            return Err(AmclError::InvalidDomainSize);
+        }
+
+        let mut evaluations = self.fft(&a.coeffs)?;
+        for (x, y) in evaluations.iter_mut().zip(self.fft(&b.coeffs)?.iter()) {
+            x.mul(y);
+        }
+        Ok(Polynomial::new(self.ifft(&evaluations)?))
+    }
+
+    // Zero pads coefficients to the domain size.
+    fn pad(&self, coeffs: &[Fr]) -> Result<Vec<Fr>, AmclError> {
+        if coeffs.len() > self.size {
+            return Err(AmclError::InvalidDomainSize);
+        }
+
+        let mut values = coeffs.to_vec();
+        values.resize(self.size, Fr::new());
+        Ok(values)
+    }
+}
+
+// Returns [x^0, x^1, ..., x^(n - 1)].
+fn powers(x: &Fr, n: usize) -> Vec<Fr> {
+    let mut powers = Vec::with_capacity(n);
+    let mut current = Fr::one();
+    for _ in 0..n {
+        powers.push(current.clone());
+        current.mul(x);
+    }
+    powers
+}
+
+// Iterative Cooley-Tukey FFT, `values.len()` must be a power of two and `omega` a primitive
+// root of unity of that order.
+fn fft_in_place(values: &mut [Fr], omega: &Fr) {
+    let n = values.len();
+    if n <= 1 {
+        return;
+    }
+    let log_n = n.trailing_zeros() as usize;
+
+    // Bit reversal permutation
+    for i in 0..n {
+        let j = (0..log_n).fold(0, |j, bit| (j << 1) | ((i >> bit) & 1));
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+
+    let mut half = 1;
+    while half < n {
+        // Primitive (2 * half)-th root of unity
+        let mut step = omega.clone();
+        for _ in 0..(n / (2 * half)).trailing_zeros() {
+            step.sqr();
+        }
+        let twiddles = powers(&step, half);
+
+        for start in (0..n).step_by(2 * half) {
+            for (k, twiddle) in twiddles.iter().enumerate() {
+                let mut t = values[start + k + half].clone();
+                t.mul(twiddle);
+
+                let mut u = values[start + k].clone();
+                u.sub(&t);
+                values[start + k + half] = u;
+                values[start + k].add(&t);
+            }
+        }
+        half *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::RAND;
+
+    const SIZES: [usize; 6] = [1, 2, 4, 8, 16, 64];
+
+    fn rng() -> RAND {
+        let mut rng = RAND::new();
+        rng.seed(32, &[35u8; 32]);
+        rng
+    }
+
+    fn random_coeffs(n: usize, rng: &mut RAND) -> Vec<Fr> {
+        (0..n).map(|_| Fr::random(rng)).collect()
+    }
+
+    #[test]
+    fn test_root_of_unity() {
+        for size in SIZES.iter() {
+            let omega = root_of_unity(*size).unwrap();
+            assert!(omega.pow(&Big::new_int(*size as isize)).is_unity());
+            if *size > 1 {
+                assert!(!omega.pow(&Big::new_int(*size as isize / 2)).is_unity());
+            }
+        }
+        assert!(root_of_unity(3).is_err());
+        assert!(root_of_unity(0).is_err());
+    }
+
+    fn horner(coeffs: &[Fr], points: &[Fr]) -> Vec<Fr> {
+        let polynomial = Polynomial::new(coeffs.to_vec());
+        points.iter().map(|x| polynomial.evaluate(x)).collect()
+    }
+
+    #[test]
+    fn test_fft_matches_horner() {
+        let mut rng = rng();
+        for size in SIZES.iter() {
+            let domain = Domain::new(*size).unwrap();
+            // Full size and fewer coefficients than the domain
+            for n in [*size, (*size + 1) / 2].iter() {
+                let coeffs = random_coeffs(*n, &mut rng);
+                assert_eq!(
+                    domain.fft(&coeffs).unwrap(),
+                    horner(&coeffs, &domain.elements())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluate_many_matches_horner() {
+        let mut rng = rng();
+        // Below, at and above the threshold, with uneven splits
+        for count in [0, 1, MULTIPOINT_THRESHOLD, MULTIPOINT_THRESHOLD + 1, 23, 64].iter() {
+            let points = random_coeffs(*count, &mut rng);
+            for degree in [0, 5, 40].iter() {
+                let coeffs = random_coeffs(*degree + 1, &mut rng);
+                assert_eq!(
+                    Polynomial::new(coeffs.clone()).evaluate_many(&points),
+                    horner(&coeffs, &points)
+                );
+            }
+        }
+
+        // The zero polynomial and repeated points
+        let points = vec![Fr::new_int(3); 20];
+        assert_eq!(
+            Polynomial::new(vec![]).evaluate_many(&points),
+            vec![Fr::new(); 20]
+        );
+        let coeffs = random_coeffs(12, &mut rng);
+        assert_eq!(
+            Polynomial::new(coeffs.clone()).evaluate_many(&points),
+            horner(&coeffs, &points)
+        );
+    }
+
+    #[test]
+    fn test_ifft_inverts_fft() {
+        let mut rng = rng();
+        for size in SIZES.iter() {
+            let domain = Domain::new(*size).unwrap();
+            let coeffs = random_coeffs(*size, &mut rng);
+            assert_eq!(domain.ifft(&domain.fft(&coeffs).unwrap()).unwrap(), coeffs);
+
+            let evaluations = random_coeffs(*size, &mut rng);
+            assert_eq!(
+                domain.fft(&domain.ifft(&evaluations).unwrap()).unwrap(),
+                evaluations
+            );
+        }
+    }
+
+    #[test]
+    fn test_coset_fft_matches_horner() {
+        let mut rng = rng();
+        let shift = Fr::new_int(GENERATOR);
+        for size in SIZES.iter() {
+            let domain = Domain::new(*size).unwrap();
+            let coeffs = random_coeffs(*size, &mut rng);
+            let coset: Vec<Fr> = domain
+                .elements()
+                .iter()
+                .map(|x| {
+                    let mut x = x.clone();
+                    x.mul(&shift);
+                    x
+                })
+                .collect();
+            let evaluations = domain.coset_fft(&coeffs, &shift).unwrap();
+            assert_eq!(evaluations, horner(&coeffs, &coset));
+            assert_eq!(domain.coset_ifft(&evaluations, &shift).unwrap(), coeffs);
+        }
+    }
+
+    #[test]
+    fn test_domain_mul_matches_mul() {
+        let mut rng = rng();
+        let domain = Domain::new(16).unwrap();
+        let a = Polynomial::new(random_coeffs(7, &mut rng));
+        let b = Polynomial::new(random_coeffs(10, &mut rng));
+        assert_eq!(domain.mul(&a, &b).unwrap(), a.mul(&b));
+
+        // The product needs 17 coefficients
+        let c = Polynomial::new(random_coeffs(11, &mut rng));
+        assert!(domain.mul(&a, &c).is_err());
+    }
+
+    #[test]
+    fn test_fft_invalid_sizes() {
+        let mut rng = rng();
+        let domain = Domain::new(8).unwrap();
+        assert!(domain.fft(&random_coeffs(9, &mut rng)).is_err());
+        assert!(domain.ifft(&random_coeffs(7, &mut rng)).is_err());
+        assert!(domain.ifft(&random_coeffs(9, &mut rng)).is_err());
+        assert!(domain
+            .coset_ifft(&random_coeffs(8, &mut rng), &Fr::new())
+            .is_err());
+        assert!(Domain::new(12).is_err());
+    }
+}
@@ -0,0 +1,821 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::big::Big;
+use super::bls381::utils::{serialize_g1, serialize_g2, subgroup_check_g1, subgroup_check_g2};
+use super::ecp::ECP;
+use super::ecp2::ECP2;
+use super::hash_to_curve::expand_message_xmd;
+use super::kzg::{
+    g1_linear_combination, pairing_product_is_unity, scalar_add, scalar_mul, scalar_sub,
+};
+use super::pair;
+use super::rom::CURVE_ORDER;
+use crate::errors::AmclError;
+use crate::rand::RAND;
+use std::vec::Vec;
+
+/// Domain Separation Tag for Schnorr proof challenges
+pub const DST_CHALLENGE: &[u8] = b"PS_BLS12381_XMD:SHA-256_SCHNORR_CHALLENGE_";
+
+// Number of uniform bytes reduced to a challenge scalar
+const CHALLENGE_BYTES: usize = 48;
+
+/// Pointcheval-Sanders secret key (x, y_1, ..., y_n)
+#[derive(Clone)]
+pub struct SecretKey {
+    pub x: Big,
+    pub y: Vec<Big>,
+}
+
+/// Pointcheval-Sanders public key
+///
+/// X = [x]G2, Y_i = [y_i]G2 and, for blind issuance, Y1_i = [y_i]G1.
+#[derive(Clone)]
+pub struct PublicKey {
+    pub x_g2: ECP2,
+    pub y_g2: Vec<ECP2>,
+    pub y_g1: Vec<ECP>,
+}
+
+/// Pointcheval-Sanders signature (sigma_1, sigma_2) on G1
+#[derive(Clone)]
+pub struct Signature {
+    pub sigma_1: ECP,
+    pub sigma_2: ECP,
+}
+
+/// Non-interactive Schnorr proof of knowledge of a representation
+#[derive(Clone)]
+pub struct SchnorrProof {
+    pub challenge: Big,
+    pub responses: Vec<Big>,
+}
+
+/// Request for a blind signature
+///
+/// The commitment C = [t]G1 + sum([m_j]Y1_j) over the hidden attributes, with a proof of
+/// knowledge of its opening.
+#[derive(Clone)]
+pub struct BlindSignRequest {
+    pub commitment: ECP,
+    pub hidden_indexes: Vec<usize>,
+    pub proof: SchnorrProof,
+}
+
+/// Credential presentation
+///
+/// A randomized signature, kappa = [t]G2 + sum([m_j]Y_j) over the hidden attributes and a proof
+/// of knowledge of its opening.
+#[derive(Clone)]
+pub struct Presentation {
+    pub signature: Signature,
+    pub kappa: ECP2,
+    pub proof: SchnorrProof,
+}
+
+// Random non-zero scalar mod r.
+fn random_scalar(rng: &mut RAND) -> Big {
+    let r = Big::new_ints(&CURVE_ORDER);
+    loop {
+        let scalar = Big::randomnum(&r, rng);
+        if !scalar.is_zilch() {
+            return scalar;
+        }
+    }
+}
+
+// Challenge = OS2IP(expand_message_xmd(transcript, 48, DST_CHALLENGE)) mod r
+fn hash_challenge(transcript: &[u8]) -> Result<Big, AmclError> {
+    let uniform_bytes = expand_message_xmd(transcript, CHALLENGE_BYTES, DST_CHALLENGE)?;
+    let mut challenge = Big::from_bytes(&uniform_bytes);
+    challenge.rmod(&Big::new_ints(&CURVE_ORDER));
+    Ok(challenge)
+}
+
+fn g2_linear_combination(points: &[ECP2], scalars: &[Big]) -> ECP2 {
+    let mut result = ECP2::new();
+    for (point, scalar) in points.iter().zip(scalars.iter()) {
+        if scalar.is_zilch() {
+            continue;
+        }
+        result.add(&pair::g2mul(point, scalar));
+    }
+    result
+}
+
+// Check indexes are strictly increasing and less than `n`.
+fn check_indexes(indexes: &[usize], n: usize) -> Result<(), AmclError> {
+    for (i, index) in indexes.iter().enumerate() {
+        if *index >= n || (i > 0 && indexes[i - 1] >= *index) {
+            return Err(AmclError::InvalidDisclosedIndexes);
+        }
+    }
+    Ok(())
+}
+
+/*************************************************************************************************
+* Schnorr proofs of knowledge of a representation in G1 and G2
+*
+* Prove knowledge of w such that P = sum([w_i]B_i).
+*************************************************************************************************/
+
+fn schnorr_prove_g1(
+    bases: &[ECP],
+    witnesses: &[Big],
+    statement: &ECP,
+    context: &[u8],
+    rng: &mut RAND,
+) -> Result<SchnorrProof, AmclError> {
+    let nonces: Vec<Big> = (0..witnesses.len()).map(|_| random_scalar(rng)).collect();
+    let commitment = g1_linear_combination(bases, &nonces);
+
+    let mut transcript = context.to_vec();
+    for base in bases.iter() {
+        transcript.extend_from_slice(&serialize_g1(base));
+    }
+    transcript.extend_from_slice(&serialize_g1(statement));
+    transcript.extend_from_slice(&serialize_g1(&commitment));
+    let challenge = hash_challenge(&transcript)?;
+
+    // s_i = k_i - c * w_i
+    let responses = nonces
+        .iter()
+        .zip(witnesses.iter())
+        .map(|(k, w)| scalar_sub(k, &scalar_mul(&challenge, w)))
+        .collect();
+
+    Ok(SchnorrProof {
+        challenge,
+        responses,
+    })
+}
+
+fn schnorr_verify_g1(bases: &[ECP], statement: &ECP, proof: &SchnorrProof, context: &[u8]) -> bool {
+    if proof.responses.len() != bases.len() {
+        return false;
+    }
+
+    // R = [c]P + sum([s_i]B_i)
+    let mut commitment = g1_linear_combination(bases, &proof.responses);
+    commitment.add(&pair::g1mul(statement, &proof.challenge));
+
+    let mut transcript = context.to_vec();
+    for base in bases.iter() {
+        transcript.extend_from_slice(&serialize_g1(base));
+    }
+    transcript.extend_from_slice(&serialize_g1(statement));
+    transcript.extend_from_slice(&serialize_g1(&commitment));
+    match hash_challenge(&transcript) {
+        Ok(challenge) => challenge == proof.challenge,
+        Err(_) => false,
+    }
+}
+
+fn schnorr_prove_g2(
+    bases: &[ECP2],
+    witnesses: &[Big],
+    statement: &ECP2,
+    context: &[u8],
+    rng: &mut RAND,
+) -> Result<SchnorrProof, AmclError> {
+    let nonces: Vec<Big> = (0..witnesses.len()).map(|_| random_scalar(rng)).collect();
+    let commitment = g2_linear_combination(bases, &nonces);
+
+    let mut transcript = context.to_vec();
+    for base in bases.iter() {
+        transcript.extend_from_slice(&serialize_g2(base));
+    }
+    transcript.extend_from_slice(&serialize_g2(statement));
+    transcript.extend_from_slice(&serialize_g2(&commitment));
+    let challenge = hash_challenge(&transcript)?;
+
+    // s_i = k_i - c * w_i
+    let responses = nonces
+        .iter()
+        .zip(witnesses.iter())
+        .map(|(k, w)| scalar_sub(k, &scalar_mul(&challenge, w)))
+        .collect();
+
+    Ok(SchnorrProof {
+        challenge,
+        responses,
+    })
+}
+
+fn schnorr_verify_g2(
+    bases: &[ECP2],
+    statement: &ECP2,
+    proof: &SchnorrProof,
+    context: &[u8],
+) -> bool {
+    if proof.responses.len() != bases.len() {
+        return false;
+    }
+
+    // R = [c]P + sum([s_i]B_i)
+    let mut commitment = g2_linear_combination(bases, &proof.responses);
+    commitment.add(&pair::g2mul(statement, &proof.challenge));
+
+    let mut transcript = context.to_vec();
+    for base in bases.iter() {
+        transcript.extend_from_slice(&serialize_g2(base));
+    }
+    transcript.extend_from_slice(&serialize_g2(statement));
+    transcript.extend_from_slice(&serialize_g2(&commitment));
+    match hash_challenge(&transcript) {
+        Ok(challenge) => challenge == proof.challenge,
+        Err(_) => false,
+    }
+}
+
+/*************************************************************************************************
+* Signatures
+*
+* Pointcheval and Sanders, "Short Randomizable Signatures", CT-RSA 2016
+*************************************************************************************************/
+
+/// Generate a key pair for signing `attribute_count` attributes.
+pub fn key_generate(attribute_count: usize, rng: &mut RAND) -> (SecretKey, PublicKey) {
+    let x = random_scalar(rng);
+    let y: Vec<Big> = (0..attribute_count).map(|_| random_scalar(rng)).collect();
+
+    let g1 = ECP::generator();
+    let g2 = ECP2::generator();
+    let public_key = PublicKey {
+        x_g2: pair::g2mul(&g2, &x),
+        y_g2: y.iter().map(|y_i| pair::g2mul(&g2, y_i)).collect(),
+        y_g1: y.iter().map(|y_i| pair::g1mul(&g1, y_i)).collect(),
+    };
+
+    (SecretKey { x, y }, public_key)
+}
+
+/// Sign
+///
+/// sigma = (h, [x + sum(y_i * m_i)]h) for a random h in G1.
+pub fn sign(
+    secret_key: &SecretKey,
+    messages: &[Big],
+    rng: &mut RAND,
+) -> Result<Signature, AmclError> {
+    if messages.len() != secret_key.y.len() {
+        return Err(AmclError::InvalidMessageCount);
+    }
+
+    let h = pair::g1mul(&ECP::generator(), &random_scalar(rng));
+    let mut exponent = secret_key.x.clone();
+    for (y_i, m_i) in secret_key.y.iter().zip(messages.iter()) {
+        exponent = scalar_add(&exponent, &scalar_mul(y_i, m_i));
+    }
+
+    Ok(Signature {
+        sigma_2: pair::g1mul(&h, &exponent),
+        sigma_1: h,
+    })
+}
+
+/// Verify
+///
+/// Checks sigma_1 != 1 and e(sigma_1, X + sum([m_i]Y_i)) * e(sigma_2, -G2) == 1.
+pub fn verify(public_key: &PublicKey, messages: &[Big], signature: &Signature) -> bool {
+    if messages.len() != public_key.y_g2.len() {
+        return false;
+    }
+    if !signature.is_valid() {
+        return false;
+    }
+
+    let mut x = g2_linear_combination(&public_key.y_g2, messages);
+    x.add(&public_key.x_g2);
+
+    let mut g2 = ECP2::generator();
+    g2.neg();
+
+    pairing_product_is_unity(&[(&x, &signature.sigma_1), (&g2, &signature.sigma_2)])
+}
+
+impl Signature {
+    // sigma_1 is not the identity and both elements are in G1.
+    fn is_valid(&self) -> bool {
+        !self.sigma_1.is_infinity()
+            && subgroup_check_g1(&self.sigma_1)
+            && subgroup_check_g1(&self.sigma_2)
+    }
+
+    /// Randomize
+    ///
+    /// Returns ([r]sigma_1, [r]sigma_2), an unlinkable signature on the same messages.
+    pub fn randomize(&self, rng: &mut RAND) -> Signature {
+        let r = random_scalar(rng);
+        Signature {
+            sigma_1: pair::g1mul(&self.sigma_1, &r),
+            sigma_2: pair::g1mul(&self.sigma_2, &r),
+        }
+    }
+}
+
+/*************************************************************************************************
+* Blind issuance
+*
+* The user commits to the hidden attributes, the signer signs the commitment together with the
+* disclosed attributes and the user unblinds the result.
+*************************************************************************************************/
+
+/// Blind Sign Request
+///
+/// Returns the request and the blinding factor t needed to unblind the signature.
+pub fn blind_sign_request(
+    public_key: &PublicKey,
+    messages: &[Big],
+    hidden_indexes: &[usize],
+    context: &[u8],
+    rng: &mut RAND,
+) -> Result<(BlindSignRequest, Big), AmclError> {
+    if messages.len() != public_key.y_g1.len() {
+        return Err(AmclError::InvalidMessageCount);
+    }
+    check_indexes(hidden_indexes, messages.len())?;
+
+    // C = [t]G1 + sum([m_j]Y1_j)
+    let t = random_scalar(rng);
+    let mut bases = vec![ECP::generator()];
+    let mut witnesses = vec![t.clone()];
+    for j in hidden_indexes.iter() {
+        bases.push(public_key.y_g1[*j].clone());
+        witnesses.push(messages[*j].clone());
+    }
+    let commitment = g1_linear_combination(&bases, &witnesses);
+    let proof = schnorr_prove_g1(&bases, &witnesses, &commitment, context, rng)?;
+
+    let request = BlindSignRequest {
+        commitment,
+        hidden_indexes: hidden_indexes.to_vec(),
+        proof,
+    };
+    Ok((request, t))
+}
+
+/// Blind Sign
+///
+/// Verify the request and return ([u]G1, [u](X1 + C + sum([m_i]Y1_i))) where X1 = [x]G1 and the
+/// sum is over the disclosed attributes, given as (index, attribute) in increasing index order.
+/// The hidden and disclosed indexes together must be exactly 0..n.
+pub fn blind_sign(
+    secret_key: &SecretKey,
+    public_key: &PublicKey,
+    request: &BlindSignRequest,
+    disclosed: &[(usize, Big)],
+    context: &[u8],
+    rng: &mut RAND,
+) -> Result<Signature, AmclError> {
+    let n = public_key.y_g1.len();
+    check_indexes(&request.hidden_indexes, n)?;
+    let disclosed_indexes: Vec<usize> = disclosed.iter().map(|(i, _)| *i).collect();
+    check_indexes(&disclosed_indexes, n)?;
+    if request.hidden_indexes.len() + disclosed.len() != n {
+        return Err(AmclError::InvalidMessageCount);
+    }
+    // Both are strictly increasing so disjoint sets of n indexes below n cover 0..n
+    if disclosed_indexes
+        .iter()
+        .any(|i| request.hidden_indexes.contains(i))
+    {
+        return Err(AmclError::InvalidDisclosedIndexes);
+    }
+
+    if !subgroup_check_g1(&request.commitment) {
+        return Err(AmclError::InvalidPoint);
+    }
+    let mut bases = vec![ECP::generator()];
+    for j in request.hidden_indexes.iter() {
+        bases.push(public_key.y_g1[*j].clone());
+    }
+    if !schnorr_verify_g1(&bases, &request.commitment, &request.proof, context) {
+        return Err(AmclError::InvalidProof);
+    }
+
+    let g1 = ECP::generator();
+    let mut exponent = secret_key.x.clone();
+    for (i, m_i) in disclosed.iter() {
+        exponent = scalar_add(&exponent, &scalar_mul(&secret_key.y[*i], m_i));
+    }
+    let mut base = pair::g1mul(&g1, &exponent);
+    base.add(&request.commitment);
+
+    let u = random_scalar(rng);
+    Ok(Signature {
+        sigma_1: pair::g1mul(&g1, &u),
+        sigma_2: pair::g1mul(&base, &u),
+    })
+}
+
+/// Unblind
+///
+/// Returns (sigma_1, sigma_2 - [t]sigma_1).
+pub fn unblind(blinded_signature: &Signature, t: &Big) -> Signature {
+    let mut sigma_2 = pair::g1mul(&blinded_signature.sigma_1, t);
+    sigma_2.neg();
+    sigma_2.add(&blinded_signature.sigma_2);
+
+    Signature {
+        sigma_1: blinded_signature.sigma_1.clone(),
+        sigma_2,
+    }
+}
+
+/*************************************************************************************************
+* Show and verify
+*
+* Present a randomized signature disclosing some attributes and proving knowledge of the rest.
+*************************************************************************************************/
+
+/// Show
+///
+/// Randomize the signature with (r, t), sigma' = ([r]sigma_1, [r](sigma_2 + [t]sigma_1)),
+/// and prove knowledge of (t, m_j) for kappa = [t]G2 + sum([m_j]Y_j) over the hidden attributes.
+pub fn show(
+    public_key: &PublicKey,
+    signature: &Signature,
+    messages: &[Big],
+    disclosed_indexes: &[usize],
+    context: &[u8],
+    rng: &mut RAND,
+) -> Result<Presentation, AmclError> {
+    if messages.len() != public_key.y_g2.len() {
+        return Err(AmclError::InvalidMessageCount);
+    }
+    check_indexes(disclosed_indexes, messages.len())?;
+
+    let r = random_scalar(rng);
+    let t = random_scalar(rng);
+    let mut sigma_2 = pair::g1mul(&signature.sigma_1, &t);
+    sigma_2.add(&signature.sigma_2);
+    let randomized = Signature {
+        sigma_1: pair::g1mul(&signature.sigma_1, &r),
+        sigma_2: pair::g1mul(&sigma_2, &r),
+    };
+
+    let mut bases = vec![ECP2::generator()];
+    let mut witnesses = vec![t];
+    for (j, m_j) in messages.iter().enumerate() {
+        if !disclosed_indexes.contains(&j) {
+            bases.push(public_key.y_g2[j].clone());
+            witnesses.push(m_j.clone());
+        }
+    }
+    let kappa = g2_linear_combination(&bases, &witnesses);
+
+    let mut proof_context = context.to_vec();
+    proof_context.extend_from_slice(&serialize_g1(&randomized.sigma_1));
+    proof_context.extend_from_slice(&serialize_g1(&randomized.sigma_2));
+    let proof = schnorr_prove_g2(&bases, &witnesses, &kappa, &proof_context, rng)?;
+
+    Ok(Presentation {
+        signature: randomized,
+        kappa,
+        proof,
+    })
+}
+
+/// Verify Presentation
+///
+/// Verify the proof for kappa and e(sigma_1, X + kappa + sum([m_i]Y_i)) * e(sigma_2, -G2) == 1
+/// over the disclosed attributes, given as (index, attribute).
+pub fn verify_presentation(
+    public_key: &PublicKey,
+    presentation: &Presentation,
+    disclosed: &[(usize, Big)],
+    context: &[u8],
+) -> bool {
+    let n = public_key.y_g2.len();
+    let disclosed_indexes: Vec<usize> = disclosed.iter().map(|(i, _)| *i).collect();
+    if check_indexes(&disclosed_indexes, n).is_err() {
+        return false;
+    }
+    let signature = &presentation.signature;
+    if !signature.is_valid() || !subgroup_check_g2(&presentation.kappa) {
+        return false;
+    }
+
+    let mut bases = vec![ECP2::generator()];
+    for j in 0..n {
+        if !disclosed_indexes.contains(&j) {
+            bases.push(public_key.y_g2[j].clone());
+        }
+    }
+    let mut proof_context = context.to_vec();
+    proof_context.extend_from_slice(&serialize_g1(&signature.sigma_1));
+    proof_context.extend_from_slice(&serialize_g1(&signature.sigma_2));
+    if !schnorr_verify_g2(
+        &bases,
+        &presentation.kappa,
+        &presentation.proof,
+        &proof_context,
+    ) {
+        return false;
+    }
+
+    let disclosed_keys: Vec<ECP2> = disclosed_indexes
+        .iter()
+        .map(|i| public_key.y_g2[*i].clone())
+        .collect();
+    let disclosed_messages: Vec<Big> = disclosed.iter().map(|(_, m)| m.clone()).collect();
+    let mut x = g2_linear_combination(&disclosed_keys, &disclosed_messages);
+    x.add(&public_key.x_g2);
+    x.add(&presentation.kappa);
+
+    let mut g2 = ECP2::generator();
+    g2.neg();
+
+    pairing_product_is_unity(&[(&x, &signature.sigma_1), (&g2, &signature.sigma_2)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTEXT: &[u8] = b"ps tests";
+
+    fn setup(n: usize) -> (SecretKey, PublicKey, Vec<Big>, RAND) {
+        let mut rng = RAND::new();
+        rng.seed(32, &[33u8; 32]);
+        let (secret_key, public_key) = key_generate(n, &mut rng);
+        let messages = (0..n).map(|_| random_scalar(&mut rng)).collect();
+        (secret_key, public_key, messages, rng)
+    }
+
+    fn disclose(messages: &[Big], indexes: &[usize]) -> Vec<(usize, Big)> {
+        indexes.iter().map(|i| (*i, messages[*i].clone())).collect()
+    }
+
+    #[test]
+    fn test_sign_verify() {
+        let (secret_key, public_key, messages, mut rng) = setup(3);
+        let signature = sign(&secret_key, &messages, &mut rng).unwrap();
+        assert!(verify(&public_key, &messages, &signature));
+
+        let mut modified = messages.clone();
+        modified[1] = scalar_add(&modified[1], &Big::new_int(1));
+        assert!(!verify(&public_key, &modified, &signature));
+        assert!(!verify(&public_key, &messages[..2], &signature));
+    }
+
+    #[test]
+    fn test_randomize() {
+        let (secret_key, public_key, messages, mut rng) = setup(3);
+        let signature = sign(&secret_key, &messages, &mut rng).unwrap();
+        let randomized = signature.randomize(&mut rng);
+        assert!(verify(&public_key, &messages, &randomized));
+
+        // Neither element of the randomized signature matches the original
+        assert_ne!(
+            serialize_g1(&randomized.sigma_1),
+            serialize_g1(&signature.sigma_1)
+        );
+        assert_ne!(
+            serialize_g1(&randomized.sigma_2),
+            serialize_g1(&signature.sigma_2)
+        );
+    }
+
+    #[test]
+    fn test_show_verify() {
+        let (secret_key, public_key, messages, mut rng) = setup(4);
+        let signature = sign(&secret_key, &messages, &mut rng).unwrap();
+        let disclosures: [&[usize]; 4] = [&[], &[0], &[1, 3], &[0, 1, 2, 3]];
+        for indexes in disclosures.iter() {
+            let presentation = show(
+                &public_key,
+                &signature,
+                &messages,
+                indexes,
+                CONTEXT,
+                &mut rng,
+            )
+            .unwrap();
+            let disclosed = disclose(&messages, indexes);
+            assert!(verify_presentation(
+                &public_key,
+                &presentation,
+                &disclosed,
+                CONTEXT
+            ));
+            assert_eq!(presentation.proof.responses.len(), 1 + 4 - indexes.len());
+        }
+    }
+
+    #[test]
+    fn test_presentations_are_unlinkable() {
+        let (secret_key, public_key, messages, mut rng) = setup(2);
+        let signature = sign(&secret_key, &messages, &mut rng).unwrap();
+        let first = show(&public_key, &signature, &messages, &[0], CONTEXT, &mut rng).unwrap();
+        let second = show(&public_key, &signature, &messages, &[0], CONTEXT, &mut rng).unwrap();
+
+        let sigma_1s = [
+            &signature.sigma_1,
+            &first.signature.sigma_1,
+            &second.signature.sigma_1,
+        ];
+        let sigma_2s = [
+            &signature.sigma_2,
+            &first.signature.sigma_2,
+            &second.signature.sigma_2,
+        ];
+        for points in [sigma_1s, sigma_2s].iter() {
+            assert_ne!(serialize_g1(points[0]), serialize_g1(points[1]));
+            assert_ne!(serialize_g1(points[0]), serialize_g1(points[2]));
+            assert_ne!(serialize_g1(points[1]), serialize_g1(points[2]));
+        }
+        assert_ne!(serialize_g2(&first.kappa), serialize_g2(&second.kappa));
+    }
+
+    #[test]
+    fn test_verify_presentation_rejects() {
+        let (secret_key, public_key, messages, mut rng) = setup(3);
+        let signature = sign(&secret_key, &messages, &mut rng).unwrap();
+        let presentation = show(
+            &public_key,
+            &signature,
+            &messages,
+            &[0, 2],
+            CONTEXT,
+            &mut rng,
+        )
+        .unwrap();
+        let disclosed = disclose(&messages, &[0, 2]);
+        assert!(verify_presentation(
+            &public_key,
+            &presentation,
+            &disclosed,
+            CONTEXT
+        ));
+
+        // Wrong disclosed value
+        let mut wrong = disclosed.clone();
+        wrong[1].1 = scalar_add(&wrong[1].1, &Big::new_int(1));
+        assert!(!verify_presentation(
+            &public_key,
+            &presentation,
+            &wrong,
+            CONTEXT
+        ));
+
+        // Hidden attribute claimed as disclosed and a disclosed one dropped
+        let disclosed_all = disclose(&messages, &[0, 1, 2]);
+        assert!(!verify_presentation(
+            &public_key,
+            &presentation,
+            &disclosed_all,
+            CONTEXT
+        ));
+        assert!(!verify_presentation(
+            &public_key,
+            &presentation,
+            &disclosed[..1],
+            CONTEXT
+        ));
+
+        // Tampered Schnorr response and challenge
+        for i in 0..presentation.proof.responses.len() {
+            let mut tampered = presentation.clone();
+            tampered.proof.responses[i] =
+                scalar_add(&tampered.proof.responses[i], &Big::new_int(1));
+            assert!(!verify_presentation(
+                &public_key,
+                &tampered,
+                &disclosed,
+                CONTEXT
+            ));
+        }
+        let mut tampered = presentation.clone();
+        tampered.proof.challenge = scalar_add(&tampered.proof.challenge, &Big::new_int(1));
+        assert!(!verify_presentation(
+            &public_key,
+            &tampered,
+            &disclosed,
+            CONTEXT
+        ));
+
+        // Replay under a different context, which carries the verifier's nonce
+        assert!(!verify_presentation(
+            &public_key,
+            &presentation,
+            &disclosed,
+            b"ps tests, other nonce"
+        ));
+
+        // The proof is bound to the randomized signature it was shown with
+        let mut swapped = presentation.clone();
+        swapped.signature = presentation.signature.randomize(&mut rng);
+        assert!(!verify_presentation(
+            &public_key,
+            &swapped,
+            &disclosed,
+            CONTEXT
+        ));
+    }
+
+    #[test]
+    fn test_identity_sigma_1_rejected() {
+        let (_, public_key, messages, mut rng) = setup(2);
+
+        // (1, 1) satisfies the pairing equation for any attributes
+        let identity = Signature {
+            sigma_1: ECP::new(),
+            sigma_2: ECP::new(),
+        };
+        assert!(!verify(&public_key, &messages, &identity));
+
+        let presentation =
+            show(&public_key, &identity, &messages, &[0], CONTEXT, &mut rng).unwrap();
+        assert!(presentation.signature.sigma_1.is_infinity());
+        assert!(!verify_presentation(
+            &public_key,
+            &presentation,
+            &disclose(&messages, &[0]),
+            CONTEXT
+        ));
+    }
+
+    #[test]
+    fn test_blind_sign() {
+        let (secret_key, public_key, messages, mut rng) = setup(3);
+        let (request, t) =
+            blind_sign_request(&public_key, &messages, &[0], CONTEXT, &mut rng).unwrap();
+        let disclosed = [(1, messages[1].clone()), (2, messages[2].clone())];
+        let blinded = blind_sign(
+            &secret_key,
+            &public_key,
+            &request,
+            &disclosed,
+            CONTEXT,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(verify(&public_key, &messages, &unblind(&blinded, &t)));
+    }
+
+    #[test]
+    fn test_blind_sign_rejects_duplicated_disclosed_index() {
+        let (secret_key, public_key, messages, mut rng) = setup(3);
+        let (request, _) =
+            blind_sign_request(&public_key, &messages, &[0], CONTEXT, &mut rng).unwrap();
+
+        // Index 1 twice and index 2 never, the count still adds up to n
+        let disclosed = [(1, messages[1].clone()), (1, messages[1].clone())];
+        assert_eq!(
+            blind_sign(
+                &secret_key,
+                &public_key,
+                &request,
+                &disclosed,
+                CONTEXT,
+                &mut rng
+            )
+            .err(),
+            Some(AmclError::InvalidDisclosedIndexes)
+        );
+    }
+
+    #[test]
+    fn test_blind_sign_rejects_invalid_disclosed_indexes() {
+        let (secret_key, public_key, messages, mut rng) = setup(3);
+        let (request, _) =
+            blind_sign_request(&public_key, &messages, &[1], CONTEXT, &mut rng).unwrap();
+
+        let invalid: [&[usize]; 4] = [&[2, 0], &[0, 3], &[0, 1], &[0]];
+        for indexes in invalid.iter() {
+            let disclosed: Vec<(usize, Big)> = indexes
+                .iter()
+                .map(|i| (*i, messages[*i % 3].clone()))
+                .collect();
+            assert!(blind_sign(
+                &secret_key,
+                &public_key,
+                &request,
+                &disclosed,
+                CONTEXT,
+                &mut rng
+            )
+            .is_err());
+        }
+    }
+}
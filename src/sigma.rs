@@ -0,0 +1,753 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::bls381::utils::{subgroup_check_g1, subgroup_check_g2};
+use super::ecp::ECP;
+use super::ecp2::ECP2;
+use super::fr::{Fr, FR_BYTES};
+use super::pair;
+use super::transcript::Transcript;
+use crate::errors::AmclError;
+use crate::rand::RAND;
+use std::vec::Vec;
+
+/*************************************************************************************************
+* Sigma protocols made non-interactive with the Fiat-Shamir transform
+*
+* Proofs are held in compact (challenge, response) form, commitments are recomputed by the
+* verifier and absorbed into a `Transcript` along with the compressed statement points.
+*************************************************************************************************/
+
+/// Transcript domain of every challenge
+pub const SIGMA_DOMAIN: &[u8] = b"BLS12381_SIGMA_V1_";
+
+// Protocol labels hashed into the challenge
+const SCHNORR_LABEL: &[u8] = b"SCHNORR";
+const DLEQ_LABEL: &[u8] = b"DLEQ";
+const OR_LABEL: &[u8] = b"OR";
+
+/// Proof of knowledge of x such that public = x * base.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchnorrProof {
+    pub challenge: Fr,
+    pub response: Fr,
+}
+
+/// Proof that public_1 = x * base_1 and public_2 = x * base_2 for the same x.
+///
+/// The bases may be in the same group or one in G1 and the other in G2.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DleqProof {
+    pub challenge: Fr,
+    pub response: Fr,
+}
+
+/// Proof of knowledge of x such that publics[i] = x * base for one undisclosed i.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrProof {
+    pub challenges: Vec<Fr>,
+    pub responses: Vec<Fr>,
+}
+
+impl SchnorrProof {
+    /// Serialize as challenge || response, 32 bytes each.
+    pub fn to_bytes(&self) -> [u8; 2 * FR_BYTES] {
+        scalar_pair_to_bytes(&self.challenge, &self.response)
+    }
+
+    /// Deserialize from challenge || response.
+    pub fn from_bytes(bytes: &[u8]) -> Result<SchnorrProof, AmclError> {
+        let (challenge, response) = scalar_pair_from_bytes(bytes)?;
+        Ok(SchnorrProof {
+            challenge,
+            response,
+        })
+    }
+}
+
+impl DleqProof {
+    /// Serialize as challenge || response, 32 bytes each.
+    pub fn to_bytes(&self) -> [u8; 2 * FR_BYTES] {
+        scalar_pair_to_bytes(&self.challenge, &self.response)
+    }
+
+    /// Deserialize from challenge || response.
+    pub fn from_bytes(bytes: &[u8]) -> Result<DleqProof, AmclError> {
+        let (challenge, response) = scalar_pair_from_bytes(bytes)?;
+        Ok(DleqProof {
+            challenge,
+            response,
+        })
+    }
+}
+
+impl OrProof {
+    /// Serialize as challenge_i || response_i for each branch, 32 bytes each.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(2 * FR_BYTES * self.challenges.len());
+        for (challenge, response) in self.challenges.iter().zip(self.responses.iter()) {
+            bytes.extend_from_slice(&scalar_pair_to_bytes(challenge, response));
+        }
+        bytes
+    }
+
+    /// Deserialize from challenge_i || response_i for each branch.
+    pub fn from_bytes(bytes: &[u8]) -> Result<OrProof, AmclError> {
+        if bytes.is_empty() || bytes.len() % (2 * FR_BYTES) != 0 {
+            return Err(AmclError::InvalidProof);
+        }
+
+        let mut challenges = Vec::with_capacity(bytes.len() / (2 * FR_BYTES));
+        let mut responses = Vec::with_capacity(bytes.len() / (2 * FR_BYTES));
+        for chunk in bytes.chunks(2 * FR_BYTES) {
+            let (challenge, response) = scalar_pair_from_bytes(chunk)?;
+            challenges.push(challenge);
+            responses.push(response);
+        }
+        Ok(OrProof {
+            challenges,
+            responses,
+        })
+    }
+}
+
+/*************************************************************************************************
+* Schnorr proof of knowledge
+*************************************************************************************************/
+
+/// Prove knowledge of `secret` with public = secret * base in G1.
+pub fn schnorr_prove_g1(context: &[u8], base: &ECP, secret: &Fr, rng: &mut RAND) -> SchnorrProof {
+    schnorr_prove(context, base, secret, rng)
+}
+
+/// Verify a Schnorr proof in G1.
+pub fn schnorr_verify_g1(context: &[u8], base: &ECP, public: &ECP, proof: &SchnorrProof) -> bool {
+    schnorr_verify(context, base, public, proof)
+}
+
+/// Prove knowledge of `secret` with public = secret * base in G2.
+pub fn schnorr_prove_g2(context: &[u8], base: &ECP2, secret: &Fr, rng: &mut RAND) -> SchnorrProof {
+    schnorr_prove(context, base, secret, rng)
+}
+
+/// Verify a Schnorr proof in G2.
+pub fn schnorr_verify_g2(context: &[u8], base: &ECP2, public: &ECP2, proof: &SchnorrProof) -> bool {
+    schnorr_verify(context, base, public, proof)
+}
+
+fn schnorr_prove<P: Point>(context: &[u8], base: &P, secret: &Fr, rng: &mut RAND) -> SchnorrProof {
+    let public = base.scalar_mul(secret);
+    let nonce = random_nonzero(rng);
+    let commitment = base.scalar_mul(&nonce);
+
+    let mut transcript = sigma_transcript(SCHNORR_LABEL, context);
+    base.append_to(&mut transcript, b"base");
+    public.append_to(&mut transcript, b"public");
+    commitment.append_to(&mut transcript, b"commitment");
+    let challenge = transcript.challenge_scalar(b"challenge");
+
+    SchnorrProof {
+        response: respond(&nonce, &challenge, secret),
+        challenge,
+    }
+}
+
+fn schnorr_verify<P: Point>(context: &[u8], base: &P, public: &P, proof: &SchnorrProof) -> bool {
+    if !base.is_valid() || !public.is_valid() {
+        return false;
+    }
+
+    // R = s * base - c * public
+    let commitment = recompute_commitment(base, public, &proof.challenge, &proof.response);
+
+    let mut transcript = sigma_transcript(SCHNORR_LABEL, context);
+    base.append_to(&mut transcript, b"base");
+    public.append_to(&mut transcript, b"public");
+    commitment.append_to(&mut transcript, b"commitment");
+    transcript.challenge_scalar(b"challenge") == proof.challenge
+}
+
+/*************************************************************************************************
+* Chaum-Pedersen proof of discrete log equality
+*************************************************************************************************/
+
+/// Prove secret * base_1 and secret * base_2 share `secret`, both bases in G1.
+pub fn dleq_prove_g1(
+    context: &[u8],
+    base_1: &ECP,
+    base_2: &ECP,
+    secret: &Fr,
+    rng: &mut RAND,
+) -> DleqProof {
+    dleq_prove(context, base_1, base_2, secret, rng)
+}
+
+/// Verify a DLEQ proof with both bases in G1.
+pub fn dleq_verify_g1(
+    context: &[u8],
+    base_1: &ECP,
+    public_1: &ECP,
+    base_2: &ECP,
+    public_2: &ECP,
+    proof: &DleqProof,
+) -> bool {
+    dleq_verify(context, base_1, public_1, base_2, public_2, proof)
+}
+
+/// Prove secret * base_1 and secret * base_2 share `secret`, both bases in G2.
+pub fn dleq_prove_g2(
+    context: &[u8],
+    base_1: &ECP2,
+    base_2: &ECP2,
+    secret: &Fr,
+    rng: &mut RAND,
+) -> DleqProof {
+    dleq_prove(context, base_1, base_2, secret, rng)
+}
+
+/// Verify a DLEQ proof with both bases in G2.
+pub fn dleq_verify_g2(
+    context: &[u8],
+    base_1: &ECP2,
+    public_1: &ECP2,
+    base_2: &ECP2,
+    public_2: &ECP2,
+    proof: &DleqProof,
+) -> bool {
+    dleq_verify(context, base_1, public_1, base_2, public_2, proof)
+}
+
+/// Prove secret * base_1 in G1 and secret * base_2 in G2 share `secret`.
+///
+/// For example that a G1 commitment and a BLS public key in G2 use the same secret key.
+pub fn dleq_prove_g1_g2(
+    context: &[u8],
+    base_1: &ECP,
+    base_2: &ECP2,
+    secret: &Fr,
+    rng: &mut RAND,
+) -> DleqProof {
+    dleq_prove(context, base_1, base_2, secret, rng)
+}
+
+/// Verify a DLEQ proof with base_1 in G1 and base_2 in G2.
+pub fn dleq_verify_g1_g2(
+    context: &[u8],
+    base_1: &ECP,
+    public_1: &ECP,
+    base_2: &ECP2,
+    public_2: &ECP2,
+    proof: &DleqProof,
+) -> bool {
+    dleq_verify(context, base_1, public_1, base_2, public_2, proof)
+}
+
+fn dleq_prove<P: Point, Q: Point>(
+    context: &[u8],
+    base_1: &P,
+    base_2: &Q,
+    secret: &Fr,
+    rng: &mut RAND,
+) -> DleqProof {
+    let public_1 = base_1.scalar_mul(secret);
+    let public_2 = base_2.scalar_mul(secret);
+    let nonce = random_nonzero(rng);
+
+    let mut transcript = sigma_transcript(DLEQ_LABEL, context);
+    base_1.append_to(&mut transcript, b"base_1");
+    public_1.append_to(&mut transcript, b"public_1");
+    base_2.append_to(&mut transcript, b"base_2");
+    public_2.append_to(&mut transcript, b"public_2");
+    base_1
+        .scalar_mul(&nonce)
+        .append_to(&mut transcript, b"commitment_1");
+    base_2
+        .scalar_mul(&nonce)
+        .append_to(&mut transcript, b"commitment_2");
+    let challenge = transcript.challenge_scalar(b"challenge");
+
+    DleqProof {
+        response: respond(&nonce, &challenge, secret),
+        challenge,
+    }
+}
+
+fn dleq_verify<P: Point, Q: Point>(
+    context: &[u8],
+    base_1: &P,
+    public_1: &P,
+    base_2: &Q,
+    public_2: &Q,
+    proof: &DleqProof,
+) -> bool {
+    if !base_1.is_valid() || !public_1.is_valid() || !base_2.is_valid() || !public_2.is_valid() {
+        return false;
+    }
+
+    let mut transcript = sigma_transcript(DLEQ_LABEL, context);
+    base_1.append_to(&mut transcript, b"base_1");
+    public_1.append_to(&mut transcript, b"public_1");
+    base_2.append_to(&mut transcript, b"base_2");
+    public_2.append_to(&mut transcript, b"public_2");
+    recompute_commitment(base_1, public_1, &proof.challenge, &proof.response)
+        .append_to(&mut transcript, b"commitment_1");
+    recompute_commitment(base_2, public_2, &proof.challenge, &proof.response)
+        .append_to(&mut transcript, b"commitment_2");
+    transcript.challenge_scalar(b"challenge") == proof.challenge
+}
+
+/*************************************************************************************************
+* Disjunctive (1 of n) proofs of knowledge
+*
+* Cramer, Damgard and Schoenmakers: the branches not known are simulated with random challenges
+* and the challenges must sum to the Fiat-Shamir challenge.
+*************************************************************************************************/
+
+/// Prove knowledge of `secret` with publics[index] = secret * base in G1.
+pub fn or_prove_g1(
+    context: &[u8],
+    base: &ECP,
+    publics: &[ECP],
+    index: usize,
+    secret: &Fr,
+    rng: &mut RAND,
+) -> Result<OrProof, AmclError> {
+    or_prove(context, base, publics, index, secret, rng)
+}
+
+/// Verify a disjunctive proof in G1.
+pub fn or_verify_g1(context: &[u8], base: &ECP, publics: &[ECP], proof: &OrProof) -> bool {
+    or_verify(context, base, publics, proof)
+}
+
+/// Prove knowledge of `secret` with publics[index] = secret * base in G2.
+pub fn or_prove_g2(
+    context: &[u8],
+    base: &ECP2,
+    publics: &[ECP2],
+    index: usize,
+    secret: &Fr,
+    rng: &mut RAND,
+) -> Result<OrProof, AmclError> {
+    or_prove(context, base, publics, index, secret, rng)
+}
+
+/// Verify a disjunctive proof in G2.
+pub fn or_verify_g2(context: &[u8], base: &ECP2, publics: &[ECP2], proof: &OrProof) -> bool {
+    or_verify(context, base, publics, proof)
+}
+
+fn or_prove<P: Point>(
+    context: &[u8],
+    base: &P,
+    publics: &[P],
+    index: usize,
+    secret: &Fr,
+    rng: &mut RAND,
+) -> Result<OrProof, AmclError> {
+    if index >= publics.len() || !base.scalar_mul(secret).equals(&publics[index]) {
+        return Err(AmclError::InvalidProof);
+    }
+
+    let nonce = random_nonzero(rng);
+    let mut challenges = vec![Fr::new(); publics.len()];
+    let mut responses = vec![Fr::new(); publics.len()];
+    let mut commitments = Vec::with_capacity(publics.len());
+
+    for (i, public) in publics.iter().enumerate() {
+        if i == index {
+            commitments.push(base.scalar_mul(&nonce));
+        } else {
+            // Simulate: pick c_i, s_i and set R_i = s_i * base - c_i * public_i
+            challenges[i] = Fr::random(rng);
+            responses[i] = Fr::random(rng);
+            commitments.push(recompute_commitment(
+                base,
+                public,
+                &challenges[i],
+                &responses[i],
+            ));
+        }
+    }
+
+    // c_index = c - sum of the simulated challenges
+    let mut challenge = or_challenge(context, base, publics, &commitments);
+    for (i, c) in challenges.iter().enumerate() {
+        if i != index {
+            challenge.sub(c);
+        }
+    }
+    responses[index] = respond(&nonce, &challenge, secret);
+    challenges[index] = challenge;
+
+    Ok(OrProof {
+        challenges,
+        responses,
+    })
+}
+
+fn or_verify<P: Point>(context: &[u8], base: &P, publics: &[P], proof: &OrProof) -> bool {
+    if publics.is_empty()
+        || proof.challenges.len() != publics.len()
+        || proof.responses.len() != publics.len()
+    {
+        return false;
+    }
+    if !base.is_valid() || publics.iter().any(|p| !p.is_valid()) {
+        return false;
+    }
+
+    let mut challenge_sum = Fr::new();
+    let mut commitments = Vec::with_capacity(publics.len());
+    for ((public, challenge), response) in publics
+        .iter()
+        .zip(proof.challenges.iter())
+        .zip(proof.responses.iter())
+    {
+        commitments.push(recompute_commitment(base, public, challenge, response));
+        challenge_sum.add(challenge);
+    }
+
+    or_challenge(context, base, publics, &commitments) == challenge_sum
+}
+
+fn or_challenge<P: Point>(context: &[u8], base: &P, publics: &[P], commitments: &[P]) -> Fr {
+    let mut transcript = sigma_transcript(OR_LABEL, context);
+    base.append_to(&mut transcript, b"base");
+    transcript.append_u64(b"branches", publics.len() as u64);
+    for point in publics.iter() {
+        point.append_to(&mut transcript, b"public");
+    }
+    for point in commitments.iter() {
+        point.append_to(&mut transcript, b"commitment");
+    }
+    transcript.challenge_scalar(b"challenge")
+}
+
+/*************************************************************************************************
+* Helpers
+*************************************************************************************************/
+
+// Group operations shared by G1 and G2.
+trait Point: Clone {
+    fn scalar_mul(&self, e: &Fr) -> Self;
+    fn point_add(&mut self, other: &Self);
+    fn point_neg(&mut self);
+    fn equals(&self, other: &Self) -> bool;
+    fn is_valid(&self) -> bool;
+    fn append_to(&self, transcript: &mut Transcript, label: &[u8]);
+}
+
+impl Point for ECP {
+    fn scalar_mul(&self, e: &Fr) -> ECP {
+        pair::g1mul(self, &e.redc())
+    }
+
+    fn point_add(&mut self, other: &ECP) {
+        self.add(other);
+    }
+
+    fn point_neg(&mut self) {
+        self.neg();
+    }
+
+    fn equals(&self, other: &ECP) -> bool {
+        ECP::equals(self, other)
+    }
+
+    fn is_valid(&self) -> bool {
+        !self.is_infinity() && subgroup_check_g1(self)
+    }
+
+    fn append_to(&self, transcript: &mut Transcript, label: &[u8]) {
+        transcript.append_g1(label, self);
+    }
+}
+
+impl Point for ECP2 {
+    fn scalar_mul(&self, e: &Fr) -> ECP2 {
+        pair::g2mul(self, &e.redc())
+    }
+
+    fn point_add(&mut self, other: &ECP2) {
+        self.add(other);
+    }
+
+    fn point_neg(&mut self) {
+        self.neg();
+    }
+
+    fn equals(&self, other: &ECP2) -> bool {
+        ECP2::equals(self, other)
+    }
+
+    fn is_valid(&self) -> bool {
+        !self.is_infinity() && subgroup_check_g2(self)
+    }
+
+    fn append_to(&self, transcript: &mut Transcript, label: &[u8]) {
+        transcript.append_g2(label, self);
+    }
+}
+
+// R = s * base - c * public
+fn recompute_commitment<P: Point>(base: &P, public: &P, challenge: &Fr, response: &Fr) -> P {
+    let mut commitment = base.scalar_mul(response);
+    let mut c_public = public.scalar_mul(challenge);
+    c_public.point_neg();
+    commitment.point_add(&c_public);
+    commitment
+}
+
+// s = k + c * x
+fn respond(nonce: &Fr, challenge: &Fr, secret: &Fr) -> Fr {
+    let mut response = challenge.clone();
+    response.mul(secret);
+    response.add(nonce);
+    response
+}
+
+fn random_nonzero(rng: &mut RAND) -> Fr {
+    let mut k = Fr::random(rng);
+    while k.is_zilch() {
+        k = Fr::random(rng);
+    }
+    k
+}
+
+// Transcript for `label` bound to the caller's context.
+fn sigma_transcript(label: &[u8], context: &[u8]) -> Transcript {
+    let mut transcript = Transcript::new(SIGMA_DOMAIN);
+    transcript.append_message(b"protocol", label);
+    transcript.append_message(b"context", context);
+    transcript
+}
+
+fn scalar_pair_to_bytes(a: &Fr, b: &Fr) -> [u8; 2 * FR_BYTES] {
+    let mut bytes = [0u8; 2 * FR_BYTES];
+    bytes[..FR_BYTES].copy_from_slice(&a.to_bytes());
+    bytes[FR_BYTES..].copy_from_slice(&b.to_bytes());
+    bytes
+}
+
+fn scalar_pair_from_bytes(bytes: &[u8]) -> Result<(Fr, Fr), AmclError> {
+    if bytes.len() != 2 * FR_BYTES {
+        return Err(AmclError::InvalidProof);
+    }
+    Ok((
+        Fr::from_bytes(&bytes[..FR_BYTES])?,
+        Fr::from_bytes(&bytes[FR_BYTES..])?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTEXT: &[u8] = b"sigma tests";
+
+    fn rng() -> RAND {
+        let mut rng = RAND::new();
+        rng.seed(32, &[39u8; 32]);
+        rng
+    }
+
+    // Change the challenge and response in turn.
+    fn tampered(challenge: &Fr, response: &Fr) -> [(Fr, Fr); 2] {
+        let mut c = challenge.clone();
+        c.add(&Fr::one());
+        let mut s = response.clone();
+        s.add(&Fr::one());
+        [(c, response.clone()), (challenge.clone(), s)]
+    }
+
+    #[test]
+    fn test_schnorr_g1() {
+        let mut rng = rng();
+        let base = ECP::generator();
+        let secret = Fr::random(&mut rng);
+        let public = base.scalar_mul(&secret);
+
+        let proof = schnorr_prove_g1(CONTEXT, &base, &secret, &mut rng);
+        assert!(schnorr_verify_g1(CONTEXT, &base, &public, &proof));
+        let decoded = SchnorrProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(schnorr_verify_g1(CONTEXT, &base, &public, &decoded));
+
+        assert!(!schnorr_verify_g1(b"other", &base, &public, &proof));
+        assert!(!schnorr_verify_g1(CONTEXT, &base, &base, &proof));
+        assert!(!schnorr_verify_g1(CONTEXT, &public, &public, &proof));
+        assert!(!schnorr_verify_g1(CONTEXT, &base, &ECP::new(), &proof));
+        for (challenge, response) in tampered(&proof.challenge, &proof.response).iter() {
+            let proof = SchnorrProof {
+                challenge: challenge.clone(),
+                response: response.clone(),
+            };
+            assert!(!schnorr_verify_g1(CONTEXT, &base, &public, &proof));
+        }
+    }
+
+    #[test]
+    fn test_schnorr_g2() {
+        let mut rng = rng();
+        let base = ECP2::generator();
+        let secret = Fr::random(&mut rng);
+        let public = base.scalar_mul(&secret);
+
+        let proof = schnorr_prove_g2(CONTEXT, &base, &secret, &mut rng);
+        assert!(schnorr_verify_g2(CONTEXT, &base, &public, &proof));
+
+        assert!(!schnorr_verify_g2(b"other", &base, &public, &proof));
+        assert!(!schnorr_verify_g2(CONTEXT, &base, &base, &proof));
+        for (challenge, response) in tampered(&proof.challenge, &proof.response).iter() {
+            let proof = SchnorrProof {
+                challenge: challenge.clone(),
+                response: response.clone(),
+            };
+            assert!(!schnorr_verify_g2(CONTEXT, &base, &public, &proof));
+        }
+    }
+
+    #[test]
+    fn test_dleq() {
+        let mut rng = rng();
+        let secret = Fr::random(&mut rng);
+        let other = Fr::random(&mut rng);
+        let g1 = ECP::generator();
+        let h1 = g1.scalar_mul(&Fr::random(&mut rng));
+        let g2 = ECP2::generator();
+        let h2 = g2.scalar_mul(&Fr::random(&mut rng));
+
+        // G1 and G1
+        let proof = dleq_prove_g1(CONTEXT, &g1, &h1, &secret, &mut rng);
+        let (p1, q1) = (g1.scalar_mul(&secret), h1.scalar_mul(&secret));
+        assert!(dleq_verify_g1(CONTEXT, &g1, &p1, &h1, &q1, &proof));
+        assert!(!dleq_verify_g1(
+            CONTEXT,
+            &g1,
+            &p1,
+            &h1,
+            &h1.scalar_mul(&other),
+            &proof
+        ));
+        assert!(!dleq_verify_g1(b"other", &g1, &p1, &h1, &q1, &proof));
+        for (challenge, response) in tampered(&proof.challenge, &proof.response).iter() {
+            let proof = DleqProof {
+                challenge: challenge.clone(),
+                response: response.clone(),
+            };
+            assert!(!dleq_verify_g1(CONTEXT, &g1, &p1, &h1, &q1, &proof));
+        }
+
+        // G2 and G2
+        let proof = dleq_prove_g2(CONTEXT, &g2, &h2, &secret, &mut rng);
+        let (p2, q2) = (g2.scalar_mul(&secret), h2.scalar_mul(&secret));
+        assert!(dleq_verify_g2(CONTEXT, &g2, &p2, &h2, &q2, &proof));
+        assert!(!dleq_verify_g2(CONTEXT, &g2, &q2, &h2, &p2, &proof));
+
+        // G1 and G2
+        let proof = dleq_prove_g1_g2(CONTEXT, &g1, &g2, &secret, &mut rng);
+        let decoded = DleqProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(dleq_verify_g1_g2(CONTEXT, &g1, &p1, &g2, &p2, &decoded));
+        assert!(!dleq_verify_g1_g2(
+            CONTEXT,
+            &g1,
+            &p1,
+            &g2,
+            &g2.scalar_mul(&other),
+            &proof
+        ));
+        for (challenge, response) in tampered(&proof.challenge, &proof.response).iter() {
+            let proof = DleqProof {
+                challenge: challenge.clone(),
+                response: response.clone(),
+            };
+            assert!(!dleq_verify_g1_g2(CONTEXT, &g1, &p1, &g2, &p2, &proof));
+        }
+    }
+
+    #[test]
+    fn test_or_g1() {
+        let mut rng = rng();
+        let base = ECP::generator();
+        let secrets: Vec<Fr> = (0..4).map(|_| Fr::random(&mut rng)).collect();
+        let publics: Vec<ECP> = secrets.iter().map(|x| base.scalar_mul(x)).collect();
+
+        for (index, secret) in secrets.iter().enumerate() {
+            let proof = or_prove_g1(CONTEXT, &base, &publics, index, secret, &mut rng).unwrap();
+            assert!(or_verify_g1(CONTEXT, &base, &publics, &proof));
+            let decoded = OrProof::from_bytes(&proof.to_bytes()).unwrap();
+            assert!(or_verify_g1(CONTEXT, &base, &publics, &decoded));
+
+            assert!(!or_verify_g1(b"other", &base, &publics, &proof));
+            assert!(!or_verify_g1(CONTEXT, &base, &publics[..3], &proof));
+            let mut reordered = publics.clone();
+            reordered.swap(0, 1);
+            assert!(!or_verify_g1(CONTEXT, &base, &reordered, &proof));
+
+            // Tamper with every challenge and response
+            for i in 0..publics.len() {
+                for (challenge, response) in
+                    tampered(&proof.challenges[i], &proof.responses[i]).iter()
+                {
+                    let mut modified = proof.clone();
+                    modified.challenges[i] = challenge.clone();
+                    modified.responses[i] = response.clone();
+                    assert!(!or_verify_g1(CONTEXT, &base, &publics, &modified));
+                }
+            }
+        }
+
+        // The secret must open the branch at the index
+        assert_eq!(
+            or_prove_g1(CONTEXT, &base, &publics, 1, &secrets[0], &mut rng),
+            Err(AmclError::InvalidProof)
+        );
+        assert!(or_prove_g1(CONTEXT, &base, &publics, 4, &secrets[0], &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_or_g2() {
+        let mut rng = rng();
+        let base = ECP2::generator();
+        let secret = Fr::random(&mut rng);
+        let publics = vec![
+            base.scalar_mul(&Fr::random(&mut rng)),
+            base.scalar_mul(&secret),
+        ];
+
+        let proof = or_prove_g2(CONTEXT, &base, &publics, 1, &secret, &mut rng).unwrap();
+        assert!(or_verify_g2(CONTEXT, &base, &publics, &proof));
+
+        let mut modified = proof.clone();
+        modified.challenges.swap(0, 1);
+        assert!(!or_verify_g2(CONTEXT, &base, &publics, &modified));
+        let mut modified = proof.clone();
+        modified.responses[0].add(&Fr::one());
+        assert!(!or_verify_g2(CONTEXT, &base, &publics, &modified));
+    }
+
+    #[test]
+    fn test_invalid_encodings() {
+        assert!(SchnorrProof::from_bytes(&[0u8; 63]).is_err());
+        assert!(DleqProof::from_bytes(&[0xffu8; 64]).is_err());
+        assert!(OrProof::from_bytes(&[]).is_err());
+        assert!(OrProof::from_bytes(&[0u8; 96]).is_err());
+    }
+}
@@ -0,0 +1,598 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use crate::errors::AmclError;
+use crate::hash256::HASH256;
+use std::vec::Vec;
+
+/*************************************************************************************************
+* SSZ merkleization
+*
+* https://github.com/ethereum/consensus-specs/blob/dev/ssz/simple-serialize.md#merkleization
+*************************************************************************************************/
+
+/// The size of a merkle chunk in bytes
+pub const BYTES_PER_CHUNK: usize = 32;
+
+/// A 32 byte merkle root
+pub type Root = [u8; BYTES_PER_CHUNK];
+
+// Deepest supported tree, the largest list limit in the consensus specs is 2^40
+const MAX_DEPTH: usize = 48;
+
+/// Types with an SSZ hash_tree_root.
+pub trait HashTreeRoot {
+    fn hash_tree_root(&self) -> Root;
+}
+
+/// SSZ basic types, which are packed into chunks in lists and vectors.
+pub trait BasicType: HashTreeRoot {
+    /// Serialized size in bytes
+    const SIZE: usize;
+
+    /// Append the little-endian serialization.
+    fn serialize_into(&self, bytes: &mut Vec<u8>);
+}
+
+macro_rules! impl_uint {
+    ($t:ty) => {
+        impl BasicType for $t {
+            const SIZE: usize = core::mem::size_of::<$t>();
+
+            fn serialize_into(&self, bytes: &mut Vec<u8>) {
+                bytes.extend_from_slice(&self.to_le_bytes());
+            }
+        }
+
+        impl HashTreeRoot for $t {
+            fn hash_tree_root(&self) -> Root {
+                let mut root = [0u8; BYTES_PER_CHUNK];
+                let bytes = self.to_le_bytes();
+                root[..bytes.len()].copy_from_slice(&bytes);
+                root
+            }
+        }
+    };
+}
+
+impl_uint!(u8);
+impl_uint!(u16);
+impl_uint!(u32);
+impl_uint!(u64);
+
+impl BasicType for bool {
+    const SIZE: usize = 1;
+
+    fn serialize_into(&self, bytes: &mut Vec<u8>) {
+        bytes.push(*self as u8);
+    }
+}
+
+impl HashTreeRoot for bool {
+    fn hash_tree_root(&self) -> Root {
+        (*self as u8).hash_tree_root()
+    }
+}
+
+// Fixed length byte vectors, e.g. Bytes4, Bytes32 (Root), Bytes48 (BLSPubkey) and Bytes96
+macro_rules! impl_byte_vector {
+    ($n:expr) => {
+        impl HashTreeRoot for [u8; $n] {
+            fn hash_tree_root(&self) -> Root {
+                hash_tree_root_byte_vector(self)
+            }
+        }
+    };
+}
+
+impl_byte_vector!(4);
+impl_byte_vector!(8);
+impl_byte_vector!(20);
+impl_byte_vector!(32);
+impl_byte_vector!(48);
+impl_byte_vector!(96);
+
+/// Hash
+///
+/// SHA-256(a || b)
+pub fn hash(a: &[u8], b: &[u8]) -> Root {
+    let mut hash256 = HASH256::new();
+    hash256.process_array(a);
+    hash256.process_array(b);
+    hash256.hash()
+}
+
+/// Pack
+///
+/// Split serialized basic values into chunks, right padding the last with zeros.
+pub fn pack(bytes: &[u8]) -> Vec<Root> {
+    bytes
+        .chunks(BYTES_PER_CHUNK)
+        .map(|chunk| {
+            let mut root = [0u8; BYTES_PER_CHUNK];
+            root[..chunk.len()].copy_from_slice(chunk);
+            root
+        })
+        .collect()
+}
+
+/// Merkleize
+///
+/// Merkle root of `chunks` padded with zero chunks to `limit` rounded up to a power of two,
+/// or to the number of chunks rounded up to a power of two when there is no limit.
+pub fn merkleize(chunks: &[Root], limit: Option<usize>) -> Result<Root, AmclError> {
+    let limit = match limit {
+        Some(limit) if chunks.len() > limit => return Err(AmclError::InvalidSszLength),
+        Some(limit) => limit,
+        None => chunks.len(),
+    };
+    if limit > 1 << MAX_DEPTH {
+        return Err(AmclError::InvalidSszLength);
+    }
+    let depth = next_power_of_two_exponent(limit);
+
+    // zero_hashes[i] is the root of a tree of depth i with only zero chunks
+    let mut zero_hashes = Vec::with_capacity(depth + 1);
+    zero_hashes.push([0u8; BYTES_PER_CHUNK]);
+    for i in 0..depth {
+        let zero = hash(&zero_hashes[i], &zero_hashes[i]);
+        zero_hashes.push(zero);
+    }
+
+    let mut layer = chunks.to_vec();
+    for zero in zero_hashes.iter().take(depth) {
+        if layer.len() % 2 == 1 {
+            layer.push(*zero);
+        }
+        layer = layer
+            .chunks(2)
+            .map(|pair| hash(&pair[0], &pair[1]))
+            .collect();
+    }
+
+    Ok(layer.first().cloned().unwrap_or(zero_hashes[depth]))
+}
+
+/// Mix In Length
+///
+/// SHA-256(root || uint256(length))
+pub fn mix_in_length(root: &Root, length: usize) -> Root {
+    hash(root, &(length as u64).hash_tree_root())
+}
+
+/// is_valid_merkle_branch
+///
+/// Check that `leaf` is at `index` of a tree of `depth` with `root`, `branch` holding the
+/// sibling hashes from the leaf upwards.
+pub fn is_valid_merkle_branch(
+    leaf: &Root,
+    branch: &[Root],
+    depth: usize,
+    index: u64,
+    root: &Root,
+) -> bool {
+    if branch.len() != depth || depth >= 64 {
+        return false;
+    }
+
+    let mut value = *leaf;
+    for (i, sibling) in branch.iter().enumerate() {
+        if (index >> i) & 1 == 1 {
+            value = hash(sibling, &value);
+        } else {
+            value = hash(&value, sibling);
+        }
+    }
+    value == *root
+}
+
+/// Hash tree root of a fixed length byte vector.
+pub fn hash_tree_root_byte_vector(bytes: &[u8]) -> Root {
+    merkleize(&pack(bytes), None).expect("no limit")
+}
+
+/// Hash tree root of a byte list with a maximum length of `limit` bytes.
+pub fn hash_tree_root_byte_list(bytes: &[u8], limit: usize) -> Result<Root, AmclError> {
+    if bytes.len() > limit {
+        return Err(AmclError::InvalidSszLength);
+    }
+    let root = merkleize(&pack(bytes), Some(chunk_count(limit, 1)))?;
+    Ok(mix_in_length(&root, bytes.len()))
+}
+
+/// Hash tree root of a vector of basic values.
+pub fn hash_tree_root_basic_vector<T: BasicType>(values: &[T]) -> Root {
+    merkleize(&pack(&serialize_basic(values)), None).expect("no limit")
+}
+
+/// Hash tree root of a list of basic values with a maximum length of `limit`.
+pub fn hash_tree_root_basic_list<T: BasicType>(
+    values: &[T],
+    limit: usize,
+) -> Result<Root, AmclError> {
+    if values.len() > limit {
+        return Err(AmclError::InvalidSszLength);
+    }
+    let bytes = serialize_basic(values);
+    let root = merkleize(&pack(&bytes), Some(chunk_count(limit, T::SIZE)))?;
+    Ok(mix_in_length(&root, values.len()))
+}
+
+/// Hash tree root of a vector of composite values.
+pub fn hash_tree_root_vector<T: HashTreeRoot>(values: &[T]) -> Root {
+    let chunks: Vec<Root> = values.iter().map(|v| v.hash_tree_root()).collect();
+    merkleize(&chunks, None).expect("no limit")
+}
+
+/// Hash tree root of a list of composite values with a maximum length of `limit`.
+pub fn hash_tree_root_list<T: HashTreeRoot>(values: &[T], limit: usize) -> Result<Root, AmclError> {
+    let chunks: Vec<Root> = values.iter().map(|v| v.hash_tree_root()).collect();
+    let root = merkleize(&chunks, Some(limit))?;
+    Ok(mix_in_length(&root, values.len()))
+}
+
+/// Hash tree root of a container given the roots of its fields in order.
+pub fn hash_tree_root_container(field_roots: &[Root]) -> Root {
+    merkleize(field_roots, None).expect("no limit")
+}
+
+/*************************************************************************************************
+* Bitfields
+*************************************************************************************************/
+
+/// A fixed length SSZ Bitvector[N].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bitvector {
+    pub bits: Vec<bool>,
+}
+
+/// A variable length SSZ Bitlist[N] holding at most `limit` bits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bitlist {
+    pub bits: Vec<bool>,
+    pub limit: usize,
+}
+
+impl Bitvector {
+    /// New
+    ///
+    /// A Bitvector of `length` unset bits.
+    pub fn new(length: usize) -> Bitvector {
+        Bitvector {
+            bits: vec![false; length],
+        }
+    }
+
+    /// Serialize, bit i is bit (i % 8) of byte (i / 8).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bits_to_bytes(&self.bits)
+    }
+
+    /// Deserialize a Bitvector of `length` bits, any padding bits must be unset.
+    pub fn from_bytes(bytes: &[u8], length: usize) -> Result<Bitvector, AmclError> {
+        if bytes.len() != (length + 7) / 8 {
+            return Err(AmclError::InvalidSszLength);
+        }
+        let bits = bytes_to_bits(bytes);
+        if bits[length..].iter().any(|b| *b) {
+            return Err(AmclError::InvalidSszLength);
+        }
+        Ok(Bitvector {
+            bits: bits[..length].to_vec(),
+        })
+    }
+
+    /// Number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.bits.iter().filter(|b| **b).count()
+    }
+}
+
+impl HashTreeRoot for Bitvector {
+    fn hash_tree_root(&self) -> Root {
+        merkleize(
+            &pack(&self.to_bytes()),
+            Some(chunk_count(self.bits.len(), 0)),
+        )
+        .expect("chunks fit the limit")
+    }
+}
+
+impl Bitlist {
+    /// New
+    ///
+    /// A Bitlist of `length` unset bits holding at most `limit` bits.
+    pub fn new(length: usize, limit: usize) -> Result<Bitlist, AmclError> {
+        if length > limit {
+            return Err(AmclError::InvalidSszLength);
+        }
+        Ok(Bitlist {
+            bits: vec![false; length],
+            limit,
+        })
+    }
+
+    /// Serialize with a delimiting set bit after the last bit.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bits = self.bits.clone();
+        bits.push(true);
+        bits_to_bytes(&bits)
+    }
+
+    /// Deserialize, the highest set bit of the last byte is the delimiter.
+    pub fn from_bytes(bytes: &[u8], limit: usize) -> Result<Bitlist, AmclError> {
+        let last = match bytes.last() {
+            Some(last) if *last != 0 => *last,
+            _ => return Err(AmclError::InvalidSszLength),
+        };
+
+        let length = 8 * (bytes.len() - 1) + 7 - last.leading_zeros() as usize;
+        if length > limit {
+            return Err(AmclError::InvalidSszLength);
+        }
+
+        let mut bits = bytes_to_bits(bytes);
+        bits.truncate(length);
+        Ok(Bitlist { bits, limit })
+    }
+
+    /// Number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.bits.iter().filter(|b| **b).count()
+    }
+}
+
+impl HashTreeRoot for Bitlist {
+    fn hash_tree_root(&self) -> Root {
+        let root = merkleize(
+            &pack(&bits_to_bytes(&self.bits)),
+            Some(chunk_count(self.limit, 0)),
+        )
+        .expect("length is within the limit");
+        mix_in_length(&root, self.bits.len())
+    }
+}
+
+// Number of chunks for `limit` elements of `element_size` bytes, or bits if the size is 0.
+fn chunk_count(limit: usize, element_size: usize) -> usize {
+    let bytes = if element_size == 0 {
+        (limit + 7) / 8
+    } else {
+        limit * element_size
+    };
+    (bytes + BYTES_PER_CHUNK - 1) / BYTES_PER_CHUNK
+}
+
+// Smallest d with 2^d >= n.
+fn next_power_of_two_exponent(n: usize) -> usize {
+    n.next_power_of_two().trailing_zeros() as usize
+}
+
+fn serialize_basic<T: BasicType>(values: &[T]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for value in values {
+        value.serialize_into(&mut bytes);
+    }
+    bytes
+}
+
+fn bits_to_bytes(bits: &[bool]) -> Vec<u8> {
+    let mut bytes = vec![0u8; (bits.len() + 7) / 8];
+    for (i, bit) in bits.iter().enumerate() {
+        if *bit {
+            bytes[i / 8] |= 1 << (i % 8);
+        }
+    }
+    bytes
+}
+
+fn bytes_to_bits(bytes: &[u8]) -> Vec<bool> {
+    (0..8 * bytes.len())
+        .map(|i| bytes[i / 8] >> (i % 8) & 1 == 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Known answers computed independently with Python's hashlib.
+
+    fn root(s: &str) -> Root {
+        let mut root = [0u8; BYTES_PER_CHUNK];
+        root.copy_from_slice(&hex::decode(s).unwrap());
+        root
+    }
+
+    fn chunks(n: usize) -> Vec<Root> {
+        (0..n).map(|i| [i as u8 + 1; BYTES_PER_CHUNK]).collect()
+    }
+
+    #[test]
+    fn test_merkleize_limits() {
+        // Limit 0 is the zero chunk and admits no chunks
+        assert_eq!(merkleize(&[], Some(0)), Ok([0u8; BYTES_PER_CHUNK]));
+        assert_eq!(
+            merkleize(&chunks(1), Some(0)),
+            Err(AmclError::InvalidSszLength)
+        );
+
+        // Limit 1 is the chunk itself
+        assert_eq!(merkleize(&chunks(1), Some(1)), Ok(chunks(1)[0]));
+        assert_eq!(merkleize(&[], Some(1)), Ok([0u8; BYTES_PER_CHUNK]));
+
+        // Empty trees are the zero hashes
+        let zero_2 = "db56114e00fdd4c1f85c892bf35ac9a89289aaecb1ebd0a96cde606a748b5d71";
+        assert_eq!(merkleize(&[], Some(4)), Ok(root(zero_2)));
+
+        // Limit 5 pads to 8 chunks
+        let three_of_five = "16e7bc9c1de9b5c63df0e8ef884b1006155d390b6e1aee4ffd3844e548ec2d41";
+        assert_eq!(merkleize(&chunks(3), Some(5)), Ok(root(three_of_five)));
+        assert_eq!(merkleize(&chunks(3), Some(8)), Ok(root(three_of_five)));
+        let five = "6c1cfb22738edf2a397893ab3bd49b601f5dfc69439772b613f6fad2889ebbd6";
+        assert_eq!(merkleize(&chunks(5), Some(5)), Ok(root(five)));
+        assert_eq!(merkleize(&chunks(5), None), Ok(root(five)));
+        assert_eq!(
+            merkleize(&chunks(6), Some(5)),
+            Err(AmclError::InvalidSszLength)
+        );
+
+        assert_eq!(
+            merkleize(&[], Some((1 << MAX_DEPTH) + 1)),
+            Err(AmclError::InvalidSszLength)
+        );
+    }
+
+    #[test]
+    fn test_mix_in_length() {
+        let expected = "644fd7e0aacdc1fce278db70a606ba562eb7f8be1a1d3c3aa5e3f4f0b682e248";
+        assert_eq!(mix_in_length(&chunks(1)[0], 5), root(expected));
+    }
+
+    #[test]
+    fn test_merkle_branch() {
+        let leaves = chunks(4);
+        let left = hash(&leaves[0], &leaves[1]);
+        let right = hash(&leaves[2], &leaves[3]);
+        let tree_root = root("2c0c4083be2badf7c9f9046d8730d21e034c1ce50f519c166d7605848b17b0d5");
+        assert_eq!(hash(&left, &right), tree_root);
+        assert_eq!(merkleize(&leaves, None), Ok(tree_root));
+
+        let branch = [leaves[3], left];
+        assert!(is_valid_merkle_branch(
+            &leaves[2], &branch, 2, 2, &tree_root
+        ));
+
+        // Flipped sibling
+        let mut flipped = branch;
+        flipped[0][0] ^= 1;
+        assert!(!is_valid_merkle_branch(
+            &leaves[2], &flipped, 2, 2, &tree_root
+        ));
+        let mut flipped = branch;
+        flipped[1][31] ^= 0x80;
+        assert!(!is_valid_merkle_branch(
+            &leaves[2], &flipped, 2, 2, &tree_root
+        ));
+
+        // Wrong leaf, index or depth
+        assert!(!is_valid_merkle_branch(
+            &leaves[3], &branch, 2, 2, &tree_root
+        ));
+        assert!(!is_valid_merkle_branch(
+            &leaves[2], &branch, 2, 3, &tree_root
+        ));
+        assert!(!is_valid_merkle_branch(
+            &leaves[2], &branch, 1, 2, &tree_root
+        ));
+        assert!(!is_valid_merkle_branch(
+            &leaves[2],
+            &branch[..1],
+            1,
+            0,
+            &tree_root
+        ));
+    }
+
+    #[test]
+    fn test_bitvector() {
+        // Bits 0 and 9 of a Bitvector[10]
+        let mut bitvector = Bitvector::new(10);
+        bitvector.bits[0] = true;
+        bitvector.bits[9] = true;
+        assert_eq!(bitvector.to_bytes(), vec![0x01, 0x02]);
+        assert_eq!(
+            Bitvector::from_bytes(&[0x01, 0x02], 10),
+            Ok(bitvector.clone())
+        );
+        assert_eq!(bitvector.count_ones(), 2);
+        let mut expected = [0u8; BYTES_PER_CHUNK];
+        expected[..2].copy_from_slice(&[0x01, 0x02]);
+        assert_eq!(bitvector.hash_tree_root(), expected);
+
+        // Padding bits must be unset
+        assert_eq!(
+            Bitvector::from_bytes(&[0x01, 0x04], 10),
+            Err(AmclError::InvalidSszLength)
+        );
+        assert_eq!(
+            Bitvector::from_bytes(&[0x01, 0x80], 10),
+            Err(AmclError::InvalidSszLength)
+        );
+        assert!(Bitvector::from_bytes(&[0xff, 0xff], 16).is_ok());
+
+        // Wrong number of bytes
+        assert_eq!(
+            Bitvector::from_bytes(&[0x01], 10),
+            Err(AmclError::InvalidSszLength)
+        );
+        assert_eq!(
+            Bitvector::from_bytes(&[0x01, 0x02, 0x00], 10),
+            Err(AmclError::InvalidSszLength)
+        );
+    }
+
+    #[test]
+    fn test_bitlist() {
+        // [1, 1, 0] with the delimiter at bit 3
+        let bitlist = Bitlist::from_bytes(&[0x0b], 8).unwrap();
+        assert_eq!(bitlist.bits, vec![true, true, false]);
+        assert_eq!(bitlist.limit, 8);
+        assert_eq!(bitlist.to_bytes(), vec![0x0b]);
+        assert_eq!(bitlist.count_ones(), 2);
+        let expected = "a8e9d684dceaef6e6a478c2130ee96a72d37aae54289bcb5972f31c027994f5f";
+        assert_eq!(bitlist.hash_tree_root(), root(expected));
+
+        // The delimiter may be the only bit
+        let empty = Bitlist::from_bytes(&[0x01], 8).unwrap();
+        assert!(empty.bits.is_empty());
+        assert_eq!(empty, Bitlist::new(0, 8).unwrap());
+
+        // A full byte puts the delimiter in the next byte
+        let full = Bitlist::from_bytes(&[0xff, 0x01], 8).unwrap();
+        assert_eq!(full.bits, vec![true; 8]);
+        assert_eq!(full.to_bytes(), vec![0xff, 0x01]);
+
+        // Missing delimiter
+        assert_eq!(
+            Bitlist::from_bytes(&[], 8),
+            Err(AmclError::InvalidSszLength)
+        );
+        assert_eq!(
+            Bitlist::from_bytes(&[0x00], 8),
+            Err(AmclError::InvalidSszLength)
+        );
+        assert_eq!(
+            Bitlist::from_bytes(&[0x0b, 0x00], 8),
+            Err(AmclError::InvalidSszLength)
+        );
+
+        // Longer than the limit
+        assert_eq!(
+            Bitlist::from_bytes(&[0xff, 0x01], 7),
+            Err(AmclError::InvalidSszLength)
+        );
+        assert_eq!(
+            Bitlist::from_bytes(&[0x0b], 2),
+            Err(AmclError::InvalidSszLength)
+        );
+        assert_eq!(Bitlist::new(9, 8), Err(AmclError::InvalidSszLength));
+    }
+}
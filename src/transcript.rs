@@ -0,0 +1,248 @@
+/*
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+*/
+use sgx_tstd as std;
+
+use super::big::{Big, MODBYTES};
+use super::bls381::utils::{serialize_g1, serialize_g2};
+use super::ecp::ECP;
+use super::ecp2::ECP2;
+use super::fp12::FP12;
+use super::fr::Fr;
+use super::hash_to_curve::expand_message_xmd;
+use crate::errors::AmclError;
+use std::vec::Vec;
+
+/// Domain separation tag for expand_message_xmd when extracting challenges
+pub const TRANSCRIPT_DST: &[u8] = b"BLS12381_TRANSCRIPT_XMD:SHA-256_V1_";
+
+// Uniform bytes reduced to a challenge scalar, 128 bits above the size of r
+const CHALLENGE_SCALAR_BYTES: usize = 48;
+// Label absorbing each challenge back into the transcript
+const CHALLENGE_LABEL: &[u8] = b"challenge";
+
+/// Fiat-Shamir Transcript
+///
+/// Accumulates labeled, length prefixed messages. Every item is absorbed as
+/// I2OSP(len(label), 8) || label || I2OSP(len(data), 8) || data so no two sequences of appends
+/// share an encoding. Challenges are expand_message_xmd of everything absorbed so far and are
+/// themselves absorbed, so each challenge binds all earlier ones.
+#[derive(Clone)]
+pub struct Transcript {
+    state: Vec<u8>,
+}
+
+impl Transcript {
+    /// New
+    ///
+    /// Creates a transcript separated by the protocol name `domain`.
+    pub fn new(domain: &[u8]) -> Transcript {
+        let mut transcript = Transcript { state: vec![] };
+        transcript.append_message(b"domain", domain);
+        transcript
+    }
+
+    /// Append Message
+    ///
+    /// Absorbs arbitrary bytes under `label`.
+    pub fn append_message(&mut self, label: &[u8], message: &[u8]) {
+        self.state
+            .extend_from_slice(&(label.len() as u64).to_be_bytes());
+        self.state.extend_from_slice(label);
+        self.state
+            .extend_from_slice(&(message.len() as u64).to_be_bytes());
+        self.state.extend_from_slice(message);
+    }
+
+    /// Append u64
+    ///
+    /// Absorbs an 8 byte big-endian integer.
+    pub fn append_u64(&mut self, label: &[u8], value: u64) {
+        self.append_message(label, &value.to_be_bytes());
+    }
+
+    /// Append Scalar
+    ///
+    /// Absorbs a scalar as 32 big-endian bytes.
+    pub fn append_scalar(&mut self, label: &[u8], scalar: &Fr) {
+        self.append_message(label, &scalar.to_bytes());
+    }
+
+    /// Append Big
+    ///
+    /// Absorbs a Big as MODBYTES big-endian bytes.
+    pub fn append_big(&mut self, label: &[u8], big: &Big) {
+        let mut bytes = [0u8; MODBYTES];
+        big.to_bytes(&mut bytes);
+        self.append_message(label, &bytes);
+    }
+
+    /// Append G1
+    ///
+    /// Absorbs a G1 point in compressed form.
+    pub fn append_g1(&mut self, label: &[u8], point: &ECP) {
+        self.append_message(label, &serialize_g1(point));
+    }
+
+    /// Append G2
+    ///
+    /// Absorbs a G2 point in compressed form.
+    pub fn append_g2(&mut self, label: &[u8], point: &ECP2) {
+        self.append_message(label, &serialize_g2(point));
+    }
+
+    /// Append FP12
+    ///
+    /// Absorbs an FP12 as its 12 coefficients of MODBYTES each.
+    pub fn append_fp12(&mut self, label: &[u8], element: &FP12) {
+        let mut bytes = [0u8; 12 * MODBYTES];
+        element.to_bytes(&mut bytes);
+        self.append_message(label, &bytes);
+    }
+
+    /// Challenge Bytes
+    ///
+    /// Extracts `len` uniform bytes under `label`, at most 255 * 32.
+    pub fn challenge_bytes(&mut self, label: &[u8], len: usize) -> Result<Vec<u8>, AmclError> {
+        self.append_u64(label, len as u64);
+        let output = expand_message_xmd(&self.state, len, TRANSCRIPT_DST)?;
+        self.append_message(CHALLENGE_LABEL, &output);
+        Ok(output)
+    }
+
+    /// Challenge Scalar
+    ///
+    /// Extracts a uniformly distributed scalar mod r under `label`.
+    pub fn challenge_scalar(&mut self, label: &[u8]) -> Fr {
+        let output = self
+            .challenge_bytes(label, CHALLENGE_SCALAR_BYTES)
+            .expect("challenge length is in range");
+        Fr::new_big(&Big::from_bytes(&output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge(transcript: &Transcript) -> Vec<u8> {
+        transcript.clone().challenge_bytes(b"c", 32).unwrap()
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let mut a = Transcript::new(b"test");
+        let mut b = Transcript::new(b"test");
+        for transcript in [&mut a, &mut b].iter_mut() {
+            transcript.append_message(b"message", b"hello");
+            transcript.append_u64(b"count", 7);
+            transcript.append_g1(b"g1", &ECP::generator());
+            transcript.append_g2(b"g2", &ECP2::generator());
+            transcript.append_scalar(b"scalar", &Fr::new_int(5));
+        }
+        assert_eq!(challenge(&a), challenge(&b));
+        assert_eq!(a.challenge_scalar(b"s"), b.challenge_scalar(b"s"));
+        assert_eq!(
+            a.challenge_bytes(b"c", 100).unwrap(),
+            b.challenge_bytes(b"c", 100).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_domain_separation() {
+        assert_ne!(
+            challenge(&Transcript::new(b"a")),
+            challenge(&Transcript::new(b"b"))
+        );
+    }
+
+    #[test]
+    fn test_label_separation() {
+        let mut a = Transcript::new(b"test");
+        a.append_message(b"x", b"data");
+        let mut b = Transcript::new(b"test");
+        b.append_message(b"y", b"data");
+        assert_ne!(challenge(&a), challenge(&b));
+
+        // The same bytes as message or label
+        let mut a = Transcript::new(b"test");
+        a.append_message(b"", b"data");
+        let mut b = Transcript::new(b"test");
+        b.append_message(b"data", b"");
+        assert_ne!(challenge(&a), challenge(&b));
+    }
+
+    #[test]
+    fn test_length_separation() {
+        // Moving bytes between label and message
+        let mut a = Transcript::new(b"test");
+        a.append_message(b"ab", b"c");
+        let mut b = Transcript::new(b"test");
+        b.append_message(b"a", b"bc");
+        assert_ne!(challenge(&a), challenge(&b));
+
+        // One message against two
+        let mut a = Transcript::new(b"test");
+        a.append_message(b"m", b"ab");
+        let mut b = Transcript::new(b"test");
+        b.append_message(b"m", b"a");
+        b.append_message(b"m", b"b");
+        assert_ne!(challenge(&a), challenge(&b));
+
+        // The requested length is absorbed, so a shorter output is not a prefix
+        let mut a = Transcript::new(b"test");
+        let mut b = Transcript::new(b"test");
+        let short = a.challenge_bytes(b"c", 16).unwrap();
+        let long = b.challenge_bytes(b"c", 32).unwrap();
+        assert_ne!(&long[..16], &short[..]);
+    }
+
+    #[test]
+    fn test_order_matters() {
+        let mut a = Transcript::new(b"test");
+        a.append_u64(b"x", 1);
+        a.append_u64(b"y", 2);
+        let mut b = Transcript::new(b"test");
+        b.append_u64(b"y", 2);
+        b.append_u64(b"x", 1);
+        assert_ne!(challenge(&a), challenge(&b));
+    }
+
+    #[test]
+    fn test_challenges_chain() {
+        let mut transcript = Transcript::new(b"test");
+        let first = transcript.challenge_bytes(b"c", 32).unwrap();
+        let second = transcript.challenge_bytes(b"c", 32).unwrap();
+        assert_ne!(first, second);
+        assert_ne!(
+            transcript.challenge_scalar(b"s"),
+            transcript.challenge_scalar(b"s")
+        );
+    }
+
+    #[test]
+    fn test_challenge_bytes_length() {
+        let mut transcript = Transcript::new(b"test");
+        assert_eq!(transcript.challenge_bytes(b"c", 1).unwrap().len(), 1);
+        assert_eq!(
+            transcript.challenge_bytes(b"c", 255 * 32).unwrap().len(),
+            255 * 32
+        );
+        assert!(transcript.challenge_bytes(b"c", 255 * 32 + 1).is_err());
+    }
+}